@@ -0,0 +1,41 @@
+-- Migration 017: Booking receipts and commission invoices.
+-- Tables: invoice_counters, invoices.
+--
+-- Invoice numbers are allocated from `invoice_counters` inside the same
+-- transaction that inserts the invoice. The counter row stays locked until
+-- commit, so numbers are sequential and gap-free per series (French
+-- invoicing rules), unlike a SEQUENCE which skips values on rollback.
+
+BEGIN;
+
+-- ──────────────────────── Invoice counters ────────────────────────
+
+CREATE TABLE IF NOT EXISTS invoice_counters (
+    series      TEXT PRIMARY KEY,
+    last_number BIGINT NOT NULL DEFAULT 0 CHECK (last_number >= 0)
+);
+
+-- ──────────────────────── Invoices ────────────────────────
+
+CREATE TABLE IF NOT EXISTS invoices (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    booking_id      UUID NOT NULL REFERENCES bookings(id),
+    center_id       UUID NOT NULL REFERENCES centers(id),
+    client_id       UUID NOT NULL REFERENCES profiles(id),
+    kind            TEXT NOT NULL CHECK (kind IN ('receipt', 'commission_invoice')),
+    series          TEXT NOT NULL,
+    number          BIGINT NOT NULL CHECK (number > 0),
+    invoice_number  TEXT NOT NULL UNIQUE,
+    currency        TEXT NOT NULL,
+    amount          NUMERIC NOT NULL,
+    -- Frozen seller/buyer/line details at issue time
+    details         JSONB NOT NULL,
+    issued_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (series, number),
+    UNIQUE (booking_id, kind)
+);
+
+CREATE INDEX IF NOT EXISTS idx_invoices_center_id ON invoices(center_id, issued_at DESC);
+CREATE INDEX IF NOT EXISTS idx_invoices_client_id ON invoices(client_id);
+
+COMMIT;
@@ -0,0 +1,39 @@
+-- Migration 018: VAT breakdown on bookings and transactions.
+-- Columns: tax_country, price_vat_rate, price_net, price_vat,
+--          fee_vat_rate, fee_net, fee_vat, fee_tax_regime.
+--
+-- Gross amounts are the existing columns (bookings.total_price /
+-- commission_amount, transactions.amount / platform_fee). The split amounts
+-- are plain NUMERIC like them, so zero-decimal currencies (IDR, KRW) do not
+-- overflow; rates are NUMERIC(5,2). Rows created before this migration, or
+-- in a country without configured rates, keep NULL tax columns.
+
+BEGIN;
+
+-- ──────────────────────── Bookings ────────────────────────
+
+ALTER TABLE bookings
+    ADD COLUMN IF NOT EXISTS tax_country    TEXT,
+    ADD COLUMN IF NOT EXISTS price_vat_rate NUMERIC(5,2),
+    ADD COLUMN IF NOT EXISTS price_net      NUMERIC,
+    ADD COLUMN IF NOT EXISTS price_vat      NUMERIC,
+    ADD COLUMN IF NOT EXISTS fee_vat_rate   NUMERIC(5,2),
+    ADD COLUMN IF NOT EXISTS fee_net        NUMERIC,
+    ADD COLUMN IF NOT EXISTS fee_vat        NUMERIC,
+    ADD COLUMN IF NOT EXISTS fee_tax_regime TEXT
+        CHECK (fee_tax_regime IN ('french_vat', 'reverse_charge', 'out_of_scope'));
+
+-- ──────────────────────── Transactions ────────────────────────
+
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS tax_country    TEXT,
+    ADD COLUMN IF NOT EXISTS price_vat_rate NUMERIC(5,2),
+    ADD COLUMN IF NOT EXISTS price_net      NUMERIC,
+    ADD COLUMN IF NOT EXISTS price_vat      NUMERIC,
+    ADD COLUMN IF NOT EXISTS fee_vat_rate   NUMERIC(5,2),
+    ADD COLUMN IF NOT EXISTS fee_net        NUMERIC,
+    ADD COLUMN IF NOT EXISTS fee_vat        NUMERIC,
+    ADD COLUMN IF NOT EXISTS fee_tax_regime TEXT
+        CHECK (fee_tax_regime IN ('french_vat', 'reverse_charge', 'out_of_scope'));
+
+COMMIT;
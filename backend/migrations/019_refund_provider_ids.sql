@@ -0,0 +1,44 @@
+-- Migration 019: Link approved refunds to the payment provider's refund.
+-- Columns: refunds.stripe_refund_id, refunds.status ('processing'),
+--          refunds.stripe_payment_intent_id, refunds.processed_at.
+--
+-- Approving a refund marks it `processing` before the payment provider is
+-- called, so a retry after a failed or interrupted approval reuses the
+-- same idempotency key instead of refunding twice. Approval records the
+-- PaymentIntent it refunds and when the refund was issued, which date and
+-- match refunds in the accounting exports. Refunds approved before this
+-- migration are backfilled with their last update and the booking's first
+-- payment, the one approval has always refunded.
+
+BEGIN;
+
+-- ──────────────────────── Refunds ────────────────────────
+
+ALTER TABLE refunds ADD COLUMN IF NOT EXISTS stripe_refund_id TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_refunds_stripe_refund_id
+    ON refunds(stripe_refund_id) WHERE stripe_refund_id IS NOT NULL;
+
+ALTER TABLE refunds DROP CONSTRAINT IF EXISTS refunds_status_check;
+ALTER TABLE refunds ADD CONSTRAINT refunds_status_check
+    CHECK (status IN ('pending', 'processing', 'approved', 'rejected'));
+
+ALTER TABLE refunds
+    ADD COLUMN IF NOT EXISTS stripe_payment_intent_id TEXT,
+    ADD COLUMN IF NOT EXISTS processed_at             TIMESTAMPTZ;
+
+UPDATE refunds r
+SET stripe_payment_intent_id = (
+        SELECT t.stripe_payment_intent_id FROM transactions t
+        WHERE t.booking_id = r.booking_id AND t.stripe_payment_intent_id IS NOT NULL
+          AND t.deleted_at IS NULL
+        ORDER BY t.created_at ASC
+        LIMIT 1
+    ),
+    processed_at = r.updated_at
+WHERE r.status = 'approved' AND r.processed_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_refunds_processed_at
+    ON refunds(processed_at) WHERE status = 'approved';
+
+COMMIT;
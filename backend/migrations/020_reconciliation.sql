@@ -0,0 +1,62 @@
+-- Migration 020: Payout records and Stripe reconciliation.
+-- Tables: payouts, reconciliation_runs, reconciliation_discrepancies.
+--
+-- `payouts` records every transfer sent to a connected account so it can be
+-- matched against Stripe and deducted from the center's available balance.
+
+BEGIN;
+
+-- ──────────────────────── Payouts ────────────────────────
+
+CREATE TABLE IF NOT EXISTS payouts (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    center_id           UUID NOT NULL REFERENCES centers(id),
+    stripe_transfer_id  TEXT UNIQUE,
+    amount              NUMERIC NOT NULL CHECK (amount > 0),
+    currency            TEXT NOT NULL,
+    status              TEXT NOT NULL DEFAULT 'created'
+                        CHECK (status IN ('created', 'failed', 'reversed')),
+    requested_by        UUID REFERENCES profiles(id),
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_payouts_center_id ON payouts(center_id, created_at DESC);
+
+-- ──────────────────────── Reconciliation runs ────────────────────────
+
+CREATE TABLE IF NOT EXISTS reconciliation_runs (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    period_start        TIMESTAMPTZ NOT NULL,
+    period_end          TIMESTAMPTZ NOT NULL,
+    status              TEXT NOT NULL DEFAULT 'running'
+                        CHECK (status IN ('running', 'completed', 'failed')),
+    stripe_entries      INT NOT NULL DEFAULT 0,
+    local_entries       INT NOT NULL DEFAULT 0,
+    discrepancy_count   INT NOT NULL DEFAULT 0,
+    error               TEXT,
+    started_by          UUID REFERENCES profiles(id),
+    started_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at        TIMESTAMPTZ,
+    CHECK (period_end > period_start)
+);
+
+CREATE INDEX IF NOT EXISTS idx_reconciliation_runs_started_at ON reconciliation_runs(started_at DESC);
+
+-- ──────────────────────── Discrepancies ────────────────────────
+
+CREATE TABLE IF NOT EXISTS reconciliation_discrepancies (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    run_id          UUID NOT NULL REFERENCES reconciliation_runs(id) ON DELETE CASCADE,
+    kind            TEXT NOT NULL,
+    booking_id      UUID,
+    local_ref       TEXT,
+    stripe_ref      TEXT,
+    local_amount    NUMERIC,
+    stripe_amount   NUMERIC,
+    currency        TEXT,
+    details         TEXT NOT NULL DEFAULT ''
+);
+
+CREATE INDEX IF NOT EXISTS idx_reconciliation_discrepancies_run_id ON reconciliation_discrepancies(run_id);
+
+COMMIT;
@@ -0,0 +1,54 @@
+-- Migration 021: Monthly commission statements for centers, and exchange
+-- rates for the FEC export.
+-- Tables: commission_statements, exchange_rates.
+--
+-- A statement is generated once per center and period and stored as a
+-- frozen JSON snapshot; CSV/JSON downloads are rendered from the snapshot
+-- so later edits to bookings or payments never change an issued statement.
+--
+-- A FEC is kept in euros: movements in another currency are converted at
+-- the rate of their date, the original amount going to Montantdevise. Rates
+-- follow the ECB reference convention, units of `currency` for one euro,
+-- and are entered by admins (`/admin/accounting/exchange-rates`).
+
+BEGIN;
+
+-- ──────────────────────── Commission statements ────────────────────────
+
+CREATE TABLE IF NOT EXISTS commission_statements (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    center_id           UUID NOT NULL REFERENCES centers(id),
+    -- Inclusive first day and exclusive end of the period
+    period_start        DATE NOT NULL,
+    period_end          DATE NOT NULL,
+    currency            TEXT NOT NULL,
+    opening_balance     NUMERIC NOT NULL,
+    gross_total         NUMERIC NOT NULL,
+    commission_total    NUMERIC NOT NULL,
+    refund_total        NUMERIC NOT NULL,
+    payout_total        NUMERIC NOT NULL,
+    closing_balance     NUMERIC NOT NULL,
+    snapshot            JSONB NOT NULL,
+    generated_by        UUID REFERENCES profiles(id),
+    generated_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (period_end > period_start),
+    UNIQUE (center_id, period_start, period_end)
+);
+
+CREATE INDEX IF NOT EXISTS idx_commission_statements_center_id
+    ON commission_statements(center_id, period_start DESC);
+
+-- ──────────────────────── Exchange rates ────────────────────────
+
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    currency            TEXT NOT NULL CHECK (currency ~ '^[A-Z]{3}$' AND currency <> 'EUR'),
+    rate_date           DATE NOT NULL,
+    rate                NUMERIC(20, 10) NOT NULL CHECK (rate > 0),
+    updated_by          UUID REFERENCES profiles(id),
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (currency, rate_date)
+);
+
+COMMIT;
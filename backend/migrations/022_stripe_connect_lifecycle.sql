@@ -0,0 +1,29 @@
+-- Migration 022: Stripe Connect account lifecycle for centers.
+-- Columns: centers.stripe_payouts_enabled, stripe_details_submitted,
+-- stripe_requirements_currently_due, stripe_requirements_past_due,
+-- stripe_disabled_reason, stripe_requirements_deadline,
+-- stripe_status_synced_at, stripe_deauthorized_at.
+--
+-- `stripe_onboarding_complete` keeps mirroring `charges_enabled`. A center
+-- whose account was disconnected (`account.application.deauthorized`) has
+-- `stripe_deauthorized_at` set: checkout and payouts are refused until a new
+-- account is connected and Stripe enables charges on it.
+
+BEGIN;
+
+-- ──────────────────────── Connect status ────────────────────────
+
+ALTER TABLE centers
+    ADD COLUMN IF NOT EXISTS stripe_payouts_enabled            BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN IF NOT EXISTS stripe_details_submitted          BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN IF NOT EXISTS stripe_requirements_currently_due TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS stripe_requirements_past_due      TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS stripe_disabled_reason            TEXT,
+    ADD COLUMN IF NOT EXISTS stripe_requirements_deadline      TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS stripe_status_synced_at           TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS stripe_deauthorized_at            TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_centers_stripe_account_id
+    ON centers(stripe_account_id) WHERE stripe_account_id IS NOT NULL;
+
+COMMIT;
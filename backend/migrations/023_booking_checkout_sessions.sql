@@ -0,0 +1,22 @@
+-- Migration 023: Track the active Stripe Checkout Session of a booking.
+-- Columns: bookings.checkout_session_id, checkout_session_url,
+-- checkout_session_amount, checkout_session_expires_at.
+--
+-- `POST /bookings/{id}/checkout` returns the stored session while it is
+-- still valid for the current amount instead of opening a new one; a
+-- replaced or cancelled booking's session is expired through the Stripe API.
+
+BEGIN;
+
+-- ──────────────────────── Active checkout session ────────────────────────
+
+ALTER TABLE bookings
+    ADD COLUMN IF NOT EXISTS checkout_session_id         TEXT,
+    ADD COLUMN IF NOT EXISTS checkout_session_url        TEXT,
+    ADD COLUMN IF NOT EXISTS checkout_session_amount     NUMERIC,
+    ADD COLUMN IF NOT EXISTS checkout_session_expires_at TIMESTAMPTZ;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_bookings_checkout_session_id
+    ON bookings(checkout_session_id) WHERE checkout_session_id IS NOT NULL;
+
+COMMIT;
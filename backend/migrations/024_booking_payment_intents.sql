@@ -0,0 +1,38 @@
+-- Migration 024: Embedded payments with PaymentIntents.
+-- Columns: bookings.payment_intent_id.
+-- Indexes: one live transaction per PaymentIntent.
+--
+-- Both `checkout.session.completed` and `payment_intent.succeeded` may record
+-- the transaction of a payment; the unique index makes the second insert a
+-- no-op instead of a duplicate row.
+
+BEGIN;
+
+-- ──────────────────────── Booking PaymentIntent ────────────────────────
+
+ALTER TABLE bookings
+    ADD COLUMN IF NOT EXISTS payment_intent_id TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_bookings_payment_intent_id
+    ON bookings(payment_intent_id) WHERE payment_intent_id IS NOT NULL;
+
+-- ──────────────────────── Transaction idempotency ────────────────────────
+
+-- Soft-delete duplicates left by concurrent webhook deliveries, keeping the
+-- oldest row of each PaymentIntent.
+UPDATE transactions t
+SET deleted_at = NOW(), updated_at = NOW()
+WHERE t.deleted_at IS NULL
+  AND t.stripe_payment_intent_id IS NOT NULL
+  AND EXISTS (
+      SELECT 1 FROM transactions older
+      WHERE older.stripe_payment_intent_id = t.stripe_payment_intent_id
+        AND older.deleted_at IS NULL
+        AND (older.created_at, older.id) < (t.created_at, t.id)
+  );
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_transactions_payment_intent_unique
+    ON transactions(stripe_payment_intent_id)
+    WHERE stripe_payment_intent_id IS NOT NULL AND deleted_at IS NULL;
+
+COMMIT;
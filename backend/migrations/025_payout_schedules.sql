@@ -0,0 +1,70 @@
+-- Migration 025: Automatic scheduled payouts.
+-- Tables: center_payout_schedules, disputes.
+-- Columns: payouts.period_key, transactions.stripe_destination_account.
+--
+-- The payouts job (`GET /api/v1/jobs/payouts`) transfers the available
+-- balance of every center with a weekly or monthly schedule once per period.
+-- `(center_id, period_key)` is unique, so re-running the job never creates a
+-- second transfer for the same period. Centers with an open dispute are
+-- skipped until Stripe closes it.
+--
+-- A payment made with `transfer_data.destination` is credited to the
+-- center's connected account by Stripe itself: it must not be paid out a
+-- second time from the platform balance. The webhook records the
+-- destination from the PaymentIntent's `destination_account` metadata (set
+-- at checkout) or its `transfer_data`. Rows recorded before this migration
+-- carry no destination and count as platform charges.
+
+BEGIN;
+
+-- ──────────────────────── Payout schedules ────────────────────────
+
+CREATE TABLE IF NOT EXISTS center_payout_schedules (
+    center_id           UUID PRIMARY KEY REFERENCES centers(id),
+    frequency           TEXT NOT NULL DEFAULT 'manual'
+                        CHECK (frequency IN ('manual', 'weekly', 'monthly')),
+    -- ISO weekday, 1 = Monday (weekly schedules)
+    weekday             SMALLINT CHECK (weekday BETWEEN 1 AND 7),
+    -- Capped at 28 so every month has the day (monthly schedules)
+    day_of_month        SMALLINT CHECK (day_of_month BETWEEN 1 AND 28),
+    minimum_amount      NUMERIC NOT NULL DEFAULT 0 CHECK (minimum_amount >= 0),
+    currency            TEXT NOT NULL DEFAULT 'EUR',
+    updated_by          UUID REFERENCES profiles(id),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (frequency <> 'weekly' OR weekday IS NOT NULL),
+    CHECK (frequency <> 'monthly' OR day_of_month IS NOT NULL)
+);
+
+-- ──────────────────────── Scheduled payouts ────────────────────────
+
+-- ISO week (`2026-W42`) or month (`2026-10`) of an automatic payout;
+-- NULL for payouts requested by the owner.
+ALTER TABLE payouts
+    ADD COLUMN IF NOT EXISTS period_key TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_payouts_center_period
+    ON payouts(center_id, period_key) WHERE period_key IS NOT NULL;
+
+-- ──────────────────────── Destination charges ────────────────────────
+
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS stripe_destination_account TEXT;
+
+-- ──────────────────────── Disputes ────────────────────────
+
+CREATE TABLE IF NOT EXISTS disputes (
+    id                          UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    stripe_dispute_id           TEXT NOT NULL UNIQUE,
+    stripe_payment_intent_id    TEXT,
+    booking_id                  UUID REFERENCES bookings(id),
+    center_id                   UUID REFERENCES centers(id),
+    amount                      NUMERIC NOT NULL,
+    currency                    TEXT NOT NULL,
+    reason                      TEXT NOT NULL,
+    status                      TEXT NOT NULL,
+    created_at                  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at                  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_disputes_center_status ON disputes(center_id, status);
+
+COMMIT;
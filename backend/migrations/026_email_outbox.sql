@@ -0,0 +1,40 @@
+-- Migration 026: Durable email outbox.
+-- Tables: email_outbox.
+--
+-- Handlers render the email and insert it in the same transaction as the
+-- change it reports. The outbox job (`GET /api/v1/jobs/email-outbox`) sends
+-- due messages through SMTP, retries failures with exponential backoff and
+-- moves a message to `dead` after `max_attempts`; admins can resend it.
+
+BEGIN;
+
+-- ──────────────────────── Email outbox ────────────────────────
+
+CREATE TABLE IF NOT EXISTS email_outbox (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Template name, NULL for free-form messages (contact form)
+    template            TEXT,
+    locale              TEXT,
+    profile_id          UUID REFERENCES profiles(id),
+    to_address          TEXT NOT NULL,
+    reply_to            TEXT,
+    subject             TEXT NOT NULL,
+    body                TEXT NOT NULL,
+    status              TEXT NOT NULL DEFAULT 'pending'
+                        CHECK (status IN ('pending', 'sending', 'sent', 'dead')),
+    attempts            INT NOT NULL DEFAULT 0,
+    max_attempts        INT NOT NULL DEFAULT 8,
+    next_attempt_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- A `sending` message whose lock expired is claimed again
+    locked_until        TIMESTAMPTZ,
+    last_error          TEXT,
+    sent_at             TIMESTAMPTZ,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_outbox_due
+    ON email_outbox(next_attempt_at) WHERE status IN ('pending', 'sending');
+CREATE INDEX IF NOT EXISTS idx_email_outbox_status ON email_outbox(status, created_at DESC);
+
+COMMIT;
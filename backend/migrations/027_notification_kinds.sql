@@ -0,0 +1,17 @@
+-- Migration 027: User-facing notification center.
+-- Columns: notifications.kind.
+--
+-- Notifications are created by booking, review, payout and membership
+-- events; `kind` identifies the event so clients can pick an icon, and
+-- `link` holds the frontend deep link (without locale prefix).
+
+BEGIN;
+
+-- ──────────────────────── Notifications ────────────────────────
+
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS kind TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_notifications_user_created
+    ON notifications(user_id, created_at DESC);
+
+COMMIT;
@@ -0,0 +1,39 @@
+-- Migration 028: Dive reminders and pre-dive checklists.
+-- Tables: center_reminder_settings.
+-- Columns: centers.timezone, bookings.reminder_sent_at.
+--
+-- The reminders job (`GET /api/v1/jobs/dive-reminders`) emails and notifies
+-- the client of every confirmed booking whose dive starts within the
+-- center's `hours_before` window. The dive start is `booking_date +
+-- time_slot` in the center's timezone. `reminder_sent_at` is set in the same
+-- transaction that enqueues the reminder, so each booking gets exactly one.
+
+BEGIN;
+
+-- ──────────────────────── Centers ────────────────────────
+
+-- IANA timezone name (`pg_timezone_names`)
+ALTER TABLE centers
+    ADD COLUMN IF NOT EXISTS timezone TEXT NOT NULL DEFAULT 'Europe/Paris';
+
+-- ──────────────────────── Reminder settings ────────────────────────
+
+CREATE TABLE IF NOT EXISTS center_reminder_settings (
+    center_id           UUID PRIMARY KEY REFERENCES centers(id),
+    enabled             BOOLEAN NOT NULL DEFAULT true,
+    hours_before        SMALLINT NOT NULL DEFAULT 24 CHECK (hours_before BETWEEN 1 AND 168),
+    -- Items shown after the certification card and logbook
+    checklist           TEXT[] NOT NULL DEFAULT '{}',
+    updated_by          UUID REFERENCES profiles(id),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- ──────────────────────── Bookings ────────────────────────
+
+ALTER TABLE bookings
+    ADD COLUMN IF NOT EXISTS reminder_sent_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_bookings_reminder_due
+    ON bookings(booking_date) WHERE status = 'confirmed' AND reminder_sent_at IS NULL;
+
+COMMIT;
@@ -0,0 +1,105 @@
+-- Migration 029: Real-time events over LISTEN/NOTIFY.
+-- Functions: notify_app_event, trg_booking_events, trg_transaction_events,
+--            trg_review_events, trg_notification_events, trg_membership_events.
+--
+-- Triggers publish small JSON payloads on the `app_events` channel. Every
+-- API instance listens on it and forwards the events to its SSE clients
+-- (`GET /api/v1/events`), so a change made through any instance, or
+-- directly in the database, reaches every dashboard. Payloads only carry
+-- ids: clients refetch what they display.
+--
+-- Payload: {"type": "...", "id": "...", "center_id": "...", "user_id": "..."}
+
+BEGIN;
+
+CREATE OR REPLACE FUNCTION notify_app_event(event_type TEXT, subject_id UUID, center_id UUID, user_id UUID)
+RETURNS void AS $$
+    SELECT pg_notify('app_events', json_build_object(
+        'type', event_type, 'id', subject_id, 'center_id', center_id, 'user_id', user_id
+    )::text);
+$$ LANGUAGE sql;
+
+-- ──────────────────────── Bookings ────────────────────────
+
+CREATE OR REPLACE FUNCTION trg_booking_events() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        PERFORM notify_app_event('booking.created', NEW.id, NEW.center_id, NEW.client_id);
+    ELSIF NEW.status IS DISTINCT FROM OLD.status AND NEW.status::text IN ('confirmed', 'cancelled') THEN
+        PERFORM notify_app_event('booking.' || NEW.status::text, NEW.id, NEW.center_id, NEW.client_id);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS booking_events ON bookings;
+CREATE TRIGGER booking_events
+    AFTER INSERT OR UPDATE OF status ON bookings
+    FOR EACH ROW EXECUTE FUNCTION trg_booking_events();
+
+-- ──────────────────────── Payments ────────────────────────
+
+CREATE OR REPLACE FUNCTION trg_transaction_events() RETURNS trigger AS $$
+BEGIN
+    IF NEW.status::text = 'succeeded'
+       AND (TG_OP = 'INSERT' OR OLD.status IS DISTINCT FROM NEW.status) THEN
+        PERFORM notify_app_event('payment.received', b.id, b.center_id, b.client_id)
+        FROM bookings b WHERE b.id = NEW.booking_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS transaction_events ON transactions;
+CREATE TRIGGER transaction_events
+    AFTER INSERT OR UPDATE OF status ON transactions
+    FOR EACH ROW EXECUTE FUNCTION trg_transaction_events();
+
+-- ──────────────────────── Reviews ────────────────────────
+
+CREATE OR REPLACE FUNCTION trg_review_events() RETURNS trigger AS $$
+BEGIN
+    PERFORM notify_app_event('review.created', NEW.id, NEW.center_id, NEW.client_id);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS review_events ON reviews;
+CREATE TRIGGER review_events
+    AFTER INSERT ON reviews
+    FOR EACH ROW EXECUTE FUNCTION trg_review_events();
+
+-- ──────────────────────── Notifications ────────────────────────
+
+CREATE OR REPLACE FUNCTION trg_notification_events() RETURNS trigger AS $$
+BEGIN
+    PERFORM notify_app_event('notification.created', NEW.id, NULL, NEW.user_id);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS notification_events ON notifications;
+CREATE TRIGGER notification_events
+    AFTER INSERT ON notifications
+    FOR EACH ROW EXECUTE FUNCTION trg_notification_events();
+
+-- ──────────────────────── Memberships ────────────────────────
+
+-- Lets open streams pick up centers the user joined or left.
+CREATE OR REPLACE FUNCTION trg_membership_events() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        PERFORM notify_app_event('membership.changed', OLD.id, OLD.fk_center, OLD.fk_profile);
+    ELSE
+        PERFORM notify_app_event('membership.changed', NEW.id, NEW.fk_center, NEW.fk_profile);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS membership_events ON tli_pr_ce;
+CREATE TRIGGER membership_events
+    AFTER INSERT OR DELETE OR UPDATE OF role_in_center ON tli_pr_ce
+    FOR EACH ROW EXECUTE FUNCTION trg_membership_events();
+
+COMMIT;
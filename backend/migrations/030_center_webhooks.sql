@@ -0,0 +1,149 @@
+-- Migration 030: Outgoing signed webhooks for center integrations.
+-- Tables: webhook_endpoints, webhook_deliveries.
+-- Functions: enqueue_center_webhooks, trg_booking_webhooks, trg_transaction_webhooks.
+--
+-- Centers register HTTPS endpoints with the event types they want. Triggers
+-- insert one delivery per matching endpoint in the same transaction as the
+-- booking or payment change. The webhooks job (`GET /api/v1/jobs/webhooks`)
+-- POSTs the JSON payload signed with the endpoint secret
+-- (`EviDive-Signature: t=<unix>,v1=<hex HMAC-SHA256 of "t.body">`), retries
+-- failures with exponential backoff and keeps every attempt's outcome in
+-- the delivery log.
+
+BEGIN;
+
+-- ──────────────────────── Endpoints ────────────────────────
+
+CREATE TABLE IF NOT EXISTS webhook_endpoints (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    center_id           UUID NOT NULL REFERENCES centers(id),
+    url                 TEXT NOT NULL CHECK (url LIKE 'https://%'),
+    description         TEXT,
+    event_types         TEXT[] NOT NULL CHECK (cardinality(event_types) > 0),
+    secret              TEXT NOT NULL,
+    is_active           BOOLEAN NOT NULL DEFAULT true,
+    created_by          UUID REFERENCES profiles(id),
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    deleted_at          TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_endpoints_center
+    ON webhook_endpoints(center_id) WHERE deleted_at IS NULL;
+
+-- ──────────────────────── Deliveries ────────────────────────
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    endpoint_id         UUID NOT NULL REFERENCES webhook_endpoints(id),
+    -- Shared by the deliveries of one event to several endpoints
+    event_id            UUID NOT NULL,
+    event_type          TEXT NOT NULL,
+    payload             JSONB NOT NULL,
+    -- Set on manual redeliveries
+    redelivery_of       UUID REFERENCES webhook_deliveries(id),
+    status              TEXT NOT NULL DEFAULT 'pending'
+                        CHECK (status IN ('pending', 'sending', 'succeeded', 'failed')),
+    attempts            INT NOT NULL DEFAULT 0,
+    max_attempts        INT NOT NULL DEFAULT 8,
+    next_attempt_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_until        TIMESTAMPTZ,
+    response_status     INT,
+    -- First KB of the last response
+    response_body       TEXT,
+    last_error          TEXT,
+    delivered_at        TIMESTAMPTZ,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due
+    ON webhook_deliveries(next_attempt_at) WHERE status IN ('pending', 'sending');
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_endpoint
+    ON webhook_deliveries(endpoint_id, created_at DESC);
+
+-- ──────────────────────── Enqueue ────────────────────────
+
+CREATE OR REPLACE FUNCTION enqueue_center_webhooks(center UUID, event_type TEXT, data JSONB)
+RETURNS void AS $$
+DECLARE
+    event_id UUID := gen_random_uuid();
+BEGIN
+    INSERT INTO webhook_deliveries (endpoint_id, event_id, event_type, payload)
+    SELECT e.id, event_id, event_type, jsonb_build_object(
+        'id', event_id,
+        'type', event_type,
+        'created_at', NOW(),
+        'center_id', center,
+        'data', data
+    )
+    FROM webhook_endpoints e
+    WHERE e.center_id = center AND e.is_active AND e.deleted_at IS NULL
+      AND event_type = ANY(e.event_types);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION trg_booking_webhooks() RETURNS trigger AS $$
+DECLARE
+    booking JSONB := jsonb_build_object(
+        'id', NEW.id,
+        'service_id', NEW.service_id,
+        'booking_date', NEW.booking_date,
+        'time_slot', to_char(NEW.time_slot, 'HH24:MI'),
+        'participants', NEW.participants,
+        'total_price', NEW.total_price,
+        'currency', NEW.currency,
+        'status', NEW.status::text
+    );
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        PERFORM enqueue_center_webhooks(NEW.center_id, 'booking.created', jsonb_build_object('booking', booking));
+    ELSIF NEW.status IS DISTINCT FROM OLD.status
+          AND NEW.status::text IN ('confirmed', 'cancelled', 'completed') THEN
+        PERFORM enqueue_center_webhooks(NEW.center_id, 'booking.' || NEW.status::text, jsonb_build_object('booking', booking));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS booking_webhooks ON bookings;
+CREATE TRIGGER booking_webhooks
+    AFTER INSERT OR UPDATE OF status ON bookings
+    FOR EACH ROW EXECUTE FUNCTION trg_booking_webhooks();
+
+CREATE OR REPLACE FUNCTION trg_transaction_webhooks() RETURNS trigger AS $$
+DECLARE
+    center UUID;
+    event_type TEXT;
+BEGIN
+    IF TG_OP = 'UPDATE' AND NEW.status IS NOT DISTINCT FROM OLD.status THEN
+        RETURN NULL;
+    END IF;
+    event_type := CASE NEW.status::text
+        WHEN 'succeeded' THEN 'payment.received'
+        WHEN 'refunded' THEN 'payment.refunded'
+    END;
+    IF event_type IS NULL THEN
+        RETURN NULL;
+    END IF;
+
+    SELECT b.center_id INTO center FROM bookings b WHERE b.id = NEW.booking_id;
+    PERFORM enqueue_center_webhooks(center, event_type, jsonb_build_object('payment', jsonb_build_object(
+        'id', NEW.id,
+        'booking_id', NEW.booking_id,
+        'amount', NEW.amount,
+        'vendor_amount', NEW.vendor_amount,
+        'platform_fee', NEW.platform_fee,
+        'currency', NEW.currency,
+        'status', NEW.status::text
+    )));
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS transaction_webhooks ON transactions;
+CREATE TRIGGER transaction_webhooks
+    AFTER INSERT OR UPDATE OF status ON transactions
+    FOR EACH ROW EXECUTE FUNCTION trg_transaction_webhooks();
+
+COMMIT;
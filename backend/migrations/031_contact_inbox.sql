@@ -0,0 +1,52 @@
+-- Migration 031: Contact form inbox.
+-- Tables: contact_messages, contact_replies.
+--
+-- Contact form submissions are stored instead of only being emailed. A
+-- message is addressed to the platform (center_id NULL, answered from the
+-- admin inbox) or to one center (answered by its members). Replies are
+-- emailed to the sender through the outbox and kept as the message thread.
+-- The IP hash and email columns back the per-IP and per-email throttling of
+-- `POST /api/v1/contact`.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS contact_messages (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Ticket number shown in reply subjects
+    reference           BIGINT GENERATED ALWAYS AS IDENTITY UNIQUE,
+    center_id           UUID REFERENCES centers(id),
+    name                TEXT NOT NULL,
+    email               TEXT NOT NULL,
+    subject             TEXT NOT NULL,
+    message             TEXT NOT NULL,
+    status              TEXT NOT NULL DEFAULT 'new'
+                        CHECK (status IN ('new', 'replied', 'closed')),
+    -- SHA-256 of the client IP, never the address itself
+    ip_hash             TEXT,
+    user_agent          TEXT,
+    last_reply_at       TIMESTAMPTZ,
+    closed_at           TIMESTAMPTZ,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_contact_messages_inbox
+    ON contact_messages(center_id, status, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_contact_messages_ip
+    ON contact_messages(ip_hash, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_contact_messages_email
+    ON contact_messages(lower(email), created_at DESC);
+
+CREATE TABLE IF NOT EXISTS contact_replies (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    message_id          UUID NOT NULL REFERENCES contact_messages(id) ON DELETE CASCADE,
+    author_id           UUID REFERENCES profiles(id),
+    body                TEXT NOT NULL,
+    email_id            UUID REFERENCES email_outbox(id),
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_contact_replies_message
+    ON contact_replies(message_id, created_at);
+
+COMMIT;
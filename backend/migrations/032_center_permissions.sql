@@ -0,0 +1,24 @@
+-- Migration 032: Per-member permission overrides for center roles.
+-- Columns: tli_pr_ce.granted_capabilities, tli_pr_ce.revoked_capabilities.
+--
+-- Each role has a default set of capabilities (defined in the API, see
+-- `services::permissions`). A member's own grants are added to the role
+-- defaults and revocations removed from them; owners always keep every
+-- capability.
+
+BEGIN;
+
+ALTER TABLE tli_pr_ce
+    ADD COLUMN IF NOT EXISTS granted_capabilities TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS revoked_capabilities TEXT[] NOT NULL DEFAULT '{}';
+
+ALTER TABLE tli_pr_ce DROP CONSTRAINT IF EXISTS tli_pr_ce_capabilities_check;
+ALTER TABLE tli_pr_ce ADD CONSTRAINT tli_pr_ce_capabilities_check CHECK (
+    (granted_capabilities || revoked_capabilities) <@ ARRAY[
+        'view_bookings', 'manage_bookings', 'manage_services', 'manage_schedule',
+        'manage_staff', 'manage_members', 'view_finance', 'manage_payouts',
+        'manage_center', 'manage_integrations', 'manage_reviews', 'manage_messages'
+    ]::TEXT[]
+);
+
+COMMIT;
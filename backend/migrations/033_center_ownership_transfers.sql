@@ -0,0 +1,45 @@
+-- Migration 033: Two-step center ownership transfer.
+-- Tables: center_ownership_transfers.
+-- Columns: centers.stripe_reverification_required_at.
+--
+-- The owner offers the center to an existing member, who accepts or
+-- declines. Acceptance swaps `centers.owner_id` and the `tli_pr_ce` roles in
+-- one transaction. Every offer and its outcome stay in the table, as the
+-- state of the workflow: rows are updated as offers are answered and keep
+-- neither IP nor request id. The audit trail is `audit_log`, where the
+-- handlers record the offer, the answer and the owner and role changes.
+--
+-- The Stripe Connect account belongs to the previous owner's legal entity:
+-- after a transfer payments are collected by the platform and payouts are
+-- paused until the new owner connects their own account.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS center_ownership_transfers (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    center_id           UUID NOT NULL REFERENCES centers(id),
+    from_profile        UUID NOT NULL REFERENCES profiles(id),
+    to_profile          UUID NOT NULL REFERENCES profiles(id),
+    status              TEXT NOT NULL DEFAULT 'pending'
+                        CHECK (status IN ('pending', 'accepted', 'declined', 'cancelled', 'expired')),
+    message             TEXT,
+    -- Stripe account of the center when the transfer was accepted
+    previous_stripe_account_id TEXT,
+    expires_at          TIMESTAMPTZ NOT NULL DEFAULT NOW() + INTERVAL '7 days',
+    responded_at        TIMESTAMPTZ,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (from_profile <> to_profile)
+);
+
+-- At most one open offer per center
+CREATE UNIQUE INDEX IF NOT EXISTS idx_ownership_transfers_pending
+    ON center_ownership_transfers(center_id) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_ownership_transfers_recipient
+    ON center_ownership_transfers(to_profile, status);
+CREATE INDEX IF NOT EXISTS idx_ownership_transfers_center
+    ON center_ownership_transfers(center_id, created_at DESC);
+
+ALTER TABLE centers
+    ADD COLUMN IF NOT EXISTS stripe_reverification_required_at TIMESTAMPTZ;
+
+COMMIT;
@@ -0,0 +1,36 @@
+-- Migration 034: Email invitations to join a center's team.
+-- Tables: center_invitations.
+--
+-- People without an account are invited by email with the role they will
+-- get. The link holds a random token of which only the SHA-256 is stored;
+-- resending replaces it. Accepting while signed in with the invited address
+-- creates the `tli_pr_ce` row.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS center_invitations (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    center_id           UUID NOT NULL REFERENCES centers(id),
+    email               TEXT NOT NULL,
+    role_in_center      TEXT NOT NULL,
+    token_hash          TEXT NOT NULL UNIQUE,
+    status              TEXT NOT NULL DEFAULT 'pending'
+                        CHECK (status IN ('pending', 'accepted', 'revoked', 'expired')),
+    invited_by          UUID REFERENCES profiles(id),
+    locale              TEXT NOT NULL DEFAULT 'en',
+    expires_at          TIMESTAMPTZ NOT NULL,
+    send_count          INT NOT NULL DEFAULT 1,
+    last_sent_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    accepted_by         UUID REFERENCES profiles(id),
+    accepted_at         TIMESTAMPTZ,
+    revoked_at          TIMESTAMPTZ,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One open invitation per address and center
+CREATE UNIQUE INDEX IF NOT EXISTS idx_center_invitations_pending
+    ON center_invitations(center_id, lower(email)) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_center_invitations_center
+    ON center_invitations(center_id, created_at DESC);
+
+COMMIT;
@@ -0,0 +1,50 @@
+-- Migration 035: Audit log of administrative and center mutations.
+-- Tables: audit_log.
+-- Functions: audit_log_append_only.
+--
+-- One row per mutation: who (actor_id, kept after the profile is deleted),
+-- what (action, entity_type, entity_id, center_id), the fields that changed
+-- (before/after) and where from (ip, request_id). Rows are never updated or
+-- deleted, so snapshots leave personal data out from the start (names,
+-- contact details, notes, messages): an account erasure has nothing to
+-- remove here.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id              BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    actor_id        UUID,
+    action          TEXT NOT NULL,
+    entity_type     TEXT NOT NULL,
+    entity_id       TEXT,
+    center_id       UUID,
+    before          JSONB,
+    after           JSONB,
+    ip              INET,
+    request_id      TEXT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_created ON audit_log(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log(actor_id, id DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log(entity_type, entity_id, id DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_center ON audit_log(center_id, id DESC) WHERE center_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_audit_log_action ON audit_log(action text_pattern_ops);
+
+CREATE OR REPLACE FUNCTION audit_log_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_audit_log_append_only ON audit_log;
+CREATE TRIGGER trg_audit_log_append_only
+    BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION audit_log_append_only();
+
+DROP TRIGGER IF EXISTS trg_audit_log_no_truncate ON audit_log;
+CREATE TRIGGER trg_audit_log_no_truncate
+    BEFORE TRUNCATE ON audit_log
+    FOR EACH STATEMENT EXECUTE FUNCTION audit_log_append_only();
+
+COMMIT;
@@ -0,0 +1,28 @@
+-- Migration 036: User bans.
+-- Tables: user_bans.
+--
+-- A ban keeps a user from booking, from reviewing, or from using the
+-- platform at all, with a reason and an optional expiry. Bans are lifted
+-- rather than deleted so the history stays. Blacklisting used to soft-delete
+-- the profile, which the API never checked for signed-in users.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS user_bans (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id             UUID NOT NULL REFERENCES profiles(id),
+    scope               TEXT NOT NULL CHECK (scope IN ('booking', 'review', 'platform')),
+    reason              TEXT NOT NULL,
+    -- NULL = until lifted
+    expires_at          TIMESTAMPTZ,
+    banned_by           UUID REFERENCES profiles(id),
+    lifted_at           TIMESTAMPTZ,
+    lifted_by           UUID REFERENCES profiles(id),
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_bans_user ON user_bans(user_id, created_at DESC);
+-- Bans still in force (expiry is checked at read time)
+CREATE INDEX IF NOT EXISTS idx_user_bans_open ON user_bans(user_id, scope) WHERE lifted_at IS NULL;
+
+COMMIT;
@@ -0,0 +1,13 @@
+-- Migration 037: Account erasure.
+-- Columns: profiles.erased_at.
+--
+-- Erasing an account anonymizes the profile and the personal data attached
+-- to it (notes, review comments, contact messages, email copies) instead of
+-- deleting rows: bookings, invoices, refunds and payouts are kept for
+-- accounting. `erased_at` tells an erased profile from a soft-deleted one.
+
+BEGIN;
+
+ALTER TABLE profiles ADD COLUMN IF NOT EXISTS erased_at TIMESTAMPTZ;
+
+COMMIT;
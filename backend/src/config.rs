@@ -17,6 +17,13 @@ pub struct Config {
     pub smtp_user: Option<String>,
     pub smtp_pass: Option<String>,
     pub smtp_from: Option<String>,
+    /// Bearer token expected on `/jobs/*` requests (Vercel Cron sends
+    /// `Authorization: Bearer $CRON_SECRET`). Jobs are disabled when unset.
+    pub cron_secret: Option<String>,
+    /// HMAC key of the client IP hashes stored with contact messages.
+    /// Contact messages are stored without an IP hash, and throttled per
+    /// email only, when unset.
+    pub ip_hash_secret: Option<String>,
 }
 
 impl Config {
@@ -36,6 +43,8 @@ impl Config {
             smtp_user: optional_env("SMTP_USER"),
             smtp_pass: optional_env("SMTP_PASS"),
             smtp_from: optional_env("SMTP_FROM"),
+            cron_secret: optional_env("CRON_SECRET"),
+            ip_hash_secret: optional_env("IP_HASH_SECRET"),
         })
     }
 
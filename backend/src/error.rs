@@ -16,8 +16,12 @@ pub enum AppError {
     Unauthorized,
     /// 403 - insufficient permissions
     Forbidden,
+    /// 403 - the user is banned; the message says why and until when
+    Banned(String),
     /// 409 - conflict (duplicate, etc.)
     Conflict(String),
+    /// 429 - rate limit reached
+    TooManyRequests(String),
     /// 500 - internal server error (details hidden from client)
     Internal(String),
 }
@@ -29,7 +33,9 @@ impl IntoResponse for AppError {
             Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             Self::Unauthorized => (StatusCode::UNAUTHORIZED, "Authentication required".to_owned()),
             Self::Forbidden => (StatusCode::FORBIDDEN, "Insufficient permissions".to_owned()),
+            Self::Banned(msg) => (StatusCode::FORBIDDEN, msg),
             Self::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            Self::TooManyRequests(msg) => (StatusCode::TOO_MANY_REQUESTS, msg),
             Self::Internal(detail) => {
                 tracing::error!(error = %detail, "Internal server error");
                 (
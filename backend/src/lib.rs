@@ -18,13 +18,12 @@ use std::time::Duration;
 
 use axum::http::{header, HeaderName, Method};
 use axum::Router;
-use jsonwebtoken::{Algorithm, DecodingKey};
 use lettre::{AsyncSmtpTransport, Tokio1Executor};
 use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
-use stripe::Client as StripeClient;
 use tower_http::compression::CompressionLayer;
 use axum::http::HeaderValue;
 use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::set_header::SetResponseHeaderLayer;
 use tower_http::trace::TraceLayer;
 
@@ -33,10 +32,10 @@ pub struct AppState {
     pub pool: sqlx::PgPool,
     pub config: Config,
     pub mailer: Option<AsyncSmtpTransport<Tokio1Executor>>,
-    pub stripe: StripeClient,
-    pub jwt_decoding_key: DecodingKey,
-    pub jwt_algorithm: Algorithm,
+    pub payments: Arc<dyn services::payment_gateway::PaymentGateway>,
+    pub jwks: middleware::jwks::JwksCache,
     pub jwt_issuer: String,
+    pub events: services::events::EventHub,
 }
 
 /// Create a lazy connection pool — does NOT connect at creation time.
@@ -86,11 +85,13 @@ pub fn create_pool_lazy(database_url: &str) -> anyhow::Result<sqlx::PgPool> {
 /// Skips rate limiting (Vercel handles that at the edge) and migrations
 /// (run separately via `sqlx migrate run`).
 pub async fn create_app(pool: sqlx::PgPool, config: Config) -> anyhow::Result<Router> {
-    // Fetch JWKS from Supabase for JWT verification (ES256 / ECC P-256)
-    let (jwt_decoding_key, jwt_algorithm, _kid) =
-        middleware::auth::fetch_jwks(&config.jwks_url())
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to fetch JWKS: {e}"))?;
+    // Load the Supabase signing keys for JWT verification (ES256 / ECC P-256).
+    // An unreachable JWKS endpoint must not fail a cold start: the first
+    // authenticated request retries.
+    let jwks = middleware::jwks::JwksCache::new(config.jwks_url());
+    if let Err(e) = jwks.refresh().await {
+        tracing::warn!(error = %e, "JWKS unavailable at startup, will retry on first authenticated request");
+    }
 
     let jwt_issuer = format!(
         "{}/auth/v1",
@@ -113,7 +114,10 @@ pub async fn create_app(pool: sqlx::PgPool, config: Config) -> anyhow::Result<Ro
         tracing::info!("SMTP not configured — email features disabled");
         None
     };
-    let stripe = services::stripe::build_stripe_client(&config);
+    if config.ip_hash_secret.is_none() {
+        tracing::warn!("IP_HASH_SECRET not set — contact messages are throttled per email only");
+    }
+    let payments = Arc::new(services::stripe::StripeGateway::from_config(&config));
 
     // Build CORS layer
     let cors = build_cors(&config);
@@ -122,10 +126,10 @@ pub async fn create_app(pool: sqlx::PgPool, config: Config) -> anyhow::Result<Ro
         pool,
         config,
         mailer,
-        stripe,
-        jwt_decoding_key,
-        jwt_algorithm,
+        payments,
+        jwks,
         jwt_issuer,
+        events: services::events::EventHub::new(),
     });
 
     let app = Router::new()
@@ -152,7 +156,11 @@ pub async fn create_app(pool: sqlx::PgPool, config: Config) -> anyhow::Result<Ro
             HeaderValue::from_static("max-age=63072000; includeSubDomains; preload"),
         ))
         .layer(CompressionLayer::new())
+        // Every request gets an X-Request-Id (the caller's, if sent), echoed
+        // in the response and recorded in the audit log.
+        .layer(PropagateRequestIdLayer::x_request_id())
         .layer(TraceLayer::new_for_http())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
         .with_state(state);
 
     Ok(app)
@@ -65,7 +65,7 @@ async fn main() {
     // Build the full application router via the shared create_app function
     let app = evidive_api::create_app(pool, config)
         .await
-        // SAFETY: If the app cannot build (e.g. JWKS unreachable), the server cannot function.
+        // SAFETY: If the app cannot build, the server cannot function.
         .expect("Failed to build application");
 
     // Rate limiting per IP (production only).
@@ -0,0 +1,70 @@
+//! Request details for the audit log.
+
+use std::convert::Infallible;
+use std::net::IpAddr;
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::middleware::client_ip::client_ip;
+use crate::services::audit::{self, Entity, NewEntry};
+
+/// Where a mutation comes from: the client IP and the `X-Request-Id` set by
+/// the request id layer (or the caller).
+#[derive(Debug, Clone, Default)]
+pub struct Audit {
+    pub ip: Option<IpAddr>,
+    pub request_id: Option<String>,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Audit {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            ip: client_ip(&parts.headers),
+            request_id: parts
+                .headers
+                .get("x-request-id")
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.chars().take(100).collect()),
+        })
+    }
+}
+
+impl Audit {
+    /// Record that `actor` applied `action` to row `id` of `entity`, which
+    /// was `before` and is now read back from the database.
+    pub async fn record(
+        &self,
+        conn: &mut sqlx::PgConnection,
+        actor: Uuid,
+        action: &str,
+        entity: Entity,
+        id: impl std::fmt::Display,
+        before: Option<Value>,
+    ) -> Result<i64, AppError> {
+        let id = id.to_string();
+        let after = audit::snapshot(&mut *conn, entity, &id).await?;
+        let center_id = audit::center_of(entity, &id, after.as_ref().or(before.as_ref()));
+        let (before, after) = audit::changes(before, after);
+        audit::insert(
+            &mut *conn,
+            &NewEntry {
+                actor_id: Some(actor),
+                action: action.to_owned(),
+                entity,
+                entity_id: Some(id),
+                center_id,
+                before,
+                after,
+                ip: self.ip,
+                request_id: self.request_id.clone(),
+            },
+        )
+        .await
+    }
+}
@@ -2,11 +2,13 @@ use std::sync::Arc;
 
 use axum::extract::FromRequestParts;
 use axum::http::request::Parts;
-use jsonwebtoken::{decode, jwk, Algorithm, DecodingKey, Validation};
+use jsonwebtoken::{decode, decode_header, Validation};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::error::AppError;
+use crate::services::bans::{self, Scope};
+use crate::services::permissions::{Capability, MemberPermissions};
 use crate::AppState;
 
 /// JWT claims extracted from the `Authorization: Bearer <token>` header.
@@ -23,7 +25,8 @@ pub struct Claims {
     pub iat: usize,
 }
 
-/// Extractor that validates the JWT from the Authorization header.
+/// Extractor that validates the JWT from the Authorization header, and
+/// rejects deleted accounts and users banned from the platform.
 /// Use as a handler parameter: `AuthUser(claims): AuthUser`
 pub struct AuthUser(pub Claims);
 
@@ -44,17 +47,41 @@ impl FromRequestParts<Arc<AppState>> for AuthUser {
             .strip_prefix("Bearer ")
             .ok_or(AppError::Unauthorized)?;
 
-        let mut validation = Validation::new(state.jwt_algorithm);
+        // The token names its signing key; an unknown one reloads the JWKS.
+        let header = decode_header(token).map_err(|e| {
+            tracing::warn!(error = %e, "JWT header is invalid");
+            AppError::Unauthorized
+        })?;
+        let signing_key = state
+            .jwks
+            .key_for(header.kid.as_deref())
+            .await
+            .ok_or_else(|| {
+                tracing::warn!(kid = ?header.kid, "No JWKS key for JWT");
+                AppError::Unauthorized
+            })?;
+
+        let mut validation = Validation::new(signing_key.algorithm);
         validation.set_audience(&["authenticated"]);
         validation.set_issuer(&[&state.jwt_issuer]);
 
         let token_data =
-            decode::<Claims>(token, &state.jwt_decoding_key, &validation).map_err(|e| {
+            decode::<Claims>(token, &signing_key.key, &validation).map_err(|e| {
                 tracing::warn!(error = %e, "JWT validation failed");
                 AppError::Unauthorized
             })?;
-
-        Ok(AuthUser(token_data.claims))
+        let claims = token_data.claims;
+
+        // A token outlives the account: check it is still usable.
+        let (deleted, ban) = bans::standing(&state.pool, claims.sub).await?;
+        if deleted {
+            return Err(AppError::Unauthorized);
+        }
+        if let Some(ban) = ban {
+            return Err(AppError::Banned(ban.message()));
+        }
+
+        Ok(AuthUser(claims))
     }
 }
 
@@ -124,47 +151,25 @@ pub async fn require_center_owner(
     }
 }
 
-/// Fetch the JWKS from Supabase and build a `DecodingKey`.
-/// Called once at startup. Panics if the key cannot be fetched (server cannot run without it).
-pub async fn fetch_jwks(
-    jwks_url: &str,
-) -> Result<(DecodingKey, Algorithm, String), Box<dyn std::error::Error + Send + Sync>> {
-    tracing::info!(url = %jwks_url, "Fetching JWKS for JWT verification");
-
-    let response = reqwest::get(jwks_url).await?;
-    let jwks: jwk::JwkSet = response.json().await?;
-
-    let key = jwks
-        .keys
-        .first()
-        .ok_or("JWKS response contains no keys")?;
-
-    let algorithm = match key
-        .common
-        .key_algorithm
-        .ok_or("JWK missing algorithm field")?
-    {
-        jwk::KeyAlgorithm::ES256 => Algorithm::ES256,
-        jwk::KeyAlgorithm::ES384 => Algorithm::ES384,
-        jwk::KeyAlgorithm::RS256 => Algorithm::RS256,
-        jwk::KeyAlgorithm::RS384 => Algorithm::RS384,
-        jwk::KeyAlgorithm::RS512 => Algorithm::RS512,
-        other => return Err(format!("Unsupported JWT algorithm: {other:?}").into()),
-    };
-
-    let kid = key
-        .common
-        .key_id
-        .clone()
-        .unwrap_or_default();
-
-    let decoding_key = DecodingKey::from_jwk(key)?;
-
-    tracing::info!(
-        algorithm = ?algorithm,
-        kid = %kid,
-        "JWKS loaded successfully"
-    );
-
-    Ok((decoding_key, algorithm, kid))
+/// Require that the authenticated user holds `capability` in a center,
+/// from their role or their own overrides. Returns their permissions.
+pub async fn require_center_capability(
+    pool: &sqlx::PgPool,
+    user_id: Uuid,
+    center_id: Uuid,
+    capability: Capability,
+) -> Result<MemberPermissions, AppError> {
+    match crate::services::permissions::load(pool, user_id, center_id).await? {
+        Some(permissions) if permissions.allows(capability) => Ok(permissions),
+        _ => Err(AppError::Forbidden),
+    }
+}
+
+/// Reject users banned from `action` (booking, reviewing). Platform bans
+/// are already rejected by [`AuthUser`].
+pub async fn require_not_banned(pool: &sqlx::PgPool, user_id: Uuid, action: Scope) -> Result<(), AppError> {
+    match bans::active(pool, user_id, action).await? {
+        Some(ban) => Err(AppError::Banned(ban.message())),
+        None => Ok(()),
+    }
 }
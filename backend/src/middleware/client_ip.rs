@@ -0,0 +1,20 @@
+//! Client IP behind the proxy in front of the API.
+//!
+//! Vercel and the Docker deployment both sit behind a proxy, so the peer
+//! address is the proxy's. The client is read from the same headers as the
+//! rate limiter's `SmartIpKeyExtractor` (see `main.rs`); it is only as
+//! trustworthy as the proxy that sets them.
+
+use std::net::IpAddr;
+
+use axum::http::HeaderMap;
+
+/// Client IP as set by the proxy in front of the API: the first
+/// `X-Forwarded-For` entry, else `X-Real-IP`.
+pub fn client_ip(headers: &HeaderMap) -> Option<IpAddr> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    header("x-forwarded-for")
+        .and_then(|list| list.split(',').next())
+        .and_then(|ip| ip.trim().parse().ok())
+        .or_else(|| header("x-real-ip").and_then(|ip| ip.trim().parse().ok()))
+}
@@ -0,0 +1,182 @@
+//! Cache of the Supabase JWT signing keys, keyed by `kid`.
+//!
+//! Supabase rotates its signing keys: during a rotation tokens signed with
+//! the old and the new key are both valid, and the JWKS lists both. The
+//! cache is loaded at startup and reloaded when a token names a key it does
+//! not know, at most once per [`MIN_REFRESH_INTERVAL`] so that forged `kid`s
+//! cannot hammer the JWKS endpoint. A failed startup load is not fatal: the
+//! first authenticated request retries it.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use jsonwebtoken::jwk::{self, AlgorithmParameters, EllipticCurve, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey};
+use tokio::sync::Mutex;
+
+/// Shortest delay between two JWKS requests.
+pub const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone)]
+pub struct SigningKey {
+    pub key: DecodingKey,
+    pub algorithm: Algorithm,
+}
+
+pub struct JwksCache {
+    /// `None` for a fixed key set (tests), which is never refreshed.
+    url: Option<String>,
+    client: reqwest::Client,
+    /// Keys by `kid`; a key published without `kid` is stored under `""`.
+    keys: RwLock<HashMap<String, SigningKey>>,
+    /// Last fetch attempt, successful or not. Also serializes refreshes.
+    last_attempt: Mutex<Option<Instant>>,
+}
+
+impl JwksCache {
+    /// Empty cache for `url`; call [`JwksCache::refresh`] to load it.
+    pub fn new(url: impl Into<String>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            url: Some(url.into()),
+            client,
+            keys: RwLock::new(HashMap::new()),
+            last_attempt: Mutex::new(None),
+        }
+    }
+
+    /// Cache holding a single fixed key, for tests and local tooling.
+    pub fn with_key(kid: Option<&str>, key: DecodingKey, algorithm: Algorithm) -> Self {
+        let keys = HashMap::from([(kid.unwrap_or_default().to_owned(), SigningKey { key, algorithm })]);
+        Self {
+            url: None,
+            client: reqwest::Client::new(),
+            keys: RwLock::new(keys),
+            last_attempt: Mutex::new(None),
+        }
+    }
+
+    /// Key for a token's `kid`, refreshing the set once if it is unknown.
+    pub async fn key_for(&self, kid: Option<&str>) -> Option<SigningKey> {
+        if let Some(key) = self.select(kid) {
+            return Some(key);
+        }
+        if let Err(e) = self.refresh_if_due().await {
+            tracing::warn!(error = %e, "JWKS refresh failed");
+        }
+        self.select(kid)
+    }
+
+    /// Cached key for `kid`. A token without `kid` is accepted only while
+    /// the set holds a single key, as there is no way to choose otherwise.
+    pub fn select(&self, kid: Option<&str>) -> Option<SigningKey> {
+        let keys = self.keys.read().unwrap_or_else(|e| e.into_inner());
+        match kid {
+            Some(kid) => keys.get(kid).cloned(),
+            None if keys.len() == 1 => keys.values().next().cloned(),
+            None => None,
+        }
+    }
+
+    pub fn kids(&self) -> Vec<String> {
+        let keys = self.keys.read().unwrap_or_else(|e| e.into_inner());
+        let mut kids: Vec<String> = keys.keys().cloned().collect();
+        kids.sort();
+        kids
+    }
+
+    /// Reload the key set now, whatever the last attempt.
+    pub async fn refresh(&self) -> Result<usize, String> {
+        let mut last_attempt = self.last_attempt.lock().await;
+        self.fetch(&mut last_attempt).await
+    }
+
+    /// Reload the key set unless it was attempted less than
+    /// [`MIN_REFRESH_INTERVAL`] ago. Concurrent callers wait for the running
+    /// refresh instead of starting their own.
+    async fn refresh_if_due(&self) -> Result<usize, String> {
+        let mut last_attempt = self.last_attempt.lock().await;
+        if last_attempt.is_some_and(|at| at.elapsed() < MIN_REFRESH_INTERVAL) {
+            return Ok(0);
+        }
+        self.fetch(&mut last_attempt).await
+    }
+
+    async fn fetch(&self, last_attempt: &mut Option<Instant>) -> Result<usize, String> {
+        let Some(url) = self.url.as_deref() else {
+            return Ok(0);
+        };
+        *last_attempt = Some(Instant::now());
+        tracing::info!(url = %url, "Fetching JWKS for JWT verification");
+
+        let set: JwkSet = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())?;
+        let keys = parse_jwks(&set);
+        if keys.is_empty() {
+            return Err("JWKS response contains no usable keys".to_owned());
+        }
+
+        let count = keys.len();
+        *self.keys.write().unwrap_or_else(|e| e.into_inner()) = keys;
+        tracing::info!(keys = count, kids = ?self.kids(), "JWKS loaded successfully");
+        Ok(count)
+    }
+}
+
+/// Signing keys of a JWKS by `kid`, skipping keys that cannot verify
+/// Supabase tokens (encryption keys, symmetric or unsupported algorithms).
+pub fn parse_jwks(set: &JwkSet) -> HashMap<String, SigningKey> {
+    set.keys
+        .iter()
+        .filter(|key| !matches!(key.common.public_key_use, Some(jwk::PublicKeyUse::Encryption)))
+        .filter_map(|key| {
+            let kid = key.common.key_id.clone().unwrap_or_default();
+            let Some(algorithm) = key_algorithm(key) else {
+                tracing::warn!(kid = %kid, "Skipping JWK with unsupported algorithm");
+                return None;
+            };
+            match DecodingKey::from_jwk(key) {
+                Ok(key) => Some((kid, SigningKey { key, algorithm })),
+                Err(e) => {
+                    tracing::warn!(kid = %kid, error = %e, "Skipping invalid JWK");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// The key's declared algorithm, or the one implied by its curve or type.
+fn key_algorithm(key: &jwk::Jwk) -> Option<Algorithm> {
+    match key.common.key_algorithm {
+        Some(jwk::KeyAlgorithm::ES256) => Some(Algorithm::ES256),
+        Some(jwk::KeyAlgorithm::ES384) => Some(Algorithm::ES384),
+        Some(jwk::KeyAlgorithm::RS256) => Some(Algorithm::RS256),
+        Some(jwk::KeyAlgorithm::RS384) => Some(Algorithm::RS384),
+        Some(jwk::KeyAlgorithm::RS512) => Some(Algorithm::RS512),
+        Some(_) => None,
+        None => match &key.algorithm {
+            AlgorithmParameters::EllipticCurve(ec) => match ec.curve {
+                EllipticCurve::P256 => Some(Algorithm::ES256),
+                EllipticCurve::P384 => Some(Algorithm::ES384),
+                _ => None,
+            },
+            AlgorithmParameters::RSA(_) => Some(Algorithm::RS256),
+            _ => None,
+        },
+    }
+}
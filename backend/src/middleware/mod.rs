@@ -1 +1,4 @@
+pub mod audit;
 pub mod auth;
+pub mod client_ip;
+pub mod jwks;
@@ -1,10 +1,12 @@
 pub mod center;
+pub mod money;
 pub mod platform_config;
 pub mod profile;
 pub mod reference;
 pub mod service;
 
 pub use center::{Center, CenterSummary, ProfileCenterSummary};
+pub use money::{Money, MoneyError, RoundingMode};
 pub use platform_config::PlatformConfig;
 pub use profile::Profile;
 pub use reference::{CertificationRef, CountryRef, DiveTypeRef, ServiceCategoryRef};
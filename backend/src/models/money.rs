@@ -0,0 +1,200 @@
+//! Currency-aware monetary amounts.
+//!
+//! Every conversion between a `NUMERIC` amount stored in Postgres and the
+//! integer minor units Stripe expects goes through [`Money`], so fractional
+//! cents, zero-decimal currencies (JPY) and three-decimal currencies (BHD)
+//! are handled the same way everywhere.
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Serialize;
+
+/// Errors raised when building or converting a [`Money`] value.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum MoneyError {
+    #[error("Unsupported currency: {0}")]
+    UnsupportedCurrency(String),
+    #[error("Currency mismatch: {left} vs {right}")]
+    CurrencyMismatch { left: String, right: String },
+    #[error("Amount {0} is out of range for Stripe minor units")]
+    OutOfRange(Decimal),
+}
+
+/// How to round an amount that has more decimals than its currency allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Round half away from zero (2.345 → 2.35). Default for prices and fees.
+    HalfUp,
+    /// Banker's rounding (2.345 → 2.34).
+    HalfEven,
+    /// Truncate towards zero. Used when paying money out.
+    Down,
+    /// Round away from zero.
+    Up,
+}
+
+impl RoundingMode {
+    fn strategy(self) -> RoundingStrategy {
+        match self {
+            Self::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            Self::HalfEven => RoundingStrategy::MidpointNearestEven,
+            Self::Down => RoundingStrategy::ToZero,
+            Self::Up => RoundingStrategy::AwayFromZero,
+        }
+    }
+}
+
+/// ISO 4217 minor unit (number of decimals) for an active currency code.
+/// Returns `None` for unknown codes. Expects an upper-case code.
+pub fn iso_minor_units(code: &str) -> Option<u32> {
+    let units = match code {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF"
+        | "UGX" | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        "CLF" | "UYW" => 4,
+        "AED" | "AFN" | "ALL" | "AMD" | "ANG" | "AOA" | "ARS" | "AUD" | "AWG" | "AZN"
+        | "BAM" | "BBD" | "BDT" | "BGN" | "BMD" | "BND" | "BOB" | "BRL" | "BSD" | "BTN"
+        | "BWP" | "BYN" | "BZD" | "CAD" | "CDF" | "CHF" | "CNY" | "COP" | "CRC" | "CUP"
+        | "CVE" | "CZK" | "DKK" | "DOP" | "DZD" | "EGP" | "ERN" | "ETB" | "EUR" | "FJD"
+        | "FKP" | "GBP" | "GEL" | "GHS" | "GIP" | "GMD" | "GTQ" | "GYD" | "HKD" | "HNL"
+        | "HTG" | "HUF" | "IDR" | "ILS" | "INR" | "IRR" | "JMD" | "KES" | "KGS" | "KHR"
+        | "KPW" | "KYD" | "KZT" | "LAK" | "LBP" | "LKR" | "LRD" | "LSL" | "MAD" | "MDL"
+        | "MGA" | "MKD" | "MMK" | "MNT" | "MOP" | "MRU" | "MUR" | "MVR" | "MWK" | "MXN"
+        | "MYR" | "MZN" | "NAD" | "NGN" | "NIO" | "NOK" | "NPR" | "NZD" | "PAB" | "PEN"
+        | "PGK" | "PHP" | "PKR" | "PLN" | "QAR" | "RON" | "RSD" | "RUB" | "SAR" | "SBD"
+        | "SCR" | "SDG" | "SEK" | "SGD" | "SHP" | "SLE" | "SOS" | "SRD" | "SSP" | "STN"
+        | "SVC" | "SYP" | "SZL" | "THB" | "TJS" | "TMT" | "TOP" | "TRY" | "TTD" | "TWD"
+        | "TZS" | "UAH" | "USD" | "UYU" | "UZS" | "VES" | "WST" | "XCD" | "YER" | "ZAR"
+        | "ZMW" | "ZWL" => 2,
+        _ => return None,
+    };
+    Some(units)
+}
+
+/// Number of decimals Stripe uses for the integer `amount` of a currency.
+///
+/// Stripe follows ISO 4217 except for a few legacy cases: ISK and UGX are sent
+/// as two-decimal amounts (always a multiple of 100), and MGA is zero-decimal.
+pub fn stripe_minor_units(code: &str) -> Option<u32> {
+    match code {
+        "ISK" | "UGX" => Some(2),
+        "MGA" => Some(0),
+        other => iso_minor_units(other),
+    }
+}
+
+/// An amount in a specific ISO 4217 currency.
+///
+/// The currency code is always stored upper-case and is guaranteed to be
+/// present in the minor-unit table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Money {
+    amount: Decimal,
+    currency: String,
+}
+
+impl Money {
+    /// Build a money value, validating the currency code.
+    pub fn new(amount: Decimal, currency: &str) -> Result<Self, MoneyError> {
+        let currency = currency.trim().to_uppercase();
+        if iso_minor_units(&currency).is_none() {
+            return Err(MoneyError::UnsupportedCurrency(currency));
+        }
+        Ok(Self { amount, currency })
+    }
+
+    /// Zero in the given currency.
+    pub fn zero(currency: &str) -> Result<Self, MoneyError> {
+        Self::new(Decimal::ZERO, currency)
+    }
+
+    /// Convert an integer Stripe amount (e.g. `amount_total`) back to a decimal.
+    pub fn from_stripe_minor(minor: i64, currency: &str) -> Result<Self, MoneyError> {
+        let currency = currency.trim().to_uppercase();
+        let scale = stripe_minor_units(&currency)
+            .ok_or_else(|| MoneyError::UnsupportedCurrency(currency.clone()))?;
+        let amount = Decimal::new(minor, scale).normalize();
+        Ok(Self { amount, currency })
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    /// ISO 4217 minor units of this value's currency.
+    pub fn minor_units(&self) -> u32 {
+        // Every constructor rejects currencies missing from the table, so the
+        // fallback is unreachable; 2 is the exponent of most currencies.
+        iso_minor_units(&self.currency).unwrap_or(2)
+    }
+
+    /// Round to the number of decimals the currency allows.
+    pub fn round(&self, mode: RoundingMode) -> Self {
+        Self {
+            amount: self
+                .amount
+                .round_dp_with_strategy(self.minor_units(), mode.strategy()),
+            currency: self.currency.clone(),
+        }
+    }
+
+    /// `rate` percent of this amount (e.g. a 20% commission), rounded to the currency.
+    pub fn percentage(&self, rate: Decimal, mode: RoundingMode) -> Self {
+        Self {
+            amount: self.amount * rate / Decimal::ONE_HUNDRED,
+            currency: self.currency.clone(),
+        }
+        .round(mode)
+    }
+
+    /// Subtract two amounts of the same currency.
+    pub fn checked_sub(&self, other: &Self) -> Result<Self, MoneyError> {
+        if self.currency != other.currency {
+            return Err(MoneyError::CurrencyMismatch {
+                left: self.currency.clone(),
+                right: other.currency.clone(),
+            });
+        }
+        Ok(Self {
+            amount: self.amount - other.amount,
+            currency: self.currency.clone(),
+        })
+    }
+
+    /// Integer amount in Stripe minor units.
+    ///
+    /// The value is first rounded with `mode` to the precision both ISO and
+    /// Stripe agree on, then scaled to Stripe's representation.
+    pub fn to_stripe_minor(&self, mode: RoundingMode) -> Result<i64, MoneyError> {
+        let iso = self.minor_units();
+        let stripe = stripe_minor_units(&self.currency).unwrap_or(iso);
+        let rounded = self
+            .amount
+            .round_dp_with_strategy(iso.min(stripe), mode.strategy());
+        let scaled = rounded
+            .checked_mul(Decimal::from(10_i64.pow(stripe)))
+            .ok_or(MoneyError::OutOfRange(self.amount))?;
+        scaled
+            .trunc()
+            .to_i64()
+            .ok_or(MoneyError::OutOfRange(self.amount))
+    }
+
+    /// Currency as the `async-stripe` enum.
+    pub fn stripe_currency(&self) -> Result<stripe::Currency, MoneyError> {
+        self.currency
+            .to_lowercase()
+            .parse()
+            .map_err(|_| MoneyError::UnsupportedCurrency(self.currency.clone()))
+    }
+}
+
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.amount, self.currency)
+    }
+}
@@ -10,7 +10,9 @@ use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::error::AppError;
+use crate::middleware::audit::Audit;
 use crate::middleware::auth::{require_admin, AuthUser};
+use crate::services::audit::{self, Entity};
 use crate::AppState;
 
 /// Build the `/admin` sub-router. All routes require admin_diver role.
@@ -178,11 +180,14 @@ impl CenterStatusAction {
 async fn update_center_status(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path(center_id): Path<Uuid>,
     Json(body): Json<UpdateCenterStatusBody>,
 ) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
 
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Center, center_id).await?;
     let result = sqlx::query(
         r#"
         UPDATE centers
@@ -192,7 +197,7 @@ async fn update_center_status(
     )
     .bind(body.status.as_str())
     .bind(center_id)
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -200,6 +205,8 @@ async fn update_center_status(
             "Center {center_id} not found"
         )));
     }
+    audit.record(&mut tx, claims.sub, "center.update_status", Entity::Center, center_id, before).await?;
+    tx.commit().await?;
 
     Ok((
         StatusCode::OK,
@@ -270,6 +277,7 @@ impl UserRoleAction {
 async fn update_user_role(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path(user_id): Path<Uuid>,
     Json(body): Json<UpdateUserRoleBody>,
 ) -> Result<impl IntoResponse, AppError> {
@@ -282,6 +290,8 @@ async fn update_user_role(
         ));
     }
 
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::User, user_id).await?;
     let result = sqlx::query(
         r#"
         UPDATE profiles
@@ -291,7 +301,7 @@ async fn update_user_role(
     )
     .bind(body.role.as_str())
     .bind(user_id)
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -299,6 +309,8 @@ async fn update_user_role(
             "User {user_id} not found"
         )));
     }
+    audit.record(&mut tx, claims.sub, "user.update_role", Entity::User, user_id, before).await?;
+    tx.commit().await?;
 
     Ok((
         StatusCode::OK,
@@ -382,11 +394,14 @@ impl BookingStatusAction {
 async fn update_booking_status(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path(booking_id): Path<Uuid>,
     Json(body): Json<UpdateBookingStatusBody>,
 ) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
 
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Booking, booking_id).await?;
     let result = sqlx::query(
         r#"
         UPDATE bookings
@@ -396,7 +411,7 @@ async fn update_booking_status(
     )
     .bind(body.status.as_str())
     .bind(booking_id)
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -404,6 +419,8 @@ async fn update_booking_status(
             "Booking {booking_id} not found"
         )));
     }
+    audit.record(&mut tx, claims.sub, "booking.update_status", Entity::Booking, booking_id, before).await?;
+    tx.commit().await?;
 
     Ok((
         StatusCode::OK,
@@ -463,11 +480,14 @@ struct TogglePublishBody {
 async fn toggle_review_publish(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path(review_id): Path<Uuid>,
     Json(body): Json<TogglePublishBody>,
 ) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
 
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Review, review_id).await?;
     let result = sqlx::query(
         r#"
         UPDATE reviews
@@ -477,7 +497,7 @@ async fn toggle_review_publish(
     )
     .bind(body.is_published)
     .bind(review_id)
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -485,6 +505,8 @@ async fn toggle_review_publish(
             "Review {review_id} not found"
         )));
     }
+    audit.record(&mut tx, claims.sub, "review.publish", Entity::Review, review_id, before).await?;
+    tx.commit().await?;
 
     Ok((
         StatusCode::OK,
@@ -499,10 +521,13 @@ async fn toggle_review_publish(
 async fn soft_delete_review(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path(review_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
 
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Review, review_id).await?;
     let result = sqlx::query(
         r#"
         UPDATE reviews
@@ -511,7 +536,7 @@ async fn soft_delete_review(
         "#,
     )
     .bind(review_id)
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -519,6 +544,8 @@ async fn soft_delete_review(
             "Review {review_id} not found"
         )));
     }
+    audit.record(&mut tx, claims.sub, "review.delete", Entity::Review, review_id, before).await?;
+    tx.commit().await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
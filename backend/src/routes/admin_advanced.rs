@@ -1,19 +1,31 @@
-//! Advanced admin routes: users CRUD, services admin, tags, locations, extras,
-//! notifications, coupons, vendors, refunds, reports, plannings, settings categories.
+//! Advanced admin routes: users CRUD, bans and GDPR export, services admin, tags, locations, extras,
+//! notifications, coupons, vendors, refunds, reports, reconciliation, email
+//! outbox, contact inbox, audit log, accounting exports, plannings, settings
+//! categories.
 
 use std::sync::Arc;
 
 use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
 use axum::response::IntoResponse;
-use axum::routing::{delete, get, patch, post};
+use axum::routing::{delete, get, patch, post, put};
 use axum::{Json, Router};
 use rust_decimal::Decimal;
 use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::error::AppError;
+use crate::middleware::audit::Audit;
 use crate::middleware::auth::{require_admin, AuthUser};
+use crate::models::Money;
+use crate::services::audit::{self, Entity};
+use crate::services::bans::{self, Scope};
+use crate::services::email::{Template, Value};
+use crate::services::contact::{self, Inbox};
+use crate::services::{fec, outbox};
+use crate::services::payment_gateway::RefundRequest;
+use crate::services::privacy;
+use crate::services::reconciliation;
 use crate::AppState;
 
 pub fn router() -> Router<Arc<AppState>> {
@@ -22,6 +34,9 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/users/{user_id}", get(get_user).patch(update_user).delete(delete_user))
         .route("/users/{user_id}/blacklist", post(blacklist_user))
         .route("/users/{user_id}/unblacklist", post(unblacklist_user))
+        .route("/users/{user_id}/export", get(export_user_data))
+        .route("/users/{user_id}/bans", get(list_user_bans).post(create_user_ban))
+        .route("/users/{user_id}/bans/{ban_id}", delete(lift_user_ban))
         // Services admin
         .route("/services", get(list_all_services))
         .route("/services/{service_id}", delete(delete_service_admin))
@@ -65,6 +80,26 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/refunds/{refund_id}/reject", post(reject_refund))
         // Reports
         .route("/reports", get(get_reports))
+        // Reconciliation
+        .route("/reconciliation", get(list_reconciliation_runs).post(run_reconciliation))
+        .route("/reconciliation/{run_id}", get(get_reconciliation_run))
+        // Email outbox
+        .route("/emails", get(list_emails))
+        .route("/emails/{email_id}", get(get_email))
+        .route("/emails/{email_id}/resend", post(resend_email))
+        // Contact inbox
+        .route("/contact-messages", get(list_contact_messages))
+        .route("/contact-messages/{message_id}", get(get_contact_message))
+        .route("/contact-messages/{message_id}/reply", post(reply_contact_message))
+        .route("/contact-messages/{message_id}/status", patch(update_contact_status))
+        // Audit log
+        .route("/audit-log", get(list_audit_log))
+        .route("/audit-log/{entry_id}", get(get_audit_entry))
+        // Accounting
+        .route("/accounting/fec", get(export_fec))
+        .route("/accounting/chart-of-accounts", get(get_chart_of_accounts))
+        .route("/accounting/exchange-rates", get(list_exchange_rates))
+        .route("/accounting/exchange-rates/{currency}/{date}", put(put_exchange_rate))
         // Plannings
         .route("/plannings", get(get_plannings))
         // Settings categories
@@ -73,6 +108,8 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/settings/currency", get(get_settings_by_cat).put(update_settings_by_cat))
         .route("/settings/display", get(get_settings_by_cat).put(update_settings_by_cat))
         .route("/settings/global", get(get_settings_by_cat).put(update_settings_by_cat))
+        .route("/settings/tax", get(get_settings_by_cat).put(update_settings_by_cat))
+        .route("/settings/accounting", get(get_settings_by_cat).put(update_settings_by_cat))
 }
 
 // ═══════════════════════════════════════════════════════════
@@ -89,6 +126,8 @@ struct UserDetail {
     avatar_url: Option<String>,
     phone: Option<String>,
     preferred_locale: Option<String>,
+    /// "blocked" while banned from the platform, else "active"
+    status: String,
     created_at: chrono::DateTime<chrono::Utc>,
     updated_at: chrono::DateTime<chrono::Utc>,
 }
@@ -100,7 +139,14 @@ async fn get_user(
 ) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
     let user = sqlx::query_as::<_, UserDetail>(
-        "SELECT id, role::text AS role, first_name, last_name, display_name, avatar_url, phone, preferred_locale, created_at, updated_at FROM profiles WHERE id = $1 AND deleted_at IS NULL",
+        r#"SELECT p.id, p.role::text AS role, p.first_name, p.last_name, p.display_name, p.avatar_url, p.phone, p.preferred_locale,
+                  CASE WHEN EXISTS(
+                      SELECT 1 FROM user_bans b
+                      WHERE b.user_id = p.id AND b.scope = 'platform' AND b.lifted_at IS NULL
+                        AND (b.expires_at IS NULL OR b.expires_at > NOW())
+                  ) THEN 'blocked' ELSE 'active' END AS status,
+                  p.created_at, p.updated_at
+           FROM profiles p WHERE p.id = $1 AND p.deleted_at IS NULL"#,
     )
     .bind(user_id)
     .fetch_optional(&state.pool)
@@ -121,10 +167,13 @@ struct UpdateUserBody {
 async fn update_user(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path(user_id): Path<Uuid>,
     Json(body): Json<UpdateUserBody>,
 ) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::User, user_id).await?;
     let result = sqlx::query(
         r#"UPDATE profiles SET
             first_name = COALESCE($1, first_name),
@@ -141,55 +190,177 @@ async fn update_user(
     .bind(body.phone.as_deref())
     .bind(body.role.as_deref())
     .bind(user_id)
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await?;
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("User not found".to_owned()));
     }
+    audit.record(&mut tx, claims.sub, "user.update", Entity::User, user_id, before).await?;
+    tx.commit().await?;
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": "User updated" }))))
 }
 
+/// Erasure on the user's behalf (GDPR): personal data is anonymized,
+/// financial records are kept.
 async fn delete_user(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path(user_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
     if user_id == claims.sub {
         return Err(AppError::BadRequest("Cannot delete yourself".to_owned()));
     }
-    sqlx::query("UPDATE profiles SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
-        .bind(user_id)
-        .execute(&state.pool)
-        .await?;
+    let mut tx = state.pool.begin().await?;
+    let summary = privacy::erase(&mut tx, user_id).await?;
+    // No `before`: the audit log must not keep what was just erased.
+    audit.record(&mut tx, claims.sub, "user.erase", Entity::User, user_id, None).await?;
+    tx.commit().await?;
+    tracing::info!(%user_id, ?summary, "User erased");
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Same document as `GET /profile/me/export`, for access requests made to
+/// support.
+async fn export_user_data(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let document = privacy::export(&state.pool, user_id).await?;
+    let disposition = format!("attachment; filename=\"{}\"", privacy::export_filename(user_id, chrono::Utc::now().date_naive()));
+    Ok((StatusCode::OK, [(header::CONTENT_DISPOSITION, disposition)], Json(document)))
+}
+
+#[derive(Debug, Deserialize)]
+struct BanBody {
+    /// "booking", "review" or "platform"
+    scope: String,
+    reason: String,
+    /// Permanent until lifted when absent
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlacklistBody {
+    reason: Option<String>,
+}
+
+/// Ban `user_id`, cancel their pending bookings when the scope covers
+/// booking, then release those bookings' payments.
+async fn ban_user(
+    state: &AppState,
+    audit: &Audit,
+    admin_id: Uuid,
+    user_id: Uuid,
+    scope: Scope,
+    reason: &str,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<serde_json::Value, AppError> {
+    let mut tx = state.pool.begin().await?;
+    let (ban, cancelled) = bans::create(&mut tx, user_id, scope, reason, expires_at, admin_id).await?;
+    audit.record(&mut tx, admin_id, "user.ban", Entity::Ban, ban.id, None).await?;
+    for booking in &cancelled {
+        audit.record(&mut tx, admin_id, "booking.cancel_for_ban", Entity::Booking, booking.booking_id, None).await?;
+    }
+    tx.commit().await?;
+    bans::release_payments(state.payments.as_ref(), &cancelled).await;
+
+    let cancelled_ids: Vec<Uuid> = cancelled.iter().map(|b| b.booking_id).collect();
+    Ok(serde_json::json!({ "data": ban, "cancelled_bookings": cancelled_ids }))
+}
+
+/// Permanent platform ban, kept for the user page's blacklist button.
 async fn blacklist_user(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path(user_id): Path<Uuid>,
+    body: Option<Json<BlacklistBody>>,
 ) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
-    // Soft-delete = blacklist for now
-    sqlx::query("UPDATE profiles SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
-        .bind(user_id)
-        .execute(&state.pool)
-        .await?;
-    Ok((StatusCode::OK, Json(serde_json::json!({ "message": "User blacklisted" }))))
+    let reason = body.and_then(|Json(b)| b.reason).unwrap_or_else(|| "Blacklisted by an administrator".to_owned());
+    let result = ban_user(&state, &audit, claims.sub, user_id, Scope::Platform, &reason, None).await?;
+    Ok((StatusCode::OK, Json(result)))
 }
 
+/// Lift every ban of the user still in force. Accounts blacklisted before
+/// bans existed were soft-deleted instead; they are restored too, unless
+/// they have since been erased.
 async fn unblacklist_user(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path(user_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
-    sqlx::query("UPDATE profiles SET deleted_at = NULL, updated_at = NOW() WHERE id = $1")
-        .bind(user_id)
-        .execute(&state.pool)
-        .await?;
-    Ok((StatusCode::OK, Json(serde_json::json!({ "message": "User unblacklisted" }))))
+    let mut tx = state.pool.begin().await?;
+    let mut lifted = Vec::new();
+    for ban in bans::in_force(&mut tx, user_id).await? {
+        let before = audit::snapshot(&mut *tx, Entity::Ban, ban.id).await?;
+        bans::lift(&mut tx, user_id, ban.id, claims.sub).await?;
+        audit.record(&mut tx, claims.sub, "user.unban", Entity::Ban, ban.id, before).await?;
+        lifted.push(ban.id);
+    }
+    let before = audit::snapshot(&mut *tx, Entity::User, user_id).await?;
+    let restored = sqlx::query(
+        "UPDATE profiles SET deleted_at = NULL, updated_at = NOW() \
+         WHERE id = $1 AND deleted_at IS NOT NULL AND erased_at IS NULL",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected()
+        > 0;
+    if restored {
+        audit.record(&mut tx, claims.sub, "user.restore", Entity::User, user_id, before).await?;
+    }
+    tx.commit().await?;
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({ "message": "User unblacklisted", "lifted": lifted, "restored": restored })),
+    ))
+}
+
+async fn list_user_bans(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let rows = bans::list(&state.pool, user_id).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": rows }))))
+}
+
+async fn create_user_ban(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    audit: Audit,
+    Path(user_id): Path<Uuid>,
+    Json(body): Json<BanBody>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let scope = Scope::parse(body.scope.trim())
+        .ok_or_else(|| AppError::BadRequest("scope must be booking, review or platform".to_owned()))?;
+    let result = ban_user(&state, &audit, claims.sub, user_id, scope, &body.reason, body.expires_at).await?;
+    Ok((StatusCode::CREATED, Json(result)))
+}
+
+async fn lift_user_ban(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    audit: Audit,
+    Path((user_id, ban_id)): Path<(Uuid, Uuid)>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Ban, ban_id).await?;
+    let ban = bans::lift(&mut tx, user_id, ban_id, claims.sub).await?;
+    audit.record(&mut tx, claims.sub, "user.unban", Entity::Ban, ban_id, before).await?;
+    tx.commit().await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": ban }))))
 }
 
 // ═══════════════════════════════════════════════════════════
@@ -229,13 +400,18 @@ async fn list_all_services(
 async fn delete_service_admin(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path(service_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Service, service_id).await?;
     sqlx::query("UPDATE services SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
         .bind(service_id)
-        .execute(&state.pool)
+        .execute(&mut *tx)
         .await?;
+    audit.record(&mut tx, claims.sub, "service.delete", Entity::Service, service_id, before).await?;
+    tx.commit().await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -246,13 +422,18 @@ async fn delete_service_admin(
 async fn approve_review(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path(review_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Review, review_id).await?;
     sqlx::query("UPDATE reviews SET is_published = true, updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
         .bind(review_id)
-        .execute(&state.pool)
+        .execute(&mut *tx)
         .await?;
+    audit.record(&mut tx, claims.sub, "review.approve", Entity::Review, review_id, before).await?;
+    tx.commit().await?;
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Review approved" }))))
 }
 
@@ -274,10 +455,13 @@ struct AdminUpdateCenterBody {
 async fn update_center_admin(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path(center_id): Path<Uuid>,
     Json(body): Json<AdminUpdateCenterBody>,
 ) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Center, center_id).await?;
     let result = sqlx::query(
         r#"UPDATE centers SET
             name = COALESCE($1, name), email = COALESCE($2, email),
@@ -294,11 +478,13 @@ async fn update_center_admin(
     .bind(body.description.as_deref())
     .bind(body.is_featured)
     .bind(center_id)
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await?;
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("Center not found".to_owned()));
     }
+    audit.record(&mut tx, claims.sub, "center.update", Entity::Center, center_id, before).await?;
+    tx.commit().await?;
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Center updated" }))))
 }
 
@@ -318,19 +504,26 @@ async fn list_tags(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUse
 #[derive(Debug, Deserialize)]
 struct CreateTagBody { name: String }
 
-async fn create_tag(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Json(body): Json<CreateTagBody>) -> Result<impl IntoResponse, AppError> {
+async fn create_tag(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Json(body): Json<CreateTagBody>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
     let name = body.name.trim();
     if name.is_empty() { return Err(AppError::BadRequest("Tag name required".to_owned())); }
+    let mut tx = state.pool.begin().await?;
     let id: Uuid = sqlx::query_scalar("INSERT INTO tags (name) VALUES ($1) ON CONFLICT (name) DO NOTHING RETURNING id")
-        .bind(name).fetch_optional(&state.pool).await?.ok_or_else(|| AppError::Conflict("Tag already exists".to_owned()))?;
+        .bind(name).fetch_optional(&mut *tx).await?.ok_or_else(|| AppError::Conflict("Tag already exists".to_owned()))?;
+    audit.record(&mut tx, claims.sub, "tag.create", Entity::Tag, id, None).await?;
+    tx.commit().await?;
     Ok((StatusCode::CREATED, Json(serde_json::json!({ "data": { "id": id } }))))
 }
 
-async fn delete_tag(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(tag_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
+async fn delete_tag(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(tag_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
-    sqlx::query("DELETE FROM tli_ce_ta WHERE fk_tag = $1").bind(tag_id).execute(&state.pool).await?;
-    sqlx::query("DELETE FROM tags WHERE id = $1").bind(tag_id).execute(&state.pool).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Tag, tag_id).await?;
+    sqlx::query("DELETE FROM tli_ce_ta WHERE fk_tag = $1").bind(tag_id).execute(&mut *tx).await?;
+    sqlx::query("DELETE FROM tags WHERE id = $1").bind(tag_id).execute(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "tag.delete", Entity::Tag, tag_id, before).await?;
+    tx.commit().await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -350,25 +543,36 @@ async fn list_locations(State(state): State<Arc<AppState>>, AuthUser(claims): Au
 #[derive(Debug, Deserialize)]
 struct CreateLocationBody { name: String, description: Option<String>, latitude: Option<Decimal>, longitude: Option<Decimal>, depth_max: Option<i32>, difficulty: Option<String>, country: Option<String>, region: Option<String> }
 
-async fn create_location(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Json(body): Json<CreateLocationBody>) -> Result<impl IntoResponse, AppError> {
+async fn create_location(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Json(body): Json<CreateLocationBody>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
+    let mut tx = state.pool.begin().await?;
     let id: Uuid = sqlx::query_scalar("INSERT INTO locations (name, description, latitude, longitude, depth_max, difficulty, country, region) VALUES ($1,$2,$3,$4,$5,$6,$7,$8) RETURNING id")
         .bind(body.name.trim()).bind(body.description.as_deref()).bind(body.latitude).bind(body.longitude).bind(body.depth_max).bind(body.difficulty.as_deref()).bind(body.country.as_deref()).bind(body.region.as_deref())
-        .fetch_one(&state.pool).await?;
+        .fetch_one(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "location.create", Entity::Location, id, None).await?;
+    tx.commit().await?;
     Ok((StatusCode::CREATED, Json(serde_json::json!({ "data": { "id": id } }))))
 }
 
-async fn update_location(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(location_id): Path<Uuid>, Json(body): Json<CreateLocationBody>) -> Result<impl IntoResponse, AppError> {
+async fn update_location(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(location_id): Path<Uuid>, Json(body): Json<CreateLocationBody>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Location, location_id).await?;
     sqlx::query("UPDATE locations SET name=COALESCE($1,name), description=COALESCE($2,description), latitude=COALESCE($3,latitude), longitude=COALESCE($4,longitude), depth_max=COALESCE($5,depth_max), difficulty=COALESCE($6,difficulty), country=COALESCE($7,country), region=COALESCE($8,region), updated_at=NOW() WHERE id=$9 AND deleted_at IS NULL")
         .bind(body.name.trim()).bind(body.description.as_deref()).bind(body.latitude).bind(body.longitude).bind(body.depth_max).bind(body.difficulty.as_deref()).bind(body.country.as_deref()).bind(body.region.as_deref()).bind(location_id)
-        .execute(&state.pool).await?;
+        .execute(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "location.update", Entity::Location, location_id, before).await?;
+    tx.commit().await?;
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Location updated" }))))
 }
 
-async fn delete_location(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(location_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
+async fn delete_location(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(location_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
-    sqlx::query("UPDATE locations SET deleted_at=NOW(), updated_at=NOW() WHERE id=$1 AND deleted_at IS NULL").bind(location_id).execute(&state.pool).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Location, location_id).await?;
+    sqlx::query("UPDATE locations SET deleted_at=NOW(), updated_at=NOW() WHERE id=$1 AND deleted_at IS NULL").bind(location_id).execute(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "location.delete", Entity::Location, location_id, before).await?;
+    tx.commit().await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -388,25 +592,36 @@ async fn list_extras(State(state): State<Arc<AppState>>, AuthUser(claims): AuthU
 #[derive(Debug, Deserialize)]
 struct CreateExtraBody { name: String, description: Option<String>, price: Decimal, currency: Option<String> }
 
-async fn create_extra(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Json(body): Json<CreateExtraBody>) -> Result<impl IntoResponse, AppError> {
+async fn create_extra(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Json(body): Json<CreateExtraBody>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
+    let mut tx = state.pool.begin().await?;
     let id: Uuid = sqlx::query_scalar("INSERT INTO service_extras (name, description, price, currency) VALUES ($1,$2,$3,$4) RETURNING id")
         .bind(body.name.trim()).bind(body.description.as_deref()).bind(body.price).bind(body.currency.as_deref().unwrap_or("EUR"))
-        .fetch_one(&state.pool).await?;
+        .fetch_one(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "extra.create", Entity::Extra, id, None).await?;
+    tx.commit().await?;
     Ok((StatusCode::CREATED, Json(serde_json::json!({ "data": { "id": id } }))))
 }
 
-async fn update_extra(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(extra_id): Path<Uuid>, Json(body): Json<CreateExtraBody>) -> Result<impl IntoResponse, AppError> {
+async fn update_extra(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(extra_id): Path<Uuid>, Json(body): Json<CreateExtraBody>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Extra, extra_id).await?;
     sqlx::query("UPDATE service_extras SET name=COALESCE($1,name), description=COALESCE($2,description), price=COALESCE($3,price), currency=COALESCE($4,currency), updated_at=NOW() WHERE id=$5 AND deleted_at IS NULL")
         .bind(body.name.trim()).bind(body.description.as_deref()).bind(body.price).bind(body.currency.as_deref()).bind(extra_id)
-        .execute(&state.pool).await?;
+        .execute(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "extra.update", Entity::Extra, extra_id, before).await?;
+    tx.commit().await?;
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Extra updated" }))))
 }
 
-async fn delete_extra(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(extra_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
+async fn delete_extra(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(extra_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
-    sqlx::query("UPDATE service_extras SET deleted_at=NOW(), updated_at=NOW() WHERE id=$1 AND deleted_at IS NULL").bind(extra_id).execute(&state.pool).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Extra, extra_id).await?;
+    sqlx::query("UPDATE service_extras SET deleted_at=NOW(), updated_at=NOW() WHERE id=$1 AND deleted_at IS NULL").bind(extra_id).execute(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "extra.delete", Entity::Extra, extra_id, before).await?;
+    tx.commit().await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -426,27 +641,38 @@ async fn list_coupons(State(state): State<Arc<AppState>>, AuthUser(claims): Auth
 #[derive(Debug, Deserialize)]
 struct CreateCouponBody { code: String, discount_type: String, discount_value: Decimal, currency: Option<String>, max_uses: Option<i32>, expires_at: Option<String> }
 
-async fn create_coupon(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Json(body): Json<CreateCouponBody>) -> Result<impl IntoResponse, AppError> {
+async fn create_coupon(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Json(body): Json<CreateCouponBody>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
     let exp = body.expires_at.as_deref().and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok()).map(|dt| dt.with_timezone(&chrono::Utc));
+    let mut tx = state.pool.begin().await?;
     let id: Uuid = sqlx::query_scalar("INSERT INTO coupons (code, discount_type, discount_value, currency, max_uses, expires_at) VALUES ($1,$2,$3,$4,$5,$6) RETURNING id")
         .bind(body.code.trim().to_uppercase()).bind(&body.discount_type).bind(body.discount_value).bind(body.currency.as_deref().unwrap_or("EUR")).bind(body.max_uses.unwrap_or(1)).bind(exp)
-        .fetch_one(&state.pool).await?;
+        .fetch_one(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "coupon.create", Entity::Coupon, id, None).await?;
+    tx.commit().await?;
     Ok((StatusCode::CREATED, Json(serde_json::json!({ "data": { "id": id } }))))
 }
 
-async fn update_coupon(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(coupon_id): Path<Uuid>, Json(body): Json<CreateCouponBody>) -> Result<impl IntoResponse, AppError> {
+async fn update_coupon(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(coupon_id): Path<Uuid>, Json(body): Json<CreateCouponBody>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
     let exp = body.expires_at.as_deref().and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok()).map(|dt| dt.with_timezone(&chrono::Utc));
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Coupon, coupon_id).await?;
     sqlx::query("UPDATE coupons SET code=COALESCE($1,code), discount_type=COALESCE($2,discount_type), discount_value=COALESCE($3,discount_value), currency=COALESCE($4,currency), max_uses=COALESCE($5,max_uses), expires_at=COALESCE($6,expires_at), updated_at=NOW() WHERE id=$7")
         .bind(body.code.trim().to_uppercase()).bind(&body.discount_type).bind(body.discount_value).bind(body.currency.as_deref()).bind(body.max_uses).bind(exp).bind(coupon_id)
-        .execute(&state.pool).await?;
+        .execute(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "coupon.update", Entity::Coupon, coupon_id, before).await?;
+    tx.commit().await?;
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Coupon updated" }))))
 }
 
-async fn delete_coupon(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(coupon_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
+async fn delete_coupon(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(coupon_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
-    sqlx::query("DELETE FROM coupons WHERE id = $1").bind(coupon_id).execute(&state.pool).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Coupon, coupon_id).await?;
+    sqlx::query("DELETE FROM coupons WHERE id = $1").bind(coupon_id).execute(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "coupon.delete", Entity::Coupon, coupon_id, before).await?;
+    tx.commit().await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -463,11 +689,15 @@ async fn list_coupon_sources(State(state): State<Arc<AppState>>, AuthUser(claims
 #[derive(Debug, Deserialize)]
 struct UpdateCouponSourceBody { label: Option<String>, is_active: Option<bool>, max_claims: Option<i32> }
 
-async fn update_coupon_source(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(source_id): Path<Uuid>, Json(body): Json<UpdateCouponSourceBody>) -> Result<impl IntoResponse, AppError> {
+async fn update_coupon_source(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(source_id): Path<Uuid>, Json(body): Json<UpdateCouponSourceBody>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::CouponSource, source_id).await?;
     sqlx::query("UPDATE coupon_sources SET label=COALESCE($1,label), is_active=COALESCE($2,is_active), max_claims=COALESCE($3,max_claims), updated_at=NOW() WHERE id=$4")
         .bind(body.label.as_deref()).bind(body.is_active).bind(body.max_claims).bind(source_id)
-        .execute(&state.pool).await?;
+        .execute(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "coupon_source.update", Entity::CouponSource, source_id, before).await?;
+    tx.commit().await?;
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Coupon source updated" }))))
 }
 
@@ -518,24 +748,49 @@ async fn list_vendors(State(state): State<Arc<AppState>>, AuthUser(claims): Auth
 #[derive(Debug, Deserialize)]
 struct UpdateVendorCommissionBody { commission_rate: Decimal }
 
-async fn update_vendor_commission(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(_vendor_id): Path<Uuid>, Json(body): Json<UpdateVendorCommissionBody>) -> Result<impl IntoResponse, AppError> {
+async fn update_vendor_commission(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(_vendor_id): Path<Uuid>, Json(body): Json<UpdateVendorCommissionBody>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
     // Store vendor-specific rate in platform config (per-vendor rates not yet in schema)
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Setting, "commission_rate").await?;
     sqlx::query("INSERT INTO t_platform_config (key, value, category, is_secret) VALUES ('commission_rate', $1, 'finance', false) ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value, updated_at = NOW(), updated_by = $2")
         .bind(body.commission_rate.to_string()).bind(claims.sub)
-        .execute(&state.pool).await?;
+        .execute(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "vendor.update_commission", Entity::Setting, "commission_rate", before).await?;
+    tx.commit().await?;
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Commission rate updated" }))))
 }
 
-async fn suspend_vendor(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(vendor_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
+async fn suspend_vendor(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(vendor_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
-    sqlx::query("UPDATE centers SET status = 'suspended'::center_status, updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL").bind(vendor_id).execute(&state.pool).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Center, vendor_id).await?;
+    sqlx::query("UPDATE centers SET status = 'suspended'::center_status, updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL").bind(vendor_id).execute(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "center.suspend", Entity::Center, vendor_id, before).await?;
+    tx.commit().await?;
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Vendor suspended" }))))
 }
 
-async fn activate_vendor(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(vendor_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
+async fn activate_vendor(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(vendor_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
-    sqlx::query("UPDATE centers SET status = 'active'::center_status, updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL").bind(vendor_id).execute(&state.pool).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Center, vendor_id).await?;
+    let previous = sqlx::query_as::<_, (String, String)>(
+        r#"UPDATE centers c SET status = 'active'::center_status, updated_at = NOW()
+           FROM (SELECT id, status FROM centers WHERE id = $1 AND deleted_at IS NULL FOR UPDATE) old
+           WHERE c.id = old.id RETURNING old.status::text, c.name"#,
+    ).bind(vendor_id).fetch_optional(&mut *tx).await?;
+
+    // Reactivating a suspended center is not an approval.
+    if let Some((_, name)) = previous.filter(|(status, _)| status == "pending") {
+        let owners: Vec<Uuid> = sqlx::query_scalar("SELECT fk_profile FROM tli_pr_ce WHERE fk_center = $1 AND role_in_center = 'owner'")
+            .bind(vendor_id).fetch_all(&mut *tx).await?;
+        for owner in owners {
+            outbox::enqueue_template(&mut tx, owner, Template::CenterApproved, vec![("center_name", name.clone().into())]).await?;
+        }
+    }
+    audit.record(&mut tx, claims.sub, "center.activate", Entity::Center, vendor_id, before).await?;
+    tx.commit().await?;
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Vendor activated" }))))
 }
 
@@ -567,31 +822,43 @@ async fn get_commission_config(State(state): State<Arc<AppState>>, AuthUser(clai
 #[derive(Debug, Deserialize)]
 struct UpdateCommissionConfigBody { rate: Decimal }
 
-async fn update_commission_config(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Json(body): Json<UpdateCommissionConfigBody>) -> Result<impl IntoResponse, AppError> {
+async fn update_commission_config(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Json(body): Json<UpdateCommissionConfigBody>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
     // Store in platform config
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Setting, "commission_rate").await?;
     sqlx::query("INSERT INTO t_platform_config (key, value, category, is_secret) VALUES ('commission_rate', $1, 'finance', false) ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value, updated_at = NOW(), updated_by = $2")
         .bind(body.rate.to_string()).bind(claims.sub)
-        .execute(&state.pool).await?;
+        .execute(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "commission.update_config", Entity::Setting, "commission_rate", before).await?;
+    tx.commit().await?;
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Commission config updated" }))))
 }
 
-async fn mark_commission_paid(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(commission_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
+async fn mark_commission_paid(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(commission_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Booking, commission_id).await?;
     sqlx::query("UPDATE bookings SET status = 'completed'::booking_status, updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
-        .bind(commission_id).execute(&state.pool).await?;
+        .bind(commission_id).execute(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "commission.mark_paid", Entity::Booking, commission_id, before).await?;
+    tx.commit().await?;
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Commission marked as paid" }))))
 }
 
 #[derive(Debug, Deserialize)]
 struct BulkPayBody { commission_ids: Vec<Uuid> }
 
-async fn bulk_pay_commissions(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Json(body): Json<BulkPayBody>) -> Result<impl IntoResponse, AppError> {
+async fn bulk_pay_commissions(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Json(body): Json<BulkPayBody>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
+    let mut tx = state.pool.begin().await?;
     for id in &body.commission_ids {
+        let before = audit::snapshot(&mut *tx, Entity::Booking, id).await?;
         sqlx::query("UPDATE bookings SET status = 'completed'::booking_status, updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
-            .bind(id).execute(&state.pool).await?;
+            .bind(id).execute(&mut *tx).await?;
+        audit.record(&mut tx, claims.sub, "commission.mark_paid", Entity::Booking, id, before).await?;
     }
+    tx.commit().await?;
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": format!("{} commissions marked as paid", body.commission_ids.len()) }))))
 }
 
@@ -645,17 +912,67 @@ async fn list_refunds(State(state): State<Arc<AppState>>, AuthUser(claims): Auth
     Ok((StatusCode::OK, Json(serde_json::json!({ "data": rows }))))
 }
 
-async fn approve_refund(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(refund_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
+/// Approving issues the refund with the payment provider. The refund is
+/// marked `processing` first and sent with `refund-{id}` as idempotency key:
+/// retrying an approval whose outcome is unknown returns the same refund
+/// instead of paying it twice. A refund the provider declines goes back to
+/// `pending`.
+async fn approve_refund(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(refund_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
-    sqlx::query("UPDATE refunds SET status = 'approved', processed_by = $1, updated_at = NOW() WHERE id = $2 AND status = 'pending'")
-        .bind(claims.sub).bind(refund_id).execute(&state.pool).await?;
-    Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Refund approved" }))))
+    let mut tx = state.pool.begin().await?;
+    let (booking_id, amount, currency) = sqlx::query_as::<_, (Uuid, Decimal, String)>("SELECT booking_id, amount, currency FROM refunds WHERE id = $1 AND status IN ('pending', 'processing') FOR UPDATE")
+        .bind(refund_id).fetch_optional(&mut *tx).await?
+        .ok_or_else(|| AppError::NotFound("Pending refund not found".to_owned()))?;
+    let before = audit::snapshot(&mut *tx, Entity::Refund, refund_id).await?;
+    let pi_id: String = sqlx::query_scalar("SELECT stripe_payment_intent_id FROM transactions WHERE booking_id = $1 AND stripe_payment_intent_id IS NOT NULL AND deleted_at IS NULL ORDER BY created_at ASC LIMIT 1")
+        .bind(booking_id).fetch_optional(&mut *tx).await?
+        .ok_or_else(|| AppError::BadRequest("No captured payment to refund for this booking".to_owned()))?;
+    let amount_money = Money::new(amount, &currency).map_err(|e| AppError::BadRequest(e.to_string()))?;
+    sqlx::query("UPDATE refunds SET status = 'processing', processed_by = $1, stripe_payment_intent_id = $2, updated_at = NOW() WHERE id = $3")
+        .bind(claims.sub).bind(&pi_id).bind(refund_id).execute(&mut *tx).await?;
+    tx.commit().await?;
+
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("refund_id".to_owned(), refund_id.to_string());
+    metadata.insert("booking_id".to_owned(), booking_id.to_string());
+    let refund = match state.payments.create_refund(RefundRequest {
+        payment_intent_id: pi_id,
+        amount: Some(amount_money),
+        reverse_transfer: true,
+        metadata,
+        idempotency_key: Some(format!("refund-{refund_id}")),
+    }).await {
+        Ok(refund) => refund,
+        Err(e) if e.is_declined() => {
+            sqlx::query("UPDATE refunds SET status = 'pending', updated_at = NOW() WHERE id = $1 AND status = 'processing'")
+                .bind(refund_id).execute(&state.pool).await?;
+            return Err(e.into());
+        }
+        // The refund may exist at Stripe: stay `processing` so a retry reuses the key.
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut tx = state.pool.begin().await?;
+    let approved = sqlx::query("UPDATE refunds SET status = 'approved', stripe_refund_id = $1, processed_by = $2, processed_at = NOW(), updated_at = NOW() WHERE id = $3 AND status = 'processing'")
+        .bind(&refund.id).bind(claims.sub).bind(refund_id).execute(&mut *tx).await?
+        .rows_affected() == 1;
+    // A concurrent approval already recorded this refund.
+    if approved {
+        outbox::enqueue_booking_client(&mut tx, booking_id, Template::RefundIssued, vec![("amount", Value::Amount(amount, currency))]).await?;
+        audit.record(&mut tx, claims.sub, "refund.approve", Entity::Refund, refund_id, before).await?;
+    }
+    tx.commit().await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Refund approved", "data": { "stripe_refund_id": refund.id, "status": refund.status } }))))
 }
 
-async fn reject_refund(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(refund_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
+async fn reject_refund(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(refund_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Refund, refund_id).await?;
     sqlx::query("UPDATE refunds SET status = 'rejected', processed_by = $1, updated_at = NOW() WHERE id = $2 AND status = 'pending'")
-        .bind(claims.sub).bind(refund_id).execute(&state.pool).await?;
+        .bind(claims.sub).bind(refund_id).execute(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "refund.reject", Entity::Refund, refund_id, before).await?;
+    tx.commit().await?;
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Refund rejected" }))))
 }
 
@@ -682,11 +999,236 @@ async fn get_reports(State(state): State<Arc<AppState>>, AuthUser(claims): AuthU
             (SELECT AVG(rating::float) FROM reviews WHERE is_published = true AND deleted_at IS NULL)
         "#,
     ).bind(from_date).bind(to_date).fetch_one(&state.pool).await?;
+
+    // VAT split; bookings predating the tax breakdown count as fully net.
+    let tax = sqlx::query_as::<_, (Option<Decimal>, Option<Decimal>, Option<Decimal>, Option<Decimal>)>(
+        r#"SELECT SUM(COALESCE(price_net, total_price)), SUM(COALESCE(price_vat, 0)),
+                  SUM(COALESCE(fee_net, commission_amount)), SUM(COALESCE(fee_vat, 0))
+           FROM bookings WHERE status IN ('confirmed','completed') AND booking_date BETWEEN $1 AND $2 AND deleted_at IS NULL"#,
+    ).bind(from_date).bind(to_date).fetch_one(&state.pool).await?;
+    let by_regime = sqlx::query_as::<_, (Option<String>, i64, Option<Decimal>, Option<Decimal>, Option<Decimal>)>(
+        r#"SELECT fee_tax_regime, COUNT(*), SUM(COALESCE(fee_net, commission_amount)), SUM(COALESCE(fee_vat, 0)), SUM(commission_amount)
+           FROM bookings WHERE status IN ('confirmed','completed') AND booking_date BETWEEN $1 AND $2 AND deleted_at IS NULL
+           GROUP BY fee_tax_regime ORDER BY fee_tax_regime"#,
+    ).bind(from_date).bind(to_date).fetch_all(&state.pool).await?;
+    let commissions_by_tax_regime: Vec<serde_json::Value> = by_regime.into_iter().map(|r| serde_json::json!({
+        "regime": r.0, "bookings": r.1, "net": r.2.unwrap_or(Decimal::ZERO), "vat": r.3.unwrap_or(Decimal::ZERO), "gross": r.4.unwrap_or(Decimal::ZERO)
+    })).collect();
+
     Ok((StatusCode::OK, Json(serde_json::json!({
-        "data": { "total_bookings": stats.0, "total_revenue": stats.1, "total_commissions": stats.2, "total_reviews": stats.3, "average_rating": stats.4, "date_from": from_date, "date_to": to_date }
+        "data": {
+            "total_bookings": stats.0, "total_revenue": stats.1, "total_commissions": stats.2, "total_reviews": stats.3, "average_rating": stats.4,
+            "total_revenue_excl_vat": tax.0.unwrap_or(Decimal::ZERO), "total_revenue_vat": tax.1.unwrap_or(Decimal::ZERO),
+            "total_commissions_excl_vat": tax.2.unwrap_or(Decimal::ZERO), "total_commissions_vat": tax.3.unwrap_or(Decimal::ZERO),
+            "commissions_by_tax_regime": commissions_by_tax_regime,
+            "date_from": from_date, "date_to": to_date
+        }
     }))))
 }
 
+// ═══════════════════════════════════════════════════════════
+//  RECONCILIATION (local ledger vs Stripe balance)
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+struct ReconciliationBody { date_from: String, date_to: String }
+
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+struct ReconciliationRunRow { id: Uuid, period_start: chrono::DateTime<chrono::Utc>, period_end: chrono::DateTime<chrono::Utc>, status: String, stripe_entries: i32, local_entries: i32, discrepancy_count: i32, error: Option<String>, started_by: Option<Uuid>, started_at: chrono::DateTime<chrono::Utc>, completed_at: Option<chrono::DateTime<chrono::Utc>> }
+
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+struct DiscrepancyRow { id: Uuid, kind: String, booking_id: Option<Uuid>, local_ref: Option<String>, stripe_ref: Option<String>, local_amount: Option<Decimal>, stripe_amount: Option<Decimal>, currency: Option<String>, details: String }
+
+/// Runs synchronously over `[date_from, date_to]` (inclusive days, UTC).
+async fn run_reconciliation(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Json(body): Json<ReconciliationBody>) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let parse = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|_| AppError::BadRequest("Invalid date format, expected YYYY-MM-DD".to_owned()));
+    let from = parse(&body.date_from)?.and_time(chrono::NaiveTime::MIN).and_utc();
+    let to = (parse(&body.date_to)? + chrono::Duration::days(1)).and_time(chrono::NaiveTime::MIN).and_utc();
+    if to - from > chrono::Duration::days(93) {
+        return Err(AppError::BadRequest("Reconciliation period is limited to 93 days".to_owned()));
+    }
+    let summary = reconciliation::run(&state.pool, state.payments.as_ref(), from, to, Some(claims.sub)).await?;
+    audit.record(&mut *state.pool.acquire().await?, claims.sub, "reconciliation.run", Entity::ReconciliationRun, summary.run_id, None).await?;
+    Ok((StatusCode::CREATED, Json(serde_json::json!({ "data": summary }))))
+}
+
+async fn list_reconciliation_runs(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let rows = sqlx::query_as::<_, ReconciliationRunRow>("SELECT id, period_start, period_end, status, stripe_entries, local_entries, discrepancy_count, error, started_by, started_at, completed_at FROM reconciliation_runs ORDER BY started_at DESC LIMIT 100").fetch_all(&state.pool).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": rows }))))
+}
+
+async fn get_reconciliation_run(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(run_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let run = sqlx::query_as::<_, ReconciliationRunRow>("SELECT id, period_start, period_end, status, stripe_entries, local_entries, discrepancy_count, error, started_by, started_at, completed_at FROM reconciliation_runs WHERE id = $1")
+        .bind(run_id).fetch_optional(&state.pool).await?
+        .ok_or_else(|| AppError::NotFound("Reconciliation run not found".to_owned()))?;
+    let discrepancies = sqlx::query_as::<_, DiscrepancyRow>("SELECT id, kind, booking_id, local_ref, stripe_ref, local_amount, stripe_amount, currency, details FROM reconciliation_discrepancies WHERE run_id = $1 ORDER BY kind, booking_id")
+        .bind(run_id).fetch_all(&state.pool).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": { "run": run, "discrepancies": discrepancies } }))))
+}
+
+// ═══════════════════════════════════════════════════════════
+//  EMAIL OUTBOX (delivery status, dead letters)
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+struct EmailListQuery { status: Option<String>, limit: Option<i64> }
+
+async fn list_emails(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Query(q): Query<EmailListQuery>) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    if let Some(ref status) = q.status {
+        if !["pending", "sending", "sent", "dead"].contains(&status.as_str()) {
+            return Err(AppError::BadRequest("status must be one of: pending, sending, sent, dead".to_owned()));
+        }
+    }
+    let rows = outbox::list(&state.pool, q.status.as_deref(), q.limit.unwrap_or(100).clamp(1, 500)).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": rows }))))
+}
+
+async fn get_email(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(email_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let email = outbox::get(&state.pool, email_id).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": email }))))
+}
+
+/// Requeues a dead message with a fresh set of attempts; the next outbox job sends it.
+async fn resend_email(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(email_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let before = audit::snapshot(&state.pool, Entity::Email, email_id).await?;
+    let email = outbox::resend(&state.pool, email_id).await?;
+    audit.record(&mut *state.pool.acquire().await?, claims.sub, "email.resend", Entity::Email, email_id, before).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "message": "Email requeued", "data": email }))))
+}
+
+// ═══════════════════════════════════════════════════════════
+//  CONTACT INBOX (platform messages; center messages go to their members)
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+struct ContactReplyBody { body: String, #[serde(default)] close: bool }
+
+#[derive(Debug, Deserialize)]
+struct ContactStatusBody { status: String }
+
+async fn list_contact_messages(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Query(q): Query<EmailListQuery>) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let rows = contact::list(&state.pool, Inbox::Platform, q.status.as_deref(), q.limit.unwrap_or(100)).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": rows }))))
+}
+
+async fn get_contact_message(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(message_id): Path<Uuid>) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let thread = contact::get(&state.pool, Inbox::Platform, message_id).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": thread }))))
+}
+
+/// Emails the reply to the sender; their answer comes back to SMTP_FROM.
+async fn reply_contact_message(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(message_id): Path<Uuid>, Json(body): Json<ContactReplyBody>) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let before = audit::snapshot(&state.pool, Entity::ContactMessage, message_id).await?;
+    let reply = contact::reply(&state.pool, Inbox::Platform, message_id, claims.sub, &body.body, state.config.smtp_from.as_deref(), body.close).await?;
+    audit.record(&mut *state.pool.acquire().await?, claims.sub, "contact.reply", Entity::ContactMessage, message_id, before).await?;
+    Ok((StatusCode::CREATED, Json(serde_json::json!({ "data": reply }))))
+}
+
+async fn update_contact_status(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path(message_id): Path<Uuid>, Json(body): Json<ContactStatusBody>) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let before = audit::snapshot(&state.pool, Entity::ContactMessage, message_id).await?;
+    let message = contact::set_status(&state.pool, Inbox::Platform, message_id, &body.status).await?;
+    audit.record(&mut *state.pool.acquire().await?, claims.sub, "contact.update_status", Entity::ContactMessage, message_id, before).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": message }))))
+}
+
+// ═══════════════════════════════════════════════════════════
+//  AUDIT LOG (read-only; entries are written by the mutating handlers)
+// ═══════════════════════════════════════════════════════════
+
+/// Filters: actor_id, action (`center.` for a prefix), entity_type, entity_id,
+/// center_id, request_id, from, to; page with before_id and limit.
+async fn list_audit_log(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Query(filter): Query<audit::AuditFilter>) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let rows = audit::list(&state.pool, &filter).await?;
+    let next_before_id = rows.last().map(|e| e.id);
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": rows, "next_before_id": next_before_id }))))
+}
+
+async fn get_audit_entry(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Path(entry_id): Path<i64>) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let entry = audit::get(&state.pool, entry_id).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": entry }))))
+}
+
+// ═══════════════════════════════════════════════════════════
+//  ACCOUNTING (FEC export, chart of accounts in /settings/accounting)
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+struct FecQuery { date_from: String, date_to: String }
+
+/// Fiscal period `[date_from, date_to]` (inclusive days, UTC), at most 24 months.
+async fn export_fec(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Query(params): Query<FecQuery>) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let parse = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|_| AppError::BadRequest("Invalid date format, expected YYYY-MM-DD".to_owned()));
+    let (first_day, last_day) = (parse(&params.date_from)?, parse(&params.date_to)?);
+    if last_day < first_day || last_day - first_day > chrono::Duration::days(731) {
+        return Err(AppError::BadRequest("FEC period must be between 1 day and 24 months".to_owned()));
+    }
+    let siren = fec::company_siren(&state.pool).await?;
+    let chart = fec::ChartOfAccounts::load(&state.pool).await?;
+    let from = first_day.and_time(chrono::NaiveTime::MIN).and_utc();
+    let to = (last_day + chrono::Duration::days(1)).and_time(chrono::NaiveTime::MIN).and_utc();
+    let entries = fec::load_entries(&state.pool, &chart, from, to).await?;
+
+    let disposition = format!("attachment; filename=\"{}\"", fec::file_name(&siren, last_day));
+    Ok((StatusCode::OK, [(header::CONTENT_TYPE, "text/plain; charset=utf-8".to_owned()), (header::CONTENT_DISPOSITION, disposition)], fec::render(&entries)))
+}
+
+/// Effective chart of accounts, defaults included.
+async fn get_chart_of_accounts(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let chart = fec::ChartOfAccounts::load(&state.pool).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": chart }))))
+}
+
+#[derive(Debug, Deserialize)]
+struct ExchangeRatesQuery { currency: Option<String> }
+
+/// Rates used to convert foreign-currency movements in the FEC, newest first.
+async fn list_exchange_rates(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, Query(params): Query<ExchangeRatesQuery>) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let rates = fec::list_rates(&state.pool, params.currency.as_deref()).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": rates }))))
+}
+
+#[derive(Debug, Deserialize)]
+struct ExchangeRateBody { rate: Decimal }
+
+/// Set the rate of `currency` on `date` (units for one euro, ECB convention).
+async fn put_exchange_rate(State(state): State<Arc<AppState>>, AuthUser(claims): AuthUser, audit: Audit, Path((currency, date)): Path<(String, String)>, Json(body): Json<ExchangeRateBody>) -> Result<impl IntoResponse, AppError> {
+    require_admin(&state.pool, claims.sub).await?;
+    let rate_date = chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| AppError::BadRequest("Invalid date format, expected YYYY-MM-DD".to_owned()))?;
+    let currency = fec::validate_rate(&currency, body.rate)?;
+    let mut tx = state.pool.begin().await?;
+    let existing: Option<Uuid> = sqlx::query_scalar("SELECT id FROM exchange_rates WHERE currency = $1 AND rate_date = $2 FOR UPDATE")
+        .bind(&currency).bind(rate_date).fetch_optional(&mut *tx).await?;
+    let before = match existing {
+        Some(id) => audit::snapshot(&mut *tx, Entity::ExchangeRate, id).await?,
+        None => None,
+    };
+    let id: Uuid = sqlx::query_scalar(
+        r#"INSERT INTO exchange_rates (currency, rate_date, rate, updated_by) VALUES ($1, $2, $3, $4)
+           ON CONFLICT (currency, rate_date) DO UPDATE SET rate = EXCLUDED.rate, updated_by = EXCLUDED.updated_by, updated_at = NOW()
+           RETURNING id"#,
+    )
+    .bind(&currency).bind(rate_date).bind(body.rate).bind(claims.sub).fetch_one(&mut *tx).await?;
+    audit.record(&mut tx, claims.sub, "exchange_rate.update", Entity::ExchangeRate, id, before).await?;
+    tx.commit().await?;
+    let rate = fec::ExchangeRate { currency, rate_date, rate: body.rate };
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": rate }))))
+}
+
 // ═══════════════════════════════════════════════════════════
 //  PLANNINGS (calendar from bookings)
 // ═══════════════════════════════════════════════════════════
@@ -764,13 +1306,16 @@ struct SettingEntry {
 async fn update_settings_by_cat(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     axum::extract::OriginalUri(uri): axum::extract::OriginalUri,
     Json(body): Json<SettingsBulkBody>,
 ) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
     let category = uri.path().rsplit('/').next().unwrap_or("general");
 
+    let mut tx = state.pool.begin().await?;
     for entry in &body.settings {
+        let before = audit::snapshot(&mut *tx, Entity::Setting, &entry.key).await?;
         sqlx::query(
             "INSERT INTO t_platform_config (key, value, category, is_secret) VALUES ($1, $2, $3, false) ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value, updated_at = NOW(), updated_by = $4",
         )
@@ -778,9 +1323,11 @@ async fn update_settings_by_cat(
         .bind(&entry.value)
         .bind(category)
         .bind(claims.sub)
-        .execute(&state.pool)
+        .execute(&mut *tx)
         .await?;
+        audit.record(&mut tx, claims.sub, "setting.update", Entity::Setting, &entry.key, before).await?;
     }
+    tx.commit().await?;
 
     Ok((StatusCode::OK, Json(serde_json::json!({ "message": format!("{} settings updated", body.settings.len()) }))))
 }
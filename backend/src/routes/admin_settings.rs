@@ -13,8 +13,10 @@ use axum::{Json, Router};
 use serde::{Deserialize, Serialize};
 
 use crate::error::AppError;
+use crate::middleware::audit::Audit;
 use crate::middleware::auth::{require_admin, AuthUser};
 use crate::models::PlatformConfig;
+use crate::services::audit::{self, Entity};
 use crate::AppState;
 
 /// A config entry with the value masked if it is a secret.
@@ -93,11 +95,14 @@ struct UpdateSettingBody {
 async fn update_setting(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path(key): Path<String>,
     Json(body): Json<UpdateSettingBody>,
 ) -> Result<impl IntoResponse, AppError> {
     require_admin(&state.pool, claims.sub).await?;
 
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Setting, &key).await?;
     let result = sqlx::query(
         r#"
         UPDATE t_platform_config
@@ -108,7 +113,7 @@ async fn update_setting(
     .bind(&body.value)
     .bind(claims.sub)
     .bind(&key)
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -116,6 +121,8 @@ async fn update_setting(
             "Configuration key '{key}' not found"
         )));
     }
+    audit.record(&mut tx, claims.sub, "setting.update", Entity::Setting, &key, before).await?;
+    tx.commit().await?;
 
     Ok((
         StatusCode::OK,
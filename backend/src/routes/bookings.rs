@@ -12,7 +12,16 @@ use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::error::AppError;
-use crate::middleware::auth::{require_center_member, AuthUser};
+use crate::middleware::auth::{require_center_capability, require_not_banned, AuthUser};
+use crate::models::{Money, RoundingMode};
+use crate::services::email::Template;
+use crate::services::notifications::{self, Kind};
+use crate::services::bans::Scope;
+use crate::services::{checkout, connect, outbox};
+use crate::services::invoices::{self, DocumentKind};
+use crate::services::payment_gateway::{CheckoutRequest, PaymentIntentRequest};
+use crate::services::permissions::Capability;
+use crate::services::tax;
 use crate::AppState;
 
 /// Read the platform commission rate from `t_platform_config` (key = `commission_rate`).
@@ -40,6 +49,8 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/bookings/{booking_id}/cancel", post(cancel_booking))
         .route("/bookings/{booking_id}/confirm", post(confirm_booking))
         .route("/bookings/{booking_id}/checkout", post(checkout_booking))
+        .route("/bookings/{booking_id}/payment-intent", post(create_payment_intent))
+        .route("/bookings/{booking_id}/receipt", get(get_booking_receipt))
 }
 
 // ──────────────────────── Types ────────────────────────
@@ -96,6 +107,8 @@ async fn create_booking(
     AuthUser(claims): AuthUser,
     Json(body): Json<CreateBookingBody>,
 ) -> Result<impl IntoResponse, AppError> {
+    require_not_banned(&state.pool, claims.sub, Scope::Booking).await?;
+
     let booking_date = chrono::NaiveDate::parse_from_str(&body.booking_date, "%Y-%m-%d")
         .map_err(|_| AppError::BadRequest("Invalid date format, expected YYYY-MM-DD".to_owned()))?;
 
@@ -186,18 +199,34 @@ async fn create_booking(
     }
 
     let unit_price = service.price;
-    let total_price = unit_price * Decimal::from(body.participants);
+    let total = Money::new(unit_price * Decimal::from(body.participants), &service.currency)
+        .map_err(|e| AppError::Internal(format!("Invalid service price: {e}")))?
+        .round(RoundingMode::HalfUp);
     let rate = platform_commission_rate(&state.pool).await?;
-    let commission_amount = total_price * rate / Decimal::from(100);
+    let commission = total.percentage(rate, RoundingMode::HalfUp);
+    // Like the webhook: a center without a known country must not keep the
+    // client from booking; the breakdown is logged and stored as NULL.
+    let tax = tax::breakdown_for_center(&state.pool, body.center_id, &total, &commission).await?;
+    if tax.is_none() {
+        tracing::warn!(center_id = %body.center_id, "Tax breakdown unavailable: center country unknown");
+    }
+    let total_price = total.amount();
+    let commission_amount = commission.amount();
 
+    let mut tx = state.pool.begin().await?;
     let booking_id: Uuid = sqlx::query_scalar(
         r#"
         INSERT INTO bookings (
             client_id, center_id, service_id, booking_date, time_slot,
             participants, unit_price, total_price, commission_rate, commission_amount,
-            currency, client_note, status
+            currency, client_note, status,
+            tax_country, price_vat_rate, price_net, price_vat,
+            fee_vat_rate, fee_net, fee_vat, fee_tax_regime
+        )
+        VALUES (
+            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, 'pending',
+            $13, $14, $15, $16, $17, $18, $19, $20
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, 'pending')
         RETURNING id
         "#,
     )
@@ -213,9 +242,21 @@ async fn create_booking(
     .bind(commission_amount)
     .bind(&service.currency)
     .bind(body.client_note.as_deref().map(str::trim))
-    .fetch_one(&state.pool)
+    .bind(tax.as_ref().map(|t| t.country.as_str()))
+    .bind(tax.as_ref().map(|t| t.price.rate))
+    .bind(tax.as_ref().map(|t| t.price.net))
+    .bind(tax.as_ref().map(|t| t.price.vat))
+    .bind(tax.as_ref().map(|t| t.fee.rate))
+    .bind(tax.as_ref().map(|t| t.fee.net))
+    .bind(tax.as_ref().map(|t| t.fee.vat))
+    .bind(tax.as_ref().map(|t| t.fee_regime.as_str()))
+    .fetch_one(&mut *tx)
     .await?;
 
+    outbox::enqueue_booking_client(&mut tx, booking_id, Template::BookingCreated, Vec::new()).await?;
+    notifications::booking_event(&mut tx, booking_id, Kind::BookingRequested, Some(claims.sub)).await?;
+    tx.commit().await?;
+
     Ok((
         StatusCode::CREATED,
         Json(serde_json::json!({
@@ -223,7 +264,8 @@ async fn create_booking(
                 "id": booking_id,
                 "status": "pending",
                 "total_price": total_price,
-                "currency": service.currency
+                "currency": service.currency,
+                "tax": tax
             }
         })),
     ))
@@ -319,12 +361,41 @@ async fn get_booking_by_id(
 
     // Allow access if user is the client or a center member
     if row.client_id != claims.sub {
-        require_center_member(&state.pool, claims.sub, row.center_id).await?;
+        require_center_capability(&state.pool, claims.sub, row.center_id, Capability::ViewBookings).await?;
     }
 
     Ok((StatusCode::OK, Json(serde_json::json!({ "data": row }))))
 }
 
+// ──────────────────────── Receipt ────────────────────────
+
+/// `GET /api/v1/bookings/{booking_id}/receipt` — download the payment receipt
+/// as PDF (client or center member). Issued on first request if the webhook
+/// did not already do it.
+async fn get_booking_receipt(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(booking_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let (client_id, center_id): (Uuid, Uuid) = sqlx::query_as(
+        "SELECT client_id, center_id FROM bookings WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(booking_id)
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Booking not found".to_owned()))?;
+
+    if client_id != claims.sub {
+        require_center_capability(&state.pool, claims.sub, center_id, Capability::ViewBookings).await?;
+    }
+
+    let receipt = invoices::find_or_issue(&state.pool, booking_id, DocumentKind::Receipt)
+        .await?
+        .ok_or_else(|| AppError::NotFound("No payment recorded for this booking".to_owned()))?;
+
+    invoices::pdf_response(&receipt)
+}
+
 // ──────────────────────── Cancel ────────────────────────
 
 /// `POST /api/v1/bookings/{booking_id}/cancel` — cancel a booking.
@@ -346,7 +417,7 @@ async fn cancel_booking(
     .ok_or_else(|| AppError::NotFound("Booking not found".to_owned()))?;
 
     if booking.0 != claims.sub {
-        require_center_member(&state.pool, claims.sub, booking.1).await?;
+        require_center_capability(&state.pool, claims.sub, booking.1, Capability::ManageBookings).await?;
     }
 
     if booking.2 == "cancelled" || booking.2 == "completed" {
@@ -356,23 +427,38 @@ async fn cancel_booking(
         )));
     }
 
-    let result = sqlx::query(
+    let mut tx = state.pool.begin().await?;
+    let cancelled = sqlx::query_scalar::<_, Option<String>>(
         r#"
         UPDATE bookings
         SET status = 'cancelled'::booking_status,
             cancelled_at = NOW(),
             updated_at = NOW()
         WHERE id = $1 AND status IN ('pending', 'confirmed')
+        RETURNING payment_intent_id
         "#,
     )
     .bind(booking_id)
-    .execute(&state.pool)
+    .fetch_optional(&mut *tx)
     .await?;
 
-    if result.rows_affected() == 0 {
+    let Some(payment_intent_id) = cancelled else {
         return Err(AppError::Conflict(
             "Booking status changed concurrently".to_owned(),
         ));
+    };
+
+    let session = checkout::clear_session(&mut *tx, booking_id, None).await?;
+    outbox::enqueue_booking_client(&mut tx, booking_id, Template::BookingCancelled, Vec::new()).await?;
+    notifications::booking_event(&mut tx, booking_id, Kind::BookingCancelled, Some(claims.sub)).await?;
+    tx.commit().await?;
+
+    // A cancelled booking must not be payable any more.
+    if let Some(ref session) = session {
+        checkout::expire_quietly(state.payments.as_ref(), session).await;
+    }
+    if let Some(ref pi_id) = payment_intent_id {
+        checkout::cancel_payment_intent_quietly(state.payments.as_ref(), pi_id).await;
     }
 
     Ok((
@@ -397,7 +483,7 @@ async fn confirm_booking(
     .await?
     .ok_or_else(|| AppError::NotFound("Booking not found".to_owned()))?;
 
-    require_center_member(&state.pool, claims.sub, booking.0).await?;
+    require_center_capability(&state.pool, claims.sub, booking.0, Capability::ManageBookings).await?;
 
     if booking.1 != "pending" {
         return Err(AppError::BadRequest(format!(
@@ -406,6 +492,7 @@ async fn confirm_booking(
         )));
     }
 
+    let mut tx = state.pool.begin().await?;
     let result = sqlx::query(
         r#"
         UPDATE bookings
@@ -416,7 +503,7 @@ async fn confirm_booking(
         "#,
     )
     .bind(booking_id)
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -425,6 +512,10 @@ async fn confirm_booking(
         ));
     }
 
+    outbox::enqueue_booking_client(&mut tx, booking_id, Template::BookingConfirmed, Vec::new()).await?;
+    notifications::booking_event(&mut tx, booking_id, Kind::BookingConfirmed, Some(claims.sub)).await?;
+    tx.commit().await?;
+
     Ok((
         StatusCode::OK,
         Json(serde_json::json!({ "message": "Booking confirmed", "status": "confirmed" })),
@@ -442,37 +533,47 @@ struct CheckoutBookingRow {
     currency: String,
     status: String,
     service_name: String,
+    payment_intent_id: Option<String>,
 }
 
-/// `POST /api/v1/bookings/{booking_id}/checkout` — create a Stripe Checkout Session.
-///
-/// Returns a checkout URL the frontend uses to redirect the customer to Stripe.
-/// Metadata (`booking_id`) is attached to both the session and the PaymentIntent
-/// so the webhook handler can correlate the payment with the booking.
-///
-/// If the center has a connected Stripe account, the payment is routed there
-/// with the platform commission deducted as `application_fee_amount`.
-async fn checkout_booking(
-    State(state): State<Arc<AppState>>,
-    AuthUser(claims): AuthUser,
-    Path(booking_id): Path<Uuid>,
-) -> Result<impl IntoResponse, AppError> {
+/// Amount, routing and metadata of a booking payment, shared by the Checkout
+/// redirect and the embedded PaymentIntent flow.
+struct BookingCharge {
+    total: Money,
+    description: String,
+    /// Connected account receiving the funds, when the center is onboarded.
+    destination_account: Option<String>,
+    /// Platform commission, withheld only on destination charges.
+    application_fee: Option<Money>,
+    /// `booking_id` (and `destination_account` on destination charges),
+    /// copied to the PaymentIntent so webhooks can find the booking.
+    metadata: std::collections::HashMap<String, String>,
+}
+
+/// Lock a pending booking of `client_id` for the rest of the transaction and
+/// compute what the diver has to pay.
+async fn lock_payable_booking(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    booking_id: Uuid,
+    client_id: Uuid,
+) -> Result<(CheckoutBookingRow, BookingCharge), AppError> {
     let booking = sqlx::query_as::<_, CheckoutBookingRow>(
         r#"
         SELECT b.client_id, b.center_id, b.total_price, b.commission_amount,
                COALESCE(b.currency, 'EUR') AS currency, b.status::text AS status,
-               COALESCE(s.name, 'Dive booking') AS service_name
+               COALESCE(s.name, 'Dive booking') AS service_name, b.payment_intent_id
         FROM bookings b
         LEFT JOIN services s ON s.id = b.service_id AND s.deleted_at IS NULL
         WHERE b.id = $1 AND b.deleted_at IS NULL
+        FOR UPDATE OF b
         "#,
     )
     .bind(booking_id)
-    .fetch_optional(&state.pool)
+    .fetch_optional(&mut **tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Booking not found".to_owned()))?;
 
-    if booking.client_id != claims.sub {
+    if booking.client_id != client_id {
         return Err(AppError::Forbidden);
     }
     if booking.status != "pending" {
@@ -482,20 +583,102 @@ async fn checkout_booking(
         )));
     }
 
-    let amount_cents = (booking.total_price * Decimal::from(100))
-        .to_string()
-        .parse::<i64>()
-        .map_err(|_| AppError::Internal("Invalid total price conversion".to_owned()))?;
+    let total = Money::new(booking.total_price, &booking.currency)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    let commission = Money::new(booking.commission_amount, &booking.currency)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
 
-    let commission_cents = (booking.commission_amount * Decimal::from(100))
-        .to_string()
-        .parse::<i64>()
-        .map_err(|_| AppError::Internal("Invalid commission amount conversion".to_owned()))?;
+    let destination_account = connect::load(&mut **tx, booking.center_id)
+        .await?
+        .checkout_destination()?;
 
-    let currency_str = booking.currency.to_lowercase();
-    let currency: stripe::Currency = currency_str
-        .parse()
-        .map_err(|_| AppError::Internal(format!("Unsupported currency: {}", booking.currency)))?;
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("booking_id".to_owned(), booking_id.to_string());
+    if let Some(ref account) = destination_account {
+        metadata.insert("destination_account".to_owned(), account.clone());
+    }
+
+    let charge = BookingCharge {
+        total,
+        description: booking.service_name.clone(),
+        application_fee: destination_account.as_ref().map(|_| commission),
+        destination_account,
+        metadata,
+    };
+    Ok((booking, charge))
+}
+
+/// Make sure the booking's PaymentIntent can no longer be paid and forget it.
+/// Refuses when the diver already paid it or the payment is in flight.
+async fn release_payment_intent(
+    state: &AppState,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    booking_id: Uuid,
+    payment_intent_id: &str,
+) -> Result<(), AppError> {
+    let intent = state.payments.retrieve_payment_intent(payment_intent_id).await?;
+    if intent.is_payable() {
+        state.payments.cancel_payment_intent(payment_intent_id).await?;
+    } else if intent.status != "canceled" {
+        return Err(AppError::Conflict(
+            "A payment for this booking is already being processed".to_owned(),
+        ));
+    }
+
+    sqlx::query("UPDATE bookings SET payment_intent_id = NULL, updated_at = NOW() WHERE id = $1")
+        .bind(booking_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// `POST /api/v1/bookings/{booking_id}/checkout` — create a Stripe Checkout Session.
+///
+/// Returns a checkout URL the frontend uses to redirect the customer to Stripe.
+/// Metadata (`booking_id`) is attached to both the session and the PaymentIntent
+/// so the webhook handler can correlate the payment with the booking.
+///
+/// If the center has a connected Stripe account, the payment is routed there
+/// with the platform commission deducted as `application_fee_amount`.
+///
+/// The session is stored on the booking and returned again while it is still
+/// valid for the current price; otherwise the old session is expired before a
+/// new one is created. An embedded PaymentIntent opened for the booking is
+/// canceled first.
+async fn checkout_booking(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(booking_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut tx = state.pool.begin().await?;
+    let (booking, charge) = lock_payable_booking(&mut tx, booking_id, claims.sub).await?;
+    let stored = checkout::lock_session(&mut tx, booking_id).await?;
+
+    let now = chrono::Utc::now();
+    if let Some(ref session) = stored {
+        if session.is_reusable(booking.total_price, now) {
+            tx.commit().await?;
+            return Ok((
+                StatusCode::OK,
+                Json(serde_json::json!({ "data": {
+                    "checkout_url": session.checkout_session_url,
+                    "expires_at": session.checkout_session_expires_at,
+                } })),
+            ));
+        }
+        // Stale (price changed or about to expire): make sure it can no
+        // longer be paid before handing out a new one.
+        if session.is_open(now) {
+            state
+                .payments
+                .expire_checkout_session(&session.checkout_session_id)
+                .await?;
+        }
+    }
+
+    if let Some(ref pi_id) = booking.payment_intent_id {
+        release_payment_intent(&state, &mut tx, booking_id, pi_id).await?;
+    }
 
     let base_url = state
         .config
@@ -508,70 +691,98 @@ async fn checkout_booking(
     let success_url = format!("{base_url}/bookings/{booking_id}?status=success");
     let cancel_url = format!("{base_url}/bookings/{booking_id}?status=cancelled");
 
-    let mut metadata = std::collections::HashMap::new();
-    metadata.insert("booking_id".to_owned(), booking_id.to_string());
+    let session = state
+        .payments
+        .create_checkout_session(CheckoutRequest {
+            amount: charge.total,
+            product_name: charge.description,
+            application_fee: charge.application_fee,
+            destination_account: charge.destination_account,
+            success_url,
+            cancel_url,
+            metadata: charge.metadata,
+        })
+        .await?;
 
-    let connect_account_id = sqlx::query_as::<_, (Option<String>, Option<bool>)>(
-        "SELECT stripe_account_id, stripe_onboarding_complete FROM centers WHERE id = $1 AND deleted_at IS NULL",
-    )
-    .bind(booking.center_id)
-    .fetch_optional(&state.pool)
-    .await?
-    .and_then(|(acct_id, onboarded)| match (acct_id, onboarded) {
-        (Some(id), Some(true)) if !id.is_empty() => Some(id),
-        _ => None,
-    });
-
-    let mut payment_intent_data = stripe::CreateCheckoutSessionPaymentIntentData {
-        metadata: Some(metadata.clone()),
-        ..Default::default()
-    };
+    checkout::store_session(&mut tx, booking_id, &session, booking.total_price).await?;
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({ "data": {
+            "checkout_url": session.url,
+            "expires_at": session.expires_at,
+        } })),
+    ))
+}
 
-    if let Some(ref acct_id) = connect_account_id {
-        payment_intent_data.application_fee_amount = Some(commission_cents);
-        payment_intent_data.transfer_data = Some(
-            stripe::CreateCheckoutSessionPaymentIntentDataTransferData {
-                destination: acct_id.clone(),
-                ..Default::default()
-            },
-        );
+/// `POST /api/v1/bookings/{booking_id}/payment-intent` — create or return the
+/// booking's PaymentIntent for an embedded Stripe Elements form.
+///
+/// Same amount, `booking_id` metadata and destination-charge routing as
+/// [`checkout_booking`]. A PaymentIntent that is still payable for the current
+/// price is returned again; an open Checkout Session is expired first so the
+/// booking can only be paid through one of the two flows.
+async fn create_payment_intent(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(booking_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut tx = state.pool.begin().await?;
+    let (booking, charge) = lock_payable_booking(&mut tx, booking_id, claims.sub).await?;
+
+    if let Some(ref pi_id) = booking.payment_intent_id {
+        let intent = state.payments.retrieve_payment_intent(pi_id).await?;
+        if intent.is_payable() && intent.amount == charge.total {
+            tx.commit().await?;
+            return Ok((
+                StatusCode::OK,
+                Json(serde_json::json!({ "data": {
+                    "payment_intent_id": intent.id,
+                    "client_secret": intent.client_secret,
+                    "amount": intent.amount.amount(),
+                    "currency": intent.amount.currency(),
+                } })),
+            ));
+        }
+        release_payment_intent(&state, &mut tx, booking_id, pi_id).await?;
     }
 
-    let params = stripe::CreateCheckoutSession {
-        mode: Some(stripe::CheckoutSessionMode::Payment),
-        line_items: Some(vec![stripe::CreateCheckoutSessionLineItems {
-            price_data: Some(stripe::CreateCheckoutSessionLineItemsPriceData {
-                currency,
-                unit_amount: Some(amount_cents),
-                product_data: Some(
-                    stripe::CreateCheckoutSessionLineItemsPriceDataProductData {
-                        name: booking.service_name,
-                        ..Default::default()
-                    },
-                ),
-                ..Default::default()
-            }),
-            quantity: Some(1),
-            ..Default::default()
-        }]),
-        metadata: Some(metadata),
-        payment_intent_data: Some(payment_intent_data),
-        success_url: Some(&success_url),
-        cancel_url: Some(&cancel_url),
-        ..Default::default()
-    };
+    if let Some(session) = checkout::clear_session(&mut *tx, booking_id, None).await? {
+        if session.is_open(chrono::Utc::now()) {
+            state
+                .payments
+                .expire_checkout_session(&session.checkout_session_id)
+                .await?;
+        }
+    }
 
-    let session = stripe::CheckoutSession::create(&state.stripe, params)
-        .await
-        .map_err(|e| AppError::Internal(format!("Stripe Checkout Session creation failed: {e}")))?;
+    let intent = state
+        .payments
+        .create_payment_intent(PaymentIntentRequest {
+            amount: charge.total,
+            description: charge.description,
+            destination_account: charge.destination_account,
+            application_fee: charge.application_fee,
+            metadata: charge.metadata,
+        })
+        .await?;
 
-    let checkout_url = session
-        .url
-        .ok_or_else(|| AppError::Internal("Stripe returned a session without a URL".to_owned()))?;
+    sqlx::query("UPDATE bookings SET payment_intent_id = $1, updated_at = NOW() WHERE id = $2")
+        .bind(&intent.id)
+        .bind(booking_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
 
     Ok((
-        StatusCode::OK,
-        Json(serde_json::json!({ "data": { "checkout_url": checkout_url } })),
+        StatusCode::CREATED,
+        Json(serde_json::json!({ "data": {
+            "payment_intent_id": intent.id,
+            "client_secret": intent.client_secret,
+            "amount": intent.amount.amount(),
+            "currency": intent.amount.currency(),
+        } })),
     ))
 }
 
@@ -0,0 +1,224 @@
+//! Center webhook settings: endpoints receiving signed booking and payment
+//! events, their delivery log and manual redelivery.
+//!
+//! Managing webhooks takes `manage_integrations` (owners by default); secrets are returned on creation
+//! and rotation only.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{get, patch, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::middleware::auth::{require_center_capability, AuthUser};
+use crate::services::permissions::Capability;
+use crate::services::webhooks::{self, EndpointChanges, NewEndpoint};
+use crate::AppState;
+
+/// Resolve slug to center_id.
+async fn resolve_center_id(pool: &sqlx::PgPool, slug: &str) -> Result<Uuid, AppError> {
+    sqlx::query_scalar("SELECT id FROM centers WHERE slug = $1 AND deleted_at IS NULL")
+        .bind(slug)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Center '{slug}' not found")))
+}
+
+/// Resolve the center and check the caller may manage its integrations.
+async fn managed_center(state: &AppState, user_id: Uuid, slug: &str) -> Result<Uuid, AppError> {
+    let center_id = resolve_center_id(&state.pool, slug).await?;
+    require_center_capability(&state.pool, user_id, center_id, Capability::ManageIntegrations).await?;
+    Ok(center_id)
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/centers/{slug}/webhooks",
+            get(list_endpoints).post(create_endpoint),
+        )
+        .route(
+            "/centers/{slug}/webhooks/{endpoint_id}",
+            patch(update_endpoint).delete(delete_endpoint),
+        )
+        .route(
+            "/centers/{slug}/webhooks/{endpoint_id}/rotate-secret",
+            post(rotate_secret),
+        )
+        .route(
+            "/centers/{slug}/webhooks/{endpoint_id}/deliveries",
+            get(list_deliveries),
+        )
+        .route(
+            "/centers/{slug}/webhook-deliveries/{delivery_id}",
+            get(get_delivery),
+        )
+        .route(
+            "/centers/{slug}/webhook-deliveries/{delivery_id}/redeliver",
+            post(redeliver),
+        )
+}
+
+// ──────────────────────── Endpoints ────────────────────────
+
+/// `GET /api/v1/centers/{slug}/webhooks`
+async fn list_endpoints(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(slug): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = managed_center(&state, claims.sub, &slug).await?;
+    let endpoints = webhooks::list_endpoints(&state.pool, center_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({ "data": endpoints, "event_types": webhooks::EVENT_TYPES })),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateEndpointBody {
+    url: String,
+    description: Option<String>,
+    event_types: Vec<String>,
+}
+
+/// `POST /api/v1/centers/{slug}/webhooks` — the response holds the signing
+/// secret, which is not shown again.
+async fn create_endpoint(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(slug): Path<String>,
+    Json(body): Json<CreateEndpointBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = managed_center(&state, claims.sub, &slug).await?;
+    let (endpoint, secret) = webhooks::create_endpoint(
+        &state.pool,
+        NewEndpoint {
+            center_id,
+            url: body.url,
+            description: body.description,
+            event_types: body.event_types,
+            created_by: claims.sub,
+        },
+    )
+    .await?;
+
+    let mut data = serde_json::to_value(&endpoint)
+        .map_err(|e| AppError::Internal(format!("Serialize endpoint: {e}")))?;
+    data["secret"] = secret.into();
+    Ok((StatusCode::CREATED, Json(serde_json::json!({ "data": data }))))
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateEndpointBody {
+    url: Option<String>,
+    description: Option<String>,
+    event_types: Option<Vec<String>>,
+    is_active: Option<bool>,
+}
+
+/// `PATCH /api/v1/centers/{slug}/webhooks/{endpoint_id}`
+async fn update_endpoint(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path((slug, endpoint_id)): Path<(String, Uuid)>,
+    Json(body): Json<UpdateEndpointBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = managed_center(&state, claims.sub, &slug).await?;
+    let endpoint = webhooks::update_endpoint(
+        &state.pool,
+        center_id,
+        endpoint_id,
+        EndpointChanges {
+            url: body.url,
+            description: body.description,
+            event_types: body.event_types,
+            is_active: body.is_active,
+        },
+    )
+    .await?;
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": endpoint }))))
+}
+
+/// `DELETE /api/v1/centers/{slug}/webhooks/{endpoint_id}`
+async fn delete_endpoint(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path((slug, endpoint_id)): Path<(String, Uuid)>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = managed_center(&state, claims.sub, &slug).await?;
+    webhooks::delete_endpoint(&state.pool, center_id, endpoint_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// `POST /api/v1/centers/{slug}/webhooks/{endpoint_id}/rotate-secret`
+async fn rotate_secret(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path((slug, endpoint_id)): Path<(String, Uuid)>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = managed_center(&state, claims.sub, &slug).await?;
+    let secret = webhooks::rotate_secret(&state.pool, center_id, endpoint_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({ "data": { "id": endpoint_id, "secret": secret } })),
+    ))
+}
+
+// ──────────────────────── Delivery log ────────────────────────
+
+#[derive(Debug, Deserialize)]
+struct DeliveriesQuery {
+    status: Option<String>,
+    limit: Option<i64>,
+}
+
+/// `GET /api/v1/centers/{slug}/webhooks/{endpoint_id}/deliveries?status=&limit=`
+async fn list_deliveries(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path((slug, endpoint_id)): Path<(String, Uuid)>,
+    Query(query): Query<DeliveriesQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = managed_center(&state, claims.sub, &slug).await?;
+    let deliveries = webhooks::list_deliveries(
+        &state.pool,
+        center_id,
+        endpoint_id,
+        query.status.as_deref(),
+        query.limit.unwrap_or(50),
+    )
+    .await?;
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": deliveries }))))
+}
+
+/// `GET /api/v1/centers/{slug}/webhook-deliveries/{delivery_id}` — with payload.
+async fn get_delivery(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path((slug, delivery_id)): Path<(String, Uuid)>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = managed_center(&state, claims.sub, &slug).await?;
+    let delivery = webhooks::get_delivery(&state.pool, center_id, delivery_id).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": delivery }))))
+}
+
+/// `POST /api/v1/centers/{slug}/webhook-deliveries/{delivery_id}/redeliver`
+async fn redeliver(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path((slug, delivery_id)): Path<(String, Uuid)>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = managed_center(&state, claims.sub, &slug).await?;
+    let delivery = webhooks::redeliver(&state.pool, center_id, delivery_id).await?;
+    Ok((StatusCode::CREATED, Json(serde_json::json!({ "data": delivery }))))
+}
@@ -9,8 +9,9 @@ use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::error::AppError;
-use crate::middleware::auth::{require_center_member, AuthUser};
+use crate::middleware::auth::{require_center_capability, AuthUser};
 use crate::models::{Center, CenterSummary};
+use crate::services::permissions::Capability;
 use crate::AppState;
 
 /// Build the `/centers` sub-router.
@@ -442,7 +443,7 @@ struct UpdateCenterBody {
 
 /// `PATCH /api/v1/centers/{slug}`
 ///
-/// Center members can update their center's editable fields.
+/// Members with `manage_center` can update their center's editable fields.
 async fn update_center(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
@@ -459,8 +460,8 @@ async fn update_center(
     let center_id =
         center_id.ok_or_else(|| AppError::NotFound(format!("Center '{slug}' not found")))?;
 
-    // Verify the user is a member of this center
-    require_center_member(&state.pool, claims.sub, center_id).await?;
+    // Verify the user may edit this center
+    require_center_capability(&state.pool, claims.sub, center_id, Capability::ManageCenter).await?;
 
     sqlx::query(
         r#"
@@ -1,16 +1,23 @@
+//! Contact form and the center contact inbox.
+//!
+//! The form is public. Messages addressed to a center are answered by its
+//! members from `/centers/{slug}/contact-messages`; platform messages are
+//! answered from the admin inbox (`/admin/contact-messages`).
+
 use std::sync::Arc;
 
-use axum::extract::State;
-use axum::http::StatusCode;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
-use axum::routing::post;
-use axum::Router;
-use lettre::message::header::ContentType;
-use lettre::message::Mailbox;
-use lettre::{AsyncTransport, Message};
+use axum::routing::{get, patch, post};
+use axum::{Json, Router};
 use serde::Deserialize;
+use uuid::Uuid;
 
 use crate::error::AppError;
+use crate::middleware::auth::{require_center_capability, AuthUser};
+use crate::services::contact::{self, ContactForm, Inbox, Origin};
+use crate::services::permissions::Capability;
 use crate::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -19,82 +26,158 @@ pub struct ContactRequest {
     email: String,
     subject: String,
     message: String,
+    center_id: Option<Uuid>,
+    /// Honeypot, left empty by people.
+    website: Option<String>,
+}
+
+/// Resolve slug to center_id.
+async fn resolve_center_id(pool: &sqlx::PgPool, slug: &str) -> Result<Uuid, AppError> {
+    sqlx::query_scalar("SELECT id FROM centers WHERE slug = $1 AND deleted_at IS NULL")
+        .bind(slug)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Center '{slug}' not found")))
+}
+
+/// Resolve the center and check the caller belongs to it.
+async fn member_inbox(state: &AppState, user_id: Uuid, slug: &str) -> Result<Inbox, AppError> {
+    let center_id = resolve_center_id(&state.pool, slug).await?;
+    require_center_capability(&state.pool, user_id, center_id, Capability::ManageMessages).await?;
+    Ok(Inbox::Center(center_id))
 }
 
 pub fn router() -> Router<Arc<AppState>> {
-    Router::new().route("/contact", post(submit_contact))
+    Router::new()
+        .route("/contact", post(submit_contact))
+        .route("/centers/{slug}/contact-messages", get(list_messages))
+        .route("/centers/{slug}/contact-messages/{message_id}", get(get_message))
+        .route("/centers/{slug}/contact-messages/{message_id}/reply", post(reply_message))
+        .route("/centers/{slug}/contact-messages/{message_id}/status", patch(update_status))
 }
 
+// ──────────────────────── Contact form ────────────────────────
+
+/// `POST /api/v1/contact` — public. Throttled per IP and per email (429).
 async fn submit_contact(
     State(state): State<Arc<AppState>>,
-    axum::Json(payload): axum::Json<ContactRequest>,
+    headers: HeaderMap,
+    Json(payload): Json<ContactRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    if payload.name.trim().is_empty()
-        || payload.email.trim().is_empty()
-        || payload.subject.trim().is_empty()
-        || payload.message.trim().is_empty()
-    {
-        return Err(AppError::BadRequest(
-            "All fields are required".to_owned(),
-        ));
-    }
-
-    if !payload.email.contains('@') || !payload.email.contains('.') {
-        return Err(AppError::BadRequest("Invalid email address".to_owned()));
+    let form = ContactForm {
+        name: payload.name,
+        email: payload.email,
+        subject: payload.subject,
+        message: payload.message,
+        center_id: payload.center_id,
+        website: payload.website,
+    };
+    if form.is_spam() {
+        tracing::info!("Contact form honeypot filled, message dropped");
+        return Ok((StatusCode::OK, Json(serde_json::json!({ "status": "queued" }))));
     }
 
-    let mailer = state
-        .mailer
-        .as_ref()
-        .ok_or_else(|| AppError::Internal("Email service is not configured".to_owned()))?;
-
-    let smtp_from = state
-        .config
-        .smtp_from
-        .as_deref()
-        .ok_or_else(|| AppError::Internal("SMTP_FROM is not configured".to_owned()))?;
-
-    let from: Mailbox = smtp_from
-        .parse()
-        .map_err(|e| AppError::Internal(format!("Invalid SMTP_FROM: {e}")))?;
-
-    let reply_to: Mailbox = payload
-        .email
-        .parse()
-        .map_err(|_| AppError::BadRequest("Invalid email address".to_owned()))?;
-
-    let to: Mailbox = smtp_from
-        .parse()
-        .map_err(|e| AppError::Internal(format!("Invalid SMTP_FROM for recipient: {e}")))?;
-
-    let body = format!(
-        "New contact form submission\n\
-         \n\
-         Name: {}\n\
-         Email: {}\n\
-         Subject: {}\n\
-         \n\
-         Message:\n\
-         {}",
-        payload.name, payload.email, payload.subject, payload.message
-    );
-
-    let email = Message::builder()
-        .from(from)
-        .reply_to(reply_to)
-        .to(to)
-        .subject(format!("[EviDive Contact] {}", payload.subject))
-        .header(ContentType::TEXT_PLAIN)
-        .body(body)
-        .map_err(|e| AppError::Internal(format!("Failed to build email: {e}")))?;
-
-    mailer
-        .send(email)
-        .await
-        .map_err(|e| AppError::Internal(format!("Failed to send email: {e}")))?;
+    let message = contact::submit(
+        &state.pool,
+        &form,
+        &Origin::from_headers(&headers, state.config.ip_hash_secret.as_deref()),
+        state.config.smtp_from.as_deref(),
+    )
+    .await?;
 
     Ok((
         StatusCode::OK,
-        axum::Json(serde_json::json!({ "status": "sent" })),
+        Json(serde_json::json!({ "status": "queued", "reference": message.reference })),
     ))
 }
+
+// ──────────────────────── Center inbox ────────────────────────
+
+#[derive(Debug, Deserialize)]
+struct InboxQuery {
+    status: Option<String>,
+    limit: Option<i64>,
+}
+
+/// `GET /api/v1/centers/{slug}/contact-messages?status=&limit=`
+async fn list_messages(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(slug): Path<String>,
+    Query(query): Query<InboxQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let inbox = member_inbox(&state, claims.sub, &slug).await?;
+    let messages = contact::list(
+        &state.pool,
+        inbox,
+        query.status.as_deref(),
+        query.limit.unwrap_or(50),
+    )
+    .await?;
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": messages }))))
+}
+
+/// `GET /api/v1/centers/{slug}/contact-messages/{message_id}` — with replies.
+async fn get_message(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path((slug, message_id)): Path<(String, Uuid)>,
+) -> Result<impl IntoResponse, AppError> {
+    let inbox = member_inbox(&state, claims.sub, &slug).await?;
+    let thread = contact::get(&state.pool, inbox, message_id).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": thread }))))
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplyBody {
+    body: String,
+    #[serde(default)]
+    close: bool,
+}
+
+/// `POST /api/v1/centers/{slug}/contact-messages/{message_id}/reply`
+///
+/// The sender's answers go to the center's public address.
+async fn reply_message(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path((slug, message_id)): Path<(String, Uuid)>,
+    Json(body): Json<ReplyBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let inbox = member_inbox(&state, claims.sub, &slug).await?;
+    let center_email: Option<String> = sqlx::query_scalar("SELECT email FROM centers WHERE slug = $1")
+        .bind(&slug)
+        .fetch_optional(&state.pool)
+        .await?
+        .flatten();
+    let reply = contact::reply(
+        &state.pool,
+        inbox,
+        message_id,
+        claims.sub,
+        &body.body,
+        center_email.as_deref(),
+        body.close,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(serde_json::json!({ "data": reply }))))
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusBody {
+    status: String,
+}
+
+/// `PATCH /api/v1/centers/{slug}/contact-messages/{message_id}/status`
+async fn update_status(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path((slug, message_id)): Path<(String, Uuid)>,
+    Json(body): Json<StatusBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let inbox = member_inbox(&state, claims.sub, &slug).await?;
+    let message = contact::set_status(&state.pool, inbox, message_id, &body.status).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": message }))))
+}
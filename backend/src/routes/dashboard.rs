@@ -12,14 +12,16 @@ use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::error::AppError;
-use crate::middleware::auth::{require_center_member, AuthUser};
+use crate::middleware::auth::{require_center_capability, AuthUser};
+use crate::services::permissions::Capability;
 use crate::AppState;
 
-/// Resolve slug to center_id and verify membership.
+/// Resolve slug to center_id and verify the caller holds `capability`.
 async fn resolve_center(
     pool: &sqlx::PgPool,
     slug: &str,
     user_id: Uuid,
+    capability: Capability,
 ) -> Result<Uuid, AppError> {
     let center_id: Option<Uuid> =
         sqlx::query_scalar("SELECT id FROM centers WHERE slug = $1 AND deleted_at IS NULL")
@@ -28,7 +30,7 @@ async fn resolve_center(
             .await?;
     let center_id =
         center_id.ok_or_else(|| AppError::NotFound(format!("Center '{slug}' not found")))?;
-    require_center_member(pool, user_id, center_id).await?;
+    require_center_capability(pool, user_id, center_id, capability).await?;
     Ok(center_id)
 }
 
@@ -78,7 +80,7 @@ async fn get_kpis(
     AuthUser(claims): AuthUser,
     Path(slug): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ViewFinance).await?;
 
     let booking_stats = sqlx::query_as::<_, (i64, i64, i64, i64, i64)>(
         r#"
@@ -179,7 +181,7 @@ async fn get_calendar(
     Path(slug): Path<String>,
     Query(params): Query<CalendarQuery>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ViewBookings).await?;
 
     let today = chrono::Utc::now().date_naive();
     let date_from = params
@@ -235,7 +237,7 @@ async fn list_blocked_dates(
     AuthUser(claims): AuthUser,
     Path(slug): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ViewBookings).await?;
 
     let rows = sqlx::query_as::<_, BlockedDateRow>(
         "SELECT id, blocked_date, reason, created_at FROM blocked_dates WHERE center_id = $1 ORDER BY blocked_date ASC",
@@ -264,7 +266,7 @@ async fn create_blocked_date(
     Path(slug): Path<String>,
     Json(body): Json<CreateBlockedDateBody>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ManageSchedule).await?;
 
     let date_str = body
         .blocked_date
@@ -296,7 +298,7 @@ async fn delete_blocked_date(
     AuthUser(claims): AuthUser,
     Path((slug, date_id)): Path<(String, Uuid)>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ManageSchedule).await?;
 
     let result =
         sqlx::query("DELETE FROM blocked_dates WHERE id = $1 AND center_id = $2")
@@ -330,7 +332,7 @@ async fn list_holidays(
     AuthUser(claims): AuthUser,
     Path(slug): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ViewBookings).await?;
 
     let rows = sqlx::query_as::<_, HolidayRow>(
         "SELECT id, staff_id, title, start_date, end_date, created_at FROM holidays WHERE center_id = $1 ORDER BY start_date ASC",
@@ -360,7 +362,7 @@ async fn create_holiday(
     Path(slug): Path<String>,
     Json(body): Json<CreateHolidayBody>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ManageSchedule).await?;
 
     let start = chrono::NaiveDate::parse_from_str(&body.start_date, "%Y-%m-%d")
         .map_err(|_| AppError::BadRequest("Invalid start_date".to_owned()))?;
@@ -404,7 +406,7 @@ async fn delete_holiday(
     AuthUser(claims): AuthUser,
     Path((slug, holiday_id)): Path<(String, Uuid)>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ManageSchedule).await?;
 
     let result =
         sqlx::query("DELETE FROM holidays WHERE id = $1 AND center_id = $2")
@@ -0,0 +1,82 @@
+//! Server-Sent Events stream of real-time updates.
+//!
+//! Dashboards listen here instead of polling the calendar and booking
+//! lists. Browsers' `EventSource` cannot send an `Authorization` header, so
+//! clients use a fetch-based implementation with the usual bearer token.
+
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::get;
+use axum::Router;
+use futures_util::stream::{self, Stream, StreamExt};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::error::AppError;
+use crate::middleware::auth::AuthUser;
+use crate::services::events::{self, AppEvent, Subscription};
+use crate::AppState;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/events", get(stream_events))
+}
+
+fn sse_event(event: &AppEvent) -> Event {
+    Event::default()
+        .event(event.kind.as_str())
+        .json_data(event)
+        .unwrap_or_else(|_| Event::default().event(event.kind.as_str()))
+}
+
+/// `GET /api/v1/events` — auth, `text/event-stream` of the events of the
+/// user's centers (bookings, payments, reviews, team changes) and of the
+/// user's own bookings and notifications.
+///
+/// The first event is `ready`, with the centers followed. A `resync` event
+/// means events may have been missed and the client should refetch.
+async fn stream_events(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let receiver = state.events.subscribe(&state.pool).await?;
+    let subscription = Subscription {
+        user_id: claims.sub,
+        centers: events::member_centers(&state.pool, claims.sub).await?,
+    };
+
+    let ready = Event::default()
+        .event("ready")
+        .json_data(serde_json::json!({ "centers": subscription.centers }))
+        .unwrap_or_else(|_| Event::default().event("ready"));
+
+    let updates = stream::unfold(
+        (receiver, subscription, state),
+        |(mut receiver, mut subscription, state)| async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::debug!(skipped, "SSE client lagging, asking for resync");
+                        AppEvent::resync()
+                    }
+                    Err(RecvError::Closed) => return None,
+                };
+
+                if subscription.needs_reload(&event) {
+                    match events::member_centers(&state.pool, subscription.user_id).await {
+                        Ok(centers) => subscription.centers = centers,
+                        Err(e) => tracing::warn!(error = ?e, "Failed to reload SSE memberships"),
+                    }
+                }
+                if subscription.accepts(&event) {
+                    return Some((Ok(sse_event(&event)), (receiver, subscription, state)));
+                }
+            }
+        },
+    );
+
+    let stream = stream::iter([Ok(ready)]).chain(updates);
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
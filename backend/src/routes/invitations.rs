@@ -0,0 +1,214 @@
+//! Center invitations: invite people by email to join a center's team, and
+//! accept an invitation from its link.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::middleware::audit::Audit;
+use crate::middleware::auth::{require_center_capability, AuthUser};
+use crate::services::audit::{self, Entity};
+use crate::services::invitations;
+use crate::services::permissions::Capability;
+use crate::AppState;
+
+/// Resolve slug to center_id.
+async fn resolve_center_id(pool: &sqlx::PgPool, slug: &str) -> Result<Uuid, AppError> {
+    sqlx::query_scalar("SELECT id FROM centers WHERE slug = $1 AND deleted_at IS NULL")
+        .bind(slug)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Center '{slug}' not found")))
+}
+
+/// Frontend origin the invitation links point to.
+fn base_url(state: &AppState) -> &str {
+    state
+        .config
+        .cors_origin
+        .split(',')
+        .next()
+        .unwrap_or_default()
+        .trim()
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/centers/{slug}/invitations",
+            get(list_invitations).post(create_invitation),
+        )
+        .route("/centers/{slug}/invitations/{invitation_id}", delete(revoke_invitation))
+        .route("/centers/{slug}/invitations/{invitation_id}/resend", post(resend_invitation))
+        .route("/invitations/{token}", get(get_invitation))
+        .route("/invitations/{token}/accept", post(accept_invitation))
+}
+
+// ──────────────────────── Center side ────────────────────────
+
+/// `GET /api/v1/centers/{slug}/invitations`
+///
+/// Requires `manage_members`.
+async fn list_invitations(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(slug): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = resolve_center_id(&state.pool, &slug).await?;
+    require_center_capability(&state.pool, claims.sub, center_id, Capability::ManageMembers).await?;
+
+    let invitations = invitations::list(&state.pool, center_id).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": invitations }))))
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateInvitationBody {
+    email: String,
+    /// "manager", "employee" or "staff"
+    role_in_center: String,
+    /// Language of the email; the inviter's by default
+    locale: Option<String>,
+}
+
+/// `POST /api/v1/centers/{slug}/invitations`
+///
+/// Requires `manage_members` and every capability of the invited role.
+async fn create_invitation(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    audit: Audit,
+    Path(slug): Path<String>,
+    Json(body): Json<CreateInvitationBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = resolve_center_id(&state.pool, &slug).await?;
+    let caller =
+        require_center_capability(&state.pool, claims.sub, center_id, Capability::ManageMembers).await?;
+
+    let role = body.role_in_center.trim().to_lowercase();
+    caller.check_can_assign(&role)?;
+    let mut tx = state.pool.begin().await?;
+    let invitation = invitations::create(
+        &mut tx,
+        center_id,
+        claims.sub,
+        &body.email,
+        &role,
+        body.locale.as_deref(),
+        base_url(&state),
+    )
+    .await?;
+    audit.record(&mut tx, claims.sub, "member.invite", Entity::Invitation, invitation.id, None).await?;
+    tx.commit().await?;
+    Ok((StatusCode::CREATED, Json(serde_json::json!({ "data": invitation }))))
+}
+
+/// `POST /api/v1/centers/{slug}/invitations/{invitation_id}/resend`
+///
+/// Requires `manage_members`. The previous link stops working.
+async fn resend_invitation(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    audit: Audit,
+    Path((slug, invitation_id)): Path<(String, Uuid)>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = resolve_center_id(&state.pool, &slug).await?;
+    require_center_capability(&state.pool, claims.sub, center_id, Capability::ManageMembers).await?;
+
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Invitation, invitation_id).await?;
+    let invitation = invitations::resend(&mut tx, center_id, invitation_id, base_url(&state)).await?;
+    audit.record(&mut tx, claims.sub, "invitation.resend", Entity::Invitation, invitation_id, before).await?;
+    tx.commit().await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": invitation }))))
+}
+
+/// `DELETE /api/v1/centers/{slug}/invitations/{invitation_id}`
+///
+/// Requires `manage_members`.
+async fn revoke_invitation(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    audit: Audit,
+    Path((slug, invitation_id)): Path<(String, Uuid)>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = resolve_center_id(&state.pool, &slug).await?;
+    require_center_capability(&state.pool, claims.sub, center_id, Capability::ManageMembers).await?;
+
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Invitation, invitation_id).await?;
+    let invitation = invitations::revoke(&mut tx, center_id, invitation_id).await?;
+    audit.record(&mut tx, claims.sub, "invitation.revoke", Entity::Invitation, invitation_id, before).await?;
+    tx.commit().await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": invitation }))))
+}
+
+// ──────────────────────── Invitee side ────────────────────────
+
+/// `GET /api/v1/invitations/{token}` — public, so the page can show what
+/// the invitation is before the invitee signs up or logs in.
+async fn get_invitation(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let invitation = invitations::find_by_token(&state.pool, &token).await?;
+    let status = if invitation.status == "pending" && invitation.is_expired(chrono::Utc::now()) {
+        "expired"
+    } else {
+        invitation.status.as_str()
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "data": {
+                "center_name": invitation.center_name,
+                "inviter_name": invitation.inviter_name,
+                "email": invitation.email,
+                "role_in_center": invitation.role_in_center,
+                "status": status,
+                "expires_at": invitation.expires_at,
+            }
+        })),
+    ))
+}
+
+/// `POST /api/v1/invitations/{token}/accept` — the caller must be signed in
+/// with the invited address.
+async fn accept_invitation(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    audit: Audit,
+    Path(token): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut tx = state.pool.begin().await?;
+    let pending = invitations::find_by_token(&mut *tx, &token).await?;
+    let before = audit::snapshot(&mut *tx, Entity::Invitation, pending.id).await?;
+    let (invitation, member_id) = invitations::accept(&mut tx, &token, claims.sub).await?;
+    audit.record(&mut tx, claims.sub, "invitation.accept", Entity::Invitation, invitation.id, before).await?;
+    audit.record(&mut tx, claims.sub, "member.add", Entity::Member, member_id, None).await?;
+    tx.commit().await?;
+    let slug: Option<String> = sqlx::query_scalar("SELECT slug FROM centers WHERE id = $1")
+        .bind(invitation.center_id)
+        .fetch_optional(&state.pool)
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "data": {
+                "id": member_id,
+                "fk_profile": claims.sub,
+                "fk_center": invitation.center_id,
+                "center_slug": slug,
+                "role_in_center": invitation.role_in_center
+            }
+        })),
+    ))
+}
@@ -0,0 +1,135 @@
+//! Scheduled job endpoints, triggered by Vercel Cron (see `vercel.json`).
+//!
+//! Every route requires `Authorization: Bearer <CRON_SECRET>`. When
+//! `CRON_SECRET` is not configured, all jobs answer 401.
+
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+
+use crate::error::AppError;
+use crate::services::{outbox, payouts, reconciliation, reminders, webhooks};
+use crate::AppState;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/reconciliation", get(run_daily_reconciliation))
+        .route("/payouts", get(run_scheduled_payouts))
+        .route("/email-outbox", get(deliver_email_outbox))
+        .route("/dive-reminders", get(send_dive_reminders))
+        .route("/webhooks", get(deliver_webhooks))
+}
+
+/// Check the cron bearer token in constant time.
+fn require_cron(state: &AppState, headers: &HeaderMap) -> Result<(), AppError> {
+    let expected = state.config.cron_secret.as_deref().ok_or(AppError::Unauthorized)?;
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or(AppError::Unauthorized)?;
+
+    let matches = provided.len() == expected.len()
+        && provided
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0;
+    if matches {
+        Ok(())
+    } else {
+        Err(AppError::Unauthorized)
+    }
+}
+
+// ──────────────────────── Reconciliation ────────────────────────
+
+/// `GET /api/v1/jobs/reconciliation` — cron, reconcile the previous UTC day.
+async fn run_daily_reconciliation(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    require_cron(&state, &headers)?;
+
+    let today = chrono::Utc::now().date_naive();
+    let to = today.and_time(chrono::NaiveTime::MIN).and_utc();
+    let from = to - chrono::Duration::days(1);
+
+    let summary =
+        reconciliation::run(&state.pool, state.payments.as_ref(), from, to, None).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "data": {
+                "run_id": summary.run_id,
+                "discrepancy_count": summary.discrepancy_count,
+            }
+        })),
+    ))
+}
+
+// ──────────────────────── Scheduled payouts ────────────────────────
+
+/// `GET /api/v1/jobs/payouts` — cron, create the payouts due today (UTC) for
+/// centers with a weekly or monthly schedule. Safe to re-run.
+async fn run_scheduled_payouts(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    require_cron(&state, &headers)?;
+
+    let today = chrono::Utc::now().date_naive();
+    let run = payouts::run_scheduled(&state.pool, state.payments.as_ref(), today).await?;
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": run }))))
+}
+
+// ──────────────────────── Email outbox ────────────────────────
+
+/// `GET /api/v1/jobs/email-outbox` — cron, send due outbox messages.
+async fn deliver_email_outbox(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    require_cron(&state, &headers)?;
+
+    let run = outbox::deliver_due(&state).await?;
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": run }))))
+}
+
+// ──────────────────────── Dive reminders ────────────────────────
+
+/// `GET /api/v1/jobs/dive-reminders` — cron, remind the clients of confirmed
+/// bookings whose dive starts within their center's reminder window. Safe to
+/// re-run: each booking is reminded once.
+async fn send_dive_reminders(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    require_cron(&state, &headers)?;
+
+    let run = reminders::send_due(&state.pool).await?;
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": run }))))
+}
+
+// ──────────────────────── Center webhooks ────────────────────────
+
+/// `GET /api/v1/jobs/webhooks` — cron, send due center webhook deliveries.
+async fn deliver_webhooks(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    require_cron(&state, &headers)?;
+
+    let client = webhooks::http_client()?;
+    let run = webhooks::deliver_due(&state.pool, &client).await?;
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": run }))))
+}
@@ -1,20 +1,29 @@
 //! Center members routes: manage users linked to a center via tli_pr_ce.
 //!
-//! Only center owners can add, update, or remove members.
-//! All center members can list members.
+//! Adding, updating and removing members and their permissions takes
+//! `manage_members` (owners by default). Only owners can change other
+//! owners, and a non-owner cannot grant capabilities they do not hold.
+//! All center members can list members and read the role matrix.
 
 use std::sync::Arc;
 
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
-use axum::routing::get;
+use axum::routing::{get, put};
 use axum::{Json, Router};
 use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::error::AppError;
-use crate::middleware::auth::{require_center_member, require_center_owner, AuthUser};
+use crate::middleware::audit::Audit;
+use crate::middleware::auth::{require_center_capability, require_center_member, AuthUser};
+use crate::services::audit::{self, Entity};
+use crate::services::email::Template;
+use crate::services::invitations;
+use crate::services::notifications::{self, Kind};
+use crate::services::outbox;
+use crate::services::permissions::{self, Capability, MemberPermissions, ASSIGNABLE_ROLES};
 use crate::AppState;
 
 /// Resolve slug to center_id.
@@ -36,6 +45,55 @@ pub fn router() -> Router<Arc<AppState>> {
             "/centers/{slug}/members/{member_id}",
             axum::routing::patch(update_member_role).delete(remove_member),
         )
+        .route(
+            "/centers/{slug}/members/{member_id}/permissions",
+            put(update_member_permissions),
+        )
+        .route("/centers/{slug}/permissions", get(get_permissions))
+}
+
+/// The member row `member_id` of a center: profile and permissions.
+async fn load_member(
+    pool: &sqlx::PgPool,
+    center_id: Uuid,
+    member_id: Uuid,
+) -> Result<(Uuid, MemberPermissions), AppError> {
+    let row: Option<(Uuid, String, Vec<String>, Vec<String>)> = sqlx::query_as(
+        r#"
+        SELECT fk_profile, role_in_center, granted_capabilities, revoked_capabilities
+        FROM tli_pr_ce WHERE id = $1 AND fk_center = $2
+        "#,
+    )
+    .bind(member_id)
+    .bind(center_id)
+    .fetch_optional(pool)
+    .await?;
+    let (profile, role_in_center, granted_capabilities, revoked_capabilities) =
+        row.ok_or_else(|| AppError::NotFound("Member not found".to_owned()))?;
+    Ok((
+        profile,
+        MemberPermissions {
+            role_in_center,
+            granted_capabilities,
+            revoked_capabilities,
+        },
+    ))
+}
+
+fn check_assignable_role(role: &str) -> Result<(), AppError> {
+    if role == "owner" {
+        return Err(AppError::BadRequest(
+            "Cannot assign 'owner' role. Use an ownership transfer instead.".to_owned(),
+        ));
+    }
+    if !ASSIGNABLE_ROLES.contains(&role) {
+        return Err(AppError::BadRequest(format!(
+            "Invalid role '{}'. Allowed: {}",
+            role,
+            ASSIGNABLE_ROLES.join(", ")
+        )));
+    }
+    Ok(())
 }
 
 // ──────────────────────── Types ────────────────────────
@@ -46,6 +104,11 @@ struct MemberRow {
     fk_profile: Uuid,
     fk_center: Uuid,
     role_in_center: String,
+    granted_capabilities: Vec<String>,
+    revoked_capabilities: Vec<String>,
+    /// Effective capabilities: role defaults with the overrides applied
+    #[sqlx(skip)]
+    capabilities: Vec<Capability>,
     created_at: chrono::DateTime<chrono::Utc>,
     /// Joined from profiles table
     profile_email: Option<String>,
@@ -64,13 +127,15 @@ async fn list_members(
     let center_id = resolve_center_id(&state.pool, &slug).await?;
     require_center_member(&state.pool, claims.sub, center_id).await?;
 
-    let rows = sqlx::query_as::<_, MemberRow>(
+    let mut rows = sqlx::query_as::<_, MemberRow>(
         r#"
         SELECT
             m.id,
             m.fk_profile,
             m.fk_center,
             m.role_in_center,
+            m.granted_capabilities,
+            m.revoked_capabilities,
             m.created_at,
             u.email AS profile_email,
             p.first_name AS profile_first_name,
@@ -85,6 +150,14 @@ async fn list_members(
     .bind(center_id)
     .fetch_all(&state.pool)
     .await?;
+    for row in &mut rows {
+        row.capabilities = MemberPermissions {
+            role_in_center: row.role_in_center.clone(),
+            granted_capabilities: row.granted_capabilities.clone(),
+            revoked_capabilities: row.revoked_capabilities.clone(),
+        }
+        .effective();
+    }
 
     Ok((StatusCode::OK, Json(serde_json::json!({ "data": rows }))))
 }
@@ -93,23 +166,29 @@ async fn list_members(
 
 #[derive(Debug, Deserialize)]
 struct AddMemberBody {
-    /// Email of the user to add. Must be an existing profile.
+    /// Email of the user to add. Addresses without an account are invited.
     email: String,
-    /// Role to assign: "employee", "manager", etc. Cannot be "owner".
+    /// Role to assign: "manager", "employee" or "staff". Cannot be "owner".
     role_in_center: String,
+    /// Language of the invitation email, if one is sent
+    locale: Option<String>,
 }
 
 /// `POST /api/v1/centers/{slug}/members`
 ///
-/// Only the center owner can add members.
+/// Requires `manage_members` and every capability of the role being given.
+/// When nobody has an account with that email,
+/// an invitation is sent instead (`202 Accepted`, see `routes::invitations`).
 async fn add_member(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path(slug): Path<String>,
     Json(body): Json<AddMemberBody>,
 ) -> Result<impl IntoResponse, AppError> {
     let center_id = resolve_center_id(&state.pool, &slug).await?;
-    require_center_owner(&state.pool, claims.sub, center_id).await?;
+    let caller =
+        require_center_capability(&state.pool, claims.sub, center_id, Capability::ManageMembers).await?;
 
     let email = body.email.trim().to_lowercase();
     if email.is_empty() {
@@ -119,19 +198,8 @@ async fn add_member(
     }
 
     let role = body.role_in_center.trim().to_lowercase();
-    if role == "owner" {
-        return Err(AppError::BadRequest(
-            "Cannot assign 'owner' role. Transfer ownership is not yet supported.".to_owned(),
-        ));
-    }
-    let allowed_roles = ["employee", "manager"];
-    if !allowed_roles.contains(&role.as_str()) {
-        return Err(AppError::BadRequest(format!(
-            "Invalid role '{}'. Allowed: {}",
-            role,
-            allowed_roles.join(", ")
-        )));
-    }
+    check_assignable_role(&role)?;
+    caller.check_can_assign(&role)?;
 
     // Find the user by email in auth.users, then verify they have a profile
     let profile_id: Option<Uuid> = sqlx::query_scalar(
@@ -145,11 +213,32 @@ async fn add_member(
     .fetch_optional(&state.pool)
     .await?;
 
-    let profile_id = profile_id.ok_or_else(|| {
-        AppError::NotFound(format!(
-            "No user found with email '{email}'. They must create an account first."
-        ))
-    })?;
+    let Some(profile_id) = profile_id else {
+        let base_url = state
+            .config
+            .cors_origin
+            .split(',')
+            .next()
+            .unwrap_or_default()
+            .trim();
+        let mut tx = state.pool.begin().await?;
+        let invitation = invitations::create(
+            &mut tx,
+            center_id,
+            claims.sub,
+            &email,
+            &role,
+            body.locale.as_deref(),
+            base_url,
+        )
+        .await?;
+        audit.record(&mut tx, claims.sub, "member.invite", Entity::Invitation, invitation.id, None).await?;
+        tx.commit().await?;
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({ "data": { "invitation": invitation } })),
+        ));
+    };
 
     // Check if already a member
     let exists: bool = sqlx::query_scalar(
@@ -167,6 +256,7 @@ async fn add_member(
     }
 
     // Add the member
+    let mut tx = state.pool.begin().await?;
     let id: Uuid = sqlx::query_scalar(
         r#"
         INSERT INTO tli_pr_ce (fk_profile, fk_center, role_in_center)
@@ -177,9 +267,19 @@ async fn add_member(
     .bind(profile_id)
     .bind(center_id)
     .bind(&role)
-    .fetch_one(&state.pool)
+    .fetch_one(&mut *tx)
     .await?;
 
+    let center_name: Option<String> = sqlx::query_scalar("SELECT name FROM centers WHERE id = $1")
+        .bind(center_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let vars = vec![("center_name", center_name.unwrap_or_default().into())];
+    notifications::notify(&mut tx, profile_id, Kind::MemberAdded, &vars, Some(notifications::TEAM_LINK)).await?;
+    outbox::enqueue_template(&mut tx, profile_id, Template::MemberAdded, vars).await?;
+    audit.record(&mut tx, claims.sub, "member.add", Entity::Member, id, None).await?;
+    tx.commit().await?;
+
     Ok((
         StatusCode::CREATED,
         Json(serde_json::json!({
@@ -202,61 +302,51 @@ struct UpdateRoleBody {
 
 /// `PATCH /api/v1/centers/{slug}/members/{member_id}`
 ///
-/// Only the center owner can update a member's role.
+/// Requires `manage_members` and every capability of the new role; only
+/// owners can change another owner's role.
 async fn update_member_role(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path((slug, member_id)): Path<(String, Uuid)>,
     Json(body): Json<UpdateRoleBody>,
 ) -> Result<impl IntoResponse, AppError> {
     let center_id = resolve_center_id(&state.pool, &slug).await?;
-    require_center_owner(&state.pool, claims.sub, center_id).await?;
+    let caller =
+        require_center_capability(&state.pool, claims.sub, center_id, Capability::ManageMembers).await?;
 
     let role = body.role_in_center.trim().to_lowercase();
-    if role == "owner" {
-        return Err(AppError::BadRequest(
-            "Cannot assign 'owner' role via this endpoint.".to_owned(),
-        ));
-    }
-    let allowed_roles = ["employee", "manager"];
-    if !allowed_roles.contains(&role.as_str()) {
-        return Err(AppError::BadRequest(format!(
-            "Invalid role '{}'. Allowed: {}",
-            role,
-            allowed_roles.join(", ")
-        )));
-    }
+    check_assignable_role(&role)?;
+    caller.check_can_assign(&role)?;
 
-    // Prevent modifying the owner's own membership
-    let target_profile: Option<Uuid> = sqlx::query_scalar(
-        "SELECT fk_profile FROM tli_pr_ce WHERE id = $1 AND fk_center = $2",
-    )
-    .bind(member_id)
-    .bind(center_id)
-    .fetch_optional(&state.pool)
-    .await?;
-
-    let target_profile =
-        target_profile.ok_or_else(|| AppError::NotFound("Member not found".to_owned()))?;
+    // Prevent modifying one's own membership
+    let (target_profile, target) = load_member(&state.pool, center_id, member_id).await?;
 
     if target_profile == claims.sub {
         return Err(AppError::BadRequest(
             "You cannot change your own role.".to_owned(),
         ));
     }
+    if target.is_owner() && !caller.is_owner() {
+        return Err(AppError::Forbidden);
+    }
 
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Member, member_id).await?;
     let result = sqlx::query(
         "UPDATE tli_pr_ce SET role_in_center = $1 WHERE id = $2 AND fk_center = $3",
     )
     .bind(&role)
     .bind(member_id)
     .bind(center_id)
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("Member not found".to_owned()));
     }
+    audit.record(&mut tx, claims.sub, "member.update_role", Entity::Member, member_id, before).await?;
+    tx.commit().await?;
 
     Ok((
         StatusCode::OK,
@@ -268,42 +358,159 @@ async fn update_member_role(
 
 /// `DELETE /api/v1/centers/{slug}/members/{member_id}`
 ///
-/// Only the center owner can remove members. The owner cannot remove themselves.
+/// Requires `manage_members`; only owners can remove another owner. Nobody
+/// can remove themselves.
 async fn remove_member(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Path((slug, member_id)): Path<(String, Uuid)>,
 ) -> Result<impl IntoResponse, AppError> {
     let center_id = resolve_center_id(&state.pool, &slug).await?;
-    require_center_owner(&state.pool, claims.sub, center_id).await?;
-
-    // Prevent the owner from removing themselves
-    let target_profile: Option<Uuid> = sqlx::query_scalar(
-        "SELECT fk_profile FROM tli_pr_ce WHERE id = $1 AND fk_center = $2",
-    )
-    .bind(member_id)
-    .bind(center_id)
-    .fetch_optional(&state.pool)
-    .await?;
+    let caller =
+        require_center_capability(&state.pool, claims.sub, center_id, Capability::ManageMembers).await?;
 
-    let target_profile =
-        target_profile.ok_or_else(|| AppError::NotFound("Member not found".to_owned()))?;
+    // Prevent members from removing themselves
+    let (target_profile, target) = load_member(&state.pool, center_id, member_id).await?;
 
     if target_profile == claims.sub {
         return Err(AppError::BadRequest(
             "You cannot remove yourself from the center.".to_owned(),
         ));
     }
+    if target.is_owner() && !caller.is_owner() {
+        return Err(AppError::Forbidden);
+    }
 
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Member, member_id).await?;
     let result = sqlx::query("DELETE FROM tli_pr_ce WHERE id = $1 AND fk_center = $2")
         .bind(member_id)
         .bind(center_id)
-        .execute(&state.pool)
+        .execute(&mut *tx)
         .await?;
 
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("Member not found".to_owned()));
     }
 
+    let center_name: Option<String> = sqlx::query_scalar("SELECT name FROM centers WHERE id = $1")
+        .bind(center_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    notifications::notify(
+        &mut tx,
+        target_profile,
+        Kind::MemberRemoved,
+        &[("center_name", center_name.unwrap_or_default().into())],
+        None,
+    )
+    .await?;
+    audit.record(&mut tx, claims.sub, "member.remove", Entity::Member, member_id, before).await?;
+    tx.commit().await?;
+
     Ok(StatusCode::NO_CONTENT)
 }
+
+// ──────────────────────── Permissions ────────────────────────
+
+/// `GET /api/v1/centers/{slug}/permissions`
+///
+/// Default capabilities of each role, and the caller's own capabilities.
+async fn get_permissions(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(slug): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = resolve_center_id(&state.pool, &slug).await?;
+    let mine = permissions::load(&state.pool, claims.sub, center_id)
+        .await?
+        .ok_or(AppError::Forbidden)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "data": {
+                "capabilities": Capability::ALL,
+                "roles": permissions::matrix(),
+                "role_in_center": mine.role_in_center,
+                "mine": mine.effective(),
+            }
+        })),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdatePermissionsBody {
+    /// Capabilities added to the member's role defaults
+    #[serde(default)]
+    granted: Vec<String>,
+    /// Capabilities removed from the member's role defaults
+    #[serde(default)]
+    revoked: Vec<String>,
+}
+
+/// `PUT /api/v1/centers/{slug}/members/{member_id}/permissions`
+///
+/// Replaces the member's overrides. Requires `manage_members`; owners'
+/// permissions cannot be restricted, nobody can edit their own, and a
+/// non-owner can only grant capabilities they hold.
+async fn update_member_permissions(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    audit: Audit,
+    Path((slug, member_id)): Path<(String, Uuid)>,
+    Json(body): Json<UpdatePermissionsBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = resolve_center_id(&state.pool, &slug).await?;
+    let caller =
+        require_center_capability(&state.pool, claims.sub, center_id, Capability::ManageMembers).await?;
+
+    let granted = permissions::parse_list(&body.granted)?;
+    let revoked = permissions::parse_list(&body.revoked)?;
+
+    let (target_profile, target) = load_member(&state.pool, center_id, member_id).await?;
+    if target_profile == claims.sub {
+        return Err(AppError::BadRequest(
+            "You cannot change your own permissions.".to_owned(),
+        ));
+    }
+    if target.is_owner() {
+        return Err(AppError::BadRequest(
+            "Owners always have every permission.".to_owned(),
+        ));
+    }
+    if let Some(missing) = granted.iter().find(|c| !caller.allows(**c)) {
+        return Err(AppError::BadRequest(format!(
+            "You cannot grant '{}', which you do not have.",
+            missing.as_str()
+        )));
+    }
+
+    let mut tx = state.pool.begin().await?;
+    let before = audit::snapshot(&mut *tx, Entity::Member, member_id).await?;
+    let updated = permissions::set_overrides(
+        &mut *tx,
+        center_id,
+        member_id,
+        &target.role_in_center,
+        &granted,
+        &revoked,
+    )
+    .await?;
+    audit.record(&mut tx, claims.sub, "member.update_permissions", Entity::Member, member_id, before).await?;
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "data": {
+                "id": member_id,
+                "role_in_center": updated.role_in_center,
+                "granted_capabilities": updated.granted_capabilities,
+                "revoked_capabilities": updated.revoked_capabilities,
+                "capabilities": updated.effective(),
+            }
+        })),
+    ))
+}
@@ -2,15 +2,22 @@ pub mod admin;
 pub mod admin_advanced;
 pub mod admin_settings;
 pub mod bookings;
+pub mod center_webhooks;
 pub mod centers;
 pub mod contact;
 pub mod coupons;
 pub mod dashboard;
+pub mod events;
 pub mod health;
+pub mod invitations;
+pub mod jobs;
 pub mod members;
+pub mod notifications;
+pub mod ownership;
 pub mod payments;
 pub mod profile;
 pub mod reference;
+pub mod reminders;
 pub mod reviews;
 pub mod services;
 pub mod staff;
@@ -36,9 +43,16 @@ pub fn api_routes() -> Router<Arc<AppState>> {
         .merge(dashboard::router())
         .merge(staff::router())
         .merge(members::router())
+        .merge(ownership::router())
+        .merge(invitations::router())
+        .merge(notifications::router())
+        .merge(reminders::router())
+        .merge(events::router())
+        .merge(center_webhooks::router())
         .merge(coupons::router())
         .merge(stripe_connect::router())
         .merge(payments::router())
         .merge(contact::router())
+        .nest("/jobs", jobs::router())
         .nest("/stripe", webhook::router())
 }
@@ -0,0 +1,83 @@
+//! Notification center routes: the authenticated user's own notifications.
+//!
+//! Notifications are created by booking, review, payout and membership
+//! events (see [`crate::services::notifications`]).
+
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::middleware::auth::AuthUser;
+use crate::services::notifications;
+use crate::AppState;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/notifications", get(list_notifications))
+        .route("/notifications/read-all", post(mark_all_read))
+        .route("/notifications/{id}/read", post(mark_read))
+}
+
+// ──────────────────────── List ────────────────────────
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    #[serde(default)]
+    unread_only: bool,
+    limit: Option<i64>,
+}
+
+/// `GET /api/v1/notifications?unread_only=&limit=`
+///
+/// Newest first, with the total unread count for the badge.
+async fn list_notifications(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Query(query): Query<ListQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let limit = query.limit.unwrap_or(50);
+    let rows = notifications::list(&state.pool, claims.sub, query.unread_only, limit).await?;
+    let unread = notifications::unread_count(&state.pool, claims.sub).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({ "data": rows, "unread_count": unread })),
+    ))
+}
+
+// ──────────────────────── Mark read ────────────────────────
+
+/// `POST /api/v1/notifications/{id}/read`
+async fn mark_read(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    notifications::mark_read(&state.pool, claims.sub, id).await?;
+    let unread = notifications::unread_count(&state.pool, claims.sub).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({ "data": { "id": id, "unread_count": unread } })),
+    ))
+}
+
+/// `POST /api/v1/notifications/read-all`
+async fn mark_all_read(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+) -> Result<impl IntoResponse, AppError> {
+    let marked = notifications::mark_all_read(&state.pool, claims.sub).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({ "data": { "marked": marked, "unread_count": 0 } })),
+    ))
+}
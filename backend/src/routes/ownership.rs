@@ -0,0 +1,178 @@
+//! Center ownership transfer: the owner offers the center to a member, who
+//! accepts or declines it.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::middleware::audit::Audit;
+use crate::middleware::auth::{require_center_capability, AuthUser};
+use crate::services::audit::{self, Entity};
+use crate::services::ownership::{self, Action};
+use crate::services::permissions::Capability;
+use crate::AppState;
+
+/// Resolve slug to center_id.
+async fn resolve_center_id(pool: &sqlx::PgPool, slug: &str) -> Result<Uuid, AppError> {
+    sqlx::query_scalar("SELECT id FROM centers WHERE slug = $1 AND deleted_at IS NULL")
+        .bind(slug)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Center '{slug}' not found")))
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/centers/{slug}/ownership-transfers",
+            get(list_center_transfers).post(initiate_transfer),
+        )
+        .route("/ownership-transfers", get(list_incoming_transfers))
+        .route("/ownership-transfers/{transfer_id}/accept", post(accept_transfer))
+        .route("/ownership-transfers/{transfer_id}/decline", post(decline_transfer))
+        .route("/ownership-transfers/{transfer_id}/cancel", post(cancel_transfer))
+}
+
+// ──────────────────────── Center side ────────────────────────
+
+/// `GET /api/v1/centers/{slug}/ownership-transfers` — history of the
+/// center's transfers, for members with `manage_members`.
+async fn list_center_transfers(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(slug): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = resolve_center_id(&state.pool, &slug).await?;
+    require_center_capability(&state.pool, claims.sub, center_id, Capability::ManageMembers).await?;
+
+    let transfers = ownership::list_for_center(&state.pool, center_id).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": transfers }))))
+}
+
+#[derive(Debug, Deserialize)]
+struct InitiateTransferBody {
+    /// Member (`tli_pr_ce` id) who is offered the center
+    member_id: Uuid,
+    message: Option<String>,
+}
+
+/// `POST /api/v1/centers/{slug}/ownership-transfers` — owner only.
+async fn initiate_transfer(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    audit: Audit,
+    Path(slug): Path<String>,
+    Json(body): Json<InitiateTransferBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = resolve_center_id(&state.pool, &slug).await?;
+    let mut tx = state.pool.begin().await?;
+    let transfer = ownership::initiate(
+        &mut tx,
+        center_id,
+        claims.sub,
+        body.member_id,
+        body.message.as_deref(),
+    )
+    .await?;
+    audit.record(&mut tx, claims.sub, "ownership_transfer.offer", Entity::OwnershipTransfer, transfer.id, None).await?;
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(serde_json::json!({ "data": transfer }))))
+}
+
+// ──────────────────────── Recipient side ────────────────────────
+
+/// `GET /api/v1/ownership-transfers` — open offers made to the caller.
+async fn list_incoming_transfers(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+) -> Result<impl IntoResponse, AppError> {
+    let transfers = ownership::list_incoming(&state.pool, claims.sub).await?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": transfers }))))
+}
+
+/// Answer the offer and record it, with the owner and role changes of an
+/// acceptance, in the audit log.
+async fn respond(
+    state: &AppState,
+    audit: &Audit,
+    user_id: Uuid,
+    transfer_id: Uuid,
+    action: Action,
+) -> Result<impl IntoResponse, AppError> {
+    ownership::expire_overdue(&state.pool, transfer_id).await?;
+
+    let mut tx = state.pool.begin().await?;
+    let transfer = ownership::lock(&mut tx, transfer_id).await?;
+    let before = audit::snapshot(&mut *tx, Entity::OwnershipTransfer, transfer_id).await?;
+    // The center and the memberships of both sides, as the owner changes
+    let mut swapped = Vec::new();
+    if action == Action::Accept {
+        swapped.push((Entity::Center, transfer.center_id, audit::snapshot(&mut *tx, Entity::Center, transfer.center_id).await?));
+        let member_ids: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT id FROM tli_pr_ce WHERE fk_center = $1 AND fk_profile IN ($2, $3) ORDER BY id",
+        )
+        .bind(transfer.center_id)
+        .bind(transfer.from_profile)
+        .bind(transfer.to_profile)
+        .fetch_all(&mut *tx)
+        .await?;
+        for id in member_ids {
+            swapped.push((Entity::Member, id, audit::snapshot(&mut *tx, Entity::Member, id).await?));
+        }
+    }
+
+    let transfer = ownership::respond(&mut tx, &transfer, user_id, action).await?;
+    let action_name = format!("ownership_transfer.{}", action.status());
+    audit.record(&mut tx, user_id, &action_name, Entity::OwnershipTransfer, transfer_id, before).await?;
+    for (entity, id, before) in swapped {
+        let action_name = match entity {
+            Entity::Center => "center.transfer_ownership",
+            _ => "member.update_role",
+        };
+        audit.record(&mut tx, user_id, action_name, entity, id, before).await?;
+    }
+    tx.commit().await?;
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": transfer }))))
+}
+
+/// `POST /api/v1/ownership-transfers/{transfer_id}/accept` — recipient only.
+///
+/// The caller becomes owner and the previous owner a manager. Payouts are
+/// paused until the new owner connects their own Stripe account.
+async fn accept_transfer(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    audit: Audit,
+    Path(transfer_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    respond(&state, &audit, claims.sub, transfer_id, Action::Accept).await
+}
+
+/// `POST /api/v1/ownership-transfers/{transfer_id}/decline` — recipient only.
+async fn decline_transfer(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    audit: Audit,
+    Path(transfer_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    respond(&state, &audit, claims.sub, transfer_id, Action::Decline).await
+}
+
+/// `POST /api/v1/ownership-transfers/{transfer_id}/cancel` — initiator only.
+async fn cancel_transfer(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    audit: Audit,
+    Path(transfer_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    respond(&state, &audit, claims.sub, transfer_id, Action::Cancel).await
+}
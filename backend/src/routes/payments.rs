@@ -1,13 +1,14 @@
-//! Center-level payment routes: commissions, payments, revenue, payouts.
+//! Center-level payment routes: commissions, payments, revenue, payouts,
+//! invoices and monthly statements.
 //!
 //! These routes provide authenticated center owners and members with
-//! financial data and payout request capabilities.
+//! financial data, payout requests and the automatic payout schedule.
 
 use std::sync::Arc;
 
-use axum::extract::{Query, State};
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
 use axum::{Json, Router};
 use rust_decimal::Decimal;
@@ -15,7 +16,11 @@ use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::error::AppError;
-use crate::middleware::auth::{require_center_member, require_center_owner, AuthUser};
+use crate::middleware::auth::{require_center_capability, AuthUser};
+use crate::models::{Money, RoundingMode};
+use crate::services::{connect, invoices, payouts};
+use crate::services::permissions::{self, Capability};
+use crate::services::statements;
 use crate::AppState;
 
 pub fn router() -> Router<Arc<AppState>> {
@@ -24,6 +29,11 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/payments", get(list_payments))
         .route("/revenue", get(get_revenue_summary))
         .route("/payouts/request", post(request_payout))
+        .route("/payouts/schedule", get(get_payout_schedule).put(update_payout_schedule))
+        .route("/invoices", get(list_invoices))
+        .route("/invoices/{invoice_id}", get(download_invoice))
+        .route("/statements", get(list_statements).post(issue_statement))
+        .route("/statements/{statement_id}", get(download_statement))
 }
 
 // ──────────────────────── Types ────────────────────────
@@ -68,12 +78,27 @@ struct RevenueSummary {
     total_revenue: Decimal,
     total_commission: Decimal,
     net_revenue: Decimal,
+    revenue_excl_vat: Decimal,
+    revenue_vat: Decimal,
+    commission_excl_vat: Decimal,
+    commission_vat: Decimal,
     pending_revenue: Decimal,
     completed_revenue: Decimal,
     transaction_count: i64,
     currency: String,
 }
 
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+struct InvoiceListRow {
+    id: Uuid,
+    booking_id: Uuid,
+    kind: String,
+    invoice_number: String,
+    currency: String,
+    amount: Decimal,
+    issued_at: chrono::DateTime<chrono::Utc>,
+}
+
 #[derive(Debug, Deserialize)]
 struct PayoutRequestBody {
     center_id: Uuid,
@@ -81,6 +106,17 @@ struct PayoutRequestBody {
     currency: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct PayoutScheduleBody {
+    center_id: Uuid,
+    /// `manual`, `weekly` or `monthly`.
+    frequency: String,
+    weekday: Option<i16>,
+    day_of_month: Option<i16>,
+    minimum_amount: Option<Decimal>,
+    currency: Option<String>,
+}
+
 // ──────────────────────── Commissions ────────────────────────
 
 /// `GET /api/v1/commissions?center_id=&limit=&offset=` — auth, list commissions for a center.
@@ -89,7 +125,7 @@ async fn list_commissions(
     AuthUser(claims): AuthUser,
     Query(params): Query<CenterQuery>,
 ) -> Result<impl IntoResponse, AppError> {
-    require_center_member(&state.pool, claims.sub, params.center_id).await?;
+    require_center_capability(&state.pool, claims.sub, params.center_id, Capability::ViewFinance).await?;
 
     let limit = params.limit.unwrap_or(50).min(200);
     let offset = params.offset.unwrap_or(0).max(0);
@@ -129,7 +165,7 @@ async fn list_payments(
     AuthUser(claims): AuthUser,
     Query(params): Query<CenterQuery>,
 ) -> Result<impl IntoResponse, AppError> {
-    require_center_member(&state.pool, claims.sub, params.center_id).await?;
+    require_center_capability(&state.pool, claims.sub, params.center_id, Capability::ViewFinance).await?;
 
     let limit = params.limit.unwrap_or(50).min(200);
     let offset = params.offset.unwrap_or(0).max(0);
@@ -171,7 +207,7 @@ async fn get_revenue_summary(
     AuthUser(claims): AuthUser,
     Query(params): Query<RevenueSummaryQuery>,
 ) -> Result<impl IntoResponse, AppError> {
-    require_center_member(&state.pool, claims.sub, params.center_id).await?;
+    require_center_capability(&state.pool, claims.sub, params.center_id, Capability::ViewFinance).await?;
 
     // Aggregate from bookings (confirmed + completed)
     let booking_stats = sqlx::query_as::<_, (Option<Decimal>, Option<Decimal>, Option<Decimal>, Option<Decimal>)>(
@@ -189,6 +225,24 @@ async fn get_revenue_summary(
     .fetch_one(&state.pool)
     .await?;
 
+    // VAT split of the same bookings. Bookings created before the tax
+    // breakdown existed count as fully net so that net + VAT = gross.
+    let tax_stats = sqlx::query_as::<_, (Option<Decimal>, Option<Decimal>, Option<Decimal>, Option<Decimal>)>(
+        r#"
+        SELECT
+            SUM(COALESCE(price_net, total_price)),
+            SUM(COALESCE(price_vat, 0)),
+            SUM(COALESCE(fee_net, commission_amount)),
+            SUM(COALESCE(fee_vat, 0))
+        FROM bookings
+        WHERE center_id = $1 AND deleted_at IS NULL
+          AND status IN ('confirmed', 'completed')
+        "#,
+    )
+    .bind(params.center_id)
+    .fetch_one(&state.pool)
+    .await?;
+
     let total_revenue = booking_stats.0.unwrap_or(Decimal::ZERO);
     let total_commission = booking_stats.1.unwrap_or(Decimal::ZERO);
     let pending_revenue = booking_stats.2.unwrap_or(Decimal::ZERO);
@@ -218,6 +272,10 @@ async fn get_revenue_summary(
         total_revenue,
         total_commission,
         net_revenue: total_revenue - total_commission,
+        revenue_excl_vat: tax_stats.0.unwrap_or(Decimal::ZERO),
+        revenue_vat: tax_stats.1.unwrap_or(Decimal::ZERO),
+        commission_excl_vat: tax_stats.2.unwrap_or(Decimal::ZERO),
+        commission_vat: tax_stats.3.unwrap_or(Decimal::ZERO),
         pending_revenue,
         completed_revenue,
         transaction_count,
@@ -238,7 +296,7 @@ async fn request_payout(
     AuthUser(claims): AuthUser,
     Json(body): Json<PayoutRequestBody>,
 ) -> Result<impl IntoResponse, AppError> {
-    require_center_owner(&state.pool, claims.sub, body.center_id).await?;
+    require_center_capability(&state.pool, claims.sub, body.center_id, Capability::ManagePayouts).await?;
 
     if body.amount <= Decimal::ZERO {
         return Err(AppError::BadRequest(
@@ -246,79 +304,263 @@ async fn request_payout(
         ));
     }
 
-    // Verify the center has a completed Stripe onboarding
-    let stripe_info: Option<(Option<String>, Option<bool>)> = sqlx::query_as(
-        "SELECT stripe_account_id, stripe_onboarding_complete FROM centers WHERE id = $1 AND deleted_at IS NULL",
-    )
-    .bind(body.center_id)
-    .fetch_optional(&state.pool)
-    .await?;
-
-    let (stripe_account_id, onboarding_complete) = stripe_info
-        .ok_or_else(|| AppError::NotFound("Center not found".to_owned()))?;
+    if let Some(hold) = payouts::payout_hold(&state.pool, body.center_id).await? {
+        return Err(hold.into_error());
+    }
 
-    let stripe_account_id = stripe_account_id.ok_or_else(|| {
-        AppError::BadRequest("Center has no Stripe account configured".to_owned())
-    })?;
+    // Verify the center has a connected, onboarded Stripe account
+    let stripe_account_id = connect::load(&state.pool, body.center_id)
+        .await?
+        .payout_destination()?;
 
-    if !onboarding_complete.unwrap_or(false) {
-        return Err(AppError::BadRequest(
-            "Stripe onboarding is not complete for this center".to_owned(),
-        ));
-    }
+    // Never pay out more than requested: truncate to the currency's precision
+    let payout = Money::new(body.amount, body.currency.as_deref().unwrap_or("EUR"))
+        .map_err(|e| AppError::BadRequest(e.to_string()))?
+        .round(RoundingMode::Down);
 
     // Check available balance (net revenue minus already paid out)
-    let net_available = sqlx::query_scalar::<_, Option<Decimal>>(
-        r#"
-        SELECT SUM(b.total_price - b.commission_amount)
-        FROM bookings b
-        WHERE b.center_id = $1
-          AND b.status = 'completed'
-          AND b.deleted_at IS NULL
-        "#,
-    )
-    .bind(body.center_id)
-    .fetch_one(&state.pool)
-    .await?
-    .unwrap_or(Decimal::ZERO);
+    let mut tx = state.pool.begin().await?;
+    payouts::lock_balance(&mut tx, body.center_id).await?;
+    let net_available = payouts::available_balance(&mut *tx, body.center_id, payout.currency()).await?;
 
-    if body.amount > net_available {
+    if payout.amount() > net_available {
         return Err(AppError::BadRequest(format!(
             "Requested amount ({}) exceeds available balance ({})",
-            body.amount, net_available
+            payout.amount(), net_available
         )));
     }
 
+    // Reserve the amount before the transfer so a concurrent request sees it
+    let payout_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO payouts (center_id, amount, currency, requested_by)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#,
+    )
+    .bind(body.center_id)
+    .bind(payout.amount())
+    .bind(payout.currency())
+    .bind(claims.sub)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
     // Create the Stripe transfer
-    let currency_str = body.currency.as_deref().unwrap_or("EUR").to_lowercase();
-    let amount_cents = (body.amount * Decimal::from(100))
-        .to_string()
-        .parse::<i64>()
-        .map_err(|_| AppError::BadRequest("Invalid payout amount".to_owned()))?;
-
-    let parsed_currency: stripe::Currency = currency_str
-        .parse()
-        .map_err(|_| AppError::BadRequest(format!("Unsupported currency: {currency_str}")))?;
-
-    let mut transfer_params =
-        stripe::CreateTransfer::new(parsed_currency, stripe_account_id.clone());
-    transfer_params.amount = Some(amount_cents);
-    let description = format!("Payout for center {}", body.center_id);
-    transfer_params.description = Some(&description);
-
-    let transfer = stripe::Transfer::create(&state.stripe, transfer_params)
-        .await
-        .map_err(|e| AppError::Internal(format!("Stripe transfer failed: {e}")))?;
+    let currency_str = payout.currency().to_lowercase();
+    let transfer = payouts::send_transfer(
+        &state.pool,
+        state.payments.as_ref(),
+        body.center_id,
+        payout_id,
+        &payout,
+        stripe_account_id,
+        None,
+    )
+    .await?;
 
     Ok((
         StatusCode::CREATED,
         Json(serde_json::json!({
             "data": {
-                "transfer_id": transfer.id.to_string(),
-                "amount": body.amount,
+                "transfer_id": transfer.id,
+                "amount": payout.amount(),
                 "currency": currency_str,
                 "status": "created"
             }
         })),
     ))
 }
+
+/// `GET /api/v1/payouts/schedule?center_id=` — auth, automatic payout schedule
+/// and current available balance of a center.
+async fn get_payout_schedule(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Query(q): Query<CenterQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    require_center_capability(&state.pool, claims.sub, q.center_id, Capability::ViewFinance).await?;
+
+    let schedule = payouts::load_schedule(&state.pool, q.center_id).await?;
+    let available = payouts::available_balance(&state.pool, q.center_id, &schedule.currency).await?;
+    let hold = payouts::payout_hold(&state.pool, q.center_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "data": {
+                "schedule": schedule,
+                "available_balance": available,
+                "hold": hold,
+            }
+        })),
+    ))
+}
+
+/// `PUT /api/v1/payouts/schedule` — auth, set the automatic payout schedule of a center.
+async fn update_payout_schedule(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Json(body): Json<PayoutScheduleBody>,
+) -> Result<impl IntoResponse, AppError> {
+    require_center_capability(&state.pool, claims.sub, body.center_id, Capability::ManagePayouts).await?;
+
+    let frequency = payouts::PayoutFrequency::parse(&body.frequency).ok_or_else(|| {
+        AppError::BadRequest("frequency must be one of: manual, weekly, monthly".to_owned())
+    })?;
+    let schedule = payouts::PayoutSchedule {
+        center_id: body.center_id,
+        frequency,
+        weekday: body.weekday,
+        day_of_month: body.day_of_month,
+        minimum_amount: body.minimum_amount.unwrap_or(Decimal::ZERO),
+        currency: body.currency.unwrap_or_else(|| "EUR".to_owned()),
+        updated_at: None,
+    };
+    let saved = payouts::save_schedule(&state.pool, &schedule, claims.sub).await?;
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": saved }))))
+}
+
+// ──────────────────────── Invoices ────────────────────────
+
+/// `GET /api/v1/invoices?center_id=&limit=&offset=` — auth, list receipts and
+/// commission invoices issued for a center's bookings.
+async fn list_invoices(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Query(params): Query<CenterQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    require_center_capability(&state.pool, claims.sub, params.center_id, Capability::ViewFinance).await?;
+
+    let limit = params.limit.unwrap_or(50).min(200);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    let rows = sqlx::query_as::<_, InvoiceListRow>(
+        r#"
+        SELECT id, booking_id, kind, invoice_number, currency, amount, issued_at
+        FROM invoices
+        WHERE center_id = $1
+        ORDER BY issued_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(params.center_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": rows }))))
+}
+
+/// `GET /api/v1/invoices/{invoice_id}` — auth, download an invoice as PDF.
+/// Center members with `view_finance` can download any document of their
+/// center; divers only
+/// their own receipts.
+async fn download_invoice(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(invoice_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let invoice = invoices::find_by_id(&state.pool, invoice_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invoice not found".to_owned()))?;
+
+    let own_receipt = invoice.client_id == claims.sub
+        && invoice.kind == invoices::DocumentKind::Receipt.as_str();
+    if !own_receipt {
+        require_center_capability(&state.pool, claims.sub, invoice.center_id, Capability::ViewFinance).await?;
+    }
+
+    invoices::pdf_response(&invoice)
+}
+
+// ──────────────────────── Statements ────────────────────────
+
+#[derive(Debug, Deserialize)]
+struct IssueStatementRequest {
+    center_id: Uuid,
+    year: i32,
+    month: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatementFormatQuery {
+    format: Option<String>,
+}
+
+/// `POST /api/v1/statements` — auth, issue the commission statement of a
+/// closed month. Returns the already-issued statement when one exists.
+///
+/// Issuing freezes the statement for good: it takes `manage_payouts` or
+/// `manage_center`, not just `view_finance`.
+async fn issue_statement(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Json(body): Json<IssueStatementRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let allowed = permissions::load(&state.pool, claims.sub, body.center_id)
+        .await?
+        .is_some_and(|p| p.allows(Capability::ManagePayouts) || p.allows(Capability::ManageCenter));
+    if !allowed {
+        return Err(AppError::Forbidden);
+    }
+
+    let record =
+        statements::issue_monthly(&state.pool, body.center_id, body.year, body.month, claims.sub)
+            .await?;
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": record }))))
+}
+
+/// `GET /api/v1/statements?center_id=&limit=&offset=` — auth, list a center's
+/// issued statements.
+async fn list_statements(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Query(params): Query<CenterQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    require_center_capability(&state.pool, claims.sub, params.center_id, Capability::ViewFinance).await?;
+
+    let limit = params.limit.unwrap_or(50).min(200);
+    let offset = params.offset.unwrap_or(0).max(0);
+    let rows = statements::list(&state.pool, params.center_id, limit, offset).await?;
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": rows }))))
+}
+
+/// `GET /api/v1/statements/{statement_id}?format=json|csv` — auth, download
+/// the frozen snapshot of an issued statement.
+async fn download_statement(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(statement_id): Path<Uuid>,
+    Query(params): Query<StatementFormatQuery>,
+) -> Result<Response, AppError> {
+    let statement = statements::load_snapshot(&state.pool, statement_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Statement not found".to_owned()))?;
+    require_center_capability(&state.pool, claims.sub, statement.center_id, Capability::ViewFinance).await?;
+
+    match params.format.as_deref().unwrap_or("json") {
+        "json" => {
+            Ok((StatusCode::OK, Json(serde_json::json!({ "data": statement }))).into_response())
+        }
+        "csv" => {
+            let disposition = format!(
+                "attachment; filename=\"{}\"",
+                statements::file_name(&statement, "csv")
+            );
+            Ok((
+                [
+                    (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_owned()),
+                    (header::CONTENT_DISPOSITION, disposition),
+                ],
+                statements::to_csv(&statement),
+            )
+                .into_response())
+        }
+        _ => Err(AppError::BadRequest("format must be json or csv".to_owned())),
+    }
+}
@@ -1,21 +1,25 @@
 use std::sync::Arc;
 
 use axum::extract::State;
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
 use axum::response::IntoResponse;
 use axum::routing::get;
 use axum::{Json, Router};
 use serde::Deserialize;
 
 use crate::error::AppError;
+use crate::middleware::audit::Audit;
 use crate::middleware::auth::AuthUser;
 use crate::models::{Profile, ProfileCenterSummary};
+use crate::services::audit::Entity;
+use crate::services::privacy;
 use crate::AppState;
 
 /// Build the `/profile` sub-router. All routes require authentication.
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
-        .route("/me", get(get_my_profile).patch(update_my_profile))
+        .route("/me", get(get_my_profile).patch(update_my_profile).delete(erase_my_account))
+        .route("/me/export", get(export_my_data))
         .route("/centers", get(get_my_centers))
 }
 
@@ -145,3 +149,54 @@ async fn get_my_centers(
         Json(serde_json::json!({ "data": centers })),
     ))
 }
+
+/// `GET /api/v1/profile/me/export`
+///
+/// Downloads everything the platform holds about the authenticated user as
+/// one JSON file (GDPR right of access).
+async fn export_my_data(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+) -> Result<impl IntoResponse, AppError> {
+    let document = privacy::export(&state.pool, claims.sub).await?;
+    let disposition = format!(
+        "attachment; filename=\"{}\"",
+        privacy::export_filename(claims.sub, chrono::Utc::now().date_naive())
+    );
+
+    Ok((StatusCode::OK, [(header::CONTENT_DISPOSITION, disposition)], Json(document)))
+}
+
+/// Body for erasing the authenticated user's account.
+#[derive(Debug, Deserialize)]
+struct EraseAccountBody {
+    /// Must be `true`: erasure cannot be undone
+    confirm: bool,
+}
+
+/// `DELETE /api/v1/profile/me`
+///
+/// Erases the authenticated user's account (GDPR right to erasure): personal
+/// data is anonymized, financial records are kept. Refused while the user
+/// owns a center or has upcoming bookings.
+async fn erase_my_account(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    audit: Audit,
+    Json(body): Json<EraseAccountBody>,
+) -> Result<impl IntoResponse, AppError> {
+    if !body.confirm {
+        return Err(AppError::BadRequest("Set confirm to true to erase your account".to_owned()));
+    }
+
+    let mut tx = state.pool.begin().await?;
+    let summary = privacy::erase(&mut tx, claims.sub).await?;
+    // No `before`: the audit log must not keep what was just erased.
+    audit.record(&mut tx, claims.sub, "user.erase", Entity::User, claims.sub, None).await?;
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({ "message": "Account erased", "data": summary })),
+    ))
+}
@@ -0,0 +1,88 @@
+//! Dive reminder settings of a center: timezone, how long before the dive
+//! reminders are sent, and the center's pre-dive checklist.
+//!
+//! All center members can read the settings; changing them takes
+//! `manage_center`.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::middleware::auth::{require_center_capability, require_center_member, AuthUser};
+use crate::services::permissions::Capability;
+use crate::services::reminders::{self, ReminderSettings};
+use crate::AppState;
+
+/// Resolve slug to center_id.
+async fn resolve_center_id(pool: &sqlx::PgPool, slug: &str) -> Result<Uuid, AppError> {
+    sqlx::query_scalar("SELECT id FROM centers WHERE slug = $1 AND deleted_at IS NULL")
+        .bind(slug)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Center '{slug}' not found")))
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route(
+        "/centers/{slug}/reminders",
+        get(get_reminder_settings).put(update_reminder_settings),
+    )
+}
+
+/// `GET /api/v1/centers/{slug}/reminders`
+async fn get_reminder_settings(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(slug): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = resolve_center_id(&state.pool, &slug).await?;
+    require_center_member(&state.pool, claims.sub, center_id).await?;
+
+    let settings = reminders::load_settings(&state.pool, center_id).await?;
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": settings }))))
+}
+
+#[derive(Debug, Deserialize)]
+struct ReminderSettingsBody {
+    enabled: Option<bool>,
+    hours_before: Option<i16>,
+    timezone: Option<String>,
+    checklist: Option<Vec<String>>,
+}
+
+/// `PUT /api/v1/centers/{slug}/reminders`
+///
+/// Omitted fields keep their current value.
+async fn update_reminder_settings(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Path(slug): Path<String>,
+    Json(body): Json<ReminderSettingsBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let center_id = resolve_center_id(&state.pool, &slug).await?;
+    require_center_capability(&state.pool, claims.sub, center_id, Capability::ManageCenter).await?;
+
+    let current = reminders::load_settings(&state.pool, center_id).await?;
+    let settings = ReminderSettings {
+        center_id,
+        enabled: body.enabled.unwrap_or(current.enabled),
+        hours_before: body.hours_before.unwrap_or(current.hours_before),
+        timezone: body.timezone.unwrap_or(current.timezone),
+        checklist: body
+            .checklist
+            .map(reminders::clean_checklist)
+            .unwrap_or(current.checklist),
+        updated_at: None,
+    };
+    let saved = reminders::save_settings(&state.pool, &settings, claims.sub).await?;
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": saved }))))
+}
@@ -17,7 +17,11 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::error::AppError;
-use crate::middleware::auth::AuthUser;
+use crate::middleware::auth::{require_not_banned, AuthUser};
+use crate::services::bans::Scope;
+use crate::services::email::Value;
+use crate::services::notifications::{self, Kind};
+use crate::services::permissions::Capability;
 use crate::AppState;
 
 /// Published review visible to the public.
@@ -111,6 +115,8 @@ async fn create_review(
     AuthUser(claims): AuthUser,
     Json(body): Json<CreateReviewBody>,
 ) -> Result<impl IntoResponse, AppError> {
+    require_not_banned(&state.pool, claims.sub, Scope::Review).await?;
+
     // Validate rating
     if !(1..=5).contains(&body.rating) {
         return Err(AppError::BadRequest(
@@ -165,6 +171,7 @@ async fn create_review(
         ));
     }
 
+    let mut tx = state.pool.begin().await?;
     let review_id: Uuid = sqlx::query_scalar(
         r#"
         INSERT INTO reviews (client_id, center_id, booking_id, rating, comment, is_published)
@@ -177,8 +184,27 @@ async fn create_review(
     .bind(body.booking_id)
     .bind(body.rating)
     .bind(body.comment.as_deref().map(str::trim))
-    .fetch_one(&state.pool)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let center_name: Option<String> = sqlx::query_scalar("SELECT name FROM centers WHERE id = $1")
+        .bind(body.center_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    notifications::notify_center(
+        &mut tx,
+        body.center_id,
+        Capability::ManageReviews,
+        None,
+        Kind::ReviewReceived,
+        &[
+            ("center_name", center_name.unwrap_or_default().into()),
+            ("rating", Value::Number(body.rating.into())),
+        ],
+        Some(notifications::REVIEWS_LINK),
+    )
     .await?;
+    tx.commit().await?;
 
     Ok((
         StatusCode::CREATED,
@@ -207,7 +233,7 @@ async fn list_reviewable_bookings(
     AuthUser(claims): AuthUser,
     Path(center_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
-    crate::middleware::auth::require_center_member(&state.pool, claims.sub, center_id).await?;
+    crate::middleware::auth::require_center_capability(&state.pool, claims.sub, center_id, Capability::ViewBookings).await?;
 
     let rows = sqlx::query_as::<_, ReviewableBookingRow>(
         r#"
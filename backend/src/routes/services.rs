@@ -13,8 +13,9 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::error::AppError;
-use crate::middleware::auth::{require_center_member, AuthUser};
+use crate::middleware::auth::{require_center_capability, AuthUser};
 use crate::models::ServiceRow;
+use crate::services::permissions::Capability;
 use crate::AppState;
 
 /// GET /api/v1/centers/{slug}/services — public: list active services for a center.
@@ -44,11 +45,12 @@ async fn list_services_by_center_slug(
 
 // ──────────────────────── Authenticated center member endpoints ────────────────────────
 
-/// Helper: resolve slug to center_id and verify membership.
-async fn resolve_center_and_check_membership(
+/// Helper: resolve slug to center_id and verify the caller holds `capability`.
+async fn resolve_center_and_check(
     pool: &sqlx::PgPool,
     slug: &str,
     user_id: Uuid,
+    capability: Capability,
 ) -> Result<Uuid, AppError> {
     let center_id: Option<Uuid> =
         sqlx::query_scalar("SELECT id FROM centers WHERE slug = $1 AND deleted_at IS NULL")
@@ -59,7 +61,7 @@ async fn resolve_center_and_check_membership(
     let center_id =
         center_id.ok_or_else(|| AppError::NotFound(format!("Center '{slug}' not found")))?;
 
-    require_center_member(pool, user_id, center_id).await?;
+    require_center_capability(pool, user_id, center_id, capability).await?;
     Ok(center_id)
 }
 
@@ -85,7 +87,7 @@ async fn create_service(
     Path(slug): Path<String>,
     Json(body): Json<CreateServiceBody>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center_and_check_membership(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center_and_check(&state.pool, &slug, claims.sub, Capability::ManageServices).await?;
 
     let name = body.name.trim();
     if name.is_empty() {
@@ -159,7 +161,7 @@ async fn update_service(
     Path((slug, service_id)): Path<(String, Uuid)>,
     Json(body): Json<UpdateServiceBody>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center_and_check_membership(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center_and_check(&state.pool, &slug, claims.sub, Capability::ManageServices).await?;
 
     if let Some(duration) = body.duration_minutes {
         if duration <= 0 {
@@ -233,7 +235,7 @@ async fn delete_service(
     AuthUser(claims): AuthUser,
     Path((slug, service_id)): Path<(String, Uuid)>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center_and_check_membership(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center_and_check(&state.pool, &slug, claims.sub, Capability::ManageServices).await?;
 
     let result = sqlx::query(
         "UPDATE services SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND center_id = $2 AND deleted_at IS NULL",
@@ -275,7 +277,7 @@ async fn list_center_bookings(
     AuthUser(claims): AuthUser,
     Path(slug): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center_and_check_membership(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center_and_check(&state.pool, &slug, claims.sub, Capability::ViewBookings).await?;
 
     let bookings = sqlx::query_as::<_, CenterBookingRow>(
         r#"
@@ -322,7 +324,7 @@ async fn list_center_reviews(
     AuthUser(claims): AuthUser,
     Path(slug): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center_and_check_membership(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center_and_check(&state.pool, &slug, claims.sub, Capability::ViewBookings).await?;
 
     let reviews = sqlx::query_as::<_, CenterReviewRow>(
         r#"
@@ -359,7 +361,7 @@ async fn reply_to_review(
     Path((slug, review_id)): Path<(String, Uuid)>,
     Json(body): Json<ReplyToReviewBody>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center_and_check_membership(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center_and_check(&state.pool, &slug, claims.sub, Capability::ManageReviews).await?;
 
     let reply_text = body.reply.trim();
     if reply_text.is_empty() {
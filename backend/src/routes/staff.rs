@@ -11,14 +11,16 @@ use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::error::AppError;
-use crate::middleware::auth::{require_center_member, AuthUser};
+use crate::middleware::auth::{require_center_capability, AuthUser};
+use crate::services::permissions::Capability;
 use crate::AppState;
 
-/// Resolve slug to center_id and verify membership.
+/// Resolve slug to center_id and verify the caller holds `capability`.
 async fn resolve_center(
     pool: &sqlx::PgPool,
     slug: &str,
     user_id: Uuid,
+    capability: Capability,
 ) -> Result<Uuid, AppError> {
     let center_id: Option<Uuid> =
         sqlx::query_scalar("SELECT id FROM centers WHERE slug = $1 AND deleted_at IS NULL")
@@ -27,7 +29,7 @@ async fn resolve_center(
             .await?;
     let center_id =
         center_id.ok_or_else(|| AppError::NotFound(format!("Center '{slug}' not found")))?;
-    require_center_member(pool, user_id, center_id).await?;
+    require_center_capability(pool, user_id, center_id, capability).await?;
     Ok(center_id)
 }
 
@@ -86,7 +88,7 @@ async fn list_staff(
     AuthUser(claims): AuthUser,
     Path(slug): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ViewBookings).await?;
 
     let rows = sqlx::query_as::<_, StaffRow>(
         r#"
@@ -112,7 +114,7 @@ async fn get_staff_detail(
     AuthUser(claims): AuthUser,
     Path((slug, staff_id)): Path<(String, Uuid)>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ViewBookings).await?;
 
     let row = sqlx::query_as::<_, StaffRow>(
         r#"
@@ -150,7 +152,7 @@ async fn create_staff(
     Path(slug): Path<String>,
     Json(body): Json<CreateStaffBody>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ManageStaff).await?;
 
     let first = body.first_name.trim();
     let last = body.last_name.trim();
@@ -202,7 +204,7 @@ async fn update_staff(
     Path((slug, staff_id)): Path<(String, Uuid)>,
     Json(body): Json<UpdateStaffBody>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ManageStaff).await?;
 
     let result = sqlx::query(
         r#"
@@ -252,7 +254,7 @@ async fn update_staff_bio(
     Path((slug, staff_id)): Path<(String, Uuid)>,
     Json(body): Json<UpdateBioBody>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ManageStaff).await?;
 
     let result = sqlx::query(
         "UPDATE staff SET bio = $1, updated_at = NOW() WHERE id = $2 AND center_id = $3 AND deleted_at IS NULL",
@@ -281,7 +283,7 @@ async fn delete_staff(
     AuthUser(claims): AuthUser,
     Path((slug, staff_id)): Path<(String, Uuid)>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ManageStaff).await?;
 
     let result = sqlx::query(
         "UPDATE staff SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND center_id = $2 AND deleted_at IS NULL",
@@ -306,7 +308,7 @@ async fn get_staff_hours(
     AuthUser(claims): AuthUser,
     Path((slug, staff_id)): Path<(String, Uuid)>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ViewBookings).await?;
 
     // Verify staff belongs to center
     let exists: bool = sqlx::query_scalar(
@@ -350,7 +352,7 @@ async fn set_staff_hours(
     Path((slug, staff_id)): Path<(String, Uuid)>,
     Json(body): Json<SetStaffHoursBody>,
 ) -> Result<impl IntoResponse, AppError> {
-    let center_id = resolve_center(&state.pool, &slug, claims.sub).await?;
+    let center_id = resolve_center(&state.pool, &slug, claims.sub, Capability::ManageStaff).await?;
 
     let exists: bool = sqlx::query_scalar(
         "SELECT EXISTS(SELECT 1 FROM staff WHERE id = $1 AND center_id = $2 AND deleted_at IS NULL)",
@@ -1,12 +1,15 @@
-//! Stripe Connect routes for center owners.
+//! Stripe Connect routes for center owners (capability `manage_payouts`).
 //!
 //! These routes provide:
-//! - Creating a Stripe Connect onboarding link for a center
+//! - Creating a Stripe Connect onboarding link for a center (a new account
+//!   replaces one that was deauthorized)
+//! - Reading the account status and outstanding verification requirements
+//! - Opening the Stripe Express dashboard
 //! - Reading and updating the Stripe payout configuration
 
 use std::sync::Arc;
 
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::routing::{get, post};
@@ -15,12 +18,19 @@ use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::error::AppError;
-use crate::middleware::auth::AuthUser;
+use crate::middleware::audit::Audit;
+use crate::middleware::auth::{require_center_capability, AuthUser};
+use crate::services::audit::{self, Entity};
+use crate::services::connect;
+use crate::services::payment_gateway::AccountLinkRequest;
+use crate::services::permissions::{self, Capability};
 use crate::AppState;
 
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/stripe/connect", post(create_connect_link))
+        .route("/stripe/account", get(get_account_status))
+        .route("/stripe/dashboard-link", post(create_dashboard_link))
         .route(
             "/stripe/config",
             get(get_stripe_config).patch(update_stripe_config),
@@ -46,9 +56,26 @@ struct StripeConfigRow {
     center_name: String,
     stripe_account_id: Option<String>,
     stripe_onboarding_complete: Option<bool>,
+    stripe_payouts_enabled: bool,
+    stripe_requirements_currently_due: Vec<String>,
+    stripe_deauthorized_at: Option<chrono::DateTime<chrono::Utc>>,
     currency: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct AccountStatusQuery {
+    center_id: Uuid,
+    /// Fetch the status from Stripe instead of the last webhook snapshot.
+    #[serde(default)]
+    refresh: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct DashboardLinkResponse {
+    url: String,
+    center_id: Uuid,
+}
+
 #[derive(Debug, Deserialize)]
 struct UpdateStripeConfigBody {
     center_id: Uuid,
@@ -61,41 +88,24 @@ struct UpdateStripeConfigBody {
 async fn create_connect_link(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Json(body): Json<ConnectBody>,
 ) -> Result<impl IntoResponse, AppError> {
-    crate::middleware::auth::require_center_owner(&state.pool, claims.sub, body.center_id).await?;
-
-    // Fetch existing Stripe info
-    let center_stripe: Option<(Option<String>, Option<bool>)> = sqlx::query_as::<_, (Option<String>, Option<bool>)>(
-        "SELECT stripe_account_id, stripe_onboarding_complete FROM centers WHERE id = $1 AND deleted_at IS NULL",
-    )
-    .bind(body.center_id)
-    .fetch_optional(&state.pool)
-    .await?;
+    require_center_capability(&state.pool, claims.sub, body.center_id, Capability::ManagePayouts).await?;
 
-    let (existing_account_id, _onboarding_complete) = center_stripe
-        .ok_or_else(|| AppError::NotFound("Center not found".to_owned()))?;
+    let center = connect::load(&state.pool, body.center_id).await?;
 
-    let stripe_account_id = match existing_account_id {
-        Some(acct_id) if !acct_id.is_empty() => acct_id,
+    // A deauthorized account can no longer be used by the platform, and
+    // after an ownership transfer the account belongs to the previous owner:
+    // the center reconnects by onboarding a fresh Express account.
+    let reusable = !center.is_deauthorized() && !center.needs_reverification();
+    let stripe_account_id = match center.stripe_account_id {
+        Some(acct_id) if !acct_id.is_empty() && reusable => acct_id,
         _ => {
-            // Create a new Stripe Connect Express account
-            let params = stripe::CreateAccount::new();
-            let account = stripe::Account::create(&state.stripe, params)
-                .await
-                .map_err(|e| AppError::Internal(format!("Stripe API error: {e}")))?;
-
-            let acct_id = account.id.to_string();
-
-            // Persist the new account ID
-            sqlx::query(
-                "UPDATE centers SET stripe_account_id = $1, updated_at = NOW() WHERE id = $2",
-            )
-            .bind(&acct_id)
-            .bind(body.center_id)
-            .execute(&state.pool)
-            .await?;
-
+            let acct_id = state.payments.create_connect_account().await?;
+            let before = audit::snapshot(&state.pool, Entity::Center, body.center_id).await?;
+            connect::attach_account(&state.pool, body.center_id, &acct_id).await?;
+            audit.record(&mut *state.pool.acquire().await?, claims.sub, "stripe.connect_account", Entity::Center, body.center_id, before).await?;
             acct_id
         }
     };
@@ -108,26 +118,85 @@ async fn create_connect_link(
         .unwrap_or_default()
         .trim();
 
-    let account_id: stripe::AccountId = stripe_account_id
-        .parse()
-        .map_err(|_| AppError::Internal("Invalid stored Stripe account ID".to_owned()))?;
+    let link_url = state
+        .payments
+        .create_account_link(AccountLinkRequest {
+            account_id: stripe_account_id,
+            refresh_url: format!("{base_url}/dashboard/stripe/refresh"),
+            return_url: format!("{base_url}/dashboard/stripe/return"),
+        })
+        .await?;
 
-    let mut link_params =
-        stripe::CreateAccountLink::new(account_id, stripe::AccountLinkType::AccountOnboarding);
-    let refresh_url = format!("{base_url}/dashboard/stripe/refresh");
-    let return_url = format!("{base_url}/dashboard/stripe/return");
-    link_params.refresh_url = Some(&refresh_url);
-    link_params.return_url = Some(&return_url);
+    Ok((
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "data": ConnectLinkResponse {
+                url: link_url,
+                center_id: body.center_id,
+            }
+        })),
+    ))
+}
 
-    let link = stripe::AccountLink::create(&state.stripe, link_params)
-        .await
-        .map_err(|e| AppError::Internal(format!("Stripe API error: {e}")))?;
+// ──────────────────────── Account status ────────────────────────
+
+/// `GET /api/v1/stripe/account?center_id=&refresh=` — auth (`manage_payouts`), Connect
+/// status of a center: capabilities, outstanding requirements
+/// (`requirements.currently_due`, `past_due`) and disconnection.
+async fn get_account_status(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Query(params): Query<AccountStatusQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    require_center_capability(&state.pool, claims.sub, params.center_id, Capability::ManagePayouts).await?;
+
+    let mut center = connect::load(&state.pool, params.center_id).await?;
+
+    if params.refresh && !center.is_deauthorized() {
+        if let Some(ref acct_id) = center.stripe_account_id {
+            let status = state.payments.retrieve_account(acct_id).await?;
+            connect::store_status(&state.pool, &status).await?;
+            center = connect::load(&state.pool, params.center_id).await?;
+        }
+    }
+
+    Ok((StatusCode::OK, Json(serde_json::json!({ "data": center }))))
+}
+
+// ──────────────────────── Express dashboard ────────────────────────
+
+/// `POST /api/v1/stripe/dashboard-link` — auth (`manage_payouts`), create a single-use
+/// login link to the center's Stripe Express dashboard.
+async fn create_dashboard_link(
+    State(state): State<Arc<AppState>>,
+    AuthUser(claims): AuthUser,
+    Json(body): Json<ConnectBody>,
+) -> Result<impl IntoResponse, AppError> {
+    require_center_capability(&state.pool, claims.sub, body.center_id, Capability::ManagePayouts).await?;
+
+    let center = connect::load(&state.pool, body.center_id).await?;
+    if center.is_deauthorized() {
+        return Err(AppError::Conflict(
+            "This center's Stripe account was disconnected; reconnect it first".to_owned(),
+        ));
+    }
+    let acct_id = center
+        .stripe_account_id
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| AppError::BadRequest("Center has no Stripe account configured".to_owned()))?;
+    if !center.stripe_details_submitted {
+        return Err(AppError::BadRequest(
+            "Complete Stripe onboarding before opening the dashboard".to_owned(),
+        ));
+    }
+
+    let url = state.payments.create_login_link(&acct_id).await?;
 
     Ok((
         StatusCode::OK,
         Json(serde_json::json!({
-            "data": ConnectLinkResponse {
-                url: link.url,
+            "data": DashboardLinkResponse {
+                url,
                 center_id: body.center_id,
             }
         })),
@@ -141,23 +210,38 @@ async fn get_stripe_config(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
 ) -> Result<impl IntoResponse, AppError> {
-    let configs = sqlx::query_as::<_, StripeConfigRow>(
+    let centers = sqlx::query_as::<_, StripeConfigRow>(
         r#"
         SELECT c.id AS center_id, c.name AS center_name,
                c.stripe_account_id, c.stripe_onboarding_complete,
+               c.stripe_payouts_enabled, c.stripe_requirements_currently_due,
+               c.stripe_deauthorized_at,
                COALESCE(c.currency, 'EUR') AS currency
         FROM centers c
         INNER JOIN tli_pr_ce m ON m.fk_center = c.id AND m.fk_profile = $1
         WHERE c.deleted_at IS NULL
-          AND m.role_in_center = 'owner'
         ORDER BY c.name ASC
-        LIMIT 10
         "#,
     )
     .bind(claims.sub)
     .fetch_all(&state.pool)
     .await?;
 
+    // Only the centers whose payouts the caller manages, grants and
+    // revocations included
+    let mut configs = Vec::new();
+    for config in centers {
+        let allowed = permissions::load(&state.pool, claims.sub, config.center_id)
+            .await?
+            .is_some_and(|p| p.allows(Capability::ManagePayouts));
+        if allowed {
+            configs.push(config);
+        }
+        if configs.len() == 10 {
+            break;
+        }
+    }
+
     Ok((
         StatusCode::OK,
         Json(serde_json::json!({ "data": configs })),
@@ -170,9 +254,10 @@ async fn get_stripe_config(
 async fn update_stripe_config(
     State(state): State<Arc<AppState>>,
     AuthUser(claims): AuthUser,
+    audit: Audit,
     Json(body): Json<UpdateStripeConfigBody>,
 ) -> Result<impl IntoResponse, AppError> {
-    crate::middleware::auth::require_center_owner(&state.pool, claims.sub, body.center_id).await?;
+    require_center_capability(&state.pool, claims.sub, body.center_id, Capability::ManagePayouts).await?;
 
     if let Some(ref currency) = body.currency {
         let valid_currencies = ["EUR", "USD", "GBP", "CHF"];
@@ -183,11 +268,15 @@ async fn update_stripe_config(
             )));
         }
 
+        let mut tx = state.pool.begin().await?;
+        let before = audit::snapshot(&mut *tx, Entity::Center, body.center_id).await?;
         sqlx::query("UPDATE centers SET currency = $1, updated_at = NOW() WHERE id = $2")
             .bind(currency)
             .bind(body.center_id)
-            .execute(&state.pool)
+            .execute(&mut *tx)
             .await?;
+        audit.record(&mut tx, claims.sub, "stripe.update_config", Entity::Center, body.center_id, before).await?;
+        tx.commit().await?;
     }
 
     Ok((
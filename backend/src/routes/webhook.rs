@@ -8,6 +8,10 @@ use rust_decimal::Decimal;
 use uuid::Uuid;
 
 use crate::error::AppError;
+use crate::models::{Money, RoundingMode};
+use crate::services::email::{Template, Value};
+use crate::services::notifications::{self, Kind};
+use crate::services::{checkout, connect, invoices, outbox, tax};
 use crate::AppState;
 
 /// Verified Stripe webhook event.
@@ -79,15 +83,28 @@ pub async fn handle_stripe_webhook(
         stripe::EventType::CheckoutSessionCompleted => {
             handle_checkout_completed(&state, event.data.object).await?;
         }
+        stripe::EventType::CheckoutSessionExpired => {
+            handle_checkout_expired(&state, event.data.object).await?;
+        }
         stripe::EventType::PaymentIntentSucceeded => {
             handle_payment_intent_succeeded(&state, event.data.object).await?;
         }
         stripe::EventType::ChargeRefunded => {
             handle_charge_refunded(&state, event.data.object).await?;
         }
+        stripe::EventType::ChargeDisputeCreated
+        | stripe::EventType::ChargeDisputeUpdated
+        | stripe::EventType::ChargeDisputeClosed
+        | stripe::EventType::ChargeDisputeFundsWithdrawn
+        | stripe::EventType::ChargeDisputeFundsReinstated => {
+            handle_dispute(&state, event.data.object).await?;
+        }
         stripe::EventType::AccountUpdated => {
             handle_account_updated(&state, event.data.object).await?;
         }
+        stripe::EventType::AccountApplicationDeauthorized => {
+            handle_account_deauthorized(&state, event.account.as_deref()).await?;
+        }
         other => {
             tracing::debug!(event_type = ?other, "Unhandled event type, acknowledging");
         }
@@ -103,6 +120,7 @@ pub async fn handle_stripe_webhook(
 /// - Missing/invalid `booking_id` in metadata (EC-1)
 /// - Duplicate `stripe_payment_intent_id` already recorded (EC-2, INV-3)
 /// - Booking not found in DB
+/// - Session currency missing from the ISO 4217 minor-unit table
 /// - FK violation (booking deleted between validation and INSERT — ERR-3)
 ///
 /// 500 (triggers Stripe retry): any other DB error.
@@ -149,77 +167,192 @@ async fn handle_checkout_completed(
         }
     };
 
+    let amount_minor = session.amount_total.unwrap_or(0);
+    let currency = session.currency.map(|c| c.to_string().to_uppercase());
+    let destination = session
+        .metadata
+        .as_ref()
+        .and_then(|m| m.get("destination_account"))
+        .cloned();
+
+    record_transaction(
+        state,
+        "checkout.session.completed",
+        booking_id,
+        &pi_id,
+        amount_minor,
+        currency,
+        destination,
+    )
+    .await
+}
+
+/// INSERT the `transactions` row of a captured payment (once per
+/// PaymentIntent), then issue the booking's receipt and commission invoice.
+///
+/// Shared by `checkout.session.completed` and `payment_intent.succeeded`:
+/// Checkout payments trigger both events, embedded (Elements) payments only
+/// the latter. The unique index on `stripe_payment_intent_id` turns the
+/// second delivery into a no-op.
+///
+/// `destination` is the connected account of a destination charge: Stripe
+/// already paid the center, so the transaction never enters its payout
+/// balance.
+///
+/// Graceful returns (200, no INSERT):
+/// - Transaction already recorded for this PaymentIntent (EC-2, INV-3)
+/// - Booking not found in DB
+/// - Currency missing from the ISO 4217 minor-unit table
+/// - FK violation (booking deleted between validation and INSERT — ERR-3)
+///
+/// 500 (triggers Stripe retry): any other DB error.
+async fn record_transaction(
+    state: &AppState,
+    event: &'static str,
+    booking_id: Uuid,
+    pi_id: &str,
+    amount_minor: i64,
+    currency: Option<String>,
+    destination: Option<String>,
+) -> Result<(), AppError> {
     let already_exists: bool = sqlx::query_scalar(
         "SELECT EXISTS(SELECT 1 FROM transactions WHERE stripe_payment_intent_id = $1 AND deleted_at IS NULL)",
     )
-    .bind(&pi_id)
+    .bind(pi_id)
     .fetch_one(&state.pool)
     .await?;
 
     if already_exists {
         tracing::info!(
+            event,
             pi_id = %pi_id,
-            "checkout.session.completed: transaction already recorded (idempotent skip)"
+            "Transaction already recorded (idempotent skip)"
         );
         return Ok(());
     }
 
-    let booking: Option<(Decimal, String)> = sqlx::query_as(
-        "SELECT commission_rate, COALESCE(currency, 'EUR') AS currency FROM bookings WHERE id = $1 AND deleted_at IS NULL",
+    let booking: Option<(Uuid, Decimal, String)> = sqlx::query_as(
+        "SELECT center_id, commission_rate, COALESCE(currency, 'EUR') AS currency FROM bookings WHERE id = $1 AND deleted_at IS NULL",
     )
     .bind(booking_id)
     .fetch_optional(&state.pool)
     .await?;
 
-    let (commission_rate, booking_currency) = match booking {
+    let (center_id, commission_rate, booking_currency) = match booking {
         Some(b) => b,
         None => {
+            tracing::warn!(event, booking_id = %booking_id, "Booking not found");
+            return Ok(());
+        }
+    };
+
+    let currency = currency.unwrap_or(booking_currency);
+
+    let amount = match Money::from_stripe_minor(amount_minor, &currency) {
+        Ok(m) => m,
+        Err(e) => {
             tracing::warn!(
-                booking_id = %booking_id,
-                "checkout.session.completed: booking not found"
+                event,
+                pi_id = %pi_id,
+                error = %e,
+                "Cannot convert payment amount"
             );
             return Ok(());
         }
     };
 
-    let amount_cents = session.amount_total.unwrap_or(0);
-    let amount = Decimal::from(amount_cents) / Decimal::from(100);
-
-    let currency = session
-        .currency
-        .map(|c| c.to_string().to_uppercase())
-        .unwrap_or(booking_currency);
+    let platform_fee = amount.percentage(commission_rate, RoundingMode::HalfUp);
+    let vendor_amount = amount
+        .checked_sub(&platform_fee)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    // The payment is already captured: a center without a known country is
+    // logged and its breakdown stored as NULL rather than rejected.
+    let tax = tax::breakdown_for_center(&state.pool, center_id, &amount, &platform_fee).await?;
+    if tax.is_none() {
+        tracing::warn!(
+            event,
+            booking_id = %booking_id,
+            "Tax breakdown unavailable: center country unknown"
+        );
+    }
 
-    let platform_fee = amount * commission_rate / Decimal::from(100);
-    let vendor_amount = amount - platform_fee;
+    let (amount, platform_fee, vendor_amount) =
+        (amount.amount(), platform_fee.amount(), vendor_amount.amount());
 
+    let mut tx = state.pool.begin().await?;
     let insert_result = sqlx::query(
         r#"
         INSERT INTO transactions (
             booking_id, stripe_payment_intent_id, amount, platform_fee,
-            vendor_amount, currency, status
-        ) VALUES ($1, $2, $3, $4, $5, $6, 'succeeded'::payment_status)
+            vendor_amount, currency, status,
+            tax_country, price_vat_rate, price_net, price_vat,
+            fee_vat_rate, fee_net, fee_vat, fee_tax_regime,
+            stripe_destination_account
+        ) VALUES (
+            $1, $2, $3, $4, $5, $6, 'succeeded'::payment_status,
+            $7, $8, $9, $10, $11, $12, $13, $14, $15
+        )
+        ON CONFLICT (stripe_payment_intent_id)
+            WHERE stripe_payment_intent_id IS NOT NULL AND deleted_at IS NULL
+            DO NOTHING
         "#,
     )
     .bind(booking_id)
-    .bind(&pi_id)
+    .bind(pi_id)
     .bind(amount)
     .bind(platform_fee)
     .bind(vendor_amount)
     .bind(&currency)
-    .execute(&state.pool)
+    .bind(tax.as_ref().map(|t| t.country.as_str()))
+    .bind(tax.as_ref().map(|t| t.price.rate))
+    .bind(tax.as_ref().map(|t| t.price.net))
+    .bind(tax.as_ref().map(|t| t.price.vat))
+    .bind(tax.as_ref().map(|t| t.fee.rate))
+    .bind(tax.as_ref().map(|t| t.fee.net))
+    .bind(tax.as_ref().map(|t| t.fee.vat))
+    .bind(tax.as_ref().map(|t| t.fee_regime.as_str()))
+    .bind(&destination)
+    .execute(&mut *tx)
     .await;
 
     match insert_result {
+        Ok(result) if result.rows_affected() == 0 => {
+            tracing::info!(
+                event,
+                pi_id = %pi_id,
+                "Transaction recorded concurrently (idempotent skip)"
+            );
+        }
         Ok(_) => {
             tracing::info!(
+                event,
                 booking_id = %booking_id,
                 pi_id = %pi_id,
                 amount = %amount,
                 platform_fee = %platform_fee,
                 vendor_amount = %vendor_amount,
-                "Transaction recorded for checkout.session.completed"
+                "Transaction recorded"
             );
+
+            outbox::enqueue_booking_client(
+                &mut tx,
+                booking_id,
+                Template::PaymentReceived,
+                vec![("amount", Value::Amount(amount, currency.clone()))],
+            )
+            .await?;
+            tx.commit().await?;
+
+            // Best effort: documents are issued lazily on first download if
+            // this fails, so the webhook must not be retried because of it.
+            if let Err(e) = invoices::issue_for_booking(&state.pool, booking_id).await {
+                tracing::error!(
+                    error = ?e,
+                    booking_id = %booking_id,
+                    "Failed to issue booking receipt and commission invoice"
+                );
+            }
         }
         Err(ref e) => {
             let is_fk_violation = match e {
@@ -245,11 +378,14 @@ async fn handle_checkout_completed(
     Ok(())
 }
 
-/// Process `payment_intent.succeeded`: confirm the linked booking.
+/// Process `payment_intent.succeeded`: record the transaction and confirm
+/// the linked booking.
 ///
 /// Extracts `booking_id` from the PaymentIntent's metadata (propagated from
-/// the Checkout Session in Increment 3). Updates booking status to `confirmed`
-/// with atomic WHERE guard (same pattern as Increment 2).
+/// the Checkout Session in Increment 3, set directly by the embedded flow).
+/// The transaction is recorded through [`record_transaction`], so payments
+/// that never went through Checkout are booked too. Updates booking status to
+/// `confirmed` with atomic WHERE guard (same pattern as Increment 2).
 ///
 /// Graceful returns (200): missing metadata, booking not found, already confirmed.
 async fn handle_payment_intent_succeeded(
@@ -280,6 +416,25 @@ async fn handle_payment_intent_succeeded(
         }
     };
 
+    let pi_id = pi.id.to_string();
+    let amount_minor = if pi.amount_received > 0 { pi.amount_received } else { pi.amount };
+    let destination = pi
+        .transfer_data
+        .as_ref()
+        .map(|t| t.destination.id().to_string())
+        .or_else(|| pi.metadata.get("destination_account").cloned());
+    record_transaction(
+        state,
+        "payment_intent.succeeded",
+        booking_id,
+        &pi_id,
+        amount_minor,
+        Some(pi.currency.to_string().to_uppercase()),
+        destination,
+    )
+    .await?;
+
+    let mut tx = state.pool.begin().await?;
     let result = sqlx::query(
         r#"
         UPDATE bookings
@@ -290,7 +445,7 @@ async fn handle_payment_intent_succeeded(
         "#,
     )
     .bind(booking_id)
-    .execute(&state.pool)
+    .execute(&mut *tx)
     .await?;
 
     if result.rows_affected() == 0 {
@@ -304,6 +459,10 @@ async fn handle_payment_intent_succeeded(
             pi_id = %pi.id,
             "Booking confirmed via payment_intent.succeeded"
         );
+        outbox::enqueue_booking_client(&mut tx, booking_id, Template::BookingConfirmed, Vec::new())
+            .await?;
+        notifications::booking_event(&mut tx, booking_id, Kind::BookingConfirmed, None).await?;
+        tx.commit().await?;
     }
 
     Ok(())
@@ -369,10 +528,125 @@ async fn handle_charge_refunded(
     Ok(())
 }
 
-/// Process `account.updated`: sync center's Stripe Connect onboarding status.
+/// Process `charge.dispute.*`: store the dispute's latest status with the
+/// center of the disputed booking. Scheduled and manual payouts of a center
+/// are held while one of its disputes is open.
+async fn handle_dispute(state: &AppState, object: stripe::EventObject) -> Result<(), AppError> {
+    let dispute = match object {
+        stripe::EventObject::Dispute(d) => d,
+        _ => {
+            tracing::warn!("charge.dispute.*: unexpected event object type");
+            return Ok(());
+        }
+    };
+
+    let pi_id = dispute.payment_intent.as_ref().map(|pi| match pi {
+        stripe::Expandable::Id(id) => id.to_string(),
+        stripe::Expandable::Object(obj) => obj.id.to_string(),
+    });
+    let currency = dispute.currency.to_string().to_uppercase();
+    let amount = match Money::from_stripe_minor(dispute.amount, &currency) {
+        Ok(m) => m.amount(),
+        Err(e) => {
+            tracing::warn!(dispute_id = %dispute.id, error = %e, "charge.dispute.*: unsupported currency");
+            return Ok(());
+        }
+    };
+
+    let booking = match &pi_id {
+        Some(pi_id) => {
+            sqlx::query_as::<_, (Uuid, Uuid)>(
+                r#"
+                SELECT b.id, b.center_id
+                FROM transactions t
+                JOIN bookings b ON b.id = t.booking_id
+                WHERE t.stripe_payment_intent_id = $1 AND t.deleted_at IS NULL
+                "#,
+            )
+            .bind(pi_id)
+            .fetch_optional(&state.pool)
+            .await?
+        }
+        None => None,
+    };
+    if booking.is_none() {
+        tracing::warn!(
+            dispute_id = %dispute.id,
+            pi_id = ?pi_id,
+            "charge.dispute.*: no matching transaction, dispute stored without center"
+        );
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO disputes
+            (stripe_dispute_id, stripe_payment_intent_id, booking_id, center_id,
+             amount, currency, reason, status)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (stripe_dispute_id) DO UPDATE
+        SET status = EXCLUDED.status, reason = EXCLUDED.reason,
+            amount = EXCLUDED.amount, updated_at = NOW()
+        "#,
+    )
+    .bind(dispute.id.as_str())
+    .bind(&pi_id)
+    .bind(booking.map(|(id, _)| id))
+    .bind(booking.map(|(_, center)| center))
+    .bind(amount)
+    .bind(&currency)
+    .bind(&dispute.reason)
+    .bind(dispute.status.as_str())
+    .execute(&state.pool)
+    .await?;
+
+    tracing::info!(
+        dispute_id = %dispute.id,
+        status = dispute.status.as_str(),
+        "Dispute recorded"
+    );
+    Ok(())
+}
+
+/// Process `checkout.session.expired`: forget the booking's stored session
+/// if it is the one that expired, so the next checkout opens a fresh one.
+async fn handle_checkout_expired(
+    state: &AppState,
+    object: stripe::EventObject,
+) -> Result<(), AppError> {
+    let session = match object {
+        stripe::EventObject::CheckoutSession(s) => s,
+        _ => {
+            tracing::warn!("checkout.session.expired: unexpected event object type");
+            return Ok(());
+        }
+    };
+
+    let booking_id = session
+        .metadata
+        .as_ref()
+        .and_then(|m| m.get("booking_id"))
+        .and_then(|v| Uuid::parse_str(v).ok());
+    let Some(booking_id) = booking_id else {
+        tracing::debug!(session_id = %session.id, "checkout.session.expired: no booking_id");
+        return Ok(());
+    };
+
+    let session_id = session.id.to_string();
+    if checkout::clear_session(&state.pool, booking_id, Some(&session_id))
+        .await?
+        .is_some()
+    {
+        tracing::info!(booking_id = %booking_id, session_id = %session_id, "Checkout Session expired");
+    }
+
+    Ok(())
+}
+
+/// Process `account.updated`: sync the center's Stripe Connect status.
 ///
-/// `charges_enabled` is the authoritative signal: `true` means Stripe has
-/// verified the account and it can accept payments.
+/// `charges_enabled` is the authoritative onboarding signal (stored as
+/// `stripe_onboarding_complete`); payouts, outstanding requirements and the
+/// disabled reason are stored alongside so owners can see what Stripe needs.
 async fn handle_account_updated(
     state: &AppState,
     object: stripe::EventObject,
@@ -385,33 +659,47 @@ async fn handle_account_updated(
         }
     };
 
-    let account_id = account.id.to_string();
-    let charges_enabled = account.charges_enabled.unwrap_or(false);
-
-    let result = sqlx::query(
-        r#"
-        UPDATE centers
-        SET stripe_onboarding_complete = $1,
-            updated_at = NOW()
-        WHERE stripe_account_id = $2 AND deleted_at IS NULL
-        "#,
-    )
-    .bind(charges_enabled)
-    .bind(&account_id)
-    .execute(&state.pool)
-    .await?;
+    let status = crate::services::stripe::account_status(&account);
+    let updated = connect::store_status(&state.pool, &status).await?;
 
-    if result.rows_affected() == 0 {
+    if updated == 0 {
         tracing::debug!(
-            account_id = %account_id,
+            account_id = %status.account_id,
             "account.updated: no center found with this Stripe account"
         );
     } else {
         tracing::info!(
+            account_id = %status.account_id,
+            charges_enabled = status.charges_enabled,
+            payouts_enabled = status.payouts_enabled,
+            currently_due = status.currently_due.len(),
+            "Center Stripe account status updated"
+        );
+    }
+
+    Ok(())
+}
+
+/// Process `account.application.deauthorized`: the connected account
+/// (`event.account`) revoked the platform's access. Checkout and payouts are
+/// blocked for its center until a new account is onboarded.
+async fn handle_account_deauthorized(
+    state: &AppState,
+    account_id: Option<&str>,
+) -> Result<(), AppError> {
+    let Some(account_id) = account_id else {
+        tracing::warn!("account.application.deauthorized: event has no account");
+        return Ok(());
+    };
+
+    let updated = connect::mark_deauthorized(&state.pool, account_id).await?;
+    if updated == 0 {
+        tracing::debug!(
             account_id = %account_id,
-            charges_enabled = charges_enabled,
-            "Center Stripe onboarding status updated"
+            "account.application.deauthorized: no center found with this Stripe account"
         );
+    } else {
+        tracing::warn!(account_id = %account_id, "Center Stripe account deauthorized");
     }
 
     Ok(())
@@ -0,0 +1,337 @@
+//! Append-only audit log of administrative and center mutations.
+//!
+//! Admin and center handlers snapshot the row they change with
+//! [`snapshot`] before the change, and call `Audit::record` (see
+//! `middleware::audit`) after it, with the same transaction when they have
+//! one. The entry keeps who did it, from which IP and request, and the
+//! fields that changed. `audit_log` (migration 035) rejects updates and
+//! deletes.
+
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// Fields left out of the before/after comparison.
+const IGNORED_FIELDS: [&str; 1] = ["updated_at"];
+
+/// Columns never copied into the log, per entity: names, contact details
+/// and free text written by or about a person.
+const PROFILE_PERSONAL_FIELDS: [&str; 5] = ["first_name", "last_name", "display_name", "avatar_url", "phone"];
+const BOOKING_PERSONAL_FIELDS: [&str; 2] = ["client_note", "center_note"];
+const CONTACT_PERSONAL_FIELDS: [&str; 6] = ["name", "email", "subject", "message", "ip_hash", "user_agent"];
+const EMAIL_PERSONAL_FIELDS: [&str; 4] = ["to_address", "reply_to", "subject", "body"];
+const INVITATION_PERSONAL_FIELDS: [&str; 1] = ["email"];
+const OWNERSHIP_TRANSFER_PERSONAL_FIELDS: [&str; 1] = ["message"];
+const REVIEW_PERSONAL_FIELDS: [&str; 1] = ["comment"];
+
+/// Kinds of audited rows, and the table they live in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entity {
+    Ban,
+    Booking,
+    Center,
+    ContactMessage,
+    Coupon,
+    CouponSource,
+    Email,
+    ExchangeRate,
+    Extra,
+    Invitation,
+    Location,
+    Member,
+    OwnershipTransfer,
+    ReconciliationRun,
+    Refund,
+    Review,
+    Service,
+    Setting,
+    Tag,
+    User,
+}
+
+impl Entity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ban => "ban",
+            Self::Booking => "booking",
+            Self::Center => "center",
+            Self::ContactMessage => "contact_message",
+            Self::Coupon => "coupon",
+            Self::CouponSource => "coupon_source",
+            Self::Email => "email",
+            Self::ExchangeRate => "exchange_rate",
+            Self::Extra => "extra",
+            Self::Invitation => "invitation",
+            Self::Location => "location",
+            Self::Member => "member",
+            Self::OwnershipTransfer => "ownership_transfer",
+            Self::ReconciliationRun => "reconciliation_run",
+            Self::Refund => "refund",
+            Self::Review => "review",
+            Self::Service => "service",
+            Self::Setting => "setting",
+            Self::Tag => "tag",
+            Self::User => "user",
+        }
+    }
+
+    fn table(&self) -> &'static str {
+        match self {
+            Self::Ban => "user_bans",
+            Self::Booking => "bookings",
+            Self::Center => "centers",
+            Self::ContactMessage => "contact_messages",
+            Self::Coupon => "coupons",
+            Self::CouponSource => "coupon_sources",
+            Self::Email => "email_outbox",
+            Self::ExchangeRate => "exchange_rates",
+            Self::Extra => "service_extras",
+            Self::Invitation => "center_invitations",
+            Self::Location => "locations",
+            Self::Member => "tli_pr_ce",
+            Self::OwnershipTransfer => "center_ownership_transfers",
+            Self::ReconciliationRun => "reconciliation_runs",
+            Self::Refund => "refunds",
+            Self::Review => "reviews",
+            Self::Service => "services",
+            Self::Setting => "t_platform_config",
+            Self::Tag => "tags",
+            Self::User => "profiles",
+        }
+    }
+
+    /// Columns holding personal data, left out of snapshots so that the log
+    /// keeps nothing an account erasure would have to remove.
+    fn personal_fields(&self) -> &'static [&'static str] {
+        match self {
+            Self::Booking => &BOOKING_PERSONAL_FIELDS,
+            Self::ContactMessage => &CONTACT_PERSONAL_FIELDS,
+            Self::Email => &EMAIL_PERSONAL_FIELDS,
+            Self::Invitation => &INVITATION_PERSONAL_FIELDS,
+            Self::OwnershipTransfer => &OWNERSHIP_TRANSFER_PERSONAL_FIELDS,
+            Self::Review => &REVIEW_PERSONAL_FIELDS,
+            Self::User => &PROFILE_PERSONAL_FIELDS,
+            _ => &[],
+        }
+    }
+
+    /// Key column and its SQL type.
+    fn key(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::Setting => ("key", "text"),
+            _ => ("id", "uuid"),
+        }
+    }
+}
+
+/// Row `id` of `entity` as JSON, `None` if it does not exist. Secret
+/// platform settings have their value masked, and personal data is left
+/// out ([`strip_personal`]).
+pub async fn snapshot<'e, E>(
+    executor: E,
+    entity: Entity,
+    id: impl std::fmt::Display,
+) -> Result<Option<Value>, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let (column, sql_type) = entity.key();
+    let row: Option<Value> = sqlx::query_scalar(&format!(
+        "SELECT to_jsonb(t) FROM {} t WHERE t.{column} = $1::{sql_type}",
+        entity.table()
+    ))
+    .bind(id.to_string())
+    .fetch_optional(executor)
+    .await?;
+    Ok(row.map(|row| redact(strip_personal(entity, row))))
+}
+
+/// Remove the columns of `entity` holding personal data: names, email
+/// addresses, message bodies, notes and comments.
+pub fn strip_personal(entity: Entity, mut row: Value) -> Value {
+    if let Value::Object(fields) = &mut row {
+        for field in entity.personal_fields() {
+            fields.remove(*field);
+        }
+    }
+    row
+}
+
+/// Mask the value of secret settings, as the settings API does.
+pub fn redact(mut row: Value) -> Value {
+    if row.get("is_secret") == Some(&Value::Bool(true)) {
+        if let Some(value) = row.get_mut("value") {
+            let visible: String = value
+                .as_str()
+                .map(|v| v.chars().rev().take(4).collect::<Vec<_>>().into_iter().rev().collect())
+                .unwrap_or_default();
+            *value = Value::String(format!("****{visible}"));
+        }
+    }
+    row
+}
+
+/// The fields of two snapshots that differ. Creations and deletions keep
+/// the whole row.
+pub fn changes(before: Option<Value>, after: Option<Value>) -> (Option<Value>, Option<Value>) {
+    let (Some(Value::Object(mut before)), Some(Value::Object(mut after))) = (before.clone(), after.clone()) else {
+        return (before, after);
+    };
+    let unchanged: Vec<String> = before
+        .iter()
+        .filter(|(key, value)| IGNORED_FIELDS.contains(&key.as_str()) || after.get(*key) == Some(*value))
+        .map(|(key, _)| key.clone())
+        .collect();
+    for key in unchanged.iter().map(String::as_str).chain(IGNORED_FIELDS) {
+        before.remove(key);
+        after.remove(key);
+    }
+    (Some(Value::Object(before)), Some(Value::Object(after)))
+}
+
+/// Center a row belongs to, for the center filter.
+pub fn center_of(entity: Entity, id: &str, row: Option<&Value>) -> Option<Uuid> {
+    if entity == Entity::Center {
+        return id.parse().ok();
+    }
+    let row = row?;
+    ["center_id", "fk_center"]
+        .iter()
+        .find_map(|field| row.get(*field)?.as_str()?.parse().ok())
+}
+
+#[derive(Debug, Clone)]
+pub struct NewEntry {
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub entity: Entity,
+    pub entity_id: Option<String>,
+    pub center_id: Option<Uuid>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub ip: Option<IpAddr>,
+    pub request_id: Option<String>,
+}
+
+pub async fn insert<'e, E>(executor: E, entry: &NewEntry) -> Result<i64, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let id: i64 = sqlx::query_scalar(
+        r#"
+        INSERT INTO audit_log (actor_id, action, entity_type, entity_id, center_id, before, after, ip, request_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8::inet, $9)
+        RETURNING id
+        "#,
+    )
+    .bind(entry.actor_id)
+    .bind(&entry.action)
+    .bind(entry.entity.as_str())
+    .bind(&entry.entity_id)
+    .bind(entry.center_id)
+    .bind(&entry.before)
+    .bind(&entry.after)
+    .bind(entry.ip.map(|ip| ip.to_string()))
+    .bind(&entry.request_id)
+    .fetch_one(executor)
+    .await?;
+    Ok(id)
+}
+
+// ──────────────────────── Query ────────────────────────
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub actor_id: Option<Uuid>,
+    pub actor_name: Option<String>,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Option<String>,
+    pub center_id: Option<Uuid>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub ip: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditFilter {
+    pub actor_id: Option<Uuid>,
+    /// Exact action (`center.update_status`), or a prefix ending with a
+    /// dot (`center.`)
+    pub action: Option<String>,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<String>,
+    pub center_id: Option<Uuid>,
+    pub request_id: Option<String>,
+    pub from: Option<Timestamp>,
+    pub to: Option<Timestamp>,
+    /// Entries older than this id, for the next page
+    pub before_id: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+impl AuditFilter {
+    /// `LIKE` pattern of the action filter.
+    pub fn action_pattern(&self) -> Option<String> {
+        let action = self.action.as_deref().map(str::trim).filter(|a| !a.is_empty())?;
+        let escaped = action.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        Some(if escaped.ends_with('.') { format!("{escaped}%") } else { escaped })
+    }
+}
+
+const ENTRY_SELECT: &str = r#"
+    SELECT a.id, a.actor_id,
+           COALESCE(NULLIF(p.display_name, ''), NULLIF(TRIM(CONCAT(p.first_name, ' ', p.last_name)), '')) AS actor_name,
+           a.action, a.entity_type, a.entity_id, a.center_id, a.before, a.after,
+           host(a.ip) AS ip, a.request_id, a.created_at
+    FROM audit_log a
+    LEFT JOIN profiles p ON p.id = a.actor_id
+"#;
+
+/// Entries matching `filter`, newest first.
+pub async fn list(pool: &sqlx::PgPool, filter: &AuditFilter) -> Result<Vec<AuditEntry>, AppError> {
+    let rows = sqlx::query_as::<_, AuditEntry>(&format!(
+        r#"{ENTRY_SELECT}
+        WHERE ($1::uuid IS NULL OR a.actor_id = $1)
+          AND ($2::text IS NULL OR a.action LIKE $2)
+          AND ($3::text IS NULL OR a.entity_type = $3)
+          AND ($4::text IS NULL OR a.entity_id = $4)
+          AND ($5::uuid IS NULL OR a.center_id = $5)
+          AND ($6::text IS NULL OR a.request_id = $6)
+          AND ($7::timestamptz IS NULL OR a.created_at >= $7)
+          AND ($8::timestamptz IS NULL OR a.created_at < $8)
+          AND ($9::bigint IS NULL OR a.id < $9)
+        ORDER BY a.id DESC
+        LIMIT $10"#
+    ))
+    .bind(filter.actor_id)
+    .bind(filter.action_pattern())
+    .bind(&filter.entity_type)
+    .bind(&filter.entity_id)
+    .bind(filter.center_id)
+    .bind(&filter.request_id)
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(filter.before_id)
+    .bind(filter.limit.unwrap_or(100).clamp(1, 500))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn get(pool: &sqlx::PgPool, id: i64) -> Result<AuditEntry, AppError> {
+    sqlx::query_as::<_, AuditEntry>(&format!("{ENTRY_SELECT} WHERE a.id = $1"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Audit entry not found".to_owned()))
+}
@@ -0,0 +1,307 @@
+//! User bans.
+//!
+//! An admin bans a user from booking, from reviewing, or from the whole
+//! platform, with a reason and an optional expiry. Platform bans are
+//! enforced by the `AuthUser` extractor; the narrower scopes by
+//! `require_not_banned` in the handlers they cover. Banning from booking
+//! (or the platform) cancels the user's pending bookings in the same
+//! transaction. `user_bans` (migration 036) keeps lifted and expired bans.
+
+use serde::Serialize;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::services::checkout::{self, StoredSession};
+use crate::services::email::Template;
+use crate::services::notifications::{self, Kind};
+use crate::services::outbox;
+use crate::services::payment_gateway::PaymentGateway;
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Booking,
+    Review,
+    Platform,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Booking => "booking",
+            Self::Review => "review",
+            Self::Platform => "platform",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "booking" => Some(Self::Booking),
+            "review" => Some(Self::Review),
+            "platform" => Some(Self::Platform),
+            _ => None,
+        }
+    }
+
+    /// Whether a ban of this scope forbids `action`.
+    pub fn covers(&self, action: Scope) -> bool {
+        *self == Self::Platform || *self == action
+    }
+
+    /// Whether the user's pending bookings are cancelled by the ban.
+    pub fn cancels_bookings(&self) -> bool {
+        self.covers(Self::Booking)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Ban {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub scope: String,
+    pub reason: String,
+    pub expires_at: Option<Timestamp>,
+    pub banned_by: Option<Uuid>,
+    pub lifted_at: Option<Timestamp>,
+    pub lifted_by: Option<Uuid>,
+    pub created_at: Timestamp,
+}
+
+impl Ban {
+    /// What the banned user is told.
+    pub fn message(&self) -> String {
+        let what = match Scope::parse(&self.scope) {
+            Some(Scope::Booking) => "banned from booking",
+            Some(Scope::Review) => "banned from posting reviews",
+            _ => "suspended",
+        };
+        match self.expires_at {
+            Some(expires_at) => format!(
+                "Your account is {what} until {}: {}",
+                expires_at.format("%Y-%m-%d %H:%M UTC"),
+                self.reason
+            ),
+            None => format!("Your account is {what}: {}", self.reason),
+        }
+    }
+}
+
+/// Check a new ban: a reason is required and the expiry, if any, must be
+/// in the future.
+pub fn validate(reason: &str, expires_at: Option<Timestamp>, now: Timestamp) -> Result<(), AppError> {
+    if reason.trim().is_empty() {
+        return Err(AppError::BadRequest("A ban needs a reason".to_owned()));
+    }
+    if reason.chars().count() > 1000 {
+        return Err(AppError::BadRequest("Reason is too long (max 1000 characters)".to_owned()));
+    }
+    if expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(AppError::BadRequest("expires_at must be in the future".to_owned()));
+    }
+    Ok(())
+}
+
+const BAN_SELECT: &str = r#"
+    SELECT id, user_id, scope, reason, expires_at, banned_by, lifted_at, lifted_by, created_at
+    FROM user_bans
+"#;
+
+/// The ban keeping `user_id` from `action`, if any. Platform bans come
+/// first, then the one lasting longest.
+pub async fn active<'e, E>(executor: E, user_id: Uuid, action: Scope) -> Result<Option<Ban>, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let ban = sqlx::query_as::<_, Ban>(&format!(
+        r#"{BAN_SELECT}
+        WHERE user_id = $1
+          AND scope IN ($2, 'platform')
+          AND lifted_at IS NULL
+          AND (expires_at IS NULL OR expires_at > NOW())
+        ORDER BY scope = 'platform' DESC, expires_at DESC NULLS FIRST
+        LIMIT 1"#
+    ))
+    .bind(user_id)
+    .bind(action.as_str())
+    .fetch_optional(executor)
+    .await?;
+    Ok(ban)
+}
+
+/// Whether the profile of `user_id` is deleted, and the platform ban in
+/// force on them if any: what `AuthUser` checks on every request, in one
+/// query.
+pub async fn standing(pool: &sqlx::PgPool, user_id: Uuid) -> Result<(bool, Option<Ban>), AppError> {
+    let row = sqlx::query(
+        r#"
+        SELECT COALESCE(p.deleted_at IS NOT NULL, false) AS deleted,
+               b.id, b.user_id, b.scope, b.reason, b.expires_at, b.banned_by, b.lifted_at, b.lifted_by,
+               b.created_at
+        FROM (SELECT $1::uuid AS id) u
+        LEFT JOIN profiles p ON p.id = u.id
+        LEFT JOIN LATERAL (
+            SELECT * FROM user_bans
+            WHERE user_id = u.id AND scope = 'platform' AND lifted_at IS NULL
+              AND (expires_at IS NULL OR expires_at > NOW())
+            ORDER BY expires_at DESC NULLS FIRST
+            LIMIT 1
+        ) b ON true
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    let deleted: bool = row.try_get("deleted")?;
+    let ban = match row.try_get::<Option<Uuid>, _>("id")? {
+        Some(_) => Some(Ban::from_row(&row)?),
+        None => None,
+    };
+    Ok((deleted, ban))
+}
+
+/// Bans of `user_id` still in force, locked until the transaction ends.
+pub async fn in_force(conn: &mut sqlx::PgConnection, user_id: Uuid) -> Result<Vec<Ban>, AppError> {
+    let rows = sqlx::query_as::<_, Ban>(&format!(
+        r#"{BAN_SELECT}
+        WHERE user_id = $1 AND lifted_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())
+        ORDER BY created_at
+        FOR UPDATE"#
+    ))
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(rows)
+}
+
+/// Every ban of `user_id`, newest first.
+pub async fn list(pool: &sqlx::PgPool, user_id: Uuid) -> Result<Vec<Ban>, AppError> {
+    let rows = sqlx::query_as::<_, Ban>(&format!("{BAN_SELECT} WHERE user_id = $1 ORDER BY created_at DESC"))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+/// A pending booking cancelled by a ban, with what is left to release at
+/// the payment provider once the transaction is committed.
+#[derive(Debug, Clone)]
+pub struct CancelledBooking {
+    pub booking_id: Uuid,
+    pub payment_intent_id: Option<String>,
+    pub session: Option<StoredSession>,
+}
+
+/// Ban `user_id` for `scope`. Pending bookings are cancelled when the
+/// scope covers booking; pass them to [`release_payments`] after commit.
+pub async fn create(
+    conn: &mut sqlx::PgConnection,
+    user_id: Uuid,
+    scope: Scope,
+    reason: &str,
+    expires_at: Option<Timestamp>,
+    banned_by: Uuid,
+) -> Result<(Ban, Vec<CancelledBooking>), AppError> {
+    validate(reason, expires_at, chrono::Utc::now())?;
+    if user_id == banned_by {
+        return Err(AppError::BadRequest("Cannot ban yourself".to_owned()));
+    }
+
+    let role: Option<String> = sqlx::query_scalar("SELECT role::text FROM profiles WHERE id = $1 FOR UPDATE")
+        .bind(user_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    match role.as_deref() {
+        None => return Err(AppError::NotFound("User not found".to_owned())),
+        Some("admin_diver") => return Err(AppError::BadRequest("Admins cannot be banned".to_owned())),
+        Some(_) => {}
+    }
+
+    let ban = sqlx::query_as::<_, Ban>(
+        r#"
+        INSERT INTO user_bans (user_id, scope, reason, expires_at, banned_by)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, user_id, scope, reason, expires_at, banned_by, lifted_at, lifted_by, created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(scope.as_str())
+    .bind(reason.trim())
+    .bind(expires_at)
+    .bind(banned_by)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let cancelled = if scope.cancels_bookings() {
+        cancel_pending_bookings(conn, user_id).await?
+    } else {
+        Vec::new()
+    };
+    Ok((ban, cancelled))
+}
+
+/// Cancel the pending bookings of `user_id`. The client gets the usual
+/// cancellation email and the center staff a notification.
+async fn cancel_pending_bookings(
+    conn: &mut sqlx::PgConnection,
+    user_id: Uuid,
+) -> Result<Vec<CancelledBooking>, AppError> {
+    let rows = sqlx::query_as::<_, (Uuid, Option<String>)>(
+        r#"
+        UPDATE bookings
+        SET status = 'cancelled'::booking_status,
+            cancelled_at = NOW(),
+            updated_at = NOW()
+        WHERE client_id = $1 AND status = 'pending' AND deleted_at IS NULL
+        RETURNING id, payment_intent_id
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut cancelled = Vec::with_capacity(rows.len());
+    for (booking_id, payment_intent_id) in rows {
+        let session = checkout::clear_session(&mut *conn, booking_id, None).await?;
+        outbox::enqueue_booking_client(conn, booking_id, Template::BookingCancelled, Vec::new()).await?;
+        // As if the client had cancelled: the notification goes to the staff.
+        notifications::booking_event(conn, booking_id, Kind::BookingCancelled, Some(user_id)).await?;
+        cancelled.push(CancelledBooking { booking_id, payment_intent_id, session });
+    }
+    Ok(cancelled)
+}
+
+/// Make the cancelled bookings unpayable at the payment provider.
+pub async fn release_payments(gateway: &dyn PaymentGateway, cancelled: &[CancelledBooking]) {
+    for booking in cancelled {
+        if let Some(ref session) = booking.session {
+            checkout::expire_quietly(gateway, session).await;
+        }
+        if let Some(ref pi_id) = booking.payment_intent_id {
+            checkout::cancel_payment_intent_quietly(gateway, pi_id).await;
+        }
+    }
+}
+
+/// Lift ban `id` of `user_id`.
+pub async fn lift(
+    conn: &mut sqlx::PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+    lifted_by: Uuid,
+) -> Result<Ban, AppError> {
+    sqlx::query_as::<_, Ban>(
+        r#"
+        UPDATE user_bans SET lifted_at = NOW(), lifted_by = $3
+        WHERE id = $1 AND user_id = $2 AND lifted_at IS NULL
+        RETURNING id, user_id, scope, reason, expires_at, banned_by, lifted_at, lifted_by, created_at
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(lifted_by)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Active ban not found".to_owned()))
+}
@@ -0,0 +1,163 @@
+//! Active Stripe Checkout Session of a booking.
+//!
+//! A booking keeps at most one open session (`bookings.checkout_session_*`).
+//! Checkout returns it while it stays payable for the current amount, and
+//! only opens a new one after expiring the previous session through the
+//! gateway, so a diver can never hold two payable sessions for one booking.
+
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::services::payment_gateway::{CheckoutSession, PaymentGateway};
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// A session is not handed out when it expires within this many minutes:
+/// the diver needs time to fill in the payment form.
+pub const REUSE_MARGIN_MINUTES: i64 = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct StoredSession {
+    pub checkout_session_id: String,
+    pub checkout_session_url: String,
+    pub checkout_session_amount: Decimal,
+    pub checkout_session_expires_at: Timestamp,
+}
+
+impl StoredSession {
+    /// Whether the session can still be paid at `now` for `amount`.
+    pub fn is_reusable(&self, amount: Decimal, now: Timestamp) -> bool {
+        self.checkout_session_amount == amount
+            && self.checkout_session_expires_at
+                > now + chrono::Duration::minutes(REUSE_MARGIN_MINUTES)
+    }
+
+    /// Whether Stripe may still accept a payment on it.
+    pub fn is_open(&self, now: Timestamp) -> bool {
+        self.checkout_session_expires_at > now
+    }
+}
+
+/// Load the stored session of a booking, locking the booking row for the
+/// rest of the transaction so concurrent checkouts are serialized.
+pub async fn lock_session(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    booking_id: Uuid,
+) -> Result<Option<StoredSession>, AppError> {
+    let row = sqlx::query_as::<_, (Option<String>, Option<String>, Option<Decimal>, Option<Timestamp>)>(
+        r#"
+        SELECT checkout_session_id, checkout_session_url,
+               checkout_session_amount, checkout_session_expires_at
+        FROM bookings WHERE id = $1
+        FOR UPDATE
+        "#,
+    )
+    .bind(booking_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    Ok(match row {
+        Some((Some(id), Some(url), Some(amount), Some(expires_at))) => Some(StoredSession {
+            checkout_session_id: id,
+            checkout_session_url: url,
+            checkout_session_amount: amount,
+            checkout_session_expires_at: expires_at,
+        }),
+        _ => None,
+    })
+}
+
+pub async fn store_session(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    booking_id: Uuid,
+    session: &CheckoutSession,
+    amount: Decimal,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE bookings
+        SET checkout_session_id = $2, checkout_session_url = $3,
+            checkout_session_amount = $4, checkout_session_expires_at = $5,
+            updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(booking_id)
+    .bind(&session.id)
+    .bind(&session.url)
+    .bind(amount)
+    .bind(session.expires_at)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Forget the session of a booking when it is `session_id` (any session when
+/// `None`). Returns the session that was cleared.
+pub async fn clear_session<'e, E>(
+    executor: E,
+    booking_id: Uuid,
+    session_id: Option<&str>,
+) -> Result<Option<StoredSession>, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let row = sqlx::query_as::<_, StoredSession>(
+        r#"
+        UPDATE bookings b
+        SET checkout_session_id = NULL, checkout_session_url = NULL,
+            checkout_session_amount = NULL, checkout_session_expires_at = NULL
+        FROM (
+            SELECT id, checkout_session_id, checkout_session_url,
+                   checkout_session_amount, checkout_session_expires_at
+            FROM bookings WHERE id = $1 FOR UPDATE
+        ) old
+        WHERE b.id = old.id
+          AND old.checkout_session_id IS NOT NULL
+          AND ($2::text IS NULL OR old.checkout_session_id = $2)
+        RETURNING old.checkout_session_id, old.checkout_session_url,
+                  old.checkout_session_amount, old.checkout_session_expires_at
+        "#,
+    )
+    .bind(booking_id)
+    .bind(session_id)
+    .fetch_optional(executor)
+    .await?;
+    Ok(row)
+}
+
+/// Expire a session that may still be open. Failures are logged: the
+/// session expires on its own at `checkout_session_expires_at`.
+pub async fn expire_quietly(gateway: &dyn PaymentGateway, session: &StoredSession) {
+    if !session.is_open(chrono::Utc::now()) {
+        return;
+    }
+    if let Err(e) = gateway
+        .expire_checkout_session(&session.checkout_session_id)
+        .await
+    {
+        tracing::warn!(
+            session_id = %session.checkout_session_id,
+            error = %e,
+            "Failed to expire Checkout Session"
+        );
+    }
+}
+
+/// Cancel a PaymentIntent that may still be payable. Failures are logged:
+/// a PaymentIntent already paid or canceled cannot be canceled again.
+pub async fn cancel_payment_intent_quietly(gateway: &dyn PaymentGateway, payment_intent_id: &str) {
+    match gateway.retrieve_payment_intent(payment_intent_id).await {
+        Ok(intent) if !intent.is_payable() => {}
+        _ => {
+            if let Err(e) = gateway.cancel_payment_intent(payment_intent_id).await {
+                tracing::warn!(
+                    pi_id = %payment_intent_id,
+                    error = %e,
+                    "Failed to cancel PaymentIntent of cancelled booking"
+                );
+            }
+        }
+    }
+}
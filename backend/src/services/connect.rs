@@ -0,0 +1,192 @@
+//! Stripe Connect state of centers.
+//!
+//! The `account.updated` webhook (or an explicit refresh) stores the
+//! account's capabilities and outstanding requirements on the center;
+//! `account.application.deauthorized` marks it disconnected. Checkout and
+//! payouts go through [`CenterConnect`] so a disconnected center can neither
+//! take payments nor receive transfers until a new account is connected and
+//! Stripe enables charges on it. After an ownership transfer the account,
+//! which belongs to the previous owner, is not used either: the platform
+//! collects payments and payouts wait until the new owner connects theirs.
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::services::payment_gateway::AccountStatus;
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// Connect columns of a center.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CenterConnect {
+    pub center_id: Uuid,
+    pub stripe_account_id: Option<String>,
+    /// Mirrors `charges_enabled`.
+    pub stripe_onboarding_complete: bool,
+    pub stripe_payouts_enabled: bool,
+    pub stripe_details_submitted: bool,
+    pub stripe_requirements_currently_due: Vec<String>,
+    pub stripe_requirements_past_due: Vec<String>,
+    pub stripe_disabled_reason: Option<String>,
+    pub stripe_requirements_deadline: Option<Timestamp>,
+    pub stripe_status_synced_at: Option<Timestamp>,
+    pub stripe_deauthorized_at: Option<Timestamp>,
+    /// Set by an ownership transfer, cleared when a new account is attached.
+    pub stripe_reverification_required_at: Option<Timestamp>,
+}
+
+impl CenterConnect {
+    fn account_id(&self) -> Option<&str> {
+        self.stripe_account_id.as_deref().filter(|id| !id.is_empty())
+    }
+
+    pub fn is_deauthorized(&self) -> bool {
+        self.stripe_deauthorized_at.is_some()
+    }
+
+    pub fn needs_reverification(&self) -> bool {
+        self.stripe_reverification_required_at.is_some()
+    }
+
+    /// Destination account for a checkout: `None` when the center has not
+    /// finished onboarding or must re-verify after an ownership transfer (the
+    /// platform collects the payment), an error when its account was
+    /// disconnected.
+    pub fn checkout_destination(&self) -> Result<Option<String>, AppError> {
+        if self.is_deauthorized() {
+            return Err(AppError::Conflict(
+                "Online payment is unavailable: this center's Stripe account was disconnected"
+                    .to_owned(),
+            ));
+        }
+        Ok(self
+            .account_id()
+            .filter(|_| self.stripe_onboarding_complete && !self.needs_reverification())
+            .map(str::to_owned))
+    }
+
+    /// Destination account for a payout.
+    pub fn payout_destination(&self) -> Result<String, AppError> {
+        if self.is_deauthorized() {
+            return Err(AppError::Conflict(
+                "Payouts are blocked: this center's Stripe account was disconnected".to_owned(),
+            ));
+        }
+        if self.needs_reverification() {
+            return Err(AppError::Conflict(
+                "Payouts are paused until the new owner connects their Stripe account".to_owned(),
+            ));
+        }
+        let account_id = self.account_id().ok_or_else(|| {
+            AppError::BadRequest("Center has no Stripe account configured".to_owned())
+        })?;
+        if !self.stripe_onboarding_complete {
+            return Err(AppError::BadRequest(
+                "Stripe onboarding is not complete for this center".to_owned(),
+            ));
+        }
+        Ok(account_id.to_owned())
+    }
+}
+
+const CONNECT_COLUMNS: &str = "id AS center_id, stripe_account_id, \
+     COALESCE(stripe_onboarding_complete, false) AS stripe_onboarding_complete, \
+     stripe_payouts_enabled, stripe_details_submitted, stripe_requirements_currently_due, \
+     stripe_requirements_past_due, stripe_disabled_reason, stripe_requirements_deadline, \
+     stripe_status_synced_at, stripe_deauthorized_at, stripe_reverification_required_at";
+
+pub async fn load<'e, E>(executor: E, center_id: Uuid) -> Result<CenterConnect, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, CenterConnect>(&format!(
+        "SELECT {CONNECT_COLUMNS} FROM centers WHERE id = $1 AND deleted_at IS NULL"
+    ))
+    .bind(center_id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Center not found".to_owned()))
+}
+
+/// Store an account's status on the center it belongs to. Charges being
+/// enabled on the current account lifts a previous deauthorization.
+/// Returns the number of centers updated.
+pub async fn store_status(pool: &sqlx::PgPool, status: &AccountStatus) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        r#"
+        UPDATE centers
+        SET stripe_onboarding_complete = $2,
+            stripe_payouts_enabled = $3,
+            stripe_details_submitted = $4,
+            stripe_requirements_currently_due = $5,
+            stripe_requirements_past_due = $6,
+            stripe_disabled_reason = $7,
+            stripe_requirements_deadline = $8,
+            stripe_status_synced_at = NOW(),
+            stripe_deauthorized_at = CASE WHEN $2 THEN NULL ELSE stripe_deauthorized_at END,
+            updated_at = NOW()
+        WHERE stripe_account_id = $1 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(&status.account_id)
+    .bind(status.charges_enabled)
+    .bind(status.payouts_enabled)
+    .bind(status.details_submitted)
+    .bind(&status.currently_due)
+    .bind(&status.past_due)
+    .bind(&status.disabled_reason)
+    .bind(status.current_deadline)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Mark the center using `account_id` as disconnected from the platform.
+pub async fn mark_deauthorized(pool: &sqlx::PgPool, account_id: &str) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        r#"
+        UPDATE centers
+        SET stripe_deauthorized_at = COALESCE(stripe_deauthorized_at, NOW()),
+            stripe_onboarding_complete = false,
+            stripe_payouts_enabled = false,
+            updated_at = NOW()
+        WHERE stripe_account_id = $1 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(account_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Attach a new connected account to a center, resetting the stored status.
+/// A deauthorization stays recorded until charges are enabled on it.
+pub async fn attach_account(
+    pool: &sqlx::PgPool,
+    center_id: Uuid,
+    account_id: &str,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE centers
+        SET stripe_account_id = $1,
+            stripe_onboarding_complete = false,
+            stripe_payouts_enabled = false,
+            stripe_details_submitted = false,
+            stripe_requirements_currently_due = '{}',
+            stripe_requirements_past_due = '{}',
+            stripe_disabled_reason = NULL,
+            stripe_requirements_deadline = NULL,
+            stripe_status_synced_at = NULL,
+            stripe_reverification_required_at = NULL,
+            updated_at = NOW()
+        WHERE id = $2
+        "#,
+    )
+    .bind(account_id)
+    .bind(center_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,515 @@
+//! Contact form inbox.
+//!
+//! Submissions are stored in `contact_messages` (migration 031), addressed
+//! either to the platform, answered from the admin inbox, or to one center,
+//! whose members are notified and answer from the center inbox. Replies are
+//! emailed to the sender through the outbox and kept as the message thread.
+//!
+//! The Vercel entry point has no rate limiter, so the form protects itself:
+//! a honeypot field catches naive bots, and submissions are throttled per
+//! client IP and per sender email, counted in the database so that every
+//! instance shares the same limits. Client IPs are stored as an HMAC keyed
+//! with `IP_HASH_SECRET`, which a leak of the table alone cannot reverse;
+//! without the secret no IP is stored and only the email throttle applies.
+
+use std::net::IpAddr;
+
+use axum::http::HeaderMap;
+use lettre::message::Mailbox;
+use serde::Serialize;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::middleware::client_ip::client_ip;
+use crate::services::email::Value;
+use crate::services::notifications::{self, Kind};
+use crate::services::permissions::Capability;
+use crate::services::outbox::{self, NewEmail};
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+pub const MAX_NAME_LEN: usize = 100;
+pub const MAX_SUBJECT_LEN: usize = 200;
+pub const MAX_MESSAGE_LEN: usize = 5000;
+
+/// Messages accepted per client IP within [`THROTTLE_WINDOW_MINUTES`].
+pub const IP_LIMIT: i64 = 5;
+
+/// Messages accepted per sender email within [`THROTTLE_WINDOW_MINUTES`].
+pub const EMAIL_LIMIT: i64 = 3;
+
+pub const THROTTLE_WINDOW_MINUTES: i64 = 60;
+
+pub const STATUSES: [&str; 3] = ["new", "replied", "closed"];
+
+/// Largest page of an inbox listing.
+pub const MAX_LIMIT: i64 = 200;
+
+// ──────────────────────── Submission ────────────────────────
+
+#[derive(Debug, Clone, Default)]
+pub struct ContactForm {
+    pub name: String,
+    pub email: String,
+    pub subject: String,
+    pub message: String,
+    /// Center the message is addressed to; `None` for the platform.
+    pub center_id: Option<Uuid>,
+    /// Honeypot: hidden from people, filled in by bots.
+    pub website: Option<String>,
+}
+
+impl ContactForm {
+    /// A filled honeypot. The submission is answered as if it was accepted
+    /// and dropped, so bots get no signal.
+    pub fn is_spam(&self) -> bool {
+        self.website.as_deref().is_some_and(|w| !w.trim().is_empty())
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        let (name, email, subject, message) = (
+            self.name.trim(),
+            self.email.trim(),
+            self.subject.trim(),
+            self.message.trim(),
+        );
+        if name.is_empty() || email.is_empty() || subject.is_empty() || message.is_empty() {
+            return Err(AppError::BadRequest("All fields are required".to_owned()));
+        }
+        if !email.contains('@') || !email.contains('.') || email.parse::<Mailbox>().is_err() {
+            return Err(AppError::BadRequest("Invalid email address".to_owned()));
+        }
+        if name.chars().count() > MAX_NAME_LEN
+            || subject.chars().count() > MAX_SUBJECT_LEN
+            || message.chars().count() > MAX_MESSAGE_LEN
+        {
+            return Err(AppError::BadRequest(format!(
+                "Name, subject and message are limited to {MAX_NAME_LEN}, {MAX_SUBJECT_LEN} and {MAX_MESSAGE_LEN} characters"
+            )));
+        }
+        // Header injection through the name or the subject.
+        if name.contains(['\r', '\n']) || subject.contains(['\r', '\n']) {
+            return Err(AppError::BadRequest("Name and subject must be a single line".to_owned()));
+        }
+        Ok(())
+    }
+}
+
+/// Where the request comes from, for throttling.
+#[derive(Debug, Clone, Default)]
+pub struct Origin {
+    pub ip_hash: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl Origin {
+    pub fn from_headers(headers: &HeaderMap, ip_hash_secret: Option<&str>) -> Self {
+        Self {
+            ip_hash: ip_hash_secret
+                .zip(client_ip(headers))
+                .map(|(secret, ip)| hash_ip(secret, &ip)),
+            user_agent: headers
+                .get(axum::http::header::USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .map(|ua| ua.chars().take(500).collect()),
+        }
+    }
+}
+
+/// Stored instead of the address itself: HMAC-SHA256 keyed with `secret`,
+/// so that the 2³² IPv4 addresses cannot be hashed to find one back.
+pub fn hash_ip(secret: &str, ip: &IpAddr) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC key of any size");
+    mac.update(ip.to_string().as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Reject the submission when the IP or the email already sent their quota
+/// within the throttle window.
+pub fn check_throttle(recent_from_ip: i64, recent_from_email: i64) -> Result<(), AppError> {
+    if recent_from_ip >= IP_LIMIT || recent_from_email >= EMAIL_LIMIT {
+        return Err(AppError::TooManyRequests(
+            "Too many messages, please try again later".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ContactMessage {
+    pub id: Uuid,
+    pub reference: i64,
+    pub center_id: Option<Uuid>,
+    pub name: String,
+    pub email: String,
+    pub subject: String,
+    pub message: String,
+    pub status: String,
+    pub user_agent: Option<String>,
+    pub last_reply_at: Option<Timestamp>,
+    pub closed_at: Option<Timestamp>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+const MESSAGE_COLUMNS: &str = "id, reference, center_id, name, email, subject, message, status, \
+     user_agent, last_reply_at, closed_at, created_at, updated_at";
+
+/// Store a submission and tell its recipients: the center members in-app
+/// and the center's public address by email, or the platform admins in-app
+/// and `platform_address` by email when configured.
+pub async fn submit(
+    pool: &sqlx::PgPool,
+    form: &ContactForm,
+    origin: &Origin,
+    platform_address: Option<&str>,
+) -> Result<ContactMessage, AppError> {
+    form.validate()?;
+
+    let center: Option<(String, Option<String>)> = match form.center_id {
+        Some(center_id) => Some(
+            sqlx::query_as(
+                "SELECT name, email FROM centers WHERE id = $1 AND status = 'active' AND deleted_at IS NULL",
+            )
+            .bind(center_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Center not found".to_owned()))?,
+        ),
+        None => None,
+    };
+
+    let mut tx = pool.begin().await?;
+
+    // Concurrent submissions from the same IP or address wait for each other,
+    // so that they cannot all pass the count before any is inserted. The IP
+    // is locked first, always, so two submissions cannot deadlock.
+    if let Some(ref ip_hash) = origin.ip_hash {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended('contact-ip:' || $1, 0))")
+            .bind(ip_hash)
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended('contact-email:' || lower($1), 0))")
+        .bind(form.email.trim())
+        .execute(&mut *tx)
+        .await?;
+
+    let (from_ip, from_email): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*) FILTER (WHERE $1::text IS NOT NULL AND ip_hash = $1),
+               COUNT(*) FILTER (WHERE lower(email) = lower($2))
+        FROM contact_messages
+        WHERE created_at > NOW() - make_interval(mins => $3::int)
+        "#,
+    )
+    .bind(&origin.ip_hash)
+    .bind(form.email.trim())
+    .bind(THROTTLE_WINDOW_MINUTES as i32)
+    .fetch_one(&mut *tx)
+    .await?;
+    check_throttle(from_ip, from_email)?;
+
+    let message = sqlx::query_as::<_, ContactMessage>(&format!(
+        r#"
+        INSERT INTO contact_messages (center_id, name, email, subject, message, ip_hash, user_agent)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING {MESSAGE_COLUMNS}
+        "#
+    ))
+    .bind(form.center_id)
+    .bind(form.name.trim())
+    .bind(form.email.trim())
+    .bind(form.subject.trim())
+    .bind(form.message.trim())
+    .bind(&origin.ip_hash)
+    .bind(&origin.user_agent)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let vars: [(&str, Value); 2] = [
+        ("sender_name", message.name.as_str().into()),
+        ("subject", message.subject.as_str().into()),
+    ];
+    let (copy_to, tag) = match (&center, message.center_id) {
+        (Some((center_name, center_email)), Some(center_id)) => {
+            let link = notifications::contact_link(message.id);
+            notifications::notify_center(&mut tx, center_id, Capability::ManageMessages, None, Kind::ContactReceived, &vars, Some(&link)).await?;
+            (center_email.as_deref(), center_name.as_str())
+        }
+        _ => {
+            let admins: Vec<Uuid> = sqlx::query_scalar(
+                "SELECT id FROM profiles WHERE role = 'admin_diver' AND deleted_at IS NULL",
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+            let link = notifications::admin_contact_link(message.id);
+            for admin in admins {
+                notifications::notify(&mut tx, admin, Kind::ContactReceived, &vars, Some(&link)).await?;
+            }
+            (platform_address, "EviDive Contact")
+        }
+    };
+
+    // Delivered by the outbox job, which retries if SMTP is unavailable.
+    if let Some(to_address) = copy_to.filter(|a| !a.trim().is_empty()) {
+        outbox::enqueue(
+            &mut *tx,
+            &NewEmail {
+                to_address: to_address.to_owned(),
+                reply_to: Some(message.email.clone()),
+                subject: format!("[{tag}] {} [#{}]", message.subject, message.reference),
+                body: format!(
+                    "New contact form submission\n\
+                     \n\
+                     Name: {}\n\
+                     Email: {}\n\
+                     Subject: {}\n\
+                     \n\
+                     Message:\n\
+                     {}",
+                    message.name, message.email, message.subject, message.message
+                ),
+                ..Default::default()
+            },
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(message)
+}
+
+// ──────────────────────── Inbox ────────────────────────
+
+/// Which messages a caller may see: the platform's (admins) or one
+/// center's (its members).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Inbox {
+    Platform,
+    Center(Uuid),
+}
+
+impl Inbox {
+    fn center_id(&self) -> Option<Uuid> {
+        match self {
+            Self::Platform => None,
+            Self::Center(id) => Some(*id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ContactReply {
+    pub id: Uuid,
+    pub message_id: Uuid,
+    pub author_id: Option<Uuid>,
+    pub author_name: Option<String>,
+    pub body: String,
+    pub email_id: Option<Uuid>,
+    pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Thread {
+    #[serde(flatten)]
+    pub message: ContactMessage,
+    pub replies: Vec<ContactReply>,
+}
+
+pub fn validate_status(status: &str) -> Result<(), AppError> {
+    if !STATUSES.contains(&status) {
+        return Err(AppError::BadRequest(format!(
+            "status must be one of: {}",
+            STATUSES.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Subject of a reply: the original subject with the ticket reference, so
+/// the sender's answers can be matched to the thread.
+pub fn reply_subject(reference: i64, subject: &str) -> String {
+    let subject = subject.trim();
+    let has_prefix = subject
+        .get(..3)
+        .is_some_and(|p| p.eq_ignore_ascii_case("re:"));
+    if has_prefix {
+        format!("{subject} [#{reference}]")
+    } else {
+        format!("Re: {subject} [#{reference}]")
+    }
+}
+
+/// Reply text followed by the quoted original message.
+pub fn reply_body(reply: &str, original: &ContactMessage) -> String {
+    let quoted: Vec<String> = original
+        .message
+        .lines()
+        .map(|line| if line.is_empty() { ">".to_owned() } else { format!("> {line}") })
+        .collect();
+    format!(
+        "{}\n\n{} <{}> wrote on {}:\n{}",
+        reply.trim(),
+        original.name,
+        original.email,
+        original.created_at.format("%Y-%m-%d %H:%M UTC"),
+        quoted.join("\n")
+    )
+}
+
+/// Messages of `inbox`, newest first, optionally filtered by status.
+pub async fn list(
+    pool: &sqlx::PgPool,
+    inbox: Inbox,
+    status: Option<&str>,
+    limit: i64,
+) -> Result<Vec<ContactMessage>, AppError> {
+    if let Some(status) = status {
+        validate_status(status)?;
+    }
+    let rows = sqlx::query_as::<_, ContactMessage>(&format!(
+        r#"
+        SELECT {MESSAGE_COLUMNS} FROM contact_messages
+        WHERE center_id IS NOT DISTINCT FROM $1 AND ($2::text IS NULL OR status = $2)
+        ORDER BY created_at DESC
+        LIMIT $3
+        "#
+    ))
+    .bind(inbox.center_id())
+    .bind(status)
+    .bind(limit.clamp(1, MAX_LIMIT))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+async fn find<'e, E>(executor: E, inbox: Inbox, id: Uuid) -> Result<ContactMessage, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, ContactMessage>(&format!(
+        "SELECT {MESSAGE_COLUMNS} FROM contact_messages WHERE id = $1 AND center_id IS NOT DISTINCT FROM $2"
+    ))
+    .bind(id)
+    .bind(inbox.center_id())
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Message not found".to_owned()))
+}
+
+/// A message with its replies, oldest first.
+pub async fn get(pool: &sqlx::PgPool, inbox: Inbox, id: Uuid) -> Result<Thread, AppError> {
+    let message = find(pool, inbox, id).await?;
+    let replies = sqlx::query_as::<_, ContactReply>(
+        r#"
+        SELECT r.id, r.message_id, r.author_id,
+               COALESCE(NULLIF(p.display_name, ''), NULLIF(TRIM(CONCAT(p.first_name, ' ', p.last_name)), '')) AS author_name,
+               r.body, r.email_id, r.created_at
+        FROM contact_replies r
+        LEFT JOIN profiles p ON p.id = r.author_id
+        WHERE r.message_id = $1
+        ORDER BY r.created_at
+        "#,
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+    Ok(Thread { message, replies })
+}
+
+/// Email a reply to the sender, add it to the thread and mark the message
+/// replied, or closed when `close` is set. `reply_to` is where the sender's
+/// answer should go (the center's address for center inboxes).
+pub async fn reply(
+    pool: &sqlx::PgPool,
+    inbox: Inbox,
+    id: Uuid,
+    author_id: Uuid,
+    body: &str,
+    reply_to: Option<&str>,
+    close: bool,
+) -> Result<ContactReply, AppError> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err(AppError::BadRequest("Reply body is required".to_owned()));
+    }
+    if body.chars().count() > MAX_MESSAGE_LEN {
+        return Err(AppError::BadRequest(format!(
+            "Reply is limited to {MAX_MESSAGE_LEN} characters"
+        )));
+    }
+
+    let mut tx = pool.begin().await?;
+    let message = find(&mut *tx, inbox, id).await?;
+
+    let email_id = outbox::enqueue(
+        &mut *tx,
+        &NewEmail {
+            to_address: message.email.clone(),
+            reply_to: reply_to.filter(|a| !a.trim().is_empty()).map(str::to_owned),
+            subject: reply_subject(message.reference, &message.subject),
+            body: reply_body(body, &message),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let reply = sqlx::query_as::<_, ContactReply>(
+        r#"
+        INSERT INTO contact_replies (message_id, author_id, body, email_id)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, message_id, author_id, NULL::text AS author_name, body, email_id, created_at
+        "#,
+    )
+    .bind(id)
+    .bind(author_id)
+    .bind(body)
+    .bind(email_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE contact_messages
+        SET status = CASE WHEN $2 THEN 'closed' ELSE 'replied' END,
+            closed_at = CASE WHEN $2 THEN NOW() ELSE NULL END,
+            last_reply_at = NOW(), updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(close)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(reply)
+}
+
+pub async fn set_status(
+    pool: &sqlx::PgPool,
+    inbox: Inbox,
+    id: Uuid,
+    status: &str,
+) -> Result<ContactMessage, AppError> {
+    validate_status(status)?;
+    sqlx::query_as::<_, ContactMessage>(&format!(
+        r#"
+        UPDATE contact_messages
+        SET status = $3,
+            closed_at = CASE WHEN $3 = 'closed' THEN COALESCE(closed_at, NOW()) ELSE NULL END,
+            updated_at = NOW()
+        WHERE id = $1 AND center_id IS NOT DISTINCT FROM $2
+        RETURNING {MESSAGE_COLUMNS}
+        "#
+    ))
+    .bind(id)
+    .bind(inbox.center_id())
+    .bind(status)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Message not found".to_owned()))
+}
@@ -1,12 +1,26 @@
+//! Transactional email: SMTP transport and localized templates.
+//!
+//! Templates live in `templates/email/<locale>/<template>.txt` and are
+//! compiled into the binary. The first line holds the subject
+//! (`Subject: …`), the rest after a blank line is the plain-text body, and
+//! `{{variable}}` placeholders are filled at render time. Each recipient
+//! gets the locale of `profiles.preferred_locale`, falling back to English.
+
+use chrono::NaiveDate;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
 use lettre::transport::smtp::authentication::Credentials;
-use lettre::{AsyncSmtpTransport, Tokio1Executor};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use rust_decimal::Decimal;
+use uuid::Uuid;
 
 use crate::config::Config;
 use crate::error::AppError;
+use crate::AppState;
 
 /// Build an SMTP transport from the app config.
-/// The transport is stored in `AppState` and used by handlers
-/// via `state.mailer` with `lettre::AsyncTransport::send`.
+/// The transport is stored in `AppState` and used by the outbox job
+/// through [`send`].
 pub fn build_mailer(config: &Config) -> Result<AsyncSmtpTransport<Tokio1Executor>, AppError> {
     let host = config.smtp_host.as_deref().unwrap_or_default();
     let user = config.smtp_user.clone().unwrap_or_default();
@@ -23,3 +37,425 @@ pub fn build_mailer(config: &Config) -> Result<AsyncSmtpTransport<Tokio1Executor
 
     Ok(transport)
 }
+
+// ──────────────────────── Locales ────────────────────────
+
+/// Locales of the `ref_*` name columns and `profiles.preferred_locale`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    Fr,
+    En,
+    De,
+    Es,
+    It,
+    Pt,
+    Nl,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 7] = [
+        Locale::Fr,
+        Locale::En,
+        Locale::De,
+        Locale::Es,
+        Locale::It,
+        Locale::Pt,
+        Locale::Nl,
+    ];
+
+    pub const DEFAULT: Locale = Locale::En;
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Fr => "fr",
+            Self::En => "en",
+            Self::De => "de",
+            Self::Es => "es",
+            Self::It => "it",
+            Self::Pt => "pt",
+            Self::Nl => "nl",
+        }
+    }
+
+    /// Locale of a `preferred_locale` value; accepts region tags (`pt-BR`).
+    pub fn from_preference(preferred: Option<&str>) -> Locale {
+        let Some(tag) = preferred else {
+            return Self::DEFAULT;
+        };
+        let language = tag.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|l| l.code() == language)
+            .unwrap_or(Self::DEFAULT)
+    }
+
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        match self {
+            Self::De => date.format("%d.%m.%Y").to_string(),
+            Self::Nl => date.format("%d-%m-%Y").to_string(),
+            _ => date.format("%d/%m/%Y").to_string(),
+        }
+    }
+
+    /// `1234.50 EUR` in English, `1234,50 EUR` elsewhere.
+    pub fn format_amount(&self, amount: Decimal, currency: &str) -> String {
+        let formatted = format!("{:.2}", amount.round_dp(2));
+        match self {
+            Self::En => format!("{formatted} {currency}"),
+            _ => format!("{} {currency}", formatted.replace('.', ",")),
+        }
+    }
+}
+
+// ──────────────────────── Templates ────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Template {
+    BookingCreated,
+    BookingConfirmed,
+    BookingCancelled,
+    PaymentReceived,
+    RefundIssued,
+    CenterApproved,
+    MemberAdded,
+    DiveReminder,
+    MemberInvitation,
+}
+
+impl Template {
+    pub const ALL: [Template; 9] = [
+        Template::BookingCreated,
+        Template::BookingConfirmed,
+        Template::BookingCancelled,
+        Template::PaymentReceived,
+        Template::RefundIssued,
+        Template::CenterApproved,
+        Template::MemberAdded,
+        Template::DiveReminder,
+        Template::MemberInvitation,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::BookingCreated => "booking_created",
+            Self::BookingConfirmed => "booking_confirmed",
+            Self::BookingCancelled => "booking_cancelled",
+            Self::PaymentReceived => "payment_received",
+            Self::RefundIssued => "refund_issued",
+            Self::CenterApproved => "center_approved",
+            Self::MemberAdded => "member_added",
+            Self::DiveReminder => "dive_reminder",
+            Self::MemberInvitation => "member_invitation",
+        }
+    }
+
+    /// Variables the template may use, besides the recipient's `name`.
+    pub fn variables(&self) -> &'static [&'static str] {
+        const BOOKING: &[&str] = &[
+            "center_name",
+            "service_name",
+            "booking_date",
+            "time_slot",
+            "participants",
+            "total",
+        ];
+        const BOOKING_AMOUNT: &[&str] = &[
+            "center_name",
+            "service_name",
+            "booking_date",
+            "time_slot",
+            "participants",
+            "total",
+            "amount",
+        ];
+        match self {
+            Self::BookingCreated | Self::BookingConfirmed | Self::BookingCancelled => BOOKING,
+            Self::PaymentReceived | Self::RefundIssued => BOOKING_AMOUNT,
+            Self::CenterApproved | Self::MemberAdded => &["center_name"],
+            Self::DiveReminder => &[
+                "center_name",
+                "service_name",
+                "booking_date",
+                "time_slot",
+                "participants",
+                "meeting_point",
+                "min_certification",
+                "min_dives",
+                "checklist",
+            ],
+            Self::MemberInvitation => &[
+                "center_name",
+                "inviter_name",
+                "role",
+                "invite_url",
+                "expires_on",
+            ],
+        }
+    }
+
+    fn source(&self, locale: Locale) -> &'static str {
+        macro_rules! by_locale {
+            ($name:literal) => {
+                match locale {
+                    Locale::Fr => include_str!(concat!("../../templates/email/fr/", $name, ".txt")),
+                    Locale::En => include_str!(concat!("../../templates/email/en/", $name, ".txt")),
+                    Locale::De => include_str!(concat!("../../templates/email/de/", $name, ".txt")),
+                    Locale::Es => include_str!(concat!("../../templates/email/es/", $name, ".txt")),
+                    Locale::It => include_str!(concat!("../../templates/email/it/", $name, ".txt")),
+                    Locale::Pt => include_str!(concat!("../../templates/email/pt/", $name, ".txt")),
+                    Locale::Nl => include_str!(concat!("../../templates/email/nl/", $name, ".txt")),
+                }
+            };
+        }
+        match self {
+            Self::BookingCreated => by_locale!("booking_created"),
+            Self::BookingConfirmed => by_locale!("booking_confirmed"),
+            Self::BookingCancelled => by_locale!("booking_cancelled"),
+            Self::PaymentReceived => by_locale!("payment_received"),
+            Self::RefundIssued => by_locale!("refund_issued"),
+            Self::CenterApproved => by_locale!("center_approved"),
+            Self::MemberAdded => by_locale!("member_added"),
+            Self::DiveReminder => by_locale!("dive_reminder"),
+            Self::MemberInvitation => by_locale!("member_invitation"),
+        }
+    }
+}
+
+/// A template variable, formatted for the recipient's locale.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Text(String),
+    Number(i64),
+    Date(NaiveDate),
+    Amount(Decimal, String),
+}
+
+impl Value {
+    fn format(&self, locale: Locale) -> String {
+        match self {
+            Self::Text(s) => s.clone(),
+            Self::Number(n) => n.to_string(),
+            Self::Date(d) => locale.format_date(*d),
+            Self::Amount(amount, currency) => locale.format_amount(*amount, currency),
+        }
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Self::Text(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Self::Text(s.to_owned())
+    }
+}
+
+pub type Vars = Vec<(&'static str, Value)>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub body: String,
+}
+
+/// Replace the `{{variable}}` placeholders of `text`, formatting values for
+/// `locale`. Returns the name of the first placeholder without a value.
+pub fn fill(text: &str, locale: Locale, vars: &[(&str, Value)]) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let key = rest[start + 2..start + len].trim();
+        let (_, value) = vars
+            .iter()
+            .find(|(k, _)| *k == key)
+            .ok_or_else(|| key.to_owned())?;
+        out.push_str(&rest[..start]);
+        out.push_str(&value.format(locale));
+        rest = &rest[start + len + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Render `template` in `locale`. A placeholder without a value is an
+/// error, so a typo in a template never reaches a customer.
+pub fn render(template: Template, locale: Locale, vars: &[(&str, Value)]) -> Result<RenderedEmail, AppError> {
+    let source = template.source(locale);
+    let (subject, body) = source
+        .strip_prefix("Subject: ")
+        .and_then(|rest| rest.split_once('\n'))
+        .ok_or_else(|| {
+            AppError::Internal(format!(
+                "Email template {}/{} has no subject line",
+                locale.code(),
+                template.name()
+            ))
+        })?;
+
+    let fill = |text: &str| {
+        fill(text, locale, vars).map_err(|key| {
+            AppError::Internal(format!(
+                "Email template {}/{} uses unknown variable '{key}'",
+                locale.code(),
+                template.name()
+            ))
+        })
+    };
+
+    Ok(RenderedEmail {
+        subject: fill(subject.trim())?,
+        body: fill(body.trim_start_matches('\n'))?,
+    })
+}
+
+// ──────────────────────── Recipients ────────────────────────
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Recipient {
+    pub profile_id: Uuid,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub preferred_locale: Option<String>,
+}
+
+impl Recipient {
+    pub fn locale(&self) -> Locale {
+        Locale::from_preference(self.preferred_locale.as_deref())
+    }
+
+    /// Name used in the greeting.
+    pub fn greeting_name(&self) -> String {
+        self.display_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(str::to_owned)
+            .unwrap_or_else(|| self.email.split('@').next().unwrap_or_default().to_owned())
+    }
+}
+
+pub async fn load_recipient<'e, E>(executor: E, profile_id: Uuid) -> Result<Option<Recipient>, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let recipient = sqlx::query_as::<_, Recipient>(
+        r#"
+        SELECT p.id AS profile_id, u.email,
+               COALESCE(NULLIF(p.display_name, ''), NULLIF(TRIM(CONCAT(p.first_name, ' ', p.last_name)), '')) AS display_name,
+               p.preferred_locale
+        FROM profiles p
+        JOIN auth.users u ON u.id = p.id
+        WHERE p.id = $1 AND p.deleted_at IS NULL AND u.email IS NOT NULL
+        "#,
+    )
+    .bind(profile_id)
+    .fetch_optional(executor)
+    .await?;
+    Ok(recipient)
+}
+
+/// Booking details shared by the booking, payment and refund templates.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct BookingDetails {
+    pub client_id: Uuid,
+    pub center_id: Uuid,
+    pub client_name: Option<String>,
+    pub center_name: Option<String>,
+    pub service_name: Option<String>,
+    pub booking_date: NaiveDate,
+    pub time_slot: Option<String>,
+    pub participants: i32,
+    pub total_price: Decimal,
+    pub currency: String,
+}
+
+impl BookingDetails {
+    pub fn vars(&self) -> Vars {
+        vec![
+            ("center_name", self.center_name.clone().unwrap_or_default().into()),
+            ("client_name", self.client_name.clone().unwrap_or_default().into()),
+            ("service_name", self.service_name.clone().unwrap_or_default().into()),
+            ("booking_date", Value::Date(self.booking_date)),
+            ("time_slot", self.time_slot.clone().unwrap_or_default().into()),
+            ("participants", Value::Number(self.participants.into())),
+            ("total", Value::Amount(self.total_price, self.currency.clone())),
+        ]
+    }
+}
+
+pub async fn load_booking<'e, E>(executor: E, booking_id: Uuid) -> Result<Option<BookingDetails>, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let booking = sqlx::query_as::<_, BookingDetails>(
+        r#"
+        SELECT b.client_id, b.center_id,
+               COALESCE(NULLIF(p.display_name, ''), NULLIF(TRIM(CONCAT(p.first_name, ' ', p.last_name)), '')) AS client_name,
+               c.name AS center_name, s.name AS service_name,
+               b.booking_date, to_char(b.time_slot, 'HH24:MI') AS time_slot,
+               b.participants, b.total_price, b.currency
+        FROM bookings b
+        LEFT JOIN centers c ON c.id = b.center_id
+        LEFT JOIN services s ON s.id = b.service_id
+        LEFT JOIN profiles p ON p.id = b.client_id
+        WHERE b.id = $1
+        "#,
+    )
+    .bind(booking_id)
+    .fetch_optional(executor)
+    .await?;
+    Ok(booking)
+}
+
+// ──────────────────────── Sending ────────────────────────
+
+/// Send one message through SMTP. Handlers do not call this directly: they
+/// enqueue into the outbox (see [`crate::services::outbox`]), whose job
+/// sends and retries.
+pub async fn send(
+    state: &AppState,
+    to: &str,
+    reply_to: Option<&str>,
+    subject: &str,
+    body: &str,
+) -> Result<(), AppError> {
+    let mailer = state
+        .mailer
+        .as_ref()
+        .ok_or_else(|| AppError::Internal("Email service is not configured".to_owned()))?;
+    let from: Mailbox = state
+        .config
+        .smtp_from
+        .as_deref()
+        .ok_or_else(|| AppError::Internal("SMTP_FROM is not configured".to_owned()))?
+        .parse()
+        .map_err(|e| AppError::Internal(format!("Invalid SMTP_FROM: {e}")))?;
+    let to: Mailbox = to
+        .parse()
+        .map_err(|e| AppError::Internal(format!("Invalid recipient address: {e}")))?;
+
+    let mut builder = Message::builder().from(from).to(to);
+    if let Some(reply_to) = reply_to {
+        let reply_to: Mailbox = reply_to
+            .parse()
+            .map_err(|e| AppError::Internal(format!("Invalid reply-to address: {e}")))?;
+        builder = builder.reply_to(reply_to);
+    }
+    let message = builder
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body.to_owned())
+        .map_err(|e| AppError::Internal(format!("Failed to build email: {e}")))?;
+
+    mailer
+        .send(message)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to send email: {e}")))?;
+    Ok(())
+}
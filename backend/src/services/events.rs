@@ -0,0 +1,191 @@
+//! Real-time events for dashboards and divers.
+//!
+//! Database triggers (migration 029) publish booking, payment, review,
+//! notification and membership changes on the `app_events` channel. Each
+//! API instance runs one `LISTEN` connection, started by the first SSE
+//! client, and fans the events out to its clients through a broadcast
+//! channel. A client only receives the events of the centers it belongs to
+//! (`tli_pr_ce`) and its own.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use tokio::sync::{broadcast, OnceCell};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// `NOTIFY` channel of the triggers.
+pub const CHANNEL: &str = "app_events";
+
+/// Events buffered per instance; slower clients get a `resync`.
+pub const BUFFER: usize = 1024;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EventType {
+    #[serde(rename = "booking.created")]
+    BookingCreated,
+    #[serde(rename = "booking.confirmed")]
+    BookingConfirmed,
+    #[serde(rename = "booking.cancelled")]
+    BookingCancelled,
+    #[serde(rename = "payment.received")]
+    PaymentReceived,
+    #[serde(rename = "review.created")]
+    ReviewCreated,
+    #[serde(rename = "notification.created")]
+    NotificationCreated,
+    #[serde(rename = "membership.changed")]
+    MembershipChanged,
+    /// Events may have been missed (lagging client, lost connection):
+    /// the client should refetch what it displays.
+    #[serde(rename = "resync")]
+    Resync,
+}
+
+impl EventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BookingCreated => "booking.created",
+            Self::BookingConfirmed => "booking.confirmed",
+            Self::BookingCancelled => "booking.cancelled",
+            Self::PaymentReceived => "payment.received",
+            Self::ReviewCreated => "review.created",
+            Self::NotificationCreated => "notification.created",
+            Self::MembershipChanged => "membership.changed",
+            Self::Resync => "resync",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppEvent {
+    #[serde(rename = "type")]
+    pub kind: EventType,
+    pub id: Option<Uuid>,
+    pub center_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+}
+
+impl AppEvent {
+    /// Parse a trigger payload; unknown event types are ignored.
+    pub fn parse(payload: &str) -> Option<Self> {
+        serde_json::from_str(payload).ok()
+    }
+
+    pub fn resync() -> Self {
+        Self {
+            kind: EventType::Resync,
+            id: None,
+            center_id: None,
+            user_id: None,
+        }
+    }
+}
+
+// ──────────────────────── Subscriptions ────────────────────────
+
+/// What one SSE client may see.
+#[derive(Debug, Clone)]
+pub struct Subscription {
+    pub user_id: Uuid,
+    pub centers: HashSet<Uuid>,
+}
+
+impl Subscription {
+    /// Events about the user, or about a center the user belongs to.
+    /// `resync` goes to everyone.
+    pub fn accepts(&self, event: &AppEvent) -> bool {
+        event.kind == EventType::Resync
+            || event.user_id == Some(self.user_id)
+            || event.center_id.is_some_and(|c| self.centers.contains(&c))
+    }
+
+    /// The user joined or left a center: the center list must be reloaded.
+    pub fn needs_reload(&self, event: &AppEvent) -> bool {
+        event.kind == EventType::MembershipChanged && event.user_id == Some(self.user_id)
+    }
+}
+
+pub async fn member_centers(pool: &sqlx::PgPool, user_id: Uuid) -> Result<HashSet<Uuid>, AppError> {
+    let centers: Vec<Uuid> = sqlx::query_scalar("SELECT fk_center FROM tli_pr_ce WHERE fk_profile = $1")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(centers.into_iter().collect())
+}
+
+// ──────────────────────── Hub ────────────────────────
+
+/// Per-instance fan-out of the `app_events` channel.
+#[derive(Clone)]
+pub struct EventHub {
+    sender: broadcast::Sender<AppEvent>,
+    listening: Arc<OnceCell<()>>,
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventHub {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(BUFFER);
+        Self {
+            sender,
+            listening: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// Subscribe to every event of this instance, starting the `LISTEN`
+    /// connection on first use.
+    pub async fn subscribe(&self, pool: &sqlx::PgPool) -> Result<broadcast::Receiver<AppEvent>, AppError> {
+        self.listening
+            .get_or_try_init(|| async {
+                let mut listener = PgListener::connect_with(pool).await?;
+                listener.listen(CHANNEL).await?;
+                tokio::spawn(forward(listener, self.sender.clone()));
+                tracing::info!(channel = CHANNEL, "Listening for real-time events");
+                Ok::<(), AppError>(())
+            })
+            .await?;
+        Ok(self.sender.subscribe())
+    }
+
+    /// Send an event to this instance's clients only.
+    pub fn publish_local(&self, event: AppEvent) {
+        // No receiver is not an error: nobody is connected.
+        let _ = self.sender.send(event);
+    }
+}
+
+/// Forward notifications to the broadcast channel. `PgListener` reconnects
+/// by itself; events sent while disconnected are lost, so clients are told
+/// to resync.
+async fn forward(mut listener: PgListener, sender: broadcast::Sender<AppEvent>) {
+    loop {
+        match listener.try_recv().await {
+            Ok(Some(notification)) => match AppEvent::parse(notification.payload()) {
+                Some(event) => {
+                    let _ = sender.send(event);
+                }
+                None => tracing::warn!(payload = %notification.payload(), "Unknown real-time event"),
+            },
+            Ok(None) => {
+                tracing::warn!("Real-time event connection lost, reconnecting");
+                let _ = sender.send(AppEvent::resync());
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Real-time event listener failed");
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
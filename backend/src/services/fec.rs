@@ -0,0 +1,681 @@
+//! Fichier des Écritures Comptables (FEC) export.
+//!
+//! Turns the platform's money movements into double-entry journal entries in
+//! the format required by article A47 A-1 of the French Livre des procédures
+//! fiscales: 18 pipe-separated columns, `YYYYMMDD` dates, comma decimals.
+//!
+//! - payments (sales journal): debit Stripe for the amount collected, credit
+//!   the center's third-party account for its share, credit commission revenue
+//!   and output VAT for the platform fee;
+//! - approved refunds (sales journal): the proportional reverse of the payment,
+//!   the commission share going to the commission refunds account;
+//! - payouts (bank journal): debit the center's account, credit Stripe;
+//! - destination charges (bank journal): Stripe credits the center's share
+//!   to its connected account at once, so the payment is followed by the
+//!   same entry as a payout, and a refund of it by the reverse, as the
+//!   refund takes the share back from the connected account.
+//!
+//! Account numbers, labels and journal codes come from the `accounting`
+//! settings category (`fec_*` keys in `t_platform_config`), with PCG defaults.
+//!
+//! The file is kept in euros. Movements in another currency are converted at
+//! the `exchange_rates` rate of their date (migration 021), the original
+//! amount going to `Montantdevise`/`Idevise`.
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::money::iso_minor_units;
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// Column header line of a FEC file.
+pub const HEADER: [&str; 18] = [
+    "JournalCode",
+    "JournalLib",
+    "EcritureNum",
+    "EcritureDate",
+    "CompteNum",
+    "CompteLib",
+    "CompAuxNum",
+    "CompAuxLib",
+    "PieceRef",
+    "PieceDate",
+    "EcritureLib",
+    "Debit",
+    "Credit",
+    "EcritureLet",
+    "DateLet",
+    "ValidDate",
+    "Montantdevise",
+    "Idevise",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Account {
+    pub number: String,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Journal {
+    pub code: String,
+    pub label: String,
+}
+
+/// Accounts and journals used by the export.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChartOfAccounts {
+    /// Platform balance held at Stripe (class 5).
+    pub stripe: Account,
+    /// Amounts owed to centers, with one auxiliary account per center.
+    pub centers: Account,
+    /// Commission revenue, excluding VAT.
+    pub commission: Account,
+    /// Commission returned on refunds.
+    pub commission_refunds: Account,
+    /// Output VAT on commissions.
+    pub vat_collected: Account,
+    pub sales_journal: Journal,
+    pub bank_journal: Journal,
+}
+
+/// `(setting key, default value)` for every entry of the chart.
+pub const SETTINGS: [(&str, &str); 14] = [
+    ("fec_account_stripe", "512100"),
+    ("fec_account_stripe_label", "Stripe"),
+    ("fec_account_centers", "467100"),
+    ("fec_account_centers_label", "Centres partenaires"),
+    ("fec_account_commission", "706000"),
+    ("fec_account_commission_label", "Commissions sur réservations"),
+    ("fec_account_commission_refunds", "709000"),
+    ("fec_account_commission_refunds_label", "Commissions remboursées"),
+    ("fec_account_vat_collected", "445710"),
+    ("fec_account_vat_collected_label", "TVA collectée"),
+    ("fec_journal_sales", "VE"),
+    ("fec_journal_sales_label", "Ventes"),
+    ("fec_journal_bank", "BQ"),
+    ("fec_journal_bank_label", "Banque Stripe"),
+];
+
+impl ChartOfAccounts {
+    /// Build the chart from `(key, value)` settings, falling back to the
+    /// defaults for missing or blank keys.
+    pub fn from_settings(settings: &[(String, String)]) -> Self {
+        let get = |key: &str| -> String {
+            settings
+                .iter()
+                .find(|(k, v)| k == key && !v.trim().is_empty())
+                .map(|(_, v)| v.trim().to_owned())
+                .or_else(|| {
+                    SETTINGS
+                        .iter()
+                        .find(|(k, _)| *k == key)
+                        .map(|(_, d)| (*d).to_owned())
+                })
+                .unwrap_or_default()
+        };
+        let account = |key: &str| Account {
+            number: get(key),
+            label: get(&format!("{key}_label")),
+        };
+        let journal = |key: &str| Journal {
+            code: get(key),
+            label: get(&format!("{key}_label")),
+        };
+        Self {
+            stripe: account("fec_account_stripe"),
+            centers: account("fec_account_centers"),
+            commission: account("fec_account_commission"),
+            commission_refunds: account("fec_account_commission_refunds"),
+            vat_collected: account("fec_account_vat_collected"),
+            sales_journal: journal("fec_journal_sales"),
+            bank_journal: journal("fec_journal_bank"),
+        }
+    }
+
+    pub async fn load(pool: &sqlx::PgPool) -> Result<Self, AppError> {
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT key, value FROM t_platform_config WHERE key LIKE 'fec\\_%'")
+                .fetch_all(pool)
+                .await?;
+        Ok(Self::from_settings(&rows))
+    }
+}
+
+// ──────────────────────── Source movements ────────────────────────
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct FecPayment {
+    pub transaction_id: Uuid,
+    pub center_id: Uuid,
+    pub center_name: String,
+    pub payment_intent_id: Option<String>,
+    pub invoice_number: Option<String>,
+    pub amount: Decimal,
+    pub platform_fee: Decimal,
+    /// VAT included in `platform_fee`, when the breakdown was recorded.
+    pub fee_vat: Option<Decimal>,
+    pub currency: String,
+    /// Connected account the payment was destination-charged to.
+    pub destination_account: Option<String>,
+    pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct FecRefund {
+    pub refund_id: Uuid,
+    pub center_id: Uuid,
+    pub center_name: String,
+    pub stripe_refund_id: Option<String>,
+    pub amount: Decimal,
+    /// Original payment the refund is taken from.
+    pub payment_amount: Decimal,
+    pub payment_fee: Decimal,
+    pub payment_fee_vat: Option<Decimal>,
+    /// Connected account the original payment was destination-charged to.
+    pub destination_account: Option<String>,
+    pub currency: String,
+    pub processed_at: Timestamp,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct FecPayout {
+    pub payout_id: Uuid,
+    pub center_id: Uuid,
+    pub center_name: String,
+    pub stripe_transfer_id: Option<String>,
+    pub amount: Decimal,
+    pub currency: String,
+    pub created_at: Timestamp,
+}
+
+// ──────────────────────── Entries ────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryLine {
+    pub account: Account,
+    /// Auxiliary (third-party) account number and label.
+    pub auxiliary: Option<(String, String)>,
+    pub debit: Decimal,
+    pub credit: Decimal,
+    /// Amount in the entry's currency, once converted to euros.
+    pub foreign_amount: Option<Decimal>,
+}
+
+/// One balanced journal entry (écriture).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub journal: Journal,
+    pub date: NaiveDate,
+    pub piece_ref: String,
+    pub label: String,
+    pub currency: String,
+    pub lines: Vec<EntryLine>,
+}
+
+impl JournalEntry {
+    pub fn is_balanced(&self) -> bool {
+        let debit: Decimal = self.lines.iter().map(|l| l.debit).sum();
+        let credit: Decimal = self.lines.iter().map(|l| l.credit).sum();
+        debit == credit
+    }
+}
+
+/// Auxiliary account of a center: `C` followed by the first 10 hex digits of
+/// its id.
+pub fn center_auxiliary(center_id: Uuid, center_name: &str) -> (String, String) {
+    let hex = center_id.simple().to_string().to_uppercase();
+    (format!("C{}", &hex[..10]), center_name.to_owned())
+}
+
+fn debit(account: &Account, auxiliary: Option<(String, String)>, amount: Decimal) -> EntryLine {
+    EntryLine {
+        account: account.clone(),
+        auxiliary,
+        debit: amount,
+        credit: Decimal::ZERO,
+        foreign_amount: None,
+    }
+}
+
+fn credit(account: &Account, auxiliary: Option<(String, String)>, amount: Decimal) -> EntryLine {
+    EntryLine {
+        account: account.clone(),
+        auxiliary,
+        debit: Decimal::ZERO,
+        credit: amount,
+        foreign_amount: None,
+    }
+}
+
+/// Drop zero-amount lines (e.g. VAT on an out-of-scope commission).
+fn non_zero(lines: Vec<EntryLine>) -> Vec<EntryLine> {
+    lines
+        .into_iter()
+        .filter(|l| !l.debit.is_zero() || !l.credit.is_zero())
+        .collect()
+}
+
+pub fn payment_entry(chart: &ChartOfAccounts, p: &FecPayment) -> JournalEntry {
+    let vat = p.fee_vat.unwrap_or(Decimal::ZERO);
+    let aux = center_auxiliary(p.center_id, &p.center_name);
+    let piece_ref = p
+        .invoice_number
+        .clone()
+        .or_else(|| p.payment_intent_id.clone())
+        .unwrap_or_else(|| p.transaction_id.to_string());
+    JournalEntry {
+        journal: chart.sales_journal.clone(),
+        date: p.created_at.date_naive(),
+        label: format!("Réservation {} - commission", p.center_name),
+        piece_ref,
+        currency: p.currency.clone(),
+        lines: non_zero(vec![
+            debit(&chart.stripe, None, p.amount),
+            credit(&chart.centers, Some(aux), p.amount - p.platform_fee),
+            credit(&chart.commission, None, p.platform_fee - vat),
+            credit(&chart.vat_collected, None, vat),
+        ]),
+    }
+}
+
+/// The center's share of a destination charge, credited by Stripe to its
+/// connected account: settles the center's account like a payout.
+pub fn destination_transfer_entry(chart: &ChartOfAccounts, p: &FecPayment) -> JournalEntry {
+    let aux = center_auxiliary(p.center_id, &p.center_name);
+    let share = p.amount - p.platform_fee;
+    JournalEntry {
+        journal: chart.bank_journal.clone(),
+        date: p.created_at.date_naive(),
+        piece_ref: p
+            .payment_intent_id
+            .clone()
+            .unwrap_or_else(|| p.transaction_id.to_string()),
+        label: format!("Virement direct {}", p.center_name),
+        currency: p.currency.clone(),
+        lines: vec![
+            debit(&chart.centers, Some(aux), share),
+            credit(&chart.stripe, None, share),
+        ],
+    }
+}
+
+/// Part of `value`, an amount of the original payment, in proportion to the
+/// amount refunded.
+fn refund_share(r: &FecRefund, value: Decimal) -> Decimal {
+    if r.payment_amount.is_zero() {
+        Decimal::ZERO
+    } else {
+        (value * r.amount / r.payment_amount).round_dp(2)
+    }
+}
+
+pub fn refund_entry(chart: &ChartOfAccounts, r: &FecRefund) -> JournalEntry {
+    let commission = refund_share(r, r.payment_fee);
+    let vat = refund_share(r, r.payment_fee_vat.unwrap_or(Decimal::ZERO)).min(commission);
+    let aux = center_auxiliary(r.center_id, &r.center_name);
+    JournalEntry {
+        journal: chart.sales_journal.clone(),
+        date: r.processed_at.date_naive(),
+        piece_ref: r
+            .stripe_refund_id
+            .clone()
+            .unwrap_or_else(|| r.refund_id.to_string()),
+        label: format!("Remboursement {}", r.center_name),
+        currency: r.currency.clone(),
+        lines: non_zero(vec![
+            debit(&chart.centers, Some(aux), r.amount - commission),
+            debit(&chart.commission_refunds, None, commission - vat),
+            debit(&chart.vat_collected, None, vat),
+            credit(&chart.stripe, None, r.amount),
+        ]),
+    }
+}
+
+/// The center's share of a refunded destination charge, taken back from its
+/// connected account by the transfer reversal.
+pub fn destination_reversal_entry(chart: &ChartOfAccounts, r: &FecRefund) -> JournalEntry {
+    let aux = center_auxiliary(r.center_id, &r.center_name);
+    let share = r.amount - refund_share(r, r.payment_fee);
+    JournalEntry {
+        journal: chart.bank_journal.clone(),
+        date: r.processed_at.date_naive(),
+        piece_ref: r
+            .stripe_refund_id
+            .clone()
+            .unwrap_or_else(|| r.refund_id.to_string()),
+        label: format!("Reprise virement direct {}", r.center_name),
+        currency: r.currency.clone(),
+        lines: non_zero(vec![
+            debit(&chart.stripe, None, share),
+            credit(&chart.centers, Some(aux), share),
+        ]),
+    }
+}
+
+pub fn payout_entry(chart: &ChartOfAccounts, p: &FecPayout) -> JournalEntry {
+    let aux = center_auxiliary(p.center_id, &p.center_name);
+    JournalEntry {
+        journal: chart.bank_journal.clone(),
+        date: p.created_at.date_naive(),
+        piece_ref: p
+            .stripe_transfer_id
+            .clone()
+            .unwrap_or_else(|| p.payout_id.to_string()),
+        label: format!("Virement {}", p.center_name),
+        currency: p.currency.clone(),
+        lines: vec![
+            debit(&chart.centers, Some(aux), p.amount),
+            credit(&chart.stripe, None, p.amount),
+        ],
+    }
+}
+
+/// Build and order every entry of the period: by date, then journal.
+pub fn build_entries(
+    chart: &ChartOfAccounts,
+    payments: &[FecPayment],
+    refunds: &[FecRefund],
+    payouts: &[FecPayout],
+) -> Vec<JournalEntry> {
+    let mut entries: Vec<JournalEntry> = payments
+        .iter()
+        .map(|p| payment_entry(chart, p))
+        .chain(
+            payments
+                .iter()
+                .filter(|p| p.destination_account.is_some())
+                .map(|p| destination_transfer_entry(chart, p)),
+        )
+        .chain(refunds.iter().map(|r| refund_entry(chart, r)))
+        .chain(
+            refunds
+                .iter()
+                .filter(|r| r.destination_account.is_some())
+                .map(|r| destination_reversal_entry(chart, r)),
+        )
+        .chain(payouts.iter().map(|p| payout_entry(chart, p)))
+        .collect();
+    entries.sort_by(|a, b| (a.date, &a.journal.code).cmp(&(b.date, &b.journal.code)));
+    entries
+}
+
+// ──────────────────────── Currency conversion ────────────────────────
+
+/// Reference rate: units of `currency` for one euro on `rate_date`.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ExchangeRate {
+    pub currency: String,
+    pub rate_date: NaiveDate,
+    pub rate: Decimal,
+}
+
+/// Check a rate entered by an admin. Returns the upper-cased currency.
+pub fn validate_rate(currency: &str, rate: Decimal) -> Result<String, AppError> {
+    let currency = currency.trim().to_uppercase();
+    if iso_minor_units(&currency).is_none() {
+        return Err(AppError::BadRequest(format!("Unsupported currency: {currency}")));
+    }
+    if currency == "EUR" {
+        return Err(AppError::BadRequest("EUR needs no exchange rate".to_owned()));
+    }
+    if rate <= Decimal::ZERO {
+        return Err(AppError::BadRequest("rate must be positive".to_owned()));
+    }
+    Ok(currency)
+}
+
+/// The rate of `date`, or the last one published before it (none are on
+/// weekends and bank holidays).
+fn rate_on(rates: &[ExchangeRate], currency: &str, date: NaiveDate) -> Option<Decimal> {
+    rates
+        .iter()
+        .filter(|r| r.currency == currency && r.rate_date <= date)
+        .max_by_key(|r| r.rate_date)
+        .map(|r| r.rate)
+}
+
+/// Convert the entries in another currency to euros. Each line keeps its
+/// original amount; the rounding difference, if any, goes to the entry's
+/// largest line so that it stays balanced.
+pub fn convert_to_eur(
+    entries: Vec<JournalEntry>,
+    rates: &[ExchangeRate],
+) -> Result<Vec<JournalEntry>, AppError> {
+    entries
+        .into_iter()
+        .map(|mut entry| {
+            if entry.currency == "EUR" {
+                return Ok(entry);
+            }
+            let rate = rate_on(rates, &entry.currency, entry.date).ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "No {} exchange rate on or before {}",
+                    entry.currency, entry.date
+                ))
+            })?;
+            for line in &mut entry.lines {
+                line.foreign_amount = Some(line.debit.max(line.credit));
+                line.debit = (line.debit / rate).round_dp(2);
+                line.credit = (line.credit / rate).round_dp(2);
+            }
+            let difference: Decimal = entry.lines.iter().map(|l| l.debit - l.credit).sum();
+            if let Some(line) = entry.lines.iter_mut().max_by_key(|l| l.debit.max(l.credit)) {
+                if line.debit > line.credit {
+                    line.debit -= difference;
+                } else {
+                    line.credit += difference;
+                }
+            }
+            Ok(entry)
+        })
+        .collect()
+}
+
+// ──────────────────────── Rendering ────────────────────────
+
+fn fec_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+fn fec_amount(amount: Decimal) -> String {
+    format!("{:.2}", amount.round_dp(2)).replace('.', ",")
+}
+
+/// Field values must not contain the separator or line breaks.
+fn fec_text(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if matches!(c, '|' | '\r' | '\n' | '\t') { ' ' } else { c })
+        .collect()
+}
+
+/// Render entries as a FEC file. Entries are numbered sequentially per
+/// journal (`VE000001`, `BQ000001`…). Lines converted by [`convert_to_eur`]
+/// also fill `Montantdevise`/`Idevise` with their original amount.
+pub fn render(entries: &[JournalEntry]) -> String {
+    let mut out = HEADER.join("|");
+    out.push_str("\r\n");
+
+    let mut counters: Vec<(String, u32)> = Vec::new();
+    for entry in entries {
+        let seq = match counters.iter_mut().find(|(code, _)| *code == entry.journal.code) {
+            Some((_, n)) => {
+                *n += 1;
+                *n
+            }
+            None => {
+                counters.push((entry.journal.code.clone(), 1));
+                1
+            }
+        };
+        let num = format!("{}{seq:06}", entry.journal.code);
+        let date = fec_date(entry.date);
+
+        for line in &entry.lines {
+            let (aux_num, aux_lib) = line.auxiliary.clone().unwrap_or_default();
+            let (foreign_amount, foreign_currency) = match line.foreign_amount {
+                Some(amount) => (fec_amount(amount), entry.currency.clone()),
+                None => (String::new(), String::new()),
+            };
+            let fields = [
+                fec_text(&entry.journal.code),
+                fec_text(&entry.journal.label),
+                num.clone(),
+                date.clone(),
+                fec_text(&line.account.number),
+                fec_text(&line.account.label),
+                fec_text(&aux_num),
+                fec_text(&aux_lib),
+                fec_text(&entry.piece_ref),
+                date.clone(),
+                fec_text(&entry.label),
+                fec_amount(line.debit),
+                fec_amount(line.credit),
+                String::new(),
+                String::new(),
+                date.clone(),
+                foreign_amount,
+                foreign_currency,
+            ];
+            out.push_str(&fields.join("|"));
+            out.push_str("\r\n");
+        }
+    }
+    out
+}
+
+/// Regulatory file name: `<SIREN>FEC<closing date>.txt`.
+pub fn file_name(siren: &str, closing: NaiveDate) -> String {
+    format!("{siren}FEC{}.txt", fec_date(closing))
+}
+
+/// SIREN (first 9 digits) of the configured `company_siret`.
+pub async fn company_siren(pool: &sqlx::PgPool) -> Result<String, AppError> {
+    let siret: Option<String> =
+        sqlx::query_scalar("SELECT value FROM t_platform_config WHERE key = 'company_siret'")
+            .fetch_optional(pool)
+            .await?;
+    let digits: String = siret
+        .unwrap_or_default()
+        .chars()
+        .filter(char::is_ascii_digit)
+        .collect();
+    if digits.len() < 9 {
+        return Err(AppError::BadRequest(
+            "company_siret must be configured before exporting a FEC".to_owned(),
+        ));
+    }
+    Ok(digits[..9].to_owned())
+}
+
+// ──────────────────────── Loading ────────────────────────
+
+/// Load the movements of `[from, to)` and build the period's entries, in
+/// euros.
+pub async fn load_entries(
+    pool: &sqlx::PgPool,
+    chart: &ChartOfAccounts,
+    from: Timestamp,
+    to: Timestamp,
+) -> Result<Vec<JournalEntry>, AppError> {
+    let payments = sqlx::query_as::<_, FecPayment>(
+        r#"
+        SELECT t.id AS transaction_id, c.id AS center_id, c.name AS center_name,
+               t.stripe_payment_intent_id AS payment_intent_id, i.invoice_number,
+               t.amount, t.platform_fee, t.fee_vat,
+               COALESCE(t.currency, 'EUR') AS currency,
+               t.stripe_destination_account AS destination_account, t.created_at
+        FROM transactions t
+        INNER JOIN bookings b ON b.id = t.booking_id
+        INNER JOIN centers c ON c.id = b.center_id
+        LEFT JOIN invoices i ON i.booking_id = b.id AND i.kind = 'commission_invoice'
+        WHERE t.deleted_at IS NULL AND t.status IN ('succeeded', 'refunded')
+          AND t.created_at >= $1 AND t.created_at < $2
+        ORDER BY t.created_at ASC
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    let refunds = sqlx::query_as::<_, FecRefund>(
+        r#"
+        SELECT r.id AS refund_id, c.id AS center_id, c.name AS center_name, r.stripe_refund_id,
+               r.amount, t.amount AS payment_amount, t.platform_fee AS payment_fee,
+               t.fee_vat AS payment_fee_vat, t.stripe_destination_account AS destination_account,
+               r.currency, r.processed_at
+        FROM refunds r
+        INNER JOIN bookings b ON b.id = r.booking_id
+        INNER JOIN centers c ON c.id = b.center_id
+        INNER JOIN transactions t
+            ON t.stripe_payment_intent_id = r.stripe_payment_intent_id AND t.deleted_at IS NULL
+        WHERE r.status = 'approved' AND r.processed_at >= $1 AND r.processed_at < $2
+        ORDER BY r.processed_at ASC
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    let payouts = sqlx::query_as::<_, FecPayout>(
+        r#"
+        SELECT p.id AS payout_id, c.id AS center_id, c.name AS center_name,
+               p.stripe_transfer_id, p.amount, p.currency, p.created_at
+        FROM payouts p
+        INNER JOIN centers c ON c.id = p.center_id
+        WHERE p.status <> 'failed' AND p.created_at >= $1 AND p.created_at < $2
+        ORDER BY p.created_at ASC
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    let entries = build_entries(chart, &payments, &refunds, &payouts);
+    let mut currencies: Vec<String> = entries
+        .iter()
+        .filter(|e| e.currency != "EUR")
+        .map(|e| e.currency.clone())
+        .collect();
+    currencies.sort();
+    currencies.dedup();
+    let rates = if currencies.is_empty() {
+        Vec::new()
+    } else {
+        sqlx::query_as::<_, ExchangeRate>(
+            "SELECT currency, rate_date, rate FROM exchange_rates WHERE currency = ANY($1) AND rate_date < $2",
+        )
+        .bind(&currencies)
+        .bind(to.date_naive())
+        .fetch_all(pool)
+        .await?
+    };
+    convert_to_eur(entries, &rates)
+}
+
+/// Rates entered for `currency` (all currencies when `None`), newest first.
+pub async fn list_rates(
+    pool: &sqlx::PgPool,
+    currency: Option<&str>,
+) -> Result<Vec<ExchangeRate>, AppError> {
+    let rows = sqlx::query_as::<_, ExchangeRate>(
+        r#"
+        SELECT currency, rate_date, rate FROM exchange_rates
+        WHERE $1::text IS NULL OR currency = upper($1)
+        ORDER BY rate_date DESC, currency
+        LIMIT 500
+        "#,
+    )
+    .bind(currency)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
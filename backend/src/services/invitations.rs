@@ -0,0 +1,423 @@
+//! Email invitations to join a center's team.
+//!
+//! A member with `manage_members` invites an address with the role it will
+//! get. The email holds a random token; only its SHA-256 is stored
+//! (`center_invitations`, migration 034), so a database leak cannot be
+//! turned into working links. The invitee signs up or logs in with the
+//! invited address and accepts, which creates the `tli_pr_ce` row. Pending
+//! invitations can be resent, which replaces the token and restarts the
+//! [`INVITATION_DAYS`] deadline, or revoked.
+
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::services::email::{self, Locale, Template, Value};
+use crate::services::notifications::{self, Kind};
+use crate::services::outbox::{self, NewEmail};
+use crate::services::permissions::ASSIGNABLE_ROLES;
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// How long an invitation link stays valid.
+pub const INVITATION_DAYS: i64 = 7;
+
+/// Shortest delay between two sends of the same invitation.
+pub const RESEND_COOLDOWN_SECONDS: i64 = 60;
+
+/// 256 random bits from the OS CSPRNG, hex-encoded (URL-safe).
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// What `center_invitations.token_hash` stores for `token`.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.trim().as_bytes()))
+}
+
+pub fn invite_url(base_url: &str, token: &str) -> String {
+    format!("{}/invitations/{token}", base_url.trim_end_matches('/'))
+}
+
+/// Trimmed, lowercased address; rejects obviously invalid ones.
+pub fn normalize_email(email: &str) -> Result<String, AppError> {
+    let email = email.trim().to_lowercase();
+    match email.split_once('@') {
+        Some((local, domain)) if !local.is_empty() && domain.contains('.') && !email.contains(char::is_whitespace) => {
+            Ok(email)
+        }
+        _ => Err(AppError::BadRequest("A valid email address is required".to_owned())),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Invitation {
+    pub id: Uuid,
+    pub center_id: Uuid,
+    pub center_name: String,
+    pub email: String,
+    pub role_in_center: String,
+    pub status: String,
+    pub invited_by: Option<Uuid>,
+    pub inviter_name: Option<String>,
+    pub locale: String,
+    pub expires_at: Timestamp,
+    pub send_count: i32,
+    pub last_sent_at: Timestamp,
+    pub accepted_by: Option<Uuid>,
+    pub accepted_at: Option<Timestamp>,
+    pub revoked_at: Option<Timestamp>,
+    pub created_at: Timestamp,
+}
+
+impl Invitation {
+    pub fn is_expired(&self, now: Timestamp) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// Whether the user signed in with `user_email` may accept the invitation.
+/// It must still be open and be addressed to them.
+pub fn check_acceptable(invitation: &Invitation, user_email: &str, now: Timestamp) -> Result<(), AppError> {
+    if invitation.status != "pending" {
+        return Err(AppError::Conflict(format!(
+            "This invitation is already {}",
+            invitation.status
+        )));
+    }
+    if invitation.is_expired(now) {
+        return Err(AppError::Conflict("This invitation has expired".to_owned()));
+    }
+    if !invitation.email.eq_ignore_ascii_case(user_email.trim()) {
+        return Err(AppError::BadRequest(format!(
+            "This invitation was sent to {}. Sign in with that address to accept it.",
+            invitation.email
+        )));
+    }
+    Ok(())
+}
+
+/// Whether a pending invitation can be sent again; an expired one can, which
+/// renews it.
+pub fn check_resend(invitation: &Invitation, now: Timestamp) -> Result<(), AppError> {
+    if !matches!(invitation.status.as_str(), "pending" | "expired") {
+        return Err(AppError::Conflict(format!(
+            "This invitation is already {}",
+            invitation.status
+        )));
+    }
+    if now - invitation.last_sent_at < chrono::Duration::seconds(RESEND_COOLDOWN_SECONDS) {
+        return Err(AppError::TooManyRequests(
+            "This invitation was just sent. Try again in a minute.".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+const INVITATION_SELECT: &str = r#"
+    SELECT i.id, i.center_id, c.name AS center_name, i.email, i.role_in_center, i.status,
+           i.invited_by,
+           COALESCE(NULLIF(p.display_name, ''), NULLIF(TRIM(CONCAT(p.first_name, ' ', p.last_name)), '')) AS inviter_name,
+           i.locale, i.expires_at, i.send_count, i.last_sent_at,
+           i.accepted_by, i.accepted_at, i.revoked_at, i.created_at
+    FROM center_invitations i
+    JOIN centers c ON c.id = i.center_id
+    LEFT JOIN profiles p ON p.id = i.invited_by
+"#;
+
+async fn find<'e, E>(executor: E, id: Uuid) -> Result<Invitation, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, Invitation>(&format!("{INVITATION_SELECT} WHERE i.id = $1"))
+        .bind(id)
+        .fetch_optional(executor)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invitation not found".to_owned()))
+}
+
+/// Lock the invitation `id` of `center_id`.
+async fn lock(conn: &mut sqlx::PgConnection, center_id: Uuid, id: Uuid) -> Result<Invitation, AppError> {
+    sqlx::query("SELECT 1 FROM center_invitations WHERE id = $1 AND center_id = $2 FOR UPDATE")
+        .bind(id)
+        .bind(center_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invitation not found".to_owned()))?;
+    find(&mut *conn, id).await
+}
+
+/// Render the invitation email and put it in the outbox.
+async fn send(conn: &mut sqlx::PgConnection, invitation: &Invitation, token: &str, base_url: &str) -> Result<Uuid, AppError> {
+    let locale = Locale::from_preference(Some(&invitation.locale));
+    let vars: [(&str, Value); 6] = [
+        ("name", invitation.email.split('@').next().unwrap_or_default().into()),
+        ("center_name", invitation.center_name.as_str().into()),
+        ("inviter_name", invitation.inviter_name.clone().unwrap_or_else(|| invitation.center_name.clone()).into()),
+        ("role", invitation.role_in_center.as_str().into()),
+        ("invite_url", invite_url(base_url, token).into()),
+        ("expires_on", Value::Date(invitation.expires_at.date_naive())),
+    ];
+    let rendered = email::render(Template::MemberInvitation, locale, &vars)?;
+    outbox::enqueue(
+        &mut *conn,
+        &NewEmail {
+            template: Some(Template::MemberInvitation.name()),
+            locale: Some(locale.code()),
+            to_address: invitation.email.clone(),
+            subject: rendered.subject,
+            body: rendered.body,
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+/// Invite `email` to `center_id` as `role`. `locale` is the language of the
+/// email, by default the inviter's.
+pub async fn create(
+    conn: &mut sqlx::PgConnection,
+    center_id: Uuid,
+    invited_by: Uuid,
+    email: &str,
+    role: &str,
+    locale: Option<&str>,
+    base_url: &str,
+) -> Result<Invitation, AppError> {
+    let email = normalize_email(email)?;
+    if !ASSIGNABLE_ROLES.contains(&role) {
+        return Err(AppError::BadRequest(format!(
+            "Invalid role '{}'. Allowed: {}",
+            role,
+            ASSIGNABLE_ROLES.join(", ")
+        )));
+    }
+
+    let member: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM tli_pr_ce m JOIN auth.users u ON u.id = m.fk_profile
+            WHERE m.fk_center = $1 AND LOWER(u.email) = $2
+        )
+        "#,
+    )
+    .bind(center_id)
+    .bind(&email)
+    .fetch_one(&mut *conn)
+    .await?;
+    if member {
+        return Err(AppError::BadRequest(
+            "This user is already a member of this center.".to_owned(),
+        ));
+    }
+
+    // Invitations past their deadline no longer block a new one.
+    sqlx::query(
+        r#"
+        UPDATE center_invitations SET status = 'expired'
+        WHERE center_id = $1 AND LOWER(email) = $2 AND status = 'pending' AND expires_at <= NOW()
+        "#,
+    )
+    .bind(center_id)
+    .bind(&email)
+    .execute(&mut *conn)
+    .await?;
+
+    let locale = match locale {
+        Some(locale) => Locale::from_preference(Some(locale)),
+        None => {
+            let preferred: Option<String> = sqlx::query_scalar("SELECT preferred_locale FROM profiles WHERE id = $1")
+                .bind(invited_by)
+                .fetch_optional(&mut *conn)
+                .await?
+                .flatten();
+            Locale::from_preference(preferred.as_deref())
+        }
+    };
+
+    let token = generate_token();
+    let id: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO center_invitations (center_id, email, role_in_center, token_hash, invited_by, locale, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, NOW() + make_interval(days => $7::int))
+        ON CONFLICT DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(center_id)
+    .bind(&email)
+    .bind(role)
+    .bind(hash_token(&token))
+    .bind(invited_by)
+    .bind(locale.code())
+    .bind(INVITATION_DAYS as i32)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let id = id.ok_or_else(|| {
+        AppError::Conflict(format!(
+            "{email} already has a pending invitation. Resend it instead."
+        ))
+    })?;
+
+    let invitation = find(&mut *conn, id).await?;
+    send(&mut *conn, &invitation, &token, base_url).await?;
+
+    tracing::info!(invitation_id = %id, center_id = %center_id, invited_by = %invited_by, role = %role, "Center invitation sent");
+    Ok(invitation)
+}
+
+/// Invitations of a center, newest first. Pending ones past their deadline
+/// are reported as `expired`.
+pub async fn list(pool: &sqlx::PgPool, center_id: Uuid) -> Result<Vec<Invitation>, AppError> {
+    let mut rows = sqlx::query_as::<_, Invitation>(&format!(
+        "{INVITATION_SELECT} WHERE i.center_id = $1 ORDER BY i.created_at DESC LIMIT 200"
+    ))
+    .bind(center_id)
+    .fetch_all(pool)
+    .await?;
+    let now = chrono::Utc::now();
+    for row in &mut rows {
+        if row.status == "pending" && row.is_expired(now) {
+            row.status = "expired".to_owned();
+        }
+    }
+    Ok(rows)
+}
+
+/// Send the invitation again with a new token and a new deadline. The
+/// previous link stops working.
+pub async fn resend(conn: &mut sqlx::PgConnection, center_id: Uuid, id: Uuid, base_url: &str) -> Result<Invitation, AppError> {
+    let invitation = lock(&mut *conn, center_id, id).await?;
+    check_resend(&invitation, chrono::Utc::now())?;
+
+    let token = generate_token();
+    sqlx::query(
+        r#"
+        UPDATE center_invitations
+        SET status = 'pending', token_hash = $2, expires_at = NOW() + make_interval(days => $3::int),
+            send_count = send_count + 1, last_sent_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(hash_token(&token))
+    .bind(INVITATION_DAYS as i32)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| match e {
+        // An expired invitation renewed while a newer one is pending
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => AppError::Conflict(format!(
+            "{} already has a pending invitation",
+            invitation.email
+        )),
+        e => e.into(),
+    })?;
+    let invitation = find(&mut *conn, id).await?;
+    send(&mut *conn, &invitation, &token, base_url).await?;
+
+    tracing::info!(invitation_id = %id, center_id = %center_id, sends = invitation.send_count, "Center invitation resent");
+    Ok(invitation)
+}
+
+/// Revoke a pending invitation; its link stops working.
+pub async fn revoke(conn: &mut sqlx::PgConnection, center_id: Uuid, id: Uuid) -> Result<Invitation, AppError> {
+    let invitation = lock(&mut *conn, center_id, id).await?;
+    if !matches!(invitation.status.as_str(), "pending" | "expired") {
+        return Err(AppError::Conflict(format!(
+            "This invitation is already {}",
+            invitation.status
+        )));
+    }
+    sqlx::query("UPDATE center_invitations SET status = 'revoked', revoked_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    let invitation = find(&mut *conn, id).await?;
+    Ok(invitation)
+}
+
+/// The invitation behind a link, for the acceptance page.
+pub async fn find_by_token<'e, E>(executor: E, token: &str) -> Result<Invitation, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, Invitation>(&format!("{INVITATION_SELECT} WHERE i.token_hash = $1"))
+        .bind(hash_token(token))
+        .fetch_optional(executor)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invitation not found".to_owned()))
+}
+
+/// Accept the invitation behind `token` as `user_id`, making them a member.
+/// Returns the invitation and the new `tli_pr_ce` id.
+pub async fn accept(conn: &mut sqlx::PgConnection, token: &str, user_id: Uuid) -> Result<(Invitation, Uuid), AppError> {
+    let id: Uuid = sqlx::query_scalar("SELECT id FROM center_invitations WHERE token_hash = $1 FOR UPDATE")
+        .bind(hash_token(token))
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invitation not found".to_owned()))?;
+    let invitation = find(&mut *conn, id).await?;
+
+    let user_email: String = sqlx::query_scalar(
+        r#"
+        SELECT u.email FROM auth.users u
+        JOIN profiles p ON p.id = u.id AND p.deleted_at IS NULL
+        WHERE u.id = $1 AND u.email IS NOT NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::BadRequest("Complete your profile before accepting the invitation.".to_owned()))?;
+    check_acceptable(&invitation, &user_email, chrono::Utc::now())?;
+
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM tli_pr_ce WHERE fk_profile = $1 AND fk_center = $2)",
+    )
+    .bind(user_id)
+    .bind(invitation.center_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if exists {
+        return Err(AppError::Conflict(
+            "You are already a member of this center.".to_owned(),
+        ));
+    }
+    let member_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO tli_pr_ce (fk_profile, fk_center, role_in_center)
+        VALUES ($1, $2, $3)
+        RETURNING id
+        "#,
+    )
+    .bind(user_id)
+    .bind(invitation.center_id)
+    .bind(&invitation.role_in_center)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "UPDATE center_invitations SET status = 'accepted', accepted_by = $2, accepted_at = NOW() WHERE id = $1",
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+    notifications::notify(
+        &mut *conn,
+        user_id,
+        Kind::MemberAdded,
+        &[("center_name", invitation.center_name.as_str().into())],
+        Some(notifications::TEAM_LINK),
+    )
+    .await?;
+    let invitation = find(&mut *conn, id).await?;
+
+    tracing::info!(invitation_id = %id, center_id = %invitation.center_id, profile_id = %user_id, "Center invitation accepted");
+    Ok((invitation, member_id))
+}
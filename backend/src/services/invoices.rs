@@ -0,0 +1,582 @@
+//! Booking documents: diver receipts and commission invoices.
+//!
+//! Documents are issued once per paid booking and stored in `invoices` with
+//! a frozen JSON snapshot of seller, buyer and amounts, so later edits to a
+//! center or profile never change a document that was already issued. The
+//! PDF itself is rendered on demand from that snapshot.
+
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::money::iso_minor_units;
+use crate::services::pdf::PdfBuilder;
+use crate::services::tax::{FeeTaxRegime, TaxBreakdown, TaxLine};
+
+/// The two documents issued for each paid booking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    /// Payment receipt from the center to the diver.
+    Receipt,
+    /// Commission invoice from EviDive to the center.
+    CommissionInvoice,
+}
+
+impl DocumentKind {
+    pub const ALL: [Self; 2] = [Self::Receipt, Self::CommissionInvoice];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Receipt => "receipt",
+            Self::CommissionInvoice => "commission_invoice",
+        }
+    }
+
+    fn series_prefix(&self) -> &'static str {
+        match self {
+            Self::Receipt => "R",
+            Self::CommissionInvoice => "F",
+        }
+    }
+}
+
+/// A party named on a document (seller or buyer).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Party {
+    pub name: String,
+    pub address_lines: Vec<String>,
+    pub email: Option<String>,
+    pub siret: Option<String>,
+    pub vat_number: Option<String>,
+}
+
+/// Snapshot stored in `invoices.details` at issue time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentDetails {
+    pub seller: Party,
+    pub buyer: Party,
+    pub service_name: String,
+    pub booking_date: chrono::NaiveDate,
+    pub time_slot: chrono::NaiveTime,
+    pub participants: i32,
+    pub unit_price: Decimal,
+    pub total_price: Decimal,
+    pub commission_rate: Decimal,
+    pub amount_paid: Decimal,
+    pub platform_fee: Decimal,
+    pub vendor_amount: Decimal,
+    pub payment_reference: Option<String>,
+    pub paid_at: chrono::DateTime<chrono::Utc>,
+    /// VAT split recorded on the transaction; absent for older payments.
+    #[serde(default)]
+    pub tax: Option<TaxBreakdown>,
+}
+
+/// An issued document row from `invoices`.
+#[derive(Debug, sqlx::FromRow, Serialize)]
+pub struct InvoiceRow {
+    pub id: Uuid,
+    pub booking_id: Uuid,
+    pub center_id: Uuid,
+    pub client_id: Uuid,
+    pub kind: String,
+    pub invoice_number: String,
+    pub currency: String,
+    pub amount: Decimal,
+    pub details: serde_json::Value,
+    pub issued_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct BookingSnapshot {
+    client_id: Uuid,
+    center_id: Uuid,
+    booking_date: chrono::NaiveDate,
+    time_slot: chrono::NaiveTime,
+    participants: i32,
+    unit_price: Decimal,
+    total_price: Decimal,
+    commission_rate: Decimal,
+    service_name: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PaymentSnapshot {
+    stripe_payment_intent_id: Option<String>,
+    amount: Decimal,
+    platform_fee: Decimal,
+    vendor_amount: Decimal,
+    currency: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    tax_country: Option<String>,
+    price_vat_rate: Option<Decimal>,
+    price_net: Option<Decimal>,
+    price_vat: Option<Decimal>,
+    fee_vat_rate: Option<Decimal>,
+    fee_net: Option<Decimal>,
+    fee_vat: Option<Decimal>,
+    fee_tax_regime: Option<String>,
+}
+
+impl PaymentSnapshot {
+    fn tax_breakdown(&self) -> Option<TaxBreakdown> {
+        let fee_regime = match self.fee_tax_regime.as_deref()? {
+            "french_vat" => FeeTaxRegime::FrenchVat,
+            "reverse_charge" => FeeTaxRegime::ReverseCharge,
+            "out_of_scope" => FeeTaxRegime::OutOfScope,
+            _ => return None,
+        };
+        Some(TaxBreakdown {
+            country: self.tax_country.clone()?,
+            price: TaxLine {
+                rate: self.price_vat_rate?,
+                net: self.price_net?,
+                vat: self.price_vat?,
+                gross: self.amount,
+            },
+            fee: TaxLine {
+                rate: self.fee_vat_rate?,
+                net: self.fee_net?,
+                vat: self.fee_vat?,
+                gross: self.platform_fee,
+            },
+            fee_regime,
+        })
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct CenterParty {
+    name: String,
+    address: Option<String>,
+    postal_code: Option<String>,
+    city: Option<String>,
+    country: Option<String>,
+    email: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ClientParty {
+    first_name: Option<String>,
+    last_name: Option<String>,
+    display_name: Option<String>,
+    email: Option<String>,
+}
+
+const INVOICE_COLUMNS: &str = "id, booking_id, center_id, client_id, kind, invoice_number, \
+                               currency, amount, details, issued_at";
+
+/// Fetch an already issued document for a booking.
+pub async fn find(
+    pool: &sqlx::PgPool,
+    booking_id: Uuid,
+    kind: DocumentKind,
+) -> Result<Option<InvoiceRow>, AppError> {
+    let row = sqlx::query_as::<_, InvoiceRow>(&format!(
+        "SELECT {INVOICE_COLUMNS} FROM invoices WHERE booking_id = $1 AND kind = $2"
+    ))
+    .bind(booking_id)
+    .bind(kind.as_str())
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Fetch a document by ID.
+pub async fn find_by_id(
+    pool: &sqlx::PgPool,
+    invoice_id: Uuid,
+) -> Result<Option<InvoiceRow>, AppError> {
+    let row = sqlx::query_as::<_, InvoiceRow>(&format!(
+        "SELECT {INVOICE_COLUMNS} FROM invoices WHERE id = $1"
+    ))
+    .bind(invoice_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// Return the document, issuing all missing documents for the booking first.
+/// Returns `None` when no payment has been recorded for the booking yet.
+pub async fn find_or_issue(
+    pool: &sqlx::PgPool,
+    booking_id: Uuid,
+    kind: DocumentKind,
+) -> Result<Option<InvoiceRow>, AppError> {
+    if let Some(row) = find(pool, booking_id, kind).await? {
+        return Ok(Some(row));
+    }
+    issue_for_booking(pool, booking_id).await?;
+    find(pool, booking_id, kind).await
+}
+
+/// Issue the receipt and commission invoice for a paid booking.
+///
+/// Idempotent: documents that already exist are left untouched. The booking
+/// row is locked for the duration of the transaction so concurrent calls
+/// cannot both allocate a number for the same document.
+///
+/// Returns `false` when the booking has no recorded payment.
+pub async fn issue_for_booking(pool: &sqlx::PgPool, booking_id: Uuid) -> Result<bool, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let booking = sqlx::query_as::<_, BookingSnapshot>(
+        r#"
+        SELECT b.client_id, b.center_id, b.booking_date, b.time_slot, b.participants,
+               b.unit_price, b.total_price, b.commission_rate,
+               COALESCE(s.name, 'Dive booking') AS service_name
+        FROM bookings b
+        LEFT JOIN services s ON s.id = b.service_id
+        WHERE b.id = $1 AND b.deleted_at IS NULL
+        FOR UPDATE OF b
+        "#,
+    )
+    .bind(booking_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Booking not found".to_owned()))?;
+
+    let payment = sqlx::query_as::<_, PaymentSnapshot>(
+        r#"
+        SELECT stripe_payment_intent_id, amount, platform_fee, vendor_amount,
+               COALESCE(currency, 'EUR') AS currency, created_at,
+               tax_country, price_vat_rate, price_net, price_vat,
+               fee_vat_rate, fee_net, fee_vat, fee_tax_regime
+        FROM transactions
+        WHERE booking_id = $1 AND deleted_at IS NULL
+        ORDER BY created_at ASC
+        LIMIT 1
+        "#,
+    )
+    .bind(booking_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(payment) = payment else {
+        return Ok(false);
+    };
+
+    let existing: Vec<String> =
+        sqlx::query_scalar("SELECT kind FROM invoices WHERE booking_id = $1")
+            .bind(booking_id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+    let missing: Vec<DocumentKind> = DocumentKind::ALL
+        .into_iter()
+        .filter(|k| !existing.iter().any(|e| e == k.as_str()))
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(true);
+    }
+
+    let center = sqlx::query_as::<_, CenterParty>(
+        "SELECT name, address, postal_code, city, country, email FROM centers WHERE id = $1",
+    )
+    .bind(booking.center_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let client = sqlx::query_as::<_, ClientParty>(
+        r#"
+        SELECT p.first_name, p.last_name, p.display_name, u.email
+        FROM profiles p
+        LEFT JOIN auth.users u ON u.id = p.id
+        WHERE p.id = $1
+        "#,
+    )
+    .bind(booking.client_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let platform = platform_party(&mut tx).await?;
+    let center_party = center_party(center);
+    let client_party = client_party(client);
+    let year = chrono::Datelike::year(&chrono::Utc::now());
+
+    for kind in missing {
+        let (seller, buyer, amount) = match kind {
+            DocumentKind::Receipt => (center_party.clone(), client_party.clone(), payment.amount),
+            DocumentKind::CommissionInvoice => {
+                (platform.clone(), center_party.clone(), payment.platform_fee)
+            }
+        };
+
+        let details = DocumentDetails {
+            seller,
+            buyer,
+            service_name: booking.service_name.clone(),
+            booking_date: booking.booking_date,
+            time_slot: booking.time_slot,
+            participants: booking.participants,
+            unit_price: booking.unit_price,
+            total_price: booking.total_price,
+            commission_rate: booking.commission_rate,
+            amount_paid: payment.amount,
+            platform_fee: payment.platform_fee,
+            vendor_amount: payment.vendor_amount,
+            payment_reference: payment.stripe_payment_intent_id.clone(),
+            paid_at: payment.created_at,
+            tax: payment.tax_breakdown(),
+        };
+        let details = serde_json::to_value(&details)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize invoice details: {e}")))?;
+
+        let series = format!("{}{year}", kind.series_prefix());
+        let number = next_number(&mut tx, &series).await?;
+        let invoice_number = format!("EVD-{series}-{number:06}");
+
+        sqlx::query(
+            r#"
+            INSERT INTO invoices (
+                booking_id, center_id, client_id, kind, series, number,
+                invoice_number, currency, amount, details
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+        )
+        .bind(booking_id)
+        .bind(booking.center_id)
+        .bind(booking.client_id)
+        .bind(kind.as_str())
+        .bind(&series)
+        .bind(number)
+        .bind(&invoice_number)
+        .bind(&payment.currency)
+        .bind(amount)
+        .bind(&details)
+        .execute(&mut *tx)
+        .await?;
+
+        tracing::info!(
+            booking_id = %booking_id,
+            invoice_number = %invoice_number,
+            kind = kind.as_str(),
+            "Booking document issued"
+        );
+    }
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Allocate the next number of a series. The counter row stays locked until
+/// the surrounding transaction ends, which keeps the numbering gap-free.
+async fn next_number(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    series: &str,
+) -> Result<i64, AppError> {
+    let number: i64 = sqlx::query_scalar(
+        r#"
+        INSERT INTO invoice_counters (series, last_number)
+        VALUES ($1, 1)
+        ON CONFLICT (series) DO UPDATE SET last_number = invoice_counters.last_number + 1
+        RETURNING last_number
+        "#,
+    )
+    .bind(series)
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(number)
+}
+
+/// EviDive's legal details from `t_platform_config` (keys `company_*`).
+async fn platform_party(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<Party, AppError> {
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT key, value FROM t_platform_config WHERE key LIKE 'company\\_%'",
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let get = |key: &str| {
+        rows.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.trim().to_owned())
+            .filter(|v| !v.is_empty())
+    };
+
+    let address_lines = [
+        get("company_address"),
+        join_non_empty(&[get("company_postal_code"), get("company_city")], " "),
+        get("company_country"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    Ok(Party {
+        name: get("company_name").unwrap_or_else(|| "EviDive".to_owned()),
+        address_lines,
+        email: get("company_email"),
+        siret: get("company_siret"),
+        vat_number: get("company_vat_number"),
+    })
+}
+
+fn center_party(center: CenterParty) -> Party {
+    let address_lines = [
+        center.address,
+        join_non_empty(&[center.postal_code, center.city], " "),
+        center.country,
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|l| !l.trim().is_empty())
+    .collect();
+
+    Party {
+        name: center.name,
+        address_lines,
+        email: center.email,
+        siret: None,
+        vat_number: None,
+    }
+}
+
+fn client_party(client: ClientParty) -> Party {
+    let name = join_non_empty(&[client.first_name, client.last_name], " ")
+        .or(client.display_name)
+        .unwrap_or_else(|| "Client".to_owned());
+
+    Party {
+        name,
+        address_lines: Vec::new(),
+        email: client.email,
+        siret: None,
+        vat_number: None,
+    }
+}
+
+fn join_non_empty(parts: &[Option<String>], sep: &str) -> Option<String> {
+    let joined = parts
+        .iter()
+        .flatten()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(sep);
+    (!joined.is_empty()).then_some(joined)
+}
+
+/// Format an amount with the currency's ISO 4217 decimals, e.g. `120.00 EUR`.
+pub fn format_amount(amount: Decimal, currency: &str) -> String {
+    let decimals = iso_minor_units(currency).unwrap_or(2) as usize;
+    format!("{amount:.decimals$} {currency}")
+}
+
+/// Render a stored document as PDF bytes.
+pub fn render_pdf(invoice: &InvoiceRow) -> Result<Vec<u8>, AppError> {
+    let details: DocumentDetails = serde_json::from_value(invoice.details.clone())
+        .map_err(|e| AppError::Internal(format!("Corrupt invoice details: {e}")))?;
+    let money = |amount: Decimal| format_amount(amount, &invoice.currency);
+
+    let mut pdf = PdfBuilder::new();
+    if invoice.kind == DocumentKind::CommissionInvoice.as_str() {
+        pdf.heading("Facture de commission / Commission invoice");
+    } else {
+        pdf.heading("Reçu de paiement / Payment receipt");
+    }
+
+    pdf.row("Numéro / Number", &invoice.invoice_number)
+        .row(
+            "Date d'émission / Issue date",
+            &invoice.issued_at.format("%Y-%m-%d").to_string(),
+        )
+        .row("Réservation / Booking", &invoice.booking_id.to_string());
+
+    write_party(&mut pdf, "Émetteur / Issued by", &details.seller);
+    write_party(&mut pdf, "Destinataire / Billed to", &details.buyer);
+
+    pdf.section("Détail / Details")
+        .row("Prestation / Service", &details.service_name)
+        .row(
+            "Date de plongée / Dive date",
+            &format!(
+                "{} {}",
+                details.booking_date.format("%Y-%m-%d"),
+                details.time_slot.format("%H:%M")
+            ),
+        )
+        .row("Participants", &details.participants.to_string())
+        .row("Prix unitaire / Unit price", &money(details.unit_price));
+
+    if invoice.kind == DocumentKind::CommissionInvoice.as_str() {
+        pdf.row("Montant de la réservation / Booking amount", &money(details.amount_paid))
+            .row(
+                "Taux de commission / Commission rate",
+                &format!("{}%", details.commission_rate.normalize()),
+            )
+            .row("Reversé au centre / Paid to center", &money(details.vendor_amount))
+            .blank();
+        match details.tax {
+            Some(ref tax) => {
+                pdf.row("Commission HT / excl. VAT", &money(tax.fee.net))
+                    .row(
+                        &format!("TVA / VAT ({}%)", tax.fee.rate.normalize()),
+                        &money(tax.fee.vat),
+                    )
+                    .total_row("Commission TTC / incl. VAT", &money(invoice.amount));
+                if let Some(mention) = tax.fee_regime.invoice_mention() {
+                    pdf.blank().line(mention);
+                }
+            }
+            None => {
+                pdf.total_row("Commission due", &money(invoice.amount));
+            }
+        }
+    } else {
+        pdf.blank().total_row("Total payé / Total paid", &money(invoice.amount));
+        if let Some(ref tax) = details.tax {
+            pdf.row("Total HT / excl. VAT", &money(tax.price.net)).row(
+                &format!("dont TVA / incl. VAT ({}%)", tax.price.rate.normalize()),
+                &money(tax.price.vat),
+            );
+        }
+    }
+
+    pdf.blank().row(
+        "Payé le / Paid on",
+        &details.paid_at.format("%Y-%m-%d").to_string(),
+    );
+    if let Some(ref reference) = details.payment_reference {
+        pdf.row("Référence / Reference", reference);
+    }
+
+    Ok(pdf.finish())
+}
+
+fn write_party(pdf: &mut PdfBuilder, title: &str, party: &Party) {
+    pdf.section(title).line(&party.name);
+    for line in &party.address_lines {
+        pdf.line(line);
+    }
+    if let Some(ref email) = party.email {
+        pdf.line(email);
+    }
+    if let Some(ref siret) = party.siret {
+        pdf.line(&format!("SIRET : {siret}"));
+    }
+    if let Some(ref vat) = party.vat_number {
+        pdf.line(&format!("TVA / VAT : {vat}"));
+    }
+}
+
+/// Build the HTTP response for a document download.
+pub fn pdf_response(invoice: &InvoiceRow) -> Result<Response, AppError> {
+    let bytes = render_pdf(invoice)?;
+    let disposition = format!("attachment; filename=\"{}.pdf\"", invoice.invoice_number);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_owned()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        bytes,
+    )
+        .into_response())
+}
@@ -1,2 +1,24 @@
+pub mod audit;
+pub mod bans;
+pub mod checkout;
+pub mod connect;
+pub mod contact;
 pub mod email;
+pub mod events;
+pub mod fec;
+pub mod invitations;
+pub mod invoices;
+pub mod notifications;
+pub mod outbox;
+pub mod ownership;
+pub mod payment_gateway;
+pub mod payouts;
+pub mod pdf;
+pub mod permissions;
+pub mod privacy;
+pub mod reconciliation;
+pub mod reminders;
+pub mod statements;
 pub mod stripe;
+pub mod tax;
+pub mod webhooks;
@@ -0,0 +1,406 @@
+//! In-app notifications.
+//!
+//! Booking, review, payout, membership, ownership and contact events insert a
+//! row into `notifications` with the same transaction as the change they
+//! report. Titles and bodies are rendered in the recipient's locale when the
+//! event happens, and `link` is a frontend deep link without locale prefix
+//! (the frontend adds it).
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::services::email::{self, Locale, Value};
+use crate::services::permissions::{Capability, MemberPermissions};
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// Largest page of `GET /notifications`.
+pub const MAX_LIMIT: i64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    BookingRequested,
+    BookingConfirmed,
+    BookingCancelled,
+    ReviewReceived,
+    PayoutSent,
+    MemberAdded,
+    MemberRemoved,
+    DiveReminder,
+    ContactReceived,
+    OwnershipTransferRequested,
+    OwnershipTransferred,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 11] = [
+        Kind::BookingRequested,
+        Kind::BookingConfirmed,
+        Kind::BookingCancelled,
+        Kind::ReviewReceived,
+        Kind::PayoutSent,
+        Kind::MemberAdded,
+        Kind::MemberRemoved,
+        Kind::DiveReminder,
+        Kind::ContactReceived,
+        Kind::OwnershipTransferRequested,
+        Kind::OwnershipTransferred,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BookingRequested => "booking_requested",
+            Self::BookingConfirmed => "booking_confirmed",
+            Self::BookingCancelled => "booking_cancelled",
+            Self::ReviewReceived => "review_received",
+            Self::PayoutSent => "payout_sent",
+            Self::MemberAdded => "member_added",
+            Self::MemberRemoved => "member_removed",
+            Self::DiveReminder => "dive_reminder",
+            Self::ContactReceived => "contact_received",
+            Self::OwnershipTransferRequested => "ownership_transfer_requested",
+            Self::OwnershipTransferred => "ownership_transferred",
+        }
+    }
+
+    /// Variables used by the title and body.
+    pub fn variables(&self) -> &'static [&'static str] {
+        match self {
+            Self::BookingRequested => &["client_name", "service_name", "booking_date"],
+            Self::BookingConfirmed => &["center_name", "booking_date"],
+            Self::BookingCancelled => &["service_name", "booking_date"],
+            Self::ReviewReceived => &["center_name", "rating"],
+            Self::PayoutSent => &["center_name", "amount"],
+            Self::MemberAdded | Self::MemberRemoved => &["center_name"],
+            Self::DiveReminder => &["center_name", "service_name", "booking_date", "time_slot"],
+            Self::ContactReceived => &["sender_name", "subject"],
+            Self::OwnershipTransferRequested => &["center_name", "sender_name"],
+            Self::OwnershipTransferred => &["center_name", "owner_name"],
+        }
+    }
+
+    /// `(title, body)` in `locale`, with `{{variable}}` placeholders.
+    pub fn text(&self, locale: Locale) -> (&'static str, &'static str) {
+        use Locale::*;
+        match (self, locale) {
+            (Self::BookingRequested, Fr) => ("Nouvelle réservation", "{{client_name}} a réservé {{service_name}} pour le {{booking_date}}."),
+            (Self::BookingRequested, En) => ("New booking request", "{{client_name}} booked {{service_name}} on {{booking_date}}."),
+            (Self::BookingRequested, De) => ("Neue Buchungsanfrage", "{{client_name}} hat {{service_name}} am {{booking_date}} gebucht."),
+            (Self::BookingRequested, Es) => ("Nueva solicitud de reserva", "{{client_name}} ha reservado {{service_name}} para el {{booking_date}}."),
+            (Self::BookingRequested, It) => ("Nuova richiesta di prenotazione", "{{client_name}} ha prenotato {{service_name}} per il {{booking_date}}."),
+            (Self::BookingRequested, Pt) => ("Novo pedido de reserva", "{{client_name}} reservou {{service_name}} para {{booking_date}}."),
+            (Self::BookingRequested, Nl) => ("Nieuwe boekingsaanvraag", "{{client_name}} heeft {{service_name}} geboekt op {{booking_date}}."),
+
+            (Self::BookingConfirmed, Fr) => ("Réservation confirmée", "Votre réservation chez {{center_name}} le {{booking_date}} est confirmée."),
+            (Self::BookingConfirmed, En) => ("Booking confirmed", "Your booking at {{center_name}} on {{booking_date}} is confirmed."),
+            (Self::BookingConfirmed, De) => ("Buchung bestätigt", "Ihre Buchung bei {{center_name}} am {{booking_date}} ist bestätigt."),
+            (Self::BookingConfirmed, Es) => ("Reserva confirmada", "Tu reserva en {{center_name}} el {{booking_date}} está confirmada."),
+            (Self::BookingConfirmed, It) => ("Prenotazione confermata", "La tua prenotazione presso {{center_name}} il {{booking_date}} è confermata."),
+            (Self::BookingConfirmed, Pt) => ("Reserva confirmada", "A sua reserva em {{center_name}} a {{booking_date}} está confirmada."),
+            (Self::BookingConfirmed, Nl) => ("Boeking bevestigd", "Je boeking bij {{center_name}} op {{booking_date}} is bevestigd."),
+
+            (Self::BookingCancelled, Fr) => ("Réservation annulée", "La réservation {{service_name}} du {{booking_date}} a été annulée."),
+            (Self::BookingCancelled, En) => ("Booking cancelled", "The {{service_name}} booking on {{booking_date}} was cancelled."),
+            (Self::BookingCancelled, De) => ("Buchung storniert", "Die Buchung {{service_name}} am {{booking_date}} wurde storniert."),
+            (Self::BookingCancelled, Es) => ("Reserva cancelada", "La reserva de {{service_name}} del {{booking_date}} ha sido cancelada."),
+            (Self::BookingCancelled, It) => ("Prenotazione annullata", "La prenotazione {{service_name}} del {{booking_date}} è stata annullata."),
+            (Self::BookingCancelled, Pt) => ("Reserva cancelada", "A reserva de {{service_name}} de {{booking_date}} foi cancelada."),
+            (Self::BookingCancelled, Nl) => ("Boeking geannuleerd", "De boeking {{service_name}} op {{booking_date}} is geannuleerd."),
+
+            (Self::ReviewReceived, Fr) => ("Nouvel avis", "{{center_name}} a reçu un avis {{rating}}/5."),
+            (Self::ReviewReceived, En) => ("New review", "{{center_name}} received a {{rating}}/5 review."),
+            (Self::ReviewReceived, De) => ("Neue Bewertung", "{{center_name}} hat eine Bewertung mit {{rating}}/5 erhalten."),
+            (Self::ReviewReceived, Es) => ("Nueva reseña", "{{center_name}} ha recibido una reseña de {{rating}}/5."),
+            (Self::ReviewReceived, It) => ("Nuova recensione", "{{center_name}} ha ricevuto una recensione da {{rating}}/5."),
+            (Self::ReviewReceived, Pt) => ("Nova avaliação", "{{center_name}} recebeu uma avaliação de {{rating}}/5."),
+            (Self::ReviewReceived, Nl) => ("Nieuwe beoordeling", "{{center_name}} heeft een beoordeling van {{rating}}/5 ontvangen."),
+
+            (Self::PayoutSent, Fr) => ("Virement envoyé", "Un virement de {{amount}} a été envoyé à {{center_name}}."),
+            (Self::PayoutSent, En) => ("Payout sent", "A payout of {{amount}} was sent to {{center_name}}."),
+            (Self::PayoutSent, De) => ("Auszahlung gesendet", "Eine Auszahlung von {{amount}} wurde an {{center_name}} gesendet."),
+            (Self::PayoutSent, Es) => ("Pago enviado", "Se ha enviado un pago de {{amount}} a {{center_name}}."),
+            (Self::PayoutSent, It) => ("Pagamento inviato", "È stato inviato un pagamento di {{amount}} a {{center_name}}."),
+            (Self::PayoutSent, Pt) => ("Pagamento enviado", "Foi enviado um pagamento de {{amount}} para {{center_name}}."),
+            (Self::PayoutSent, Nl) => ("Uitbetaling verzonden", "Er is een uitbetaling van {{amount}} naar {{center_name}} verzonden."),
+
+            (Self::MemberAdded, Fr) => ("Nouvelle équipe", "Vous avez été ajouté à l'équipe de {{center_name}}."),
+            (Self::MemberAdded, En) => ("New team", "You were added to the {{center_name}} team."),
+            (Self::MemberAdded, De) => ("Neues Team", "Sie wurden dem Team von {{center_name}} hinzugefügt."),
+            (Self::MemberAdded, Es) => ("Nuevo equipo", "Te han añadido al equipo de {{center_name}}."),
+            (Self::MemberAdded, It) => ("Nuovo team", "Sei stato aggiunto al team di {{center_name}}."),
+            (Self::MemberAdded, Pt) => ("Nova equipa", "Foi adicionado à equipa de {{center_name}}."),
+            (Self::MemberAdded, Nl) => ("Nieuw team", "Je bent toegevoegd aan het team van {{center_name}}."),
+
+            (Self::MemberRemoved, Fr) => ("Équipe quittée", "Vous ne faites plus partie de l'équipe de {{center_name}}."),
+            (Self::MemberRemoved, En) => ("Removed from team", "You are no longer a member of the {{center_name}} team."),
+            (Self::MemberRemoved, De) => ("Aus dem Team entfernt", "Sie sind nicht mehr Mitglied im Team von {{center_name}}."),
+            (Self::MemberRemoved, Es) => ("Eliminado del equipo", "Ya no formas parte del equipo de {{center_name}}."),
+            (Self::MemberRemoved, It) => ("Rimosso dal team", "Non fai più parte del team di {{center_name}}."),
+            (Self::MemberRemoved, Pt) => ("Removido da equipa", "Já não faz parte da equipa de {{center_name}}."),
+            (Self::MemberRemoved, Nl) => ("Verwijderd uit team", "Je maakt geen deel meer uit van het team van {{center_name}}."),
+
+            (Self::DiveReminder, Fr) => ("Plongée à venir", "{{service_name}} avec {{center_name}} le {{booking_date}} à {{time_slot}}. N'oubliez pas votre carte de certification et votre carnet de plongée."),
+            (Self::DiveReminder, En) => ("Upcoming dive", "{{service_name}} with {{center_name}} on {{booking_date}} at {{time_slot}}. Remember your certification card and logbook."),
+            (Self::DiveReminder, De) => ("Bevorstehender Tauchgang", "{{service_name}} mit {{center_name}} am {{booking_date}} um {{time_slot}}. Denken Sie an Ihr Brevet und Ihr Logbuch."),
+            (Self::DiveReminder, Es) => ("Próxima inmersión", "{{service_name}} con {{center_name}} el {{booking_date}} a las {{time_slot}}. No olvides tu tarjeta de certificación y tu libro de inmersiones."),
+            (Self::DiveReminder, It) => ("Immersione in arrivo", "{{service_name}} con {{center_name}} il {{booking_date}} alle {{time_slot}}. Non dimenticare il brevetto e il logbook."),
+            (Self::DiveReminder, Pt) => ("Mergulho em breve", "{{service_name}} com {{center_name}} a {{booking_date}} às {{time_slot}}. Não se esqueça do cartão de certificação e do logbook."),
+            (Self::DiveReminder, Nl) => ("Duik komt eraan", "{{service_name}} met {{center_name}} op {{booking_date}} om {{time_slot}}. Vergeet je brevet en logboek niet."),
+
+            (Self::ContactReceived, Fr) => ("Nouveau message", "{{sender_name}} vous a écrit : « {{subject}} »."),
+            (Self::ContactReceived, En) => ("New message", "{{sender_name}} wrote to you: \"{{subject}}\"."),
+            (Self::ContactReceived, De) => ("Neue Nachricht", "{{sender_name}} hat Ihnen geschrieben: „{{subject}}“."),
+            (Self::ContactReceived, Es) => ("Nuevo mensaje", "{{sender_name}} te ha escrito: «{{subject}}»."),
+            (Self::ContactReceived, It) => ("Nuovo messaggio", "{{sender_name}} ti ha scritto: «{{subject}}»."),
+            (Self::ContactReceived, Pt) => ("Nova mensagem", "{{sender_name}} escreveu-lhe: «{{subject}}»."),
+            (Self::ContactReceived, Nl) => ("Nieuw bericht", "{{sender_name}} heeft je geschreven: \"{{subject}}\"."),
+
+            (Self::OwnershipTransferRequested, Fr) => ("Transfert de propriété", "{{sender_name}} vous propose de devenir propriétaire de {{center_name}}."),
+            (Self::OwnershipTransferRequested, En) => ("Ownership transfer", "{{sender_name}} offers you ownership of {{center_name}}."),
+            (Self::OwnershipTransferRequested, De) => ("Eigentumsübertragung", "{{sender_name}} bietet Ihnen an, Inhaber von {{center_name}} zu werden."),
+            (Self::OwnershipTransferRequested, Es) => ("Transferencia de propiedad", "{{sender_name}} te ofrece la propiedad de {{center_name}}."),
+            (Self::OwnershipTransferRequested, It) => ("Trasferimento di proprietà", "{{sender_name}} ti offre la proprietà di {{center_name}}."),
+            (Self::OwnershipTransferRequested, Pt) => ("Transferência de propriedade", "{{sender_name}} oferece-lhe a propriedade de {{center_name}}."),
+            (Self::OwnershipTransferRequested, Nl) => ("Eigendomsoverdracht", "{{sender_name}} biedt je het eigendom van {{center_name}} aan."),
+
+            (Self::OwnershipTransferred, Fr) => ("Nouveau propriétaire", "{{owner_name}} est désormais propriétaire de {{center_name}}."),
+            (Self::OwnershipTransferred, En) => ("New owner", "{{owner_name}} is now the owner of {{center_name}}."),
+            (Self::OwnershipTransferred, De) => ("Neuer Inhaber", "{{owner_name}} ist jetzt Inhaber von {{center_name}}."),
+            (Self::OwnershipTransferred, Es) => ("Nuevo propietario", "{{owner_name}} es ahora el propietario de {{center_name}}."),
+            (Self::OwnershipTransferred, It) => ("Nuovo proprietario", "{{owner_name}} è ora il proprietario di {{center_name}}."),
+            (Self::OwnershipTransferred, Pt) => ("Novo proprietário", "{{owner_name}} é agora o proprietário de {{center_name}}."),
+            (Self::OwnershipTransferred, Nl) => ("Nieuwe eigenaar", "{{owner_name}} is nu de eigenaar van {{center_name}}."),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedNotification {
+    pub title: String,
+    pub body: String,
+}
+
+/// Render `kind` in `locale`. A placeholder without a value is an error.
+pub fn render(kind: Kind, locale: Locale, vars: &[(&str, Value)]) -> Result<RenderedNotification, AppError> {
+    let (title, body) = kind.text(locale);
+    let fill = |text: &str| {
+        email::fill(text, locale, vars).map_err(|key| {
+            AppError::Internal(format!(
+                "Notification {}/{} uses unknown variable '{key}'",
+                locale.code(),
+                kind.as_str()
+            ))
+        })
+    };
+    Ok(RenderedNotification {
+        title: fill(title)?,
+        body: fill(body)?,
+    })
+}
+
+// ──────────────────────── Deep links ────────────────────────
+
+pub fn booking_link(booking_id: Uuid) -> String {
+    format!("/dashboard/bookings?booking_id={booking_id}")
+}
+
+pub const REVIEWS_LINK: &str = "/dashboard/reviews";
+pub const PAYMENTS_LINK: &str = "/dashboard/payments";
+pub const TEAM_LINK: &str = "/dashboard/team";
+
+pub fn contact_link(message_id: Uuid) -> String {
+    format!("/dashboard/messages?message_id={message_id}")
+}
+
+pub fn admin_contact_link(message_id: Uuid) -> String {
+    format!("/admin/contact?message_id={message_id}")
+}
+
+// ──────────────────────── Create ────────────────────────
+
+/// Notify one user in their preferred locale.
+pub async fn notify(
+    conn: &mut sqlx::PgConnection,
+    user_id: Uuid,
+    kind: Kind,
+    vars: &[(&str, Value)],
+    link: Option<&str>,
+) -> Result<Uuid, AppError> {
+    let preferred: Option<String> =
+        sqlx::query_scalar("SELECT preferred_locale FROM profiles WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .flatten();
+    let rendered = render(kind, Locale::from_preference(preferred.as_deref()), vars)?;
+
+    let id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO notifications (user_id, kind, title, body, link) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+    )
+    .bind(user_id)
+    .bind(kind.as_str())
+    .bind(&rendered.title)
+    .bind(&rendered.body)
+    .bind(link)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(id)
+}
+
+/// Notify every member of `center_id` holding `capability`, grants and
+/// revocations included, except `exclude` (the user who caused the event).
+pub async fn notify_center(
+    conn: &mut sqlx::PgConnection,
+    center_id: Uuid,
+    capability: Capability,
+    exclude: Option<Uuid>,
+    kind: Kind,
+    vars: &[(&str, Value)],
+    link: Option<&str>,
+) -> Result<usize, AppError> {
+    #[derive(sqlx::FromRow)]
+    struct Member {
+        fk_profile: Uuid,
+        #[sqlx(flatten)]
+        permissions: MemberPermissions,
+    }
+    let members: Vec<Uuid> = sqlx::query_as::<_, Member>(
+        r#"
+        SELECT m.fk_profile, m.role_in_center, m.granted_capabilities, m.revoked_capabilities
+        FROM tli_pr_ce m
+        JOIN profiles p ON p.id = m.fk_profile AND p.deleted_at IS NULL
+        WHERE m.fk_center = $1 AND ($2::uuid IS NULL OR m.fk_profile <> $2)
+        "#,
+    )
+    .bind(center_id)
+    .bind(exclude)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .filter(|m| m.permissions.allows(capability))
+    .map(|m| m.fk_profile)
+    .collect();
+
+    for member in &members {
+        notify(&mut *conn, *member, kind, vars, link).await?;
+    }
+    Ok(members.len())
+}
+
+/// Notify the parties of a booking event caused by `actor`:
+/// new bookings go to the center staff, confirmations to the client, and a
+/// cancellation to the other side (the staff when the client cancelled).
+pub async fn booking_event(
+    conn: &mut sqlx::PgConnection,
+    booking_id: Uuid,
+    kind: Kind,
+    actor: Option<Uuid>,
+) -> Result<(), AppError> {
+    let booking = email::load_booking(&mut *conn, booking_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Booking not found".to_owned()))?;
+    let vars = booking.vars();
+    let link = booking_link(booking_id);
+
+    let to_client = match kind {
+        Kind::BookingRequested => false,
+        Kind::BookingCancelled => actor != Some(booking.client_id),
+        _ => true,
+    };
+    if to_client {
+        notify(conn, booking.client_id, kind, &vars, Some(&link)).await?;
+    } else {
+        notify_center(conn, booking.center_id, Capability::ViewBookings, actor, kind, &vars, Some(&link)).await?;
+    }
+    Ok(())
+}
+
+/// Notify the owners of `center_id` that a payout was sent.
+pub async fn payout_sent(
+    conn: &mut sqlx::PgConnection,
+    center_id: Uuid,
+    amount: rust_decimal::Decimal,
+    currency: &str,
+) -> Result<(), AppError> {
+    let center_name: Option<String> = sqlx::query_scalar("SELECT name FROM centers WHERE id = $1")
+        .bind(center_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    let vars = [
+        ("center_name", center_name.unwrap_or_default().into()),
+        ("amount", Value::Amount(amount, currency.to_uppercase())),
+    ];
+    notify_center(conn, center_id, Capability::ManagePayouts, None, Kind::PayoutSent, &vars, Some(PAYMENTS_LINK)).await?;
+    Ok(())
+}
+
+// ──────────────────────── Read ────────────────────────
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Notification {
+    pub id: Uuid,
+    pub kind: Option<String>,
+    pub title: String,
+    pub body: String,
+    pub link: Option<String>,
+    pub is_read: bool,
+    pub created_at: Timestamp,
+}
+
+pub async fn list(
+    pool: &sqlx::PgPool,
+    user_id: Uuid,
+    unread_only: bool,
+    limit: i64,
+) -> Result<Vec<Notification>, AppError> {
+    let rows = sqlx::query_as::<_, Notification>(
+        r#"
+        SELECT id, kind, title, body, link, is_read, created_at
+        FROM notifications
+        WHERE user_id = $1 AND (NOT $2 OR is_read = false)
+        ORDER BY created_at DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(unread_only)
+    .bind(limit.clamp(1, MAX_LIMIT))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn unread_count(pool: &sqlx::PgPool, user_id: Uuid) -> Result<i64, AppError> {
+    let count = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND is_read = false",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
+
+/// Mark one of the user's notifications read. Another user's notification
+/// is reported as not found.
+pub async fn mark_read(pool: &sqlx::PgPool, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+    let updated = sqlx::query("UPDATE notifications SET is_read = true WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound("Notification not found".to_owned()));
+    }
+    Ok(())
+}
+
+/// Returns the number of notifications that were unread.
+pub async fn mark_all_read(pool: &sqlx::PgPool, user_id: Uuid) -> Result<u64, AppError> {
+    let updated = sqlx::query("UPDATE notifications SET is_read = true WHERE user_id = $1 AND is_read = false")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(updated.rows_affected())
+}
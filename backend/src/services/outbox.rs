@@ -0,0 +1,321 @@
+//! Durable email outbox.
+//!
+//! Handlers never talk to SMTP: they render an email and insert it into
+//! `email_outbox` with the same transaction as the change it reports, so a
+//! rolled-back change sends nothing and a committed one is never lost. The
+//! outbox job claims due messages (`FOR UPDATE SKIP LOCKED`, so concurrent
+//! runs never share a message), sends them and reschedules failures with
+//! exponential backoff. After `max_attempts` a message is `dead` until an
+//! admin resends it.
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::services::email::{self, Template, Vars};
+use crate::AppState;
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// Messages claimed per job run.
+pub const CLAIM_BATCH: i64 = 50;
+
+/// A claimed message is claimed again if its run died before finishing.
+pub const CLAIM_LOCK_MINUTES: i64 = 5;
+
+const BACKOFF_BASE_SECONDS: i64 = 60;
+const BACKOFF_MAX_SECONDS: i64 = 6 * 60 * 60;
+
+/// Delay before retrying a message that failed `attempts` times, or `None`
+/// when it has used all of its attempts and goes to the dead letters.
+///
+/// 1 min, 2 min, 4 min… capped at 6 hours.
+pub fn retry_delay(attempts: i32, max_attempts: i32) -> Option<chrono::Duration> {
+    if attempts >= max_attempts {
+        return None;
+    }
+    let exponent = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    let seconds = BACKOFF_BASE_SECONDS
+        .saturating_mul(2_i64.pow(exponent))
+        .min(BACKOFF_MAX_SECONDS);
+    Some(chrono::Duration::seconds(seconds))
+}
+
+// ──────────────────────── Enqueue ────────────────────────
+
+#[derive(Debug, Clone, Default)]
+pub struct NewEmail {
+    pub template: Option<&'static str>,
+    pub locale: Option<&'static str>,
+    pub profile_id: Option<Uuid>,
+    pub to_address: String,
+    pub reply_to: Option<String>,
+    pub subject: String,
+    pub body: String,
+}
+
+pub async fn enqueue<'e, E>(executor: E, email: &NewEmail) -> Result<Uuid, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO email_outbox (template, locale, profile_id, to_address, reply_to, subject, body)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id
+        "#,
+    )
+    .bind(email.template)
+    .bind(email.locale)
+    .bind(email.profile_id)
+    .bind(&email.to_address)
+    .bind(&email.reply_to)
+    .bind(&email.subject)
+    .bind(&email.body)
+    .fetch_one(executor)
+    .await?;
+    Ok(id)
+}
+
+/// Render `template` in the profile's locale and enqueue it. Profiles
+/// without an email address are skipped (`None`).
+pub async fn enqueue_template(
+    conn: &mut sqlx::PgConnection,
+    profile_id: Uuid,
+    template: Template,
+    mut vars: Vars,
+) -> Result<Option<Uuid>, AppError> {
+    let Some(recipient) = email::load_recipient(&mut *conn, profile_id).await? else {
+        tracing::debug!(profile_id = %profile_id, "No email address, email skipped");
+        return Ok(None);
+    };
+    let locale = recipient.locale();
+    vars.push(("name", recipient.greeting_name().into()));
+    let rendered = email::render(template, locale, &vars)?;
+
+    let id = enqueue(
+        &mut *conn,
+        &NewEmail {
+            template: Some(template.name()),
+            locale: Some(locale.code()),
+            profile_id: Some(profile_id),
+            to_address: recipient.email,
+            reply_to: None,
+            subject: rendered.subject,
+            body: rendered.body,
+        },
+    )
+    .await?;
+    Ok(Some(id))
+}
+
+/// Enqueue a booking template for the booking's client, with `extra`
+/// variables (`amount`) appended to the booking details.
+pub async fn enqueue_booking_client(
+    conn: &mut sqlx::PgConnection,
+    booking_id: Uuid,
+    template: Template,
+    extra: Vars,
+) -> Result<Option<Uuid>, AppError> {
+    let booking = email::load_booking(&mut *conn, booking_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Booking not found".to_owned()))?;
+    let mut vars = booking.vars();
+    vars.extend(extra);
+    enqueue_template(conn, booking.client_id, template, vars).await
+}
+
+// ──────────────────────── Delivery ────────────────────────
+
+#[derive(Debug, sqlx::FromRow)]
+struct ClaimedEmail {
+    id: Uuid,
+    to_address: String,
+    reply_to: Option<String>,
+    subject: String,
+    body: String,
+    attempts: i32,
+    max_attempts: i32,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct DeliveryRun {
+    pub sent: usize,
+    pub retried: usize,
+    pub dead: usize,
+}
+
+/// Send up to [`CLAIM_BATCH`] due messages.
+pub async fn deliver_due(state: &AppState) -> Result<DeliveryRun, AppError> {
+    let mut run = DeliveryRun::default();
+    if state.mailer.is_none() {
+        tracing::warn!("SMTP not configured, email outbox left pending");
+        return Ok(run);
+    }
+
+    let claimed = sqlx::query_as::<_, ClaimedEmail>(
+        r#"
+        UPDATE email_outbox o
+        SET status = 'sending', attempts = o.attempts + 1,
+            locked_until = NOW() + make_interval(mins => $2), updated_at = NOW()
+        WHERE o.id IN (
+            SELECT id FROM email_outbox
+            WHERE (status = 'pending' AND next_attempt_at <= NOW())
+               OR (status = 'sending' AND locked_until < NOW())
+            ORDER BY next_attempt_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING o.id, o.to_address, o.reply_to, o.subject, o.body, o.attempts, o.max_attempts
+        "#,
+    )
+    .bind(CLAIM_BATCH)
+    .bind(CLAIM_LOCK_MINUTES as i32)
+    .fetch_all(&state.pool)
+    .await?;
+
+    for message in claimed {
+        let result = email::send(
+            state,
+            &message.to_address,
+            message.reply_to.as_deref(),
+            &message.subject,
+            &message.body,
+        )
+        .await;
+
+        match result {
+            Ok(()) => {
+                sqlx::query(
+                    r#"
+                    UPDATE email_outbox
+                    SET status = 'sent', sent_at = NOW(), locked_until = NULL,
+                        last_error = NULL, updated_at = NOW()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(message.id)
+                .execute(&state.pool)
+                .await?;
+                run.sent += 1;
+            }
+            Err(e) => {
+                let error = match e {
+                    AppError::Internal(msg) => msg,
+                    other => format!("{other:?}"),
+                };
+                let retry = retry_delay(message.attempts, message.max_attempts);
+                sqlx::query(
+                    r#"
+                    UPDATE email_outbox
+                    SET status = CASE WHEN $2::bigint IS NULL THEN 'dead' ELSE 'pending' END,
+                        next_attempt_at = NOW() + make_interval(secs => COALESCE($2::bigint, 0)),
+                        locked_until = NULL, last_error = $3, updated_at = NOW()
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(message.id)
+                .bind(retry.map(|d| d.num_seconds()))
+                .bind(&error)
+                .execute(&state.pool)
+                .await?;
+
+                if retry.is_some() {
+                    tracing::warn!(email_id = %message.id, attempts = message.attempts, error = %error, "Email delivery failed, will retry");
+                    run.retried += 1;
+                } else {
+                    tracing::error!(email_id = %message.id, attempts = message.attempts, error = %error, "Email delivery failed, moved to dead letters");
+                    run.dead += 1;
+                }
+            }
+        }
+    }
+
+    Ok(run)
+}
+
+// ──────────────────────── Admin ────────────────────────
+
+/// Outbox row without its body, for listings.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct OutboxSummary {
+    pub id: Uuid,
+    pub template: Option<String>,
+    pub locale: Option<String>,
+    pub profile_id: Option<Uuid>,
+    pub to_address: String,
+    pub subject: String,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub next_attempt_at: Timestamp,
+    pub last_error: Option<String>,
+    pub sent_at: Option<Timestamp>,
+    pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct OutboxMessage {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub summary: OutboxSummary,
+    pub reply_to: Option<String>,
+    pub body: String,
+}
+
+const SUMMARY_COLUMNS: &str = "id, template, locale, profile_id, to_address, subject, status, attempts, \
+     max_attempts, next_attempt_at, last_error, sent_at, created_at";
+
+pub async fn list(
+    pool: &sqlx::PgPool,
+    status: Option<&str>,
+    limit: i64,
+) -> Result<Vec<OutboxSummary>, AppError> {
+    let rows = sqlx::query_as::<_, OutboxSummary>(&format!(
+        "SELECT {SUMMARY_COLUMNS} FROM email_outbox \
+         WHERE ($1::text IS NULL OR status = $1) ORDER BY created_at DESC LIMIT $2"
+    ))
+    .bind(status)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn get<'e, E>(executor: E, id: Uuid) -> Result<OutboxMessage, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, OutboxMessage>(&format!(
+        "SELECT {SUMMARY_COLUMNS}, reply_to, body FROM email_outbox WHERE id = $1"
+    ))
+    .bind(id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Email not found".to_owned()))
+}
+
+/// Put a dead (or still pending) message back in the queue with a fresh
+/// set of attempts, due immediately.
+pub async fn resend(pool: &sqlx::PgPool, id: Uuid) -> Result<OutboxMessage, AppError> {
+    let updated = sqlx::query(
+        r#"
+        UPDATE email_outbox
+        SET status = 'pending', attempts = 0, next_attempt_at = NOW(),
+            locked_until = NULL, last_error = NULL, updated_at = NOW()
+        WHERE id = $1 AND status IN ('dead', 'pending')
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    if updated.rows_affected() == 0 {
+        let current = get(pool, id).await?;
+        return Err(AppError::Conflict(format!(
+            "Only dead or pending emails can be resent, this one is '{}'",
+            current.summary.status
+        )));
+    }
+    get(pool, id).await
+}
@@ -0,0 +1,376 @@
+//! Center ownership transfer.
+//!
+//! The owner (`centers.owner_id`) offers the center to an existing member,
+//! who accepts or declines within [`OFFER_DAYS`]; the owner can cancel the
+//! offer until then. Acceptance makes the recipient owner, the previous
+//! owner a manager, and flags the Stripe account for re-verification (see
+//! `services::connect`), all in one transaction. `center_ownership_transfers`
+//! (migration 033) keeps every offer and its outcome; the handlers record
+//! the offer, the answer and the owner and role changes in `audit_log`
+//! within the same transaction.
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::services::email::Value;
+use crate::services::notifications::{self, Kind};
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// How long an offer stays open (the column default of migration 033).
+pub const OFFER_DAYS: i64 = 7;
+
+/// Role of the previous owner once the transfer is accepted.
+pub const PREVIOUS_OWNER_ROLE: &str = "manager";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Accept,
+    Decline,
+    Cancel,
+}
+
+impl Action {
+    /// Status stored when the action succeeds.
+    pub fn status(&self) -> &'static str {
+        match self {
+            Self::Accept => "accepted",
+            Self::Decline => "declined",
+            Self::Cancel => "cancelled",
+        }
+    }
+}
+
+/// Whether `user_id` may apply `action` to a transfer: the recipient
+/// accepts or declines, the initiator cancels, and only open offers can be
+/// answered.
+pub fn check_action(
+    transfer: &OwnershipTransfer,
+    user_id: Uuid,
+    action: Action,
+    now: Timestamp,
+) -> Result<(), AppError> {
+    let allowed = match action {
+        Action::Accept | Action::Decline => transfer.to_profile == user_id,
+        Action::Cancel => transfer.from_profile == user_id,
+    };
+    if !allowed {
+        return Err(AppError::Forbidden);
+    }
+    if transfer.status != "pending" {
+        return Err(AppError::Conflict(format!(
+            "This transfer is already {}",
+            transfer.status
+        )));
+    }
+    if action != Action::Cancel && transfer.is_expired(now) {
+        return Err(AppError::Conflict("This transfer has expired".to_owned()));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct OwnershipTransfer {
+    pub id: Uuid,
+    pub center_id: Uuid,
+    pub center_name: String,
+    pub from_profile: Uuid,
+    pub from_name: Option<String>,
+    pub to_profile: Uuid,
+    pub to_name: Option<String>,
+    pub status: String,
+    pub message: Option<String>,
+    pub previous_stripe_account_id: Option<String>,
+    pub expires_at: Timestamp,
+    pub responded_at: Option<Timestamp>,
+    pub created_at: Timestamp,
+}
+
+impl OwnershipTransfer {
+    pub fn is_expired(&self, now: Timestamp) -> bool {
+        now >= self.expires_at
+    }
+
+    fn recipient_name(&self) -> String {
+        self.to_name.clone().unwrap_or_default()
+    }
+}
+
+const TRANSFER_SELECT: &str = r#"
+    SELECT t.id, t.center_id, c.name AS center_name,
+           t.from_profile,
+           COALESCE(NULLIF(fp.display_name, ''), NULLIF(TRIM(CONCAT(fp.first_name, ' ', fp.last_name)), '')) AS from_name,
+           t.to_profile,
+           COALESCE(NULLIF(tp.display_name, ''), NULLIF(TRIM(CONCAT(tp.first_name, ' ', tp.last_name)), '')) AS to_name,
+           t.status, t.message, t.previous_stripe_account_id, t.expires_at, t.responded_at, t.created_at
+    FROM center_ownership_transfers t
+    JOIN centers c ON c.id = t.center_id
+    LEFT JOIN profiles fp ON fp.id = t.from_profile
+    LEFT JOIN profiles tp ON tp.id = t.to_profile
+"#;
+
+async fn find<'e, E>(executor: E, id: Uuid) -> Result<OwnershipTransfer, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, OwnershipTransfer>(&format!("{TRANSFER_SELECT} WHERE t.id = $1"))
+        .bind(id)
+        .fetch_optional(executor)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Ownership transfer not found".to_owned()))
+}
+
+/// Offer `center_id` to member `member_id` (a `tli_pr_ce` row). Only the
+/// current owner can, and a center has at most one open offer.
+pub async fn initiate(
+    conn: &mut sqlx::PgConnection,
+    center_id: Uuid,
+    owner_id: Uuid,
+    member_id: Uuid,
+    message: Option<&str>,
+) -> Result<OwnershipTransfer, AppError> {
+    let current_owner: Option<Uuid> = sqlx::query_scalar(
+        "SELECT owner_id FROM centers WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+    )
+    .bind(center_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    match current_owner {
+        None => return Err(AppError::NotFound("Center not found".to_owned())),
+        Some(owner) if owner != owner_id => return Err(AppError::Forbidden),
+        Some(_) => {}
+    }
+
+    let recipient: Uuid = sqlx::query_scalar(
+        r#"
+        SELECT m.fk_profile FROM tli_pr_ce m
+        JOIN profiles p ON p.id = m.fk_profile AND p.deleted_at IS NULL
+        WHERE m.id = $1 AND m.fk_center = $2
+        "#,
+    )
+    .bind(member_id)
+    .bind(center_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Member not found".to_owned()))?;
+    if recipient == owner_id {
+        return Err(AppError::BadRequest(
+            "You already own this center.".to_owned(),
+        ));
+    }
+
+    // Offers past their deadline no longer block a new one.
+    sqlx::query(
+        r#"
+        UPDATE center_ownership_transfers SET status = 'expired', responded_at = expires_at
+        WHERE center_id = $1 AND status = 'pending' AND expires_at <= NOW()
+        "#,
+    )
+    .bind(center_id)
+    .execute(&mut *conn)
+    .await?;
+    let pending: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM center_ownership_transfers WHERE center_id = $1 AND status = 'pending')",
+    )
+    .bind(center_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if pending {
+        return Err(AppError::Conflict(
+            "An ownership transfer is already pending for this center".to_owned(),
+        ));
+    }
+
+    let message = message.map(str::trim).filter(|m| !m.is_empty());
+    let id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO center_ownership_transfers (center_id, from_profile, to_profile, message, expires_at)
+        VALUES ($1, $2, $3, $4, NOW() + make_interval(days => $5::int))
+        RETURNING id
+        "#,
+    )
+    .bind(center_id)
+    .bind(owner_id)
+    .bind(recipient)
+    .bind(message)
+    .bind(OFFER_DAYS as i32)
+    .fetch_one(&mut *conn)
+    .await?;
+    let transfer = find(&mut *conn, id).await?;
+
+    let vars: [(&str, Value); 2] = [
+        ("center_name", transfer.center_name.as_str().into()),
+        ("sender_name", transfer.from_name.clone().unwrap_or_default().into()),
+    ];
+    notifications::notify(&mut *conn, recipient, Kind::OwnershipTransferRequested, &vars, Some(notifications::TEAM_LINK)).await?;
+
+    tracing::info!(transfer_id = %id, center_id = %center_id, from = %owner_id, to = %recipient, "Ownership transfer offered");
+    Ok(transfer)
+}
+
+/// Mark transfer `id` expired if it is still pending past its deadline.
+/// Done on its own, before answering, so that the status sticks although
+/// the answer is refused.
+pub async fn expire_overdue(pool: &sqlx::PgPool, id: Uuid) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE center_ownership_transfers SET status = 'expired', responded_at = expires_at
+        WHERE id = $1 AND status = 'pending' AND expires_at <= NOW()
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Transfer `id`, locked until the transaction ends.
+pub async fn lock(conn: &mut sqlx::PgConnection, id: Uuid) -> Result<OwnershipTransfer, AppError> {
+    sqlx::query("SELECT 1 FROM center_ownership_transfers WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    find(&mut *conn, id).await
+}
+
+/// Accept, decline or cancel `transfer`, locked with [`lock`].
+pub async fn respond(
+    conn: &mut sqlx::PgConnection,
+    transfer: &OwnershipTransfer,
+    user_id: Uuid,
+    action: Action,
+) -> Result<OwnershipTransfer, AppError> {
+    check_action(transfer, user_id, action, chrono::Utc::now())?;
+
+    let previous_account = match action {
+        Action::Accept => Some(complete(&mut *conn, transfer).await?),
+        Action::Decline | Action::Cancel => None,
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE center_ownership_transfers
+        SET status = $2, responded_at = NOW(), previous_stripe_account_id = $3
+        WHERE id = $1
+        "#,
+    )
+    .bind(transfer.id)
+    .bind(action.status())
+    .bind(previous_account.flatten())
+    .execute(&mut *conn)
+    .await?;
+    let transfer = find(&mut *conn, transfer.id).await?;
+
+    tracing::info!(
+        transfer_id = %transfer.id,
+        center_id = %transfer.center_id,
+        actor = %user_id,
+        status = %transfer.status,
+        "Ownership transfer answered"
+    );
+    Ok(transfer)
+}
+
+/// Swap the owner, as the recipient accepted. Returns the Stripe account
+/// the center had, now awaiting re-verification.
+async fn complete(
+    conn: &mut sqlx::PgConnection,
+    transfer: &OwnershipTransfer,
+) -> Result<Option<String>, AppError> {
+    let owner: Option<(Uuid, Option<String>)> = sqlx::query_as(
+        "SELECT owner_id, stripe_account_id FROM centers WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+    )
+    .bind(transfer.center_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let (owner_id, stripe_account_id) =
+        owner.ok_or_else(|| AppError::NotFound("Center not found".to_owned()))?;
+    if owner_id != transfer.from_profile {
+        return Err(AppError::Conflict(
+            "The center changed owner since this transfer was offered".to_owned(),
+        ));
+    }
+
+    let promoted = sqlx::query(
+        r#"
+        UPDATE tli_pr_ce
+        SET role_in_center = 'owner', granted_capabilities = '{}', revoked_capabilities = '{}'
+        WHERE fk_center = $1 AND fk_profile = $2
+        "#,
+    )
+    .bind(transfer.center_id)
+    .bind(transfer.to_profile)
+    .execute(&mut *conn)
+    .await?;
+    if promoted.rows_affected() == 0 {
+        return Err(AppError::Conflict(
+            "You are no longer a member of this center".to_owned(),
+        ));
+    }
+    sqlx::query(
+        r#"
+        UPDATE tli_pr_ce
+        SET role_in_center = $3, granted_capabilities = '{}', revoked_capabilities = '{}'
+        WHERE fk_center = $1 AND fk_profile = $2
+        "#,
+    )
+    .bind(transfer.center_id)
+    .bind(transfer.from_profile)
+    .bind(PREVIOUS_OWNER_ROLE)
+    .execute(&mut *conn)
+    .await?;
+
+    let stripe_account_id = stripe_account_id.filter(|a| !a.is_empty());
+    sqlx::query(
+        r#"
+        UPDATE centers
+        SET owner_id = $2,
+            stripe_reverification_required_at = CASE WHEN $3 THEN NOW() ELSE NULL END,
+            updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(transfer.center_id)
+    .bind(transfer.to_profile)
+    .bind(stripe_account_id.is_some())
+    .execute(&mut *conn)
+    .await?;
+
+    let vars: [(&str, Value); 2] = [
+        ("center_name", transfer.center_name.as_str().into()),
+        ("owner_name", transfer.recipient_name().into()),
+    ];
+    for user in [transfer.from_profile, transfer.to_profile] {
+        notifications::notify(&mut *conn, user, Kind::OwnershipTransferred, &vars, Some(notifications::TEAM_LINK)).await?;
+    }
+    Ok(stripe_account_id)
+}
+
+/// Transfers of a center, newest first.
+pub async fn list_for_center(
+    pool: &sqlx::PgPool,
+    center_id: Uuid,
+) -> Result<Vec<OwnershipTransfer>, AppError> {
+    let rows = sqlx::query_as::<_, OwnershipTransfer>(&format!(
+        "{TRANSFER_SELECT} WHERE t.center_id = $1 ORDER BY t.created_at DESC LIMIT 100"
+    ))
+    .bind(center_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Open offers made to `user_id`.
+pub async fn list_incoming(
+    pool: &sqlx::PgPool,
+    user_id: Uuid,
+) -> Result<Vec<OwnershipTransfer>, AppError> {
+    let rows = sqlx::query_as::<_, OwnershipTransfer>(&format!(
+        "{TRANSFER_SELECT} WHERE t.to_profile = $1 AND t.status = 'pending' AND t.expires_at > NOW() ORDER BY t.created_at DESC"
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
@@ -0,0 +1,562 @@
+//! Payment provider abstraction.
+//!
+//! Handlers talk to [`PaymentGateway`] (stored as `AppState::payments`)
+//! instead of calling `async-stripe` directly. Production uses
+//! [`StripeGateway`](crate::services::stripe::StripeGateway);
+//! [`InMemoryGateway`] is a deterministic fake that records every call so
+//! money paths can be exercised in tests without network access.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::error::AppError;
+use crate::models::{Money, MoneyError};
+
+/// Errors returned by a payment gateway.
+#[derive(Debug, Clone, thiserror::Error, PartialEq, Eq)]
+pub enum GatewayError {
+    /// The request could not be built (bad amount, currency, account ID…).
+    #[error("Invalid payment request: {0}")]
+    InvalidRequest(String),
+    /// The provider refused the call: nothing was created and retrying the
+    /// same request will not succeed.
+    #[error("Payment declined by provider: {0}")]
+    Declined(String),
+    /// The provider failed or could not be reached; the call may or may not
+    /// have taken effect.
+    #[error("Payment provider error: {0}")]
+    Provider(String),
+}
+
+impl GatewayError {
+    /// Whether the call definitely had no effect at the provider. Anything
+    /// else must be retried with the same idempotency key, not released.
+    pub fn is_declined(&self) -> bool {
+        matches!(self, Self::InvalidRequest(_) | Self::Declined(_))
+    }
+}
+
+impl From<MoneyError> for GatewayError {
+    fn from(err: MoneyError) -> Self {
+        Self::InvalidRequest(err.to_string())
+    }
+}
+
+impl From<GatewayError> for AppError {
+    fn from(err: GatewayError) -> Self {
+        match err {
+            GatewayError::InvalidRequest(msg) => Self::BadRequest(msg),
+            GatewayError::Declined(msg) => Self::BadRequest(format!("Stripe declined the request: {msg}")),
+            GatewayError::Provider(msg) => Self::Internal(format!("Stripe API error: {msg}")),
+        }
+    }
+}
+
+/// A hosted checkout for one booking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckoutRequest {
+    pub amount: Money,
+    pub product_name: String,
+    /// Connected account receiving the funds (destination charge), if any.
+    pub destination_account: Option<String>,
+    /// Platform commission withheld when `destination_account` is set.
+    pub application_fee: Option<Money>,
+    pub success_url: String,
+    pub cancel_url: String,
+    /// Copied to both the session and its PaymentIntent.
+    pub metadata: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckoutSession {
+    pub id: String,
+    pub url: String,
+    /// After this instant the session can no longer be paid.
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A PaymentIntent confirmed client-side with Stripe Elements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentIntentRequest {
+    pub amount: Money,
+    pub description: String,
+    /// Connected account receiving the funds (destination charge), if any.
+    pub destination_account: Option<String>,
+    /// Platform commission withheld when `destination_account` is set.
+    pub application_fee: Option<Money>,
+    pub metadata: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentIntent {
+    pub id: String,
+    /// Secret handed to Stripe.js to confirm the payment.
+    pub client_secret: String,
+    /// Stripe status (`requires_payment_method`, `processing`, `succeeded`…).
+    pub status: String,
+    pub amount: Money,
+}
+
+impl PaymentIntent {
+    /// Whether the diver can still pay it from the page.
+    pub fn is_payable(&self) -> bool {
+        matches!(
+            self.status.as_str(),
+            "requires_payment_method" | "requires_confirmation" | "requires_action"
+        )
+    }
+}
+
+/// A refund of (part of) a PaymentIntent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefundRequest {
+    pub payment_intent_id: String,
+    /// `None` refunds the full amount.
+    pub amount: Option<Money>,
+    /// Reverse the transfer to the connected account and refund the
+    /// application fee proportionally.
+    pub reverse_transfer: bool,
+    pub metadata: HashMap<String, String>,
+    /// Sent as the `Idempotency-Key`, as for transfers.
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Refund {
+    pub id: String,
+    pub status: String,
+}
+
+/// A transfer from the platform balance to a connected account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferRequest {
+    pub amount: Money,
+    pub destination_account: String,
+    pub description: Option<String>,
+    pub metadata: HashMap<String, String>,
+    /// Sent as the `Idempotency-Key`: retrying with the same key returns the
+    /// transfer created by the first attempt instead of moving money twice.
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transfer {
+    pub id: String,
+}
+
+/// An onboarding link for a connected account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountLinkRequest {
+    pub account_id: String,
+    pub refresh_url: String,
+    pub return_url: String,
+}
+
+/// Verification and capability state of a connected account.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct AccountStatus {
+    pub account_id: String,
+    pub charges_enabled: bool,
+    pub payouts_enabled: bool,
+    pub details_submitted: bool,
+    /// Fields Stripe needs before `current_deadline` (`requirements.currently_due`).
+    pub currently_due: Vec<String>,
+    /// Fields whose deadline passed; capabilities are disabled until provided.
+    pub past_due: Vec<String>,
+    pub disabled_reason: Option<String>,
+    pub current_deadline: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Kind of money movement on the platform balance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceEntryKind {
+    Charge,
+    Refund,
+    Transfer,
+}
+
+/// One movement on the platform balance, described with the amounts of its
+/// source object (charge, refund or transfer) rather than the settled amount.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceEntry {
+    /// Balance transaction ID (`txn_…`).
+    pub id: String,
+    pub kind: BalanceEntryKind,
+    /// Source object ID (`ch_…`, `re_…`, `tr_…`).
+    pub source_id: String,
+    /// PaymentIntent of a charge or refund.
+    pub payment_intent_id: Option<String>,
+    /// Always positive.
+    pub amount: Money,
+    /// Application fee taken on a destination charge.
+    pub application_fee: Option<Money>,
+    pub created: chrono::DateTime<chrono::Utc>,
+}
+
+/// Operations the platform needs from its payment provider.
+#[async_trait]
+pub trait PaymentGateway: Send + Sync {
+    async fn create_checkout_session(
+        &self,
+        request: CheckoutRequest,
+    ) -> Result<CheckoutSession, GatewayError>;
+
+    /// Expire an open Checkout Session so it can no longer be paid.
+    async fn expire_checkout_session(&self, session_id: &str) -> Result<(), GatewayError>;
+
+    async fn create_payment_intent(
+        &self,
+        request: PaymentIntentRequest,
+    ) -> Result<PaymentIntent, GatewayError>;
+
+    async fn retrieve_payment_intent(&self, id: &str) -> Result<PaymentIntent, GatewayError>;
+
+    /// Cancel a PaymentIntent that has not been paid yet.
+    async fn cancel_payment_intent(&self, id: &str) -> Result<(), GatewayError>;
+
+    async fn create_refund(&self, request: RefundRequest) -> Result<Refund, GatewayError>;
+
+    async fn create_transfer(&self, request: TransferRequest) -> Result<Transfer, GatewayError>;
+
+    /// Create a connected (Express) account and return its ID.
+    async fn create_connect_account(&self) -> Result<String, GatewayError>;
+
+    /// Create an onboarding link and return its URL.
+    async fn create_account_link(&self, request: AccountLinkRequest)
+        -> Result<String, GatewayError>;
+
+    /// Current status of a connected account.
+    async fn retrieve_account(&self, account_id: &str) -> Result<AccountStatus, GatewayError>;
+
+    /// Create a single-use Express dashboard login link and return its URL.
+    async fn create_login_link(&self, account_id: &str) -> Result<String, GatewayError>;
+
+    /// Charges, refunds and transfers created in `[from, to)`.
+    async fn list_balance_entries(
+        &self,
+        from: chrono::DateTime<chrono::Utc>,
+        to: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<BalanceEntry>, GatewayError>;
+}
+
+// ──────────────────────── In-memory fake ────────────────────────
+
+/// Every call received by an [`InMemoryGateway`], in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GatewayCall {
+    CheckoutSession(CheckoutRequest),
+    ExpireCheckoutSession(String),
+    PaymentIntent(PaymentIntentRequest),
+    RetrievePaymentIntent(String),
+    CancelPaymentIntent(String),
+    Refund(RefundRequest),
+    Transfer(TransferRequest),
+    ConnectAccount,
+    AccountLink(AccountLinkRequest),
+    RetrieveAccount(String),
+    LoginLink(String),
+}
+
+#[derive(Debug, Default)]
+struct FakeState {
+    calls: Vec<GatewayCall>,
+    next_id: u64,
+    failure: Option<GatewayError>,
+    balance: Vec<BalanceEntry>,
+    accounts: HashMap<String, AccountStatus>,
+    payment_intents: HashMap<String, PaymentIntent>,
+    /// Object created for each idempotency key.
+    idempotent: HashMap<String, String>,
+    /// IDs of the refunds and transfers actually created, in order.
+    created_refunds: Vec<String>,
+    created_transfers: Vec<String>,
+    lose_responses: bool,
+}
+
+/// Deterministic gateway for tests.
+///
+/// IDs are sequential per gateway (`cs_test_000001`, `tr_test_000002`…),
+/// [`fail_with`](Self::fail_with) makes every subsequent call fail with a
+/// provider error and [`decline_with`](Self::decline_with) with a decline, [`push_balance_entry`](Self::push_balance_entry)
+/// seeds the balance history returned by `list_balance_entries` and
+/// [`set_account_status`](Self::set_account_status) the connected accounts
+/// returned by `retrieve_account`.
+///
+/// Refunds and transfers honour their idempotency key like Stripe: a repeated
+/// key returns the object created by the first call.
+/// [`lose_responses`](Self::lose_responses) creates them but answers with a
+/// provider error, as when a response times out.
+#[derive(Debug, Default)]
+pub struct InMemoryGateway {
+    state: Mutex<FakeState>,
+}
+
+impl InMemoryGateway {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make every following call fail (`None` restores normal behaviour).
+    pub fn fail_with(&self, message: Option<&str>) {
+        self.lock().failure = message.map(|m| GatewayError::Provider(m.to_owned()));
+    }
+
+    /// Make every following call be declined (`None` restores normal behaviour).
+    pub fn decline_with(&self, message: Option<&str>) {
+        self.lock().failure = message.map(|m| GatewayError::Declined(m.to_owned()));
+    }
+
+    /// Create refunds and transfers but fail the call with a provider error
+    /// (`false` restores normal behaviour).
+    pub fn lose_responses(&self, lose: bool) {
+        self.lock().lose_responses = lose;
+    }
+
+    /// IDs of the refunds created, a repeated idempotency key counting once.
+    pub fn created_refunds(&self) -> Vec<String> {
+        self.lock().created_refunds.clone()
+    }
+
+    /// IDs of the transfers created, a repeated idempotency key counting once.
+    pub fn created_transfers(&self) -> Vec<String> {
+        self.lock().created_transfers.clone()
+    }
+
+    /// Add a movement to the fake balance history.
+    pub fn push_balance_entry(&self, entry: BalanceEntry) {
+        self.lock().balance.push(entry);
+    }
+
+    /// Change the status of a PaymentIntent created by this gateway (e.g.
+    /// `succeeded` once the diver paid).
+    pub fn set_payment_intent_status(&self, id: &str, status: &str) {
+        if let Some(pi) = self.lock().payment_intents.get_mut(id) {
+            pi.status = status.to_owned();
+        }
+    }
+
+    pub fn payment_intents(&self) -> Vec<PaymentIntentRequest> {
+        self.calls()
+            .into_iter()
+            .filter_map(|c| match c {
+                GatewayCall::PaymentIntent(r) => Some(r),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Register the status returned for a connected account.
+    pub fn set_account_status(&self, status: AccountStatus) {
+        self.lock().accounts.insert(status.account_id.clone(), status);
+    }
+
+    /// All calls received so far.
+    pub fn calls(&self) -> Vec<GatewayCall> {
+        self.lock().calls.clone()
+    }
+
+    pub fn checkout_sessions(&self) -> Vec<CheckoutRequest> {
+        self.calls()
+            .into_iter()
+            .filter_map(|c| match c {
+                GatewayCall::CheckoutSession(r) => Some(r),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn refunds(&self) -> Vec<RefundRequest> {
+        self.calls()
+            .into_iter()
+            .filter_map(|c| match c {
+                GatewayCall::Refund(r) => Some(r),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn transfers(&self) -> Vec<TransferRequest> {
+        self.calls()
+            .into_iter()
+            .filter_map(|c| match c {
+                GatewayCall::Transfer(r) => Some(r),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, FakeState> {
+        // A panic in another test thread must not poison every later call.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record the call and allocate the next ID with the given prefix.
+    fn record(&self, call: GatewayCall, prefix: &str) -> Result<String, GatewayError> {
+        let mut state = self.lock();
+        state.calls.push(call);
+        if let Some(ref failure) = state.failure {
+            return Err(failure.clone());
+        }
+        state.next_id += 1;
+        Ok(format!("{prefix}_test_{:06}", state.next_id))
+    }
+
+    /// Like [`record`](Self::record), for a call creating a refund or transfer
+    /// under `key`: a key already used returns the first object's ID.
+    fn record_idempotent(
+        &self,
+        call: GatewayCall,
+        prefix: &str,
+        key: Option<String>,
+    ) -> Result<String, GatewayError> {
+        let mut state = self.lock();
+        state.calls.push(call);
+        if let Some(ref failure) = state.failure {
+            return Err(failure.clone());
+        }
+        let existing = key.as_ref().and_then(|k| state.idempotent.get(k).cloned());
+        let id = match existing {
+            Some(id) => id,
+            None => {
+                state.next_id += 1;
+                let id = format!("{prefix}_test_{:06}", state.next_id);
+                if let Some(key) = key {
+                    state.idempotent.insert(key, id.clone());
+                }
+                match prefix {
+                    "re" => state.created_refunds.push(id.clone()),
+                    _ => state.created_transfers.push(id.clone()),
+                }
+                id
+            }
+        };
+        if state.lose_responses {
+            return Err(GatewayError::Provider("response lost".to_owned()));
+        }
+        Ok(id)
+    }
+}
+
+#[async_trait]
+impl PaymentGateway for InMemoryGateway {
+    async fn create_checkout_session(
+        &self,
+        request: CheckoutRequest,
+    ) -> Result<CheckoutSession, GatewayError> {
+        request.amount.to_stripe_minor(crate::models::RoundingMode::HalfUp)?;
+        let id = self.record(GatewayCall::CheckoutSession(request), "cs")?;
+        Ok(CheckoutSession {
+            url: format!("https://checkout.test/{id}"),
+            id,
+            // Stripe's default lifetime.
+            expires_at: chrono::Utc::now() + chrono::Duration::hours(24),
+        })
+    }
+
+    async fn expire_checkout_session(&self, session_id: &str) -> Result<(), GatewayError> {
+        self.record(GatewayCall::ExpireCheckoutSession(session_id.to_owned()), "cs")?;
+        Ok(())
+    }
+
+    async fn create_payment_intent(
+        &self,
+        request: PaymentIntentRequest,
+    ) -> Result<PaymentIntent, GatewayError> {
+        request.amount.to_stripe_minor(crate::models::RoundingMode::HalfUp)?;
+        let amount = request.amount.clone();
+        let id = self.record(GatewayCall::PaymentIntent(request), "pi")?;
+        let intent = PaymentIntent {
+            client_secret: format!("{id}_secret_test"),
+            id: id.clone(),
+            status: "requires_payment_method".to_owned(),
+            amount,
+        };
+        self.lock().payment_intents.insert(id, intent.clone());
+        Ok(intent)
+    }
+
+    async fn retrieve_payment_intent(&self, id: &str) -> Result<PaymentIntent, GatewayError> {
+        self.record(GatewayCall::RetrievePaymentIntent(id.to_owned()), "pi")?;
+        self.lock()
+            .payment_intents
+            .get(id)
+            .cloned()
+            .ok_or_else(|| GatewayError::Provider(format!("No such payment_intent: {id}")))
+    }
+
+    async fn cancel_payment_intent(&self, id: &str) -> Result<(), GatewayError> {
+        self.record(GatewayCall::CancelPaymentIntent(id.to_owned()), "pi")?;
+        match self.lock().payment_intents.get_mut(id) {
+            Some(pi) if pi.is_payable() => {
+                pi.status = "canceled".to_owned();
+                Ok(())
+            }
+            Some(pi) => Err(GatewayError::Provider(format!(
+                "PaymentIntent {id} cannot be canceled in status {}",
+                pi.status
+            ))),
+            None => Err(GatewayError::Provider(format!("No such payment_intent: {id}"))),
+        }
+    }
+
+    async fn create_refund(&self, request: RefundRequest) -> Result<Refund, GatewayError> {
+        let key = request.idempotency_key.clone();
+        let id = self.record_idempotent(GatewayCall::Refund(request), "re", key)?;
+        Ok(Refund {
+            id,
+            status: "succeeded".to_owned(),
+        })
+    }
+
+    async fn create_transfer(&self, request: TransferRequest) -> Result<Transfer, GatewayError> {
+        let key = request.idempotency_key.clone();
+        let id = self.record_idempotent(GatewayCall::Transfer(request), "tr", key)?;
+        Ok(Transfer { id })
+    }
+
+    async fn create_connect_account(&self) -> Result<String, GatewayError> {
+        self.record(GatewayCall::ConnectAccount, "acct")
+    }
+
+    async fn create_account_link(
+        &self,
+        request: AccountLinkRequest,
+    ) -> Result<String, GatewayError> {
+        let id = self.record(GatewayCall::AccountLink(request), "link")?;
+        Ok(format!("https://connect.test/{id}"))
+    }
+
+    async fn retrieve_account(&self, account_id: &str) -> Result<AccountStatus, GatewayError> {
+        self.record(GatewayCall::RetrieveAccount(account_id.to_owned()), "acct")?;
+        self.lock()
+            .accounts
+            .get(account_id)
+            .cloned()
+            .ok_or_else(|| GatewayError::Provider(format!("No such account: {account_id}")))
+    }
+
+    async fn create_login_link(&self, account_id: &str) -> Result<String, GatewayError> {
+        let id = self.record(GatewayCall::LoginLink(account_id.to_owned()), "login")?;
+        Ok(format!("https://connect.test/express/{id}"))
+    }
+
+    async fn list_balance_entries(
+        &self,
+        from: chrono::DateTime<chrono::Utc>,
+        to: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<BalanceEntry>, GatewayError> {
+        let state = self.lock();
+        if let Some(ref failure) = state.failure {
+            return Err(failure.clone());
+        }
+        Ok(state
+            .balance
+            .iter()
+            .filter(|e| e.created >= from && e.created < to)
+            .cloned()
+            .collect())
+    }
+}
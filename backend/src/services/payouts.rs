@@ -0,0 +1,642 @@
+//! Payouts of the completed-booking balance to centers.
+//!
+//! Only payments the platform collected are paid out: a destination charge
+//! already reached the center's connected account, so it never enters the
+//! balance. Refunds reduce it by the center's share.
+//!
+//! Owners either request a payout by hand (`POST /payouts/request`) or set a
+//! weekly or monthly schedule in `center_payout_schedules`; the payouts job
+//! then transfers the whole available balance once per period, as soon as it
+//! reaches the schedule's minimum. Suspended centers and centers with an open
+//! dispute are never paid out.
+//!
+//! An automatic payout is reserved in `payouts` under its `period_key`
+//! before the transfer is created, and the transfer is sent with the payout
+//! ID as idempotency key: re-running the job skips periods already paid and
+//! resumes a payout interrupted between the transfer and the database
+//! update without moving the money twice. Only a transfer Stripe declines
+//! releases its period; when the outcome is unknown (timeout, 5xx) the
+//! reservation stays and the next run retries it with the same key.
+//! Manual payouts are reserved the same way, and the job retries those left
+//! without a transfer. Balances are per currency, and manual and scheduled
+//! payouts read and reserve them under the same per-center lock
+//! ([`lock_balance`]).
+
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::{Money, RoundingMode};
+use crate::services::{connect, notifications};
+use crate::services::payment_gateway::{PaymentGateway, Transfer, TransferRequest};
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// Stripe dispute statuses that still hold the disputed funds.
+pub const OPEN_DISPUTE_STATUSES: &[&str] = &[
+    "needs_response",
+    "under_review",
+    "warning_needs_response",
+    "warning_under_review",
+];
+
+pub fn is_open_dispute(status: &str) -> bool {
+    OPEN_DISPUTE_STATUSES.contains(&status)
+}
+
+// ──────────────────────── Schedules ────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayoutFrequency {
+    /// Payouts only through `POST /payouts/request`.
+    Manual,
+    Weekly,
+    Monthly,
+}
+
+impl PayoutFrequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Manual => "manual",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "manual" => Some(Self::Manual),
+            "weekly" => Some(Self::Weekly),
+            "monthly" => Some(Self::Monthly),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PayoutSchedule {
+    pub center_id: Uuid,
+    pub frequency: PayoutFrequency,
+    /// ISO weekday of weekly payouts, 1 = Monday.
+    pub weekday: Option<i16>,
+    /// Day of monthly payouts, 1–28.
+    pub day_of_month: Option<i16>,
+    /// No automatic payout while the balance is below this amount.
+    pub minimum_amount: Decimal,
+    pub currency: String,
+    pub updated_at: Option<Timestamp>,
+}
+
+impl PayoutSchedule {
+    /// Schedule of a center that never configured one.
+    pub fn manual(center_id: Uuid) -> Self {
+        Self {
+            center_id,
+            frequency: PayoutFrequency::Manual,
+            weekday: None,
+            day_of_month: None,
+            minimum_amount: Decimal::ZERO,
+            currency: "EUR".to_owned(),
+            updated_at: None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        match self.frequency {
+            PayoutFrequency::Weekly if !matches!(self.weekday, Some(1..=7)) => {
+                return Err(AppError::BadRequest(
+                    "Weekly payouts need a weekday between 1 (Monday) and 7 (Sunday)".to_owned(),
+                ));
+            }
+            PayoutFrequency::Monthly if !matches!(self.day_of_month, Some(1..=28)) => {
+                return Err(AppError::BadRequest(
+                    "Monthly payouts need a day_of_month between 1 and 28".to_owned(),
+                ));
+            }
+            _ => {}
+        }
+        if self.minimum_amount < Decimal::ZERO {
+            return Err(AppError::BadRequest(
+                "minimum_amount cannot be negative".to_owned(),
+            ));
+        }
+        Money::new(self.minimum_amount, &self.currency)
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Period whose payout is due on `today` (`2026-W42` or `2026-10`), or
+    /// `None` before the scheduled day of the period and for manual payouts.
+    ///
+    /// The payout stays due for the rest of the period, so a day missed by
+    /// the job is caught up on the next run.
+    pub fn due_period(&self, today: NaiveDate) -> Option<String> {
+        match self.frequency {
+            PayoutFrequency::Manual => None,
+            PayoutFrequency::Weekly => {
+                let weekday = self.weekday?;
+                if (today.weekday().number_from_monday() as i16) < weekday {
+                    return None;
+                }
+                let week = today.iso_week();
+                Some(format!("{}-W{:02}", week.year(), week.week()))
+            }
+            PayoutFrequency::Monthly => {
+                let day = self.day_of_month?;
+                if (today.day() as i16) < day {
+                    return None;
+                }
+                Some(format!("{}-{:02}", today.year(), today.month()))
+            }
+        }
+    }
+
+    /// Whether an automatic payout of `available` may be made.
+    pub fn meets_minimum(&self, available: Decimal) -> bool {
+        available > Decimal::ZERO && available >= self.minimum_amount
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ScheduleRow {
+    center_id: Uuid,
+    frequency: String,
+    weekday: Option<i16>,
+    day_of_month: Option<i16>,
+    minimum_amount: Decimal,
+    currency: String,
+    updated_at: Timestamp,
+}
+
+impl ScheduleRow {
+    fn into_schedule(self) -> Result<PayoutSchedule, AppError> {
+        let frequency = PayoutFrequency::parse(&self.frequency).ok_or_else(|| {
+            AppError::Internal(format!("Unknown payout frequency '{}'", self.frequency))
+        })?;
+        Ok(PayoutSchedule {
+            center_id: self.center_id,
+            frequency,
+            weekday: self.weekday,
+            day_of_month: self.day_of_month,
+            minimum_amount: self.minimum_amount,
+            currency: self.currency,
+            updated_at: Some(self.updated_at),
+        })
+    }
+}
+
+pub async fn load_schedule<'e, E>(executor: E, center_id: Uuid) -> Result<PayoutSchedule, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let row = sqlx::query_as::<_, ScheduleRow>(
+        r#"
+        SELECT center_id, frequency, weekday, day_of_month, minimum_amount, currency, updated_at
+        FROM center_payout_schedules WHERE center_id = $1
+        "#,
+    )
+    .bind(center_id)
+    .fetch_optional(executor)
+    .await?;
+
+    match row {
+        Some(row) => row.into_schedule(),
+        None => Ok(PayoutSchedule::manual(center_id)),
+    }
+}
+
+pub async fn save_schedule<'e, E>(
+    executor: E,
+    schedule: &PayoutSchedule,
+    updated_by: Uuid,
+) -> Result<PayoutSchedule, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    schedule.validate()?;
+
+    // Only the field matching the frequency is kept.
+    let weekday = schedule.weekday.filter(|_| schedule.frequency == PayoutFrequency::Weekly);
+    let day_of_month = schedule
+        .day_of_month
+        .filter(|_| schedule.frequency == PayoutFrequency::Monthly);
+
+    let row = sqlx::query_as::<_, ScheduleRow>(
+        r#"
+        INSERT INTO center_payout_schedules
+            (center_id, frequency, weekday, day_of_month, minimum_amount, currency, updated_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (center_id) DO UPDATE
+        SET frequency = EXCLUDED.frequency, weekday = EXCLUDED.weekday,
+            day_of_month = EXCLUDED.day_of_month, minimum_amount = EXCLUDED.minimum_amount,
+            currency = EXCLUDED.currency, updated_by = EXCLUDED.updated_by,
+            updated_at = NOW()
+        RETURNING center_id, frequency, weekday, day_of_month, minimum_amount, currency, updated_at
+        "#,
+    )
+    .bind(schedule.center_id)
+    .bind(schedule.frequency.as_str())
+    .bind(weekday)
+    .bind(day_of_month)
+    .bind(schedule.minimum_amount)
+    .bind(schedule.currency.to_uppercase())
+    .bind(updated_by)
+    .fetch_one(executor)
+    .await?;
+
+    row.into_schedule()
+}
+
+// ──────────────────────── Balance and holds ────────────────────────
+
+/// Net revenue of completed bookings in `currency`, less the center's share
+/// of their refunds and everything already paid out or reserved for a
+/// payout in that currency. Read it under [`lock_balance`] when the result
+/// decides a payout.
+///
+/// Bookings paid with a destination charge are left out: Stripe credited
+/// the center's account with them at payment time, and their refunds are
+/// reversed from that account. A refund counts from the moment it is sent
+/// (`processing`), with the commission share the platform refunds too.
+pub async fn available_balance<'e, E>(executor: E, center_id: Uuid, currency: &str) -> Result<Decimal, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let balance = sqlx::query_scalar::<_, Option<Decimal>>(
+        r#"
+        WITH payable AS (
+            SELECT b.id, b.total_price, b.commission_amount
+            FROM bookings b
+            WHERE b.center_id = $1
+              AND b.status = 'completed'
+              AND b.deleted_at IS NULL
+              AND COALESCE(b.currency, 'EUR') = $2
+              AND NOT EXISTS (
+                  SELECT 1 FROM transactions t
+                  WHERE t.booking_id = b.id
+                    AND t.stripe_destination_account IS NOT NULL
+                    AND t.deleted_at IS NULL
+              )
+        )
+        SELECT COALESCE((
+            SELECT SUM(b.total_price - b.commission_amount) FROM payable b
+        ), 0) - COALESCE((
+            SELECT SUM(r.amount * (b.total_price - b.commission_amount) / b.total_price)
+            FROM refunds r
+            JOIN payable b ON b.id = r.booking_id
+            WHERE r.status IN ('processing', 'approved') AND b.total_price > 0
+        ), 0) - COALESCE((
+            SELECT SUM(p.amount)
+            FROM payouts p
+            WHERE p.center_id = $1 AND p.status <> 'failed' AND p.currency = $2
+        ), 0)
+        "#,
+    )
+    .bind(center_id)
+    .bind(currency.to_uppercase())
+    .fetch_one(executor)
+    .await?
+    .unwrap_or(Decimal::ZERO);
+    Ok(balance)
+}
+
+/// Serialize payouts of `center_id` until the transaction ends, so manual and
+/// scheduled payouts cannot both spend the same balance.
+pub async fn lock_balance(conn: &mut sqlx::PgConnection, center_id: Uuid) -> Result<(), AppError> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended('payouts:' || $1::text, 0))")
+        .bind(center_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Why a center cannot be paid out right now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayoutHold {
+    CenterSuspended,
+    OpenDispute,
+}
+
+impl PayoutHold {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::CenterSuspended => "center_suspended",
+            Self::OpenDispute => "open_dispute",
+        }
+    }
+
+    pub fn into_error(self) -> AppError {
+        AppError::Conflict(match self {
+            Self::CenterSuspended => "Payouts are unavailable while the center is suspended",
+            Self::OpenDispute => "Payouts are on hold until the center's open disputes are closed",
+        }
+        .to_owned())
+    }
+}
+
+pub async fn payout_hold<'e, E>(executor: E, center_id: Uuid) -> Result<Option<PayoutHold>, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let (suspended, disputed) = sqlx::query_as::<_, (bool, bool)>(
+        r#"
+        SELECT c.status = 'suspended'::center_status,
+               EXISTS (
+                   SELECT 1 FROM disputes d
+                   WHERE d.center_id = c.id AND d.status = ANY($2)
+               )
+        FROM centers c WHERE c.id = $1
+        "#,
+    )
+    .bind(center_id)
+    .bind(OPEN_DISPUTE_STATUSES)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Center not found".to_owned()))?;
+
+    Ok(if suspended {
+        Some(PayoutHold::CenterSuspended)
+    } else if disputed {
+        Some(PayoutHold::OpenDispute)
+    } else {
+        None
+    })
+}
+
+// ──────────────────────── Scheduled job ────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedCenter {
+    pub center_id: Uuid,
+    pub reason: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PaidCenter {
+    pub center_id: Uuid,
+    pub payout_id: Uuid,
+    pub transfer_id: String,
+    pub amount: Decimal,
+    pub currency: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ScheduledRun {
+    pub paid: Vec<PaidCenter>,
+    pub skipped: Vec<SkippedCenter>,
+    pub failed: Vec<Uuid>,
+}
+
+#[derive(sqlx::FromRow)]
+struct PeriodPayout {
+    id: Uuid,
+    amount: Decimal,
+    currency: String,
+    stripe_transfer_id: Option<String>,
+}
+
+enum Outcome {
+    Paid(PaidCenter),
+    Skipped(&'static str),
+}
+
+/// Create the payouts due on `today` for every center with a schedule, after
+/// retrying manual payouts left unsent. Errors of one center are logged and
+/// do not stop the others.
+pub async fn run_scheduled(
+    pool: &sqlx::PgPool,
+    gateway: &dyn PaymentGateway,
+    today: NaiveDate,
+) -> Result<ScheduledRun, AppError> {
+    let rows = sqlx::query_as::<_, ScheduleRow>(
+        r#"
+        SELECT s.center_id, s.frequency, s.weekday, s.day_of_month,
+               s.minimum_amount, s.currency, s.updated_at
+        FROM center_payout_schedules s
+        JOIN centers c ON c.id = s.center_id
+        WHERE s.frequency <> 'manual' AND c.deleted_at IS NULL
+        ORDER BY s.center_id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut run = ScheduledRun::default();
+    resume_manual(pool, gateway, &mut run).await?;
+    for row in rows {
+        let schedule = row.into_schedule()?;
+        let Some(period) = schedule.due_period(today) else {
+            continue;
+        };
+        match pay_period(pool, gateway, &schedule, &period).await {
+            Ok(Outcome::Paid(paid)) => run.paid.push(paid),
+            Ok(Outcome::Skipped(reason)) => run.skipped.push(SkippedCenter {
+                center_id: schedule.center_id,
+                reason,
+            }),
+            Err(e) => {
+                tracing::error!(
+                    center_id = %schedule.center_id,
+                    period = %period,
+                    error = ?e,
+                    "Scheduled payout failed"
+                );
+                run.failed.push(schedule.center_id);
+            }
+        }
+    }
+    Ok(run)
+}
+
+async fn pay_period(
+    pool: &sqlx::PgPool,
+    gateway: &dyn PaymentGateway,
+    schedule: &PayoutSchedule,
+    period: &str,
+) -> Result<Outcome, AppError> {
+    let center_id = schedule.center_id;
+
+    if let Some(hold) = payout_hold(pool, center_id).await? {
+        return Ok(Outcome::Skipped(hold.as_str()));
+    }
+    let destination = match connect::load(pool, center_id).await?.payout_destination() {
+        Ok(account) => account,
+        Err(_) => return Ok(Outcome::Skipped("not_connected")),
+    };
+
+    let mut tx = pool.begin().await?;
+    lock_balance(&mut tx, center_id).await?;
+    let existing = sqlx::query_as::<_, PeriodPayout>(
+        r#"
+        SELECT id, amount, currency, stripe_transfer_id
+        FROM payouts WHERE center_id = $1 AND period_key = $2
+        "#,
+    )
+    .bind(center_id)
+    .bind(period)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let payout = match existing {
+        Some(p) if p.stripe_transfer_id.is_some() => return Ok(Outcome::Skipped("already_paid")),
+        // Reserved by an interrupted run: retry with the same idempotency key.
+        Some(p) => p,
+        None => {
+            let available = available_balance(&mut *tx, center_id, &schedule.currency).await?;
+            let amount = Money::new(available, &schedule.currency)
+                .map_err(|e| AppError::Internal(e.to_string()))?
+                .round(RoundingMode::Down);
+            if !schedule.meets_minimum(amount.amount()) {
+                return Ok(Outcome::Skipped("below_minimum"));
+            }
+
+            sqlx::query_as::<_, PeriodPayout>(
+                r#"
+                INSERT INTO payouts (center_id, amount, currency, period_key)
+                VALUES ($1, $2, $3, $4)
+                RETURNING id, amount, currency, stripe_transfer_id
+                "#,
+            )
+            .bind(center_id)
+            .bind(amount.amount())
+            .bind(amount.currency())
+            .bind(period)
+            .fetch_one(&mut *tx)
+            .await?
+        }
+    };
+    tx.commit().await?;
+
+    let amount = Money::new(payout.amount, &payout.currency)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    let transfer = send_transfer(pool, gateway, center_id, payout.id, &amount, destination, Some(period)).await?;
+
+    tracing::info!(
+        center_id = %center_id,
+        payout_id = %payout.id,
+        transfer_id = %transfer.id,
+        period = %period,
+        "Scheduled payout created"
+    );
+
+    Ok(Outcome::Paid(PaidCenter {
+        center_id,
+        payout_id: payout.id,
+        transfer_id: transfer.id,
+        amount: payout.amount,
+        currency: payout.currency,
+    }))
+}
+
+/// Send the transfer of payout `payout_id`, reserved beforehand, with the
+/// payout ID as idempotency key, and record it. A declined transfer marks
+/// the payout failed (releasing its period); any other error leaves the
+/// reservation for a retry with the same key. `period` is that of a
+/// scheduled payout.
+pub async fn send_transfer(
+    pool: &sqlx::PgPool,
+    gateway: &dyn PaymentGateway,
+    center_id: Uuid,
+    payout_id: Uuid,
+    amount: &Money,
+    destination: String,
+    period: Option<&str>,
+) -> Result<Transfer, AppError> {
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("center_id".to_owned(), center_id.to_string());
+    metadata.insert("payout_id".to_owned(), payout_id.to_string());
+    let description = match period {
+        Some(period) => {
+            metadata.insert("period".to_owned(), period.to_owned());
+            format!("Scheduled payout {period} for center {center_id}")
+        }
+        None => format!("Payout for center {center_id}"),
+    };
+
+    let transfer = gateway
+        .create_transfer(TransferRequest {
+            amount: amount.clone(),
+            destination_account: destination,
+            description: Some(description),
+            metadata,
+            idempotency_key: Some(format!("payout-{payout_id}")),
+        })
+        .await;
+
+    let transfer = match transfer {
+        Ok(transfer) => transfer,
+        Err(e) if e.is_declined() => {
+            sqlx::query(
+                "UPDATE payouts SET status = 'failed', period_key = NULL WHERE id = $1",
+            )
+            .bind(payout_id)
+            .execute(pool)
+            .await?;
+            return Err(e.into());
+        }
+        // The transfer may exist at Stripe: keep the reservation and its key.
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("UPDATE payouts SET stripe_transfer_id = $2 WHERE id = $1")
+        .bind(payout_id)
+        .bind(&transfer.id)
+        .execute(&mut *tx)
+        .await?;
+    notifications::payout_sent(&mut tx, center_id, amount.amount(), amount.currency()).await?;
+    tx.commit().await?;
+    Ok(transfer)
+}
+
+#[derive(sqlx::FromRow)]
+struct PendingPayout {
+    id: Uuid,
+    center_id: Uuid,
+    amount: Decimal,
+    currency: String,
+}
+
+/// Retry manual payouts whose transfer outcome was left unknown, with their
+/// original idempotency key.
+async fn resume_manual(pool: &sqlx::PgPool, gateway: &dyn PaymentGateway, run: &mut ScheduledRun) -> Result<(), AppError> {
+    let pending = sqlx::query_as::<_, PendingPayout>(
+        r#"
+        SELECT id, center_id, amount, currency
+        FROM payouts
+        WHERE period_key IS NULL AND stripe_transfer_id IS NULL AND status = 'created'
+          AND created_at < NOW() - INTERVAL '10 minutes'
+        ORDER BY created_at
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for payout in pending {
+        let result = async {
+            let destination = connect::load(pool, payout.center_id).await?.payout_destination()?;
+            let amount = Money::new(payout.amount, &payout.currency)
+                .map_err(|e| AppError::Internal(e.to_string()))?;
+            send_transfer(pool, gateway, payout.center_id, payout.id, &amount, destination, None).await
+        }
+        .await;
+        match result {
+            Ok(transfer) => run.paid.push(PaidCenter {
+                center_id: payout.center_id,
+                payout_id: payout.id,
+                transfer_id: transfer.id,
+                amount: payout.amount,
+                currency: payout.currency,
+            }),
+            Err(e) => {
+                tracing::error!(payout_id = %payout.id, error = ?e, "Retrying manual payout failed");
+                run.failed.push(payout.center_id);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,207 @@
+//! Minimal PDF writer for text-only A4 documents (receipts, invoices).
+//!
+//! Uses the standard Helvetica fonts with `WinAnsiEncoding`, so no font file
+//! is embedded and the binary stays small for the serverless deployment.
+//! Supports headings, plain lines and two-column label/value rows with
+//! automatic page breaks.
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const VALUE_COLUMN: f32 = 300.0;
+
+/// Builder for a simple text PDF.
+pub struct PdfBuilder {
+    pages: Vec<String>,
+    current: String,
+    y: f32,
+}
+
+impl Default for PdfBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PdfBuilder {
+    pub fn new() -> Self {
+        Self {
+            pages: Vec::new(),
+            current: String::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    /// Bold title line.
+    pub fn heading(&mut self, text: &str) -> &mut Self {
+        self.text_at(MARGIN, text, 16.0, true);
+        self.advance(24.0)
+    }
+
+    /// Bold section title.
+    pub fn section(&mut self, text: &str) -> &mut Self {
+        self.advance(6.0);
+        self.text_at(MARGIN, text, 11.0, true);
+        self.advance(16.0)
+    }
+
+    /// Regular line of text.
+    pub fn line(&mut self, text: &str) -> &mut Self {
+        self.text_at(MARGIN, text, 10.0, false);
+        self.advance(14.0)
+    }
+
+    /// Label on the left, value in the second column.
+    pub fn row(&mut self, label: &str, value: &str) -> &mut Self {
+        self.text_at(MARGIN, label, 10.0, false);
+        self.text_at(VALUE_COLUMN, value, 10.0, false);
+        self.advance(14.0)
+    }
+
+    /// Label and value both in bold (totals).
+    pub fn total_row(&mut self, label: &str, value: &str) -> &mut Self {
+        self.text_at(MARGIN, label, 10.0, true);
+        self.text_at(VALUE_COLUMN, value, 10.0, true);
+        self.advance(14.0)
+    }
+
+    /// Empty vertical space.
+    pub fn blank(&mut self) -> &mut Self {
+        self.advance(10.0)
+    }
+
+    fn advance(&mut self, dy: f32) -> &mut Self {
+        self.y -= dy;
+        if self.y < MARGIN {
+            self.pages.push(std::mem::take(&mut self.current));
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+        self
+    }
+
+    fn text_at(&mut self, x: f32, text: &str, size: f32, bold: bool) {
+        let font = if bold { "F2" } else { "F1" };
+        self.current.push_str(&format!(
+            "BT /{font} {size} Tf {x:.1} {y:.1} Td ({}) Tj ET\n",
+            escape_text(text),
+            y = self.y,
+        ));
+    }
+
+    /// Serialize the document into PDF bytes.
+    pub fn finish(&mut self) -> Vec<u8> {
+        if !self.current.is_empty() || self.pages.is_empty() {
+            self.pages.push(std::mem::take(&mut self.current));
+        }
+
+        // Object layout: 1 catalog, 2 page tree, 3-4 fonts, then
+        // (page, content stream) pairs starting at object 5.
+        let page_ids: Vec<usize> = (0..self.pages.len()).map(|i| 5 + i * 2).collect();
+        let mut objects: Vec<Vec<u8>> = Vec::new();
+
+        objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+        let kids: Vec<String> = page_ids.iter().map(|id| format!("{id} 0 R")).collect();
+        objects.push(
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                page_ids.len()
+            )
+            .into_bytes(),
+        );
+        objects.push(
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_vec(),
+        );
+        objects.push(
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+                .to_vec(),
+        );
+
+        for (page_id, content) in page_ids.iter().zip(&self.pages) {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+                     /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    page_id + 1
+                )
+                .into_bytes(),
+            );
+            let stream = encode_win_ansi(content);
+            let mut obj = format!("<< /Length {} >>\nstream\n", stream.len()).into_bytes();
+            obj.extend_from_slice(&stream);
+            obj.extend_from_slice(b"\nendstream");
+            objects.push(obj);
+        }
+
+        let mut out: Vec<u8> = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, obj) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            out.extend_from_slice(obj);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref_offset = out.len();
+        out.extend_from_slice(format!("xref\n0 {}\n", objects.len() + 1).as_bytes());
+        out.extend_from_slice(b"0000000000 65535 f \n");
+        for offset in offsets {
+            out.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+        }
+        out.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n",
+                objects.len() + 1
+            )
+            .as_bytes(),
+        );
+        out
+    }
+}
+
+/// Escape PDF string delimiters.
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Encode a content stream to WinAnsi (CP1252). Characters outside the
+/// encoding are replaced with `?`.
+fn encode_win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            '\u{0000}'..='\u{007F}' => c as u8,
+            '\u{00A0}'..='\u{00FF}' => c as u32 as u8,
+            '€' => 0x80,
+            '‚' => 0x82,
+            '„' => 0x84,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            'Œ' => 0x8C,
+            'œ' => 0x9C,
+            'Š' => 0x8A,
+            'š' => 0x9A,
+            'Ž' => 0x8E,
+            'ž' => 0x9E,
+            'Ÿ' => 0x9F,
+            _ => b'?',
+        })
+        .collect()
+}
@@ -0,0 +1,239 @@
+//! What center members may do.
+//!
+//! Every center handler declares the [`Capability`] it needs and checks it
+//! with `require_center_capability`. A member's capabilities are the
+//! defaults of their `role_in_center`, plus the capabilities granted to them
+//! and minus the ones revoked (`tli_pr_ce`, migration 032). Owners always
+//! hold every capability, so a center can never lock itself out. A member
+//! cannot hand out capabilities they do not hold, whether as overrides or
+//! through a role.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// Roles a member can be given besides `owner`.
+pub const ASSIGNABLE_ROLES: [&str; 3] = ["manager", "employee", "staff"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Bookings list, calendar, booking details and receipts.
+    ViewBookings,
+    /// Confirm and cancel bookings on behalf of the center.
+    ManageBookings,
+    ManageServices,
+    /// Blocked dates and holidays.
+    ManageSchedule,
+    /// Instructors, their bios and working hours.
+    ManageStaff,
+    /// Team members, their roles and permissions.
+    ManageMembers,
+    /// KPIs, payments, revenue, commissions, invoices and statements.
+    ViewFinance,
+    /// Payout requests and schedule, Stripe account.
+    ManagePayouts,
+    /// Center profile and dive reminder settings.
+    ManageCenter,
+    /// Outgoing webhooks.
+    ManageIntegrations,
+    /// Replies to reviews.
+    ManageReviews,
+    /// Contact inbox.
+    ManageMessages,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 12] = [
+        Capability::ViewBookings,
+        Capability::ManageBookings,
+        Capability::ManageServices,
+        Capability::ManageSchedule,
+        Capability::ManageStaff,
+        Capability::ManageMembers,
+        Capability::ViewFinance,
+        Capability::ManagePayouts,
+        Capability::ManageCenter,
+        Capability::ManageIntegrations,
+        Capability::ManageReviews,
+        Capability::ManageMessages,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ViewBookings => "view_bookings",
+            Self::ManageBookings => "manage_bookings",
+            Self::ManageServices => "manage_services",
+            Self::ManageSchedule => "manage_schedule",
+            Self::ManageStaff => "manage_staff",
+            Self::ManageMembers => "manage_members",
+            Self::ViewFinance => "view_finance",
+            Self::ManagePayouts => "manage_payouts",
+            Self::ManageCenter => "manage_center",
+            Self::ManageIntegrations => "manage_integrations",
+            Self::ManageReviews => "manage_reviews",
+            Self::ManageMessages => "manage_messages",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == value)
+    }
+
+    /// Capabilities of `role` before per-member overrides. Unknown roles
+    /// have none.
+    pub fn defaults(role: &str) -> &'static [Capability] {
+        use Capability::*;
+        match role {
+            "owner" => &Self::ALL,
+            "manager" => &[
+                ViewBookings,
+                ManageBookings,
+                ManageServices,
+                ManageSchedule,
+                ManageStaff,
+                ViewFinance,
+                ManageCenter,
+                ManageReviews,
+                ManageMessages,
+            ],
+            "employee" => &[ViewBookings, ManageBookings, ManageMessages],
+            "staff" => &[ViewBookings],
+            _ => &[],
+        }
+    }
+}
+
+/// Parse a list of capability names, rejecting unknown ones.
+pub fn parse_list(values: &[String]) -> Result<Vec<Capability>, AppError> {
+    let mut capabilities = values
+        .iter()
+        .map(|v| {
+            let v = v.trim();
+            Capability::parse(v).ok_or_else(|| {
+                AppError::BadRequest(format!("Unknown capability '{v}'"))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    capabilities.sort();
+    capabilities.dedup();
+    Ok(capabilities)
+}
+
+/// A member's role and overrides.
+#[derive(Debug, Clone, Default, sqlx::FromRow)]
+pub struct MemberPermissions {
+    pub role_in_center: String,
+    pub granted_capabilities: Vec<String>,
+    pub revoked_capabilities: Vec<String>,
+}
+
+impl MemberPermissions {
+    pub fn is_owner(&self) -> bool {
+        self.role_in_center == "owner"
+    }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        let name = capability.as_str();
+        if self.is_owner() {
+            return true;
+        }
+        if self.revoked_capabilities.iter().any(|c| c == name) {
+            return false;
+        }
+        Capability::defaults(&self.role_in_center).contains(&capability)
+            || self.granted_capabilities.iter().any(|c| c == name)
+    }
+
+    pub fn effective(&self) -> Vec<Capability> {
+        Capability::ALL.into_iter().filter(|c| self.allows(*c)).collect()
+    }
+
+    /// Whether this member may give someone `role`, directly or through an
+    /// invitation: they must hold every default capability of that role.
+    pub fn check_can_assign(&self, role: &str) -> Result<(), AppError> {
+        match Capability::defaults(role).iter().find(|c| !self.allows(**c)) {
+            Some(missing) => Err(AppError::BadRequest(format!(
+                "You cannot assign the '{}' role, which includes '{}' you do not have.",
+                role,
+                missing.as_str()
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Role defaults, for the team settings screen.
+pub fn matrix() -> serde_json::Value {
+    let roles = ["owner"].into_iter().chain(ASSIGNABLE_ROLES);
+    roles
+        .map(|role| (role.to_owned(), serde_json::json!(Capability::defaults(role))))
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+pub async fn load(
+    pool: &sqlx::PgPool,
+    user_id: Uuid,
+    center_id: Uuid,
+) -> Result<Option<MemberPermissions>, AppError> {
+    let permissions = sqlx::query_as::<_, MemberPermissions>(
+        r#"
+        SELECT role_in_center, granted_capabilities, revoked_capabilities
+        FROM tli_pr_ce WHERE fk_profile = $1 AND fk_center = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(center_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(permissions)
+}
+
+/// Replace the overrides of member `member_id` (a `tli_pr_ce` row).
+/// Grants that the role already has and revocations it does not have are
+/// dropped, so the stored overrides stay minimal.
+pub async fn set_overrides<'e, E>(
+    executor: E,
+    center_id: Uuid,
+    member_id: Uuid,
+    role: &str,
+    granted: &[Capability],
+    revoked: &[Capability],
+) -> Result<MemberPermissions, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    if granted.iter().any(|c| revoked.contains(c)) {
+        return Err(AppError::BadRequest(
+            "A capability cannot be both granted and revoked".to_owned(),
+        ));
+    }
+    let defaults = Capability::defaults(role);
+    let granted: Vec<&str> = granted
+        .iter()
+        .filter(|c| !defaults.contains(c))
+        .map(Capability::as_str)
+        .collect();
+    let revoked: Vec<&str> = revoked
+        .iter()
+        .filter(|c| defaults.contains(c))
+        .map(Capability::as_str)
+        .collect();
+
+    sqlx::query_as::<_, MemberPermissions>(
+        r#"
+        UPDATE tli_pr_ce SET granted_capabilities = $3, revoked_capabilities = $4
+        WHERE id = $1 AND fk_center = $2
+        RETURNING role_in_center, granted_capabilities, revoked_capabilities
+        "#,
+    )
+    .bind(member_id)
+    .bind(center_id)
+    .bind(&granted)
+    .bind(&revoked)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Member not found".to_owned()))
+}
@@ -0,0 +1,319 @@
+//! GDPR data export and account erasure.
+//!
+//! [`export`] gathers what the platform holds about a user (profile,
+//! bookings, reviews, coupons, notifications, receipts, contact messages,
+//! center memberships) into one JSON document. [`erase`] anonymizes the
+//! account: the profile's names and contact details, booking notes, review
+//! comments, notifications, contact messages and their replies, and email
+//! copies are cleared,
+//! while bookings, invoices, refunds and payouts keep their amounts for
+//! accounting (the invoices' frozen buyer details included, as the law
+//! requires). The Supabase auth user is anonymized and signed out rather
+//! than deleted, profiles referencing it. The audit log, which is
+//! append-only, never holds names, contact details or message bodies (see
+//! `audit::strip_personal`).
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// Version of the export document, bumped when its layout changes.
+pub const EXPORT_FORMAT: &str = "evidive-export/1";
+
+/// What replaces erased free text.
+pub const ERASED: &str = "[erased]";
+
+/// File name offered for the export download.
+pub fn export_filename(user_id: Uuid, date: chrono::NaiveDate) -> String {
+    format!("evidive-export-{}-{}.json", date.format("%Y-%m-%d"), user_id.simple())
+}
+
+/// Sections of the export and the query listing each, ordered oldest first.
+/// `$1` is the user id.
+const EXPORT_SECTIONS: [(&str, &str); 7] = [
+    (
+        "bookings",
+        r#"SELECT b.id, c.name AS center_name, s.name AS service_name, b.booking_date, b.time_slot,
+                  b.participants, b.unit_price, b.total_price, b.currency, b.status::text AS status,
+                  b.client_note, b.created_at, b.confirmed_at, b.cancelled_at, b.completed_at
+           FROM bookings b
+           LEFT JOIN centers c ON c.id = b.center_id
+           LEFT JOIN services s ON s.id = b.service_id
+           WHERE b.client_id = $1 AND b.deleted_at IS NULL
+           ORDER BY b.created_at"#,
+    ),
+    (
+        "reviews",
+        r#"SELECT r.id, r.booking_id, c.name AS center_name, r.rating, r.comment, r.reply,
+                  r.replied_at, r.is_published, r.created_at
+           FROM reviews r
+           LEFT JOIN centers c ON c.id = r.center_id
+           WHERE r.client_id = $1 AND r.deleted_at IS NULL
+           ORDER BY r.created_at"#,
+    ),
+    (
+        "coupons",
+        r#"SELECT code, discount_type, discount_value, currency, min_amount, max_uses, used_count,
+                  is_active, expires_at, created_at
+           FROM coupons WHERE user_id = $1
+           ORDER BY created_at"#,
+    ),
+    (
+        "notifications",
+        r#"SELECT id, kind, title, body, link, is_read, created_at
+           FROM notifications WHERE user_id = $1
+           ORDER BY created_at"#,
+    ),
+    (
+        "receipts",
+        r#"SELECT invoice_number, booking_id, currency, amount, issued_at
+           FROM invoices WHERE client_id = $1 AND kind = 'receipt'
+           ORDER BY issued_at"#,
+    ),
+    (
+        "contact_messages",
+        r#"SELECT m.reference, c.name AS center_name, m.name, m.email, m.subject, m.message,
+                  m.status, m.created_at,
+                  COALESCE((SELECT jsonb_agg(jsonb_build_object('body', r.body, 'created_at', r.created_at)
+                                             ORDER BY r.created_at)
+                            FROM contact_replies r WHERE r.message_id = m.id), '[]'::jsonb) AS replies
+           FROM contact_messages m
+           LEFT JOIN centers c ON c.id = m.center_id
+           WHERE lower(m.email) = (SELECT lower(u.email) FROM auth.users u WHERE u.id = $1)
+           ORDER BY m.created_at"#,
+    ),
+    (
+        "center_memberships",
+        r#"SELECT c.name AS center_name, c.slug AS center_slug, m.role_in_center, m.created_at
+           FROM tli_pr_ce m
+           JOIN centers c ON c.id = m.fk_center
+           WHERE m.fk_profile = $1
+           ORDER BY m.created_at"#,
+    ),
+];
+
+/// Everything the platform holds about `user_id`, as one document.
+pub async fn export(pool: &sqlx::PgPool, user_id: Uuid) -> Result<Value, AppError> {
+    let profile: Value = sqlx::query_scalar(
+        r#"
+        SELECT to_jsonb(t) FROM (
+            SELECT p.id, u.email, p.role::text AS role, p.first_name, p.last_name, p.display_name,
+                   p.avatar_url, p.phone, p.preferred_locale, p.created_at, p.updated_at
+            FROM profiles p
+            LEFT JOIN auth.users u ON u.id = p.id
+            WHERE p.id = $1 AND p.erased_at IS NULL
+        ) t
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("User not found".to_owned()))?;
+
+    let mut document = json!({
+        "format": EXPORT_FORMAT,
+        "generated_at": chrono::Utc::now(),
+        "profile": profile,
+    });
+    for (section, query) in EXPORT_SECTIONS {
+        let rows: Value = sqlx::query_scalar(&format!(
+            "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]'::jsonb) FROM ({query}) t"
+        ))
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+        document[section] = rows;
+    }
+    Ok(document)
+}
+
+/// Whether an account can be erased: centers must be handed over first, and
+/// upcoming bookings cancelled (paid ones go through the refund flow).
+pub fn check_erasable(owned_centers: i64, upcoming_bookings: i64) -> Result<(), AppError> {
+    if owned_centers > 0 {
+        return Err(AppError::Conflict(
+            "Transfer the ownership of your centers before deleting your account".to_owned(),
+        ));
+    }
+    if upcoming_bookings > 0 {
+        return Err(AppError::Conflict(
+            "Cancel your upcoming bookings before deleting your account".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// Rows anonymized or removed by [`erase`].
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ErasureSummary {
+    pub bookings: u64,
+    pub reviews: u64,
+    pub coupons: u64,
+    pub notifications: u64,
+    pub contact_messages: u64,
+    pub contact_replies: u64,
+    pub emails: u64,
+    pub invitations: u64,
+    pub memberships: u64,
+}
+
+/// Anonymize the account of `user_id`. Financial records stay.
+pub async fn erase(conn: &mut sqlx::PgConnection, user_id: Uuid) -> Result<ErasureSummary, AppError> {
+    let email: Option<Option<String>> = sqlx::query_scalar(
+        r#"
+        SELECT u.email::text FROM profiles p
+        LEFT JOIN auth.users u ON u.id = p.id
+        WHERE p.id = $1 AND p.erased_at IS NULL
+        FOR UPDATE OF p
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(email) = email else {
+        return Err(AppError::NotFound("User not found".to_owned()));
+    };
+
+    let (owned_centers, upcoming_bookings): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM centers WHERE owner_id = $1 AND deleted_at IS NULL),
+            (SELECT COUNT(*) FROM bookings
+             WHERE client_id = $1 AND status IN ('pending', 'confirmed')
+               AND booking_date >= CURRENT_DATE AND deleted_at IS NULL)
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    check_erasable(owned_centers, upcoming_bookings)?;
+
+    let mut summary = ErasureSummary::default();
+
+    sqlx::query(
+        r#"
+        UPDATE profiles
+        SET first_name = NULL, last_name = NULL, display_name = NULL, avatar_url = NULL,
+            phone = NULL, erased_at = NOW(), deleted_at = COALESCE(deleted_at, NOW()), updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+
+    // The sign-in identity goes too: Supabase keeps the email, phone and
+    // provider details on `auth.users` and `auth.identities`. The row itself
+    // stays, as profiles reference it.
+    sqlx::query(
+        r#"
+        UPDATE auth.users
+        SET email = NULL, phone = NULL, encrypted_password = '', raw_user_meta_data = '{}'::jsonb,
+            banned_until = 'infinity', deleted_at = COALESCE(deleted_at, NOW()), updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query("DELETE FROM auth.identities WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM auth.sessions WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    summary.bookings = sqlx::query(
+        "UPDATE bookings SET client_note = NULL, center_note = NULL, updated_at = NOW() WHERE client_id = $1",
+    )
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    // The rating still counts in the center's average, without the text.
+    summary.reviews = sqlx::query("UPDATE reviews SET comment = NULL, updated_at = NOW() WHERE client_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+    summary.coupons = sqlx::query(
+        "UPDATE coupons SET is_active = false, updated_at = NOW() WHERE user_id = $1 AND is_active",
+    )
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    summary.notifications = sqlx::query("DELETE FROM notifications WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+    summary.memberships = sqlx::query("DELETE FROM tli_pr_ce WHERE fk_profile = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+    // Unsent copies are dropped from the queue; sent ones keep their status.
+    summary.emails = sqlx::query(
+        r#"
+        UPDATE email_outbox
+        SET to_address = '', reply_to = NULL, subject = $3, body = $3,
+            status = CASE WHEN status IN ('pending', 'sending') THEN 'dead' ELSE status END,
+            last_error = CASE WHEN status IN ('pending', 'sending') THEN 'Recipient erased' ELSE last_error END,
+            updated_at = NOW()
+        WHERE profile_id = $1 OR ($2::text IS NOT NULL AND lower(to_address) = lower($2))
+        "#,
+    )
+    .bind(user_id)
+    .bind(email.as_deref())
+    .bind(ERASED)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    if let Some(ref email) = email {
+        // Replies quote the message and address the sender by name.
+        summary.contact_replies = sqlx::query(
+            r#"
+            UPDATE contact_replies SET body = $2
+            WHERE message_id IN (SELECT id FROM contact_messages WHERE lower(email) = lower($1))
+            "#,
+        )
+        .bind(email)
+        .bind(ERASED)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+        summary.contact_messages = sqlx::query(
+            r#"
+            UPDATE contact_messages
+            SET name = $2, email = '', subject = $2, message = $2, ip_hash = NULL, user_agent = NULL,
+                status = 'closed', closed_at = COALESCE(closed_at, NOW()), updated_at = NOW()
+            WHERE lower(email) = lower($1)
+            "#,
+        )
+        .bind(email)
+        .bind(ERASED)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+        summary.invitations = sqlx::query("DELETE FROM center_invitations WHERE lower(email) = lower($1)")
+            .bind(email)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+    }
+
+    Ok(summary)
+}
@@ -0,0 +1,596 @@
+//! Reconciliation of local payment records against the payment provider.
+//!
+//! A run compares `transactions`, approved `refunds`, `payouts` and paid
+//! bookings for a period with the provider's balance history and stores
+//! every mismatch in `reconciliation_discrepancies`.
+//!
+//! Both sides are loaded with a one-day margin around the period so that a
+//! record created a few seconds before midnight on one side and after it on
+//! the other still matches. Only records inside the period are reported.
+
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::services::payment_gateway::{BalanceEntry, BalanceEntryKind, PaymentGateway};
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// Margin loaded on each side of the period to absorb clock skew.
+const MATCH_MARGIN_HOURS: i64 = 24;
+
+/// What went wrong for one record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscrepancyKind {
+    /// Booking is confirmed/paid/completed but has no `transactions` row.
+    BookingWithoutTransaction,
+    /// Stripe charge with no matching `transactions` row.
+    MissingTransaction,
+    /// `transactions` row with no matching Stripe charge.
+    TransactionNotInStripe,
+    AmountMismatch,
+    /// Stored `platform_fee` differs from the Stripe application fee.
+    FeeMismatch,
+    RefundMissingLocally,
+    RefundNotInStripe,
+    RefundAmountMismatch,
+    PayoutMissingLocally,
+    PayoutNotInStripe,
+    PayoutAmountMismatch,
+}
+
+impl DiscrepancyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BookingWithoutTransaction => "booking_without_transaction",
+            Self::MissingTransaction => "missing_transaction",
+            Self::TransactionNotInStripe => "transaction_not_in_stripe",
+            Self::AmountMismatch => "amount_mismatch",
+            Self::FeeMismatch => "fee_mismatch",
+            Self::RefundMissingLocally => "refund_missing_locally",
+            Self::RefundNotInStripe => "refund_not_in_stripe",
+            Self::RefundAmountMismatch => "refund_amount_mismatch",
+            Self::PayoutMissingLocally => "payout_missing_locally",
+            Self::PayoutNotInStripe => "payout_not_in_stripe",
+            Self::PayoutAmountMismatch => "payout_amount_mismatch",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Discrepancy {
+    pub kind: DiscrepancyKind,
+    pub booking_id: Option<Uuid>,
+    /// Local row ID (transaction, refund, payout or booking).
+    pub local_ref: Option<String>,
+    /// Stripe object ID (`ch_…`, `re_…`, `tr_…`).
+    pub stripe_ref: Option<String>,
+    pub local_amount: Option<Decimal>,
+    pub stripe_amount: Option<Decimal>,
+    pub currency: Option<String>,
+    pub details: String,
+}
+
+impl Discrepancy {
+    fn new(kind: DiscrepancyKind, details: impl Into<String>) -> Self {
+        Self {
+            kind,
+            booking_id: None,
+            local_ref: None,
+            stripe_ref: None,
+            local_amount: None,
+            stripe_amount: None,
+            currency: None,
+            details: details.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct LocalTransaction {
+    pub id: Uuid,
+    pub booking_id: Uuid,
+    pub payment_intent_id: Option<String>,
+    pub amount: Decimal,
+    pub platform_fee: Decimal,
+    pub currency: String,
+    pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct LocalRefund {
+    pub id: Uuid,
+    pub booking_id: Uuid,
+    pub stripe_refund_id: Option<String>,
+    pub amount: Decimal,
+    pub currency: String,
+    pub processed_at: Timestamp,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct LocalPayout {
+    pub id: Uuid,
+    pub center_id: Uuid,
+    pub stripe_transfer_id: Option<String>,
+    pub amount: Decimal,
+    pub currency: String,
+    pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UnpaidBooking {
+    pub booking_id: Uuid,
+    pub total_price: Decimal,
+    pub currency: String,
+}
+
+/// Local side of a reconciliation, loaded with the matching margin.
+#[derive(Debug, Clone, Default)]
+pub struct LocalLedger {
+    pub transactions: Vec<LocalTransaction>,
+    pub refunds: Vec<LocalRefund>,
+    pub payouts: Vec<LocalPayout>,
+    /// Paid bookings of the period (no margin) without a transaction.
+    pub unpaid_bookings: Vec<UnpaidBooking>,
+}
+
+impl LocalLedger {
+    pub fn len(&self) -> usize {
+        self.transactions.len() + self.refunds.len() + self.payouts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn in_period(at: Timestamp, from: Timestamp, to: Timestamp) -> bool {
+    at >= from && at < to
+}
+
+/// Compare both sides and list every discrepancy for records created in
+/// `[from, to)`. Pure: no I/O, so it can be tested with hand-built data.
+pub fn compare(
+    from: Timestamp,
+    to: Timestamp,
+    local: &LocalLedger,
+    stripe: &[BalanceEntry],
+) -> Vec<Discrepancy> {
+    let mut out = Vec::new();
+
+    for b in &local.unpaid_bookings {
+        out.push(Discrepancy {
+            booking_id: Some(b.booking_id),
+            local_ref: Some(b.booking_id.to_string()),
+            local_amount: Some(b.total_price),
+            currency: Some(b.currency.clone()),
+            ..Discrepancy::new(
+                DiscrepancyKind::BookingWithoutTransaction,
+                "Booking is marked as paid but no transaction was recorded",
+            )
+        });
+    }
+
+    let of_kind = |kind: BalanceEntryKind| stripe.iter().filter(move |e| e.kind == kind);
+
+    // ─── Charges ↔ transactions ───
+
+    let tx_by_pi: HashMap<&str, &LocalTransaction> = local
+        .transactions
+        .iter()
+        .filter_map(|t| t.payment_intent_id.as_deref().map(|pi| (pi, t)))
+        .collect();
+    let charge_by_pi: HashMap<&str, &BalanceEntry> = of_kind(BalanceEntryKind::Charge)
+        .filter_map(|e| e.payment_intent_id.as_deref().map(|pi| (pi, e)))
+        .collect();
+
+    for charge in of_kind(BalanceEntryKind::Charge).filter(|e| in_period(e.created, from, to)) {
+        let stripe_base = Discrepancy {
+            stripe_ref: Some(charge.source_id.clone()),
+            stripe_amount: Some(charge.amount.amount()),
+            currency: Some(charge.amount.currency().to_owned()),
+            ..Discrepancy::new(DiscrepancyKind::MissingTransaction, "")
+        };
+        let Some(tx) = charge
+            .payment_intent_id
+            .as_deref()
+            .and_then(|pi| tx_by_pi.get(pi))
+        else {
+            out.push(Discrepancy {
+                details: format!(
+                    "Stripe charge for PaymentIntent {} has no local transaction",
+                    charge.payment_intent_id.as_deref().unwrap_or("(none)")
+                ),
+                ..stripe_base
+            });
+            continue;
+        };
+
+        let local_base = Discrepancy {
+            booking_id: Some(tx.booking_id),
+            local_ref: Some(tx.id.to_string()),
+            ..stripe_base
+        };
+        if tx.amount != charge.amount.amount() || tx.currency != charge.amount.currency() {
+            out.push(Discrepancy {
+                kind: DiscrepancyKind::AmountMismatch,
+                local_amount: Some(tx.amount),
+                details: format!(
+                    "Local amount {} {} vs Stripe {}",
+                    tx.amount, tx.currency, charge.amount
+                ),
+                ..local_base.clone()
+            });
+        }
+        if let Some(ref fee) = charge.application_fee {
+            if tx.platform_fee != fee.amount() {
+                out.push(Discrepancy {
+                    kind: DiscrepancyKind::FeeMismatch,
+                    local_amount: Some(tx.platform_fee),
+                    stripe_amount: Some(fee.amount()),
+                    details: format!(
+                        "Local platform fee {} vs Stripe application fee {}",
+                        tx.platform_fee, fee
+                    ),
+                    ..local_base
+                });
+            }
+        }
+    }
+
+    for tx in local
+        .transactions
+        .iter()
+        .filter(|t| in_period(t.created_at, from, to))
+    {
+        let found = tx
+            .payment_intent_id
+            .as_deref()
+            .is_some_and(|pi| charge_by_pi.contains_key(pi));
+        if !found {
+            out.push(Discrepancy {
+                booking_id: Some(tx.booking_id),
+                local_ref: Some(tx.id.to_string()),
+                local_amount: Some(tx.amount),
+                currency: Some(tx.currency.clone()),
+                ..Discrepancy::new(
+                    DiscrepancyKind::TransactionNotInStripe,
+                    match tx.payment_intent_id {
+                        Some(ref pi) => format!("No Stripe charge for PaymentIntent {pi}"),
+                        None => "Transaction has no PaymentIntent".to_owned(),
+                    },
+                )
+            });
+        }
+    }
+
+    // ─── Refunds ───
+
+    let refund_by_id: HashMap<&str, &LocalRefund> = local
+        .refunds
+        .iter()
+        .filter_map(|r| r.stripe_refund_id.as_deref().map(|id| (id, r)))
+        .collect();
+    let stripe_refunds: HashMap<&str, &BalanceEntry> = of_kind(BalanceEntryKind::Refund)
+        .map(|e| (e.source_id.as_str(), e))
+        .collect();
+
+    for refund in of_kind(BalanceEntryKind::Refund).filter(|e| in_period(e.created, from, to)) {
+        let booking_id = refund
+            .payment_intent_id
+            .as_deref()
+            .and_then(|pi| tx_by_pi.get(pi))
+            .map(|t| t.booking_id);
+        let base = Discrepancy {
+            booking_id,
+            stripe_ref: Some(refund.source_id.clone()),
+            stripe_amount: Some(refund.amount.amount()),
+            currency: Some(refund.amount.currency().to_owned()),
+            ..Discrepancy::new(
+                DiscrepancyKind::RefundMissingLocally,
+                "Stripe refund has no approved local refund",
+            )
+        };
+        match refund_by_id.get(refund.source_id.as_str()) {
+            None => out.push(base),
+            Some(local_refund) if local_refund.amount != refund.amount.amount() => {
+                out.push(Discrepancy {
+                    kind: DiscrepancyKind::RefundAmountMismatch,
+                    booking_id: Some(local_refund.booking_id),
+                    local_ref: Some(local_refund.id.to_string()),
+                    local_amount: Some(local_refund.amount),
+                    details: format!(
+                        "Local refund {} {} vs Stripe {}",
+                        local_refund.amount, local_refund.currency, refund.amount
+                    ),
+                    ..base
+                });
+            }
+            Some(_) => {}
+        }
+    }
+
+    for r in local
+        .refunds
+        .iter()
+        .filter(|r| in_period(r.processed_at, from, to))
+    {
+        let found = r
+            .stripe_refund_id
+            .as_deref()
+            .is_some_and(|id| stripe_refunds.contains_key(id));
+        if !found {
+            out.push(Discrepancy {
+                booking_id: Some(r.booking_id),
+                local_ref: Some(r.id.to_string()),
+                stripe_ref: r.stripe_refund_id.clone(),
+                local_amount: Some(r.amount),
+                currency: Some(r.currency.clone()),
+                ..Discrepancy::new(
+                    DiscrepancyKind::RefundNotInStripe,
+                    if r.stripe_refund_id.is_some() {
+                        "Approved refund not found in Stripe"
+                    } else {
+                        "Refund approved without a Stripe refund"
+                    },
+                )
+            });
+        }
+    }
+
+    // ─── Transfers ↔ payouts ───
+
+    let payout_by_id: HashMap<&str, &LocalPayout> = local
+        .payouts
+        .iter()
+        .filter_map(|p| p.stripe_transfer_id.as_deref().map(|id| (id, p)))
+        .collect();
+    let stripe_transfers: HashMap<&str, &BalanceEntry> = of_kind(BalanceEntryKind::Transfer)
+        .map(|e| (e.source_id.as_str(), e))
+        .collect();
+
+    for transfer in of_kind(BalanceEntryKind::Transfer).filter(|e| in_period(e.created, from, to))
+    {
+        let base = Discrepancy {
+            stripe_ref: Some(transfer.source_id.clone()),
+            stripe_amount: Some(transfer.amount.amount()),
+            currency: Some(transfer.amount.currency().to_owned()),
+            ..Discrepancy::new(
+                DiscrepancyKind::PayoutMissingLocally,
+                "Stripe transfer has no local payout record",
+            )
+        };
+        match payout_by_id.get(transfer.source_id.as_str()) {
+            None => out.push(base),
+            Some(p) if p.amount != transfer.amount.amount() => out.push(Discrepancy {
+                kind: DiscrepancyKind::PayoutAmountMismatch,
+                local_ref: Some(p.id.to_string()),
+                local_amount: Some(p.amount),
+                details: format!(
+                    "Local payout {} {} to center {} vs Stripe {}",
+                    p.amount, p.currency, p.center_id, transfer.amount
+                ),
+                ..base
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for p in local
+        .payouts
+        .iter()
+        .filter(|p| in_period(p.created_at, from, to))
+    {
+        let found = p
+            .stripe_transfer_id
+            .as_deref()
+            .is_some_and(|id| stripe_transfers.contains_key(id));
+        if !found {
+            out.push(Discrepancy {
+                local_ref: Some(p.id.to_string()),
+                stripe_ref: p.stripe_transfer_id.clone(),
+                local_amount: Some(p.amount),
+                currency: Some(p.currency.clone()),
+                ..Discrepancy::new(
+                    DiscrepancyKind::PayoutNotInStripe,
+                    format!("Payout to center {} not found in Stripe", p.center_id),
+                )
+            });
+        }
+    }
+
+    out
+}
+
+/// Load the local side for `[from, to)` plus the matching margin.
+pub async fn load_local(
+    pool: &sqlx::PgPool,
+    from: Timestamp,
+    to: Timestamp,
+) -> Result<LocalLedger, AppError> {
+    let margin = chrono::Duration::hours(MATCH_MARGIN_HOURS);
+    let (wide_from, wide_to) = (from - margin, to + margin);
+
+    let transactions = sqlx::query_as::<_, LocalTransaction>(
+        r#"
+        SELECT id, booking_id, stripe_payment_intent_id AS payment_intent_id, amount,
+               platform_fee, COALESCE(currency, 'EUR') AS currency, created_at
+        FROM transactions
+        WHERE deleted_at IS NULL AND created_at >= $1 AND created_at < $2
+        "#,
+    )
+    .bind(wide_from)
+    .bind(wide_to)
+    .fetch_all(pool)
+    .await?;
+
+    let refunds = sqlx::query_as::<_, LocalRefund>(
+        r#"
+        SELECT id, booking_id, stripe_refund_id, amount, currency, updated_at AS processed_at
+        FROM refunds
+        WHERE status = 'approved' AND updated_at >= $1 AND updated_at < $2
+        "#,
+    )
+    .bind(wide_from)
+    .bind(wide_to)
+    .fetch_all(pool)
+    .await?;
+
+    let payouts = sqlx::query_as::<_, LocalPayout>(
+        r#"
+        SELECT id, center_id, stripe_transfer_id, amount, currency, created_at
+        FROM payouts
+        WHERE status <> 'failed' AND created_at >= $1 AND created_at < $2
+        "#,
+    )
+    .bind(wide_from)
+    .bind(wide_to)
+    .fetch_all(pool)
+    .await?;
+
+    let unpaid_bookings = sqlx::query_as::<_, UnpaidBooking>(
+        r#"
+        SELECT b.id AS booking_id, b.total_price, COALESCE(b.currency, 'EUR') AS currency
+        FROM bookings b
+        WHERE b.status IN ('confirmed', 'paid', 'completed')
+          AND b.deleted_at IS NULL
+          AND COALESCE(b.confirmed_at, b.updated_at) >= $1
+          AND COALESCE(b.confirmed_at, b.updated_at) < $2
+          AND NOT EXISTS (
+              SELECT 1 FROM transactions t
+              WHERE t.booking_id = b.id AND t.deleted_at IS NULL
+          )
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(LocalLedger {
+        transactions,
+        refunds,
+        payouts,
+        unpaid_bookings,
+    })
+}
+
+/// Result of a completed run.
+#[derive(Debug, Serialize)]
+pub struct RunSummary {
+    pub run_id: Uuid,
+    pub period_start: Timestamp,
+    pub period_end: Timestamp,
+    pub stripe_entries: i32,
+    pub local_entries: i32,
+    pub discrepancy_count: i32,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+/// Reconcile `[from, to)` and persist the report. A provider failure marks
+/// the run as `failed` and is returned as an error.
+pub async fn run(
+    pool: &sqlx::PgPool,
+    gateway: &dyn PaymentGateway,
+    from: Timestamp,
+    to: Timestamp,
+    started_by: Option<Uuid>,
+) -> Result<RunSummary, AppError> {
+    if to <= from {
+        return Err(AppError::BadRequest(
+            "Reconciliation period end must be after its start".to_owned(),
+        ));
+    }
+
+    let run_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO reconciliation_runs (period_start, period_end, started_by) VALUES ($1, $2, $3) RETURNING id",
+    )
+    .bind(from)
+    .bind(to)
+    .bind(started_by)
+    .fetch_one(pool)
+    .await?;
+
+    let margin = chrono::Duration::hours(MATCH_MARGIN_HOURS);
+    let stripe = match gateway.list_balance_entries(from - margin, to + margin).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            sqlx::query(
+                "UPDATE reconciliation_runs SET status = 'failed', error = $1, completed_at = NOW() WHERE id = $2",
+            )
+            .bind(e.to_string())
+            .bind(run_id)
+            .execute(pool)
+            .await?;
+            return Err(e.into());
+        }
+    };
+
+    let local = load_local(pool, from, to).await?;
+    let discrepancies = compare(from, to, &local, &stripe);
+
+    let stripe_entries = i32::try_from(stripe.len()).unwrap_or(i32::MAX);
+    let local_entries = i32::try_from(local.len()).unwrap_or(i32::MAX);
+    let discrepancy_count = i32::try_from(discrepancies.len()).unwrap_or(i32::MAX);
+
+    let mut tx = pool.begin().await?;
+    for d in &discrepancies {
+        sqlx::query(
+            r#"
+            INSERT INTO reconciliation_discrepancies (
+                run_id, kind, booking_id, local_ref, stripe_ref,
+                local_amount, stripe_amount, currency, details
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(run_id)
+        .bind(d.kind.as_str())
+        .bind(d.booking_id)
+        .bind(&d.local_ref)
+        .bind(&d.stripe_ref)
+        .bind(d.local_amount)
+        .bind(d.stripe_amount)
+        .bind(&d.currency)
+        .bind(&d.details)
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query(
+        r#"
+        UPDATE reconciliation_runs
+        SET status = 'completed', stripe_entries = $1, local_entries = $2,
+            discrepancy_count = $3, completed_at = NOW()
+        WHERE id = $4
+        "#,
+    )
+    .bind(stripe_entries)
+    .bind(local_entries)
+    .bind(discrepancy_count)
+    .bind(run_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        run_id = %run_id,
+        stripe_entries,
+        local_entries,
+        discrepancy_count,
+        "Reconciliation completed"
+    );
+
+    Ok(RunSummary {
+        run_id,
+        period_start: from,
+        period_end: to,
+        stripe_entries,
+        local_entries,
+        discrepancy_count,
+        discrepancies,
+    })
+}
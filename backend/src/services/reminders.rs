@@ -0,0 +1,362 @@
+//! Dive reminders and pre-dive checklists.
+//!
+//! The reminders job sends every confirmed booking one email and one in-app
+//! notification `hours_before` its dive starts. The start is `booking_date +
+//! time_slot` in the center's timezone, converted by PostgreSQL
+//! (`AT TIME ZONE`), so daylight saving changes are handled there. A
+//! reminder is claimed by setting `bookings.reminder_sent_at` in the same
+//! transaction that enqueues it: concurrent or repeated runs never send a
+//! booking's reminder twice, and a failed run sends nothing.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::services::email::{Template, Value, Vars};
+use crate::services::notifications::{self, Kind};
+use crate::services::outbox;
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+pub const DEFAULT_HOURS_BEFORE: i16 = 24;
+
+/// Reminders can be sent up to a week before the dive.
+pub const MAX_HOURS_BEFORE: i16 = 168;
+
+pub const MAX_CHECKLIST_ITEMS: usize = 20;
+pub const MAX_CHECKLIST_ITEM_LEN: usize = 200;
+
+/// Bookings reminded per job run.
+pub const BATCH: i64 = 200;
+
+/// Shown for a requirement the service does not set.
+const NO_REQUIREMENT: &str = "—";
+
+// ──────────────────────── Settings ────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReminderSettings {
+    pub center_id: Uuid,
+    pub enabled: bool,
+    pub hours_before: i16,
+    pub timezone: String,
+    pub checklist: Vec<String>,
+    pub updated_at: Option<Timestamp>,
+}
+
+impl ReminderSettings {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if !(1..=MAX_HOURS_BEFORE).contains(&self.hours_before) {
+            return Err(AppError::BadRequest(format!(
+                "hours_before must be between 1 and {MAX_HOURS_BEFORE}"
+            )));
+        }
+        if self.timezone.trim().is_empty() {
+            return Err(AppError::BadRequest("timezone is required".to_owned()));
+        }
+        if self.checklist.len() > MAX_CHECKLIST_ITEMS {
+            return Err(AppError::BadRequest(format!(
+                "A checklist has at most {MAX_CHECKLIST_ITEMS} items"
+            )));
+        }
+        if self.checklist.iter().any(|item| item.chars().count() > MAX_CHECKLIST_ITEM_LEN) {
+            return Err(AppError::BadRequest(format!(
+                "Checklist items are at most {MAX_CHECKLIST_ITEM_LEN} characters"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Trim checklist items and drop the empty ones.
+pub fn clean_checklist(items: Vec<String>) -> Vec<String> {
+    items
+        .into_iter()
+        .map(|item| item.trim().to_owned())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[derive(sqlx::FromRow)]
+struct SettingsRow {
+    center_id: Uuid,
+    enabled: Option<bool>,
+    hours_before: Option<i16>,
+    timezone: String,
+    checklist: Option<Vec<String>>,
+    updated_at: Option<Timestamp>,
+}
+
+impl From<SettingsRow> for ReminderSettings {
+    fn from(row: SettingsRow) -> Self {
+        Self {
+            center_id: row.center_id,
+            enabled: row.enabled.unwrap_or(true),
+            hours_before: row.hours_before.unwrap_or(DEFAULT_HOURS_BEFORE),
+            timezone: row.timezone,
+            checklist: row.checklist.unwrap_or_default(),
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// Settings of a center; centers that never saved any get the defaults.
+pub async fn load_settings<'e, E>(executor: E, center_id: Uuid) -> Result<ReminderSettings, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let row = sqlx::query_as::<_, SettingsRow>(
+        r#"
+        SELECT c.id AS center_id, r.enabled, r.hours_before, c.timezone, r.checklist, r.updated_at
+        FROM centers c
+        LEFT JOIN center_reminder_settings r ON r.center_id = c.id
+        WHERE c.id = $1 AND c.deleted_at IS NULL
+        "#,
+    )
+    .bind(center_id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Center not found".to_owned()))?;
+    Ok(row.into())
+}
+
+/// Save the settings and the center's timezone, which must be a name known
+/// to PostgreSQL (`Europe/Paris`, `Indian/Maldives`…).
+pub async fn save_settings(
+    pool: &sqlx::PgPool,
+    settings: &ReminderSettings,
+    updated_by: Uuid,
+) -> Result<ReminderSettings, AppError> {
+    settings.validate()?;
+
+    let known: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM pg_timezone_names WHERE name = $1)")
+            .bind(settings.timezone.trim())
+            .fetch_one(pool)
+            .await?;
+    if !known {
+        return Err(AppError::BadRequest(format!(
+            "Unknown timezone '{}'",
+            settings.timezone.trim()
+        )));
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("UPDATE centers SET timezone = $2, updated_at = NOW() WHERE id = $1")
+        .bind(settings.center_id)
+        .bind(settings.timezone.trim())
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        r#"
+        INSERT INTO center_reminder_settings (center_id, enabled, hours_before, checklist, updated_by)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (center_id) DO UPDATE
+        SET enabled = EXCLUDED.enabled, hours_before = EXCLUDED.hours_before,
+            checklist = EXCLUDED.checklist, updated_by = EXCLUDED.updated_by,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(settings.center_id)
+    .bind(settings.enabled)
+    .bind(settings.hours_before)
+    .bind(&settings.checklist)
+    .bind(updated_by)
+    .execute(&mut *tx)
+    .await?;
+    let saved = load_settings(&mut *tx, settings.center_id).await?;
+    tx.commit().await?;
+    Ok(saved)
+}
+
+// ──────────────────────── Content ────────────────────────
+
+/// Meeting point from the center address: `address, postal_code city, country`.
+pub fn meeting_point(
+    address: Option<&str>,
+    postal_code: Option<&str>,
+    city: Option<&str>,
+    country: Option<&str>,
+) -> String {
+    fn non_empty(s: Option<&str>) -> Option<&str> {
+        s.map(str::trim).filter(|s| !s.is_empty())
+    }
+    let locality = [non_empty(postal_code), non_empty(city)]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let parts: Vec<&str> = [non_empty(address), non_empty(Some(&locality)), non_empty(country)]
+        .into_iter()
+        .flatten()
+        .collect();
+    if parts.is_empty() {
+        NO_REQUIREMENT.to_owned()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Center checklist items, appended after the built-in items of the
+/// template (each on its own `  - ` line).
+pub fn checklist_lines(items: &[String]) -> String {
+    items.iter().map(|item| format!("\n  - {item}")).collect()
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ReminderDetails {
+    pub client_id: Uuid,
+    pub center_name: String,
+    pub service_name: Option<String>,
+    pub booking_date: NaiveDate,
+    pub time_slot: String,
+    pub participants: i32,
+    pub address: Option<String>,
+    pub postal_code: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub min_certification: Option<String>,
+    pub min_dives: Option<i32>,
+    pub checklist: Vec<String>,
+}
+
+impl ReminderDetails {
+    pub fn vars(&self) -> Vars {
+        vec![
+            ("center_name", self.center_name.clone().into()),
+            ("service_name", self.service_name.clone().unwrap_or_default().into()),
+            ("booking_date", Value::Date(self.booking_date)),
+            ("time_slot", self.time_slot.clone().into()),
+            ("participants", Value::Number(self.participants.into())),
+            (
+                "meeting_point",
+                meeting_point(
+                    self.address.as_deref(),
+                    self.postal_code.as_deref(),
+                    self.city.as_deref(),
+                    self.country.as_deref(),
+                )
+                .into(),
+            ),
+            (
+                "min_certification",
+                self.min_certification
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|c| !c.is_empty())
+                    .unwrap_or(NO_REQUIREMENT)
+                    .into(),
+            ),
+            (
+                "min_dives",
+                match self.min_dives {
+                    Some(n) if n > 0 => Value::Number(n.into()),
+                    _ => NO_REQUIREMENT.into(),
+                },
+            ),
+            ("checklist", checklist_lines(&self.checklist).into()),
+        ]
+    }
+}
+
+// ──────────────────────── Job ────────────────────────
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ReminderRun {
+    pub sent: usize,
+    /// Claimed by another run in the meantime.
+    pub skipped: usize,
+    pub failed: Vec<Uuid>,
+}
+
+/// Send the reminders of every confirmed booking whose dive starts within
+/// its center's reminder window. Errors of one booking are logged and do
+/// not stop the others; the booking is retried on the next run.
+pub async fn send_due(pool: &sqlx::PgPool) -> Result<ReminderRun, AppError> {
+    let due: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT b.id
+        FROM bookings b
+        JOIN centers c ON c.id = b.center_id
+        LEFT JOIN center_reminder_settings r ON r.center_id = b.center_id
+        WHERE b.status = 'confirmed' AND b.deleted_at IS NULL AND b.reminder_sent_at IS NULL
+          AND COALESCE(r.enabled, true)
+          -- Narrow to the index before converting timezones.
+          AND b.booking_date BETWEEN CURRENT_DATE - 1 AND CURRENT_DATE + 8
+          AND (b.booking_date + b.time_slot) AT TIME ZONE c.timezone > NOW()
+          AND (b.booking_date + b.time_slot) AT TIME ZONE c.timezone
+              <= NOW() + make_interval(hours => COALESCE(r.hours_before, $2)::int)
+        ORDER BY b.booking_date, b.time_slot
+        LIMIT $1
+        "#,
+    )
+    .bind(BATCH)
+    .bind(DEFAULT_HOURS_BEFORE)
+    .fetch_all(pool)
+    .await?;
+
+    let mut run = ReminderRun::default();
+    for booking_id in due {
+        match remind(pool, booking_id).await {
+            Ok(true) => run.sent += 1,
+            Ok(false) => run.skipped += 1,
+            Err(e) => {
+                tracing::error!(booking_id = %booking_id, error = ?e, "Dive reminder failed");
+                run.failed.push(booking_id);
+            }
+        }
+    }
+    Ok(run)
+}
+
+/// Claim and send one reminder. Returns `false` when the booking was
+/// already reminded or is no longer confirmed.
+async fn remind(pool: &sqlx::PgPool, booking_id: Uuid) -> Result<bool, AppError> {
+    let mut tx = pool.begin().await?;
+    let claimed = sqlx::query(
+        r#"
+        UPDATE bookings SET reminder_sent_at = NOW()
+        WHERE id = $1 AND status = 'confirmed' AND reminder_sent_at IS NULL
+        "#,
+    )
+    .bind(booking_id)
+    .execute(&mut *tx)
+    .await?;
+    if claimed.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    let details = sqlx::query_as::<_, ReminderDetails>(
+        r#"
+        SELECT b.client_id, c.name AS center_name, s.name AS service_name,
+               b.booking_date, to_char(b.time_slot, 'HH24:MI') AS time_slot, b.participants,
+               c.address, c.postal_code, c.city, c.country,
+               s.min_certification, s.min_dives,
+               COALESCE(r.checklist, '{}') AS checklist
+        FROM bookings b
+        JOIN centers c ON c.id = b.center_id
+        LEFT JOIN services s ON s.id = b.service_id
+        LEFT JOIN center_reminder_settings r ON r.center_id = b.center_id
+        WHERE b.id = $1
+        "#,
+    )
+    .bind(booking_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let vars = details.vars();
+    notifications::notify(
+        &mut tx,
+        details.client_id,
+        Kind::DiveReminder,
+        &vars,
+        Some(&notifications::booking_link(booking_id)),
+    )
+    .await?;
+    outbox::enqueue_template(&mut tx, details.client_id, Template::DiveReminder, vars).await?;
+    tx.commit().await?;
+
+    tracing::info!(booking_id = %booking_id, "Dive reminder sent");
+    Ok(true)
+}
@@ -0,0 +1,571 @@
+//! Monthly commission statements for centers.
+//!
+//! A statement lists, for one center and one calendar month, every payment
+//! (gross, commission, net), every approved refund and every payout, with the
+//! opening and closing balance owed to the center. Statements are frozen:
+//! the first generation stores a JSON snapshot in `commission_statements`
+//! and later downloads are rendered from it.
+//!
+//! A statement is in a single currency: a center whose movements are in
+//! several currencies cannot be issued one, rather than have amounts of
+//! different currencies added up.
+
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PaymentLine {
+    pub booking_id: Uuid,
+    pub booking_date: NaiveDate,
+    pub service_name: Option<String>,
+    pub client_name: Option<String>,
+    pub payment_reference: Option<String>,
+    pub paid_at: Timestamp,
+    pub gross: Decimal,
+    pub commission: Decimal,
+    pub net: Decimal,
+    pub currency: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RefundLine {
+    pub refund_id: Uuid,
+    pub booking_id: Uuid,
+    pub refunded_at: Timestamp,
+    pub amount: Decimal,
+    /// Share of the platform commission returned with the refund.
+    pub commission_refunded: Decimal,
+    /// Amount deducted from the center's balance.
+    pub net: Decimal,
+    pub currency: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PayoutLine {
+    pub payout_id: Uuid,
+    pub stripe_transfer_id: Option<String>,
+    pub paid_at: Timestamp,
+    pub amount: Decimal,
+    pub currency: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatementTotals {
+    pub opening_balance: Decimal,
+    pub gross: Decimal,
+    pub commission: Decimal,
+    pub net: Decimal,
+    pub refunds: Decimal,
+    pub payouts: Decimal,
+    pub closing_balance: Decimal,
+}
+
+/// Full content of a statement, as stored in the snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Statement {
+    pub center_id: Uuid,
+    pub center_name: String,
+    pub period_start: NaiveDate,
+    /// Exclusive.
+    pub period_end: NaiveDate,
+    pub currency: String,
+    pub payments: Vec<PaymentLine>,
+    pub refunds: Vec<RefundLine>,
+    pub payouts: Vec<PayoutLine>,
+    pub totals: StatementTotals,
+}
+
+/// A stored statement row.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StatementRecord {
+    pub id: Uuid,
+    pub center_id: Uuid,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub currency: String,
+    pub opening_balance: Decimal,
+    pub gross_total: Decimal,
+    pub commission_total: Decimal,
+    pub refund_total: Decimal,
+    pub payout_total: Decimal,
+    pub closing_balance: Decimal,
+    pub generated_at: Timestamp,
+}
+
+impl StatementTotals {
+    /// Sum the lines of a period on top of the opening balance.
+    pub fn compute(
+        opening_balance: Decimal,
+        payments: &[PaymentLine],
+        refunds: &[RefundLine],
+        payouts: &[PayoutLine],
+    ) -> Self {
+        let gross = payments.iter().map(|p| p.gross).sum();
+        let commission = payments.iter().map(|p| p.commission).sum();
+        let net: Decimal = payments.iter().map(|p| p.net).sum();
+        let refunds: Decimal = refunds.iter().map(|r| r.net).sum();
+        let payouts: Decimal = payouts.iter().map(|p| p.amount).sum();
+        Self {
+            opening_balance,
+            gross,
+            commission,
+            net,
+            refunds,
+            payouts,
+            closing_balance: opening_balance + net - refunds - payouts,
+        }
+    }
+}
+
+/// Currency of a statement: the one of every movement of the center up to
+/// the end of the period, or the center's currency when there is none.
+/// Movements in several currencies are rejected.
+pub fn statement_currency(center_currency: &str, movement_currencies: &[String]) -> Result<String, AppError> {
+    match movement_currencies {
+        [] => Ok(center_currency.to_owned()),
+        [currency] => Ok(currency.clone()),
+        several => Err(AppError::Conflict(format!(
+            "This center has movements in several currencies ({}); a statement cannot add them up",
+            several.join(", ")
+        ))),
+    }
+}
+
+/// First day of `year-month` and of the following month.
+pub fn month_bounds(year: i32, month: u32) -> Result<(NaiveDate, NaiveDate), AppError> {
+    let start = NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or_else(|| AppError::BadRequest("Invalid statement period".to_owned()))?;
+    let end = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    }
+    .ok_or_else(|| AppError::BadRequest("Invalid statement period".to_owned()))?;
+    Ok((start, end))
+}
+
+fn at_midnight(date: NaiveDate) -> Timestamp {
+    date.and_time(chrono::NaiveTime::MIN).and_utc()
+}
+
+/// Center balance (net payments − refunds − payouts) before `before`.
+async fn balance_before(
+    pool: &sqlx::PgPool,
+    center_id: Uuid,
+    before: Timestamp,
+) -> Result<Decimal, AppError> {
+    let balance: Option<Decimal> = sqlx::query_scalar(
+        r#"
+        SELECT COALESCE((
+            SELECT SUM(t.vendor_amount)
+            FROM transactions t
+            INNER JOIN bookings b ON b.id = t.booking_id
+            WHERE b.center_id = $1 AND t.deleted_at IS NULL
+              AND t.status IN ('succeeded', 'refunded') AND t.created_at < $2
+        ), 0) - COALESCE((
+            SELECT SUM(ROUND(r.amount * t.vendor_amount / NULLIF(t.amount, 0), 2))
+            FROM refunds r
+            INNER JOIN bookings b ON b.id = r.booking_id
+            INNER JOIN transactions t
+                ON t.stripe_payment_intent_id = r.stripe_payment_intent_id AND t.deleted_at IS NULL
+            WHERE b.center_id = $1 AND r.status = 'approved' AND r.processed_at < $2
+        ), 0) - COALESCE((
+            SELECT SUM(p.amount)
+            FROM payouts p
+            WHERE p.center_id = $1 AND p.status <> 'failed' AND p.created_at < $2
+        ), 0)
+        "#,
+    )
+    .bind(center_id)
+    .bind(before)
+    .fetch_one(pool)
+    .await?;
+    Ok(balance.unwrap_or(Decimal::ZERO))
+}
+
+/// Build a statement from live data, without storing it.
+pub async fn generate(
+    pool: &sqlx::PgPool,
+    center_id: Uuid,
+    period_start: NaiveDate,
+    period_end: NaiveDate,
+) -> Result<Statement, AppError> {
+    let (center_name, center_currency): (String, String) = sqlx::query_as(
+        "SELECT name, COALESCE(currency, 'EUR') FROM centers WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(center_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Center not found".to_owned()))?;
+
+    let (from, to) = (at_midnight(period_start), at_midnight(period_end));
+
+    // Every movement counted, the opening balance included
+    let currencies: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT COALESCE(t.currency, 'EUR') FROM transactions t
+        INNER JOIN bookings b ON b.id = t.booking_id
+        WHERE b.center_id = $1 AND t.deleted_at IS NULL
+          AND t.status IN ('succeeded', 'refunded') AND t.created_at < $2
+        UNION
+        SELECT r.currency FROM refunds r
+        INNER JOIN bookings b ON b.id = r.booking_id
+        WHERE b.center_id = $1 AND r.status = 'approved' AND r.processed_at < $2
+        UNION
+        SELECT p.currency FROM payouts p
+        WHERE p.center_id = $1 AND p.status <> 'failed' AND p.created_at < $2
+        ORDER BY 1
+        "#,
+    )
+    .bind(center_id)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+    let currency = statement_currency(&center_currency, &currencies)?;
+
+    let payments = sqlx::query_as::<_, PaymentLine>(
+        r#"
+        SELECT b.id AS booking_id, b.booking_date, s.name AS service_name,
+               NULLIF(TRIM(CONCAT_WS(' ', p.first_name, p.last_name)), '') AS client_name,
+               t.stripe_payment_intent_id AS payment_reference, t.created_at AS paid_at,
+               t.amount AS gross, t.platform_fee AS commission, t.vendor_amount AS net,
+               COALESCE(t.currency, 'EUR') AS currency
+        FROM transactions t
+        INNER JOIN bookings b ON b.id = t.booking_id
+        LEFT JOIN services s ON s.id = b.service_id
+        LEFT JOIN profiles p ON p.id = b.client_id
+        WHERE b.center_id = $1 AND t.deleted_at IS NULL
+          AND t.status IN ('succeeded', 'refunded')
+          AND t.created_at >= $2 AND t.created_at < $3
+        ORDER BY t.created_at ASC
+        "#,
+    )
+    .bind(center_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    let refunds = sqlx::query_as::<_, RefundLine>(
+        r#"
+        SELECT r.id AS refund_id, r.booking_id, r.processed_at AS refunded_at, r.amount,
+               ROUND(r.amount * t.platform_fee / NULLIF(t.amount, 0), 2) AS commission_refunded,
+               ROUND(r.amount * t.vendor_amount / NULLIF(t.amount, 0), 2) AS net,
+               r.currency
+        FROM refunds r
+        INNER JOIN bookings b ON b.id = r.booking_id
+        INNER JOIN transactions t
+            ON t.stripe_payment_intent_id = r.stripe_payment_intent_id AND t.deleted_at IS NULL
+        WHERE b.center_id = $1 AND r.status = 'approved'
+          AND r.processed_at >= $2 AND r.processed_at < $3
+        ORDER BY r.processed_at ASC
+        "#,
+    )
+    .bind(center_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    let payouts = sqlx::query_as::<_, PayoutLine>(
+        r#"
+        SELECT id AS payout_id, stripe_transfer_id, created_at AS paid_at, amount, currency
+        FROM payouts
+        WHERE center_id = $1 AND status <> 'failed'
+          AND created_at >= $2 AND created_at < $3
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(center_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    let opening = balance_before(pool, center_id, from).await?;
+    let totals = StatementTotals::compute(opening, &payments, &refunds, &payouts);
+
+    Ok(Statement {
+        center_id,
+        center_name,
+        period_start,
+        period_end,
+        currency,
+        payments,
+        refunds,
+        payouts,
+        totals,
+    })
+}
+
+const RECORD_COLUMNS: &str = "id, center_id, period_start, period_end, currency, opening_balance, \
+                              gross_total, commission_total, refund_total, payout_total, \
+                              closing_balance, generated_at";
+
+/// Return the stored statement for a closed month, generating and freezing
+/// it on first request.
+pub async fn issue_monthly(
+    pool: &sqlx::PgPool,
+    center_id: Uuid,
+    year: i32,
+    month: u32,
+    generated_by: Uuid,
+) -> Result<StatementRecord, AppError> {
+    let (period_start, period_end) = month_bounds(year, month)?;
+    if period_end > chrono::Utc::now().date_naive() {
+        return Err(AppError::BadRequest(
+            "Statements can only be issued for months that have ended".to_owned(),
+        ));
+    }
+
+    if let Some(existing) = find_for_period(pool, center_id, period_start, period_end).await? {
+        return Ok(existing);
+    }
+
+    let statement = generate(pool, center_id, period_start, period_end).await?;
+    let snapshot = serde_json::to_value(&statement)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize statement: {e}")))?;
+
+    // Concurrent requests race on the unique period key; the loser reads the
+    // winner's row so both callers get the same frozen statement.
+    let inserted = sqlx::query_as::<_, StatementRecord>(&format!(
+        r#"
+        INSERT INTO commission_statements (
+            center_id, period_start, period_end, currency, opening_balance, gross_total,
+            commission_total, refund_total, payout_total, closing_balance, snapshot, generated_by
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        ON CONFLICT (center_id, period_start, period_end) DO NOTHING
+        RETURNING {RECORD_COLUMNS}
+        "#
+    ))
+    .bind(center_id)
+    .bind(period_start)
+    .bind(period_end)
+    .bind(&statement.currency)
+    .bind(statement.totals.opening_balance)
+    .bind(statement.totals.gross)
+    .bind(statement.totals.commission)
+    .bind(statement.totals.refunds)
+    .bind(statement.totals.payouts)
+    .bind(statement.totals.closing_balance)
+    .bind(&snapshot)
+    .bind(generated_by)
+    .fetch_optional(pool)
+    .await?;
+
+    match inserted {
+        Some(record) => Ok(record),
+        None => find_for_period(pool, center_id, period_start, period_end)
+            .await?
+            .ok_or_else(|| AppError::Internal("Statement vanished after conflict".to_owned())),
+    }
+}
+
+async fn find_for_period(
+    pool: &sqlx::PgPool,
+    center_id: Uuid,
+    period_start: NaiveDate,
+    period_end: NaiveDate,
+) -> Result<Option<StatementRecord>, AppError> {
+    let row = sqlx::query_as::<_, StatementRecord>(&format!(
+        "SELECT {RECORD_COLUMNS} FROM commission_statements \
+         WHERE center_id = $1 AND period_start = $2 AND period_end = $3"
+    ))
+    .bind(center_id)
+    .bind(period_start)
+    .bind(period_end)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+/// List a center's statements, newest period first.
+pub async fn list(
+    pool: &sqlx::PgPool,
+    center_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<StatementRecord>, AppError> {
+    let rows = sqlx::query_as::<_, StatementRecord>(&format!(
+        "SELECT {RECORD_COLUMNS} FROM commission_statements WHERE center_id = $1 \
+         ORDER BY period_start DESC LIMIT $2 OFFSET $3"
+    ))
+    .bind(center_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Load the frozen snapshot of a stored statement.
+pub async fn load_snapshot(
+    pool: &sqlx::PgPool,
+    statement_id: Uuid,
+) -> Result<Option<Statement>, AppError> {
+    let snapshot: Option<serde_json::Value> =
+        sqlx::query_scalar("SELECT snapshot FROM commission_statements WHERE id = $1")
+            .bind(statement_id)
+            .fetch_optional(pool)
+            .await?;
+    snapshot
+        .map(|v| {
+            serde_json::from_value(v)
+                .map_err(|e| AppError::Internal(format!("Corrupt statement snapshot: {e}")))
+        })
+        .transpose()
+}
+
+/// Quote a CSV field when needed (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+fn csv_row(out: &mut String, fields: &[String]) {
+    let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+    out.push_str(&row.join(","));
+    out.push_str("\r\n");
+}
+
+/// Render a statement as CSV: one row per movement, then the totals.
+pub fn to_csv(statement: &Statement) -> String {
+    let mut out = String::new();
+    csv_row(
+        &mut out,
+        &[
+            "type",
+            "date",
+            "booking_id",
+            "reference",
+            "description",
+            "gross",
+            "commission",
+            "net",
+            "currency",
+        ]
+        .map(str::to_owned),
+    );
+
+    for p in &statement.payments {
+        let description = [p.service_name.as_deref(), p.client_name.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" - ");
+        csv_row(
+            &mut out,
+            &[
+                "payment".to_owned(),
+                p.paid_at.format("%Y-%m-%d").to_string(),
+                p.booking_id.to_string(),
+                p.payment_reference.clone().unwrap_or_default(),
+                description,
+                p.gross.to_string(),
+                p.commission.to_string(),
+                p.net.to_string(),
+                p.currency.clone(),
+            ],
+        );
+    }
+    for r in &statement.refunds {
+        csv_row(
+            &mut out,
+            &[
+                "refund".to_owned(),
+                r.refunded_at.format("%Y-%m-%d").to_string(),
+                r.booking_id.to_string(),
+                r.refund_id.to_string(),
+                String::new(),
+                (-r.amount).to_string(),
+                (-r.commission_refunded).to_string(),
+                (-r.net).to_string(),
+                r.currency.clone(),
+            ],
+        );
+    }
+    for p in &statement.payouts {
+        csv_row(
+            &mut out,
+            &[
+                "payout".to_owned(),
+                p.paid_at.format("%Y-%m-%d").to_string(),
+                String::new(),
+                p.stripe_transfer_id.clone().unwrap_or_default(),
+                String::new(),
+                String::new(),
+                String::new(),
+                (-p.amount).to_string(),
+                p.currency.clone(),
+            ],
+        );
+    }
+
+    let t = &statement.totals;
+    let currency = &statement.currency;
+    let start = statement.period_start.format("%Y-%m-%d").to_string();
+    let last_day = statement
+        .period_end
+        .pred_opt()
+        .unwrap_or(statement.period_end)
+        .format("%Y-%m-%d")
+        .to_string();
+    for (label, date, gross, commission, net) in [
+        ("opening_balance", &start, None, None, t.opening_balance),
+        (
+            "total_payments",
+            &last_day,
+            Some(t.gross),
+            Some(t.commission),
+            t.net,
+        ),
+        ("total_refunds", &last_day, None, None, -t.refunds),
+        ("total_payouts", &last_day, None, None, -t.payouts),
+        ("closing_balance", &last_day, None, None, t.closing_balance),
+    ] {
+        csv_row(
+            &mut out,
+            &[
+                label.to_owned(),
+                date.clone(),
+                String::new(),
+                String::new(),
+                String::new(),
+                gross.map(|d| d.to_string()).unwrap_or_default(),
+                commission.map(|d| d.to_string()).unwrap_or_default(),
+                net.to_string(),
+                currency.clone(),
+            ],
+        );
+    }
+    out
+}
+
+/// File name for a download, e.g. `statement-ocean-dive-2026-03.csv`.
+pub fn file_name(statement: &Statement, extension: &str) -> String {
+    let slug: String = statement
+        .center_name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    format!(
+        "statement-{slug}-{}-{:02}.{extension}",
+        statement.period_start.year(),
+        statement.period_start.month()
+    )
+}
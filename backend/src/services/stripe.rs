@@ -1,8 +1,414 @@
-use stripe::Client;
+use async_trait::async_trait;
+use stripe::{Client, RequestStrategy};
 
 use crate::config::Config;
+use crate::models::{Money, RoundingMode};
+use crate::services::payment_gateway::{
+    AccountLinkRequest, AccountStatus, BalanceEntry, BalanceEntryKind, CheckoutRequest,
+    CheckoutSession, GatewayError, PaymentGateway, PaymentIntent, PaymentIntentRequest, Refund,
+    RefundRequest, Transfer, TransferRequest,
+};
 
 /// Build a Stripe API client from the app config.
 pub fn build_stripe_client(config: &Config) -> Client {
     Client::new(&config.stripe_secret_key)
 }
+
+/// [`PaymentGateway`] backed by the Stripe API.
+pub struct StripeGateway {
+    client: Client,
+}
+
+impl StripeGateway {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(build_stripe_client(config))
+    }
+}
+
+/// Stripe does not process a request it answers with 400, 402, 403 or 404,
+/// so those are definite declines. Timeouts, network errors, 409
+/// (idempotency conflict), 429 and 5xx leave the outcome unknown.
+fn provider_error(err: stripe::StripeError) -> GatewayError {
+    match err {
+        stripe::StripeError::Stripe(ref request) if matches!(request.http_status, 400 | 402 | 403 | 404) => {
+            GatewayError::Declined(err.to_string())
+        }
+        _ => GatewayError::Provider(err.to_string()),
+    }
+}
+
+fn expandable_id<T: stripe::Object>(value: &stripe::Expandable<T>) -> String
+where
+    T::Id: std::fmt::Display,
+{
+    match value {
+        stripe::Expandable::Id(id) => id.to_string(),
+        stripe::Expandable::Object(obj) => obj.id().to_string(),
+    }
+}
+
+fn parse_account_id(account_id: &str) -> Result<stripe::AccountId, GatewayError> {
+    account_id
+        .parse()
+        .map_err(|_| GatewayError::InvalidRequest("Invalid Stripe account ID".to_owned()))
+}
+
+fn parse_payment_intent_id(id: &str) -> Result<stripe::PaymentIntentId, GatewayError> {
+    id.parse()
+        .map_err(|_| GatewayError::InvalidRequest("Invalid PaymentIntent ID".to_owned()))
+}
+
+fn payment_intent(pi: stripe::PaymentIntent) -> Result<PaymentIntent, GatewayError> {
+    let client_secret = pi.client_secret.ok_or_else(|| {
+        GatewayError::Provider("Stripe returned a PaymentIntent without a client secret".to_owned())
+    })?;
+    Ok(PaymentIntent {
+        id: pi.id.to_string(),
+        client_secret,
+        status: pi.status.as_str().to_owned(),
+        amount: Money::from_stripe_minor(pi.amount, &pi.currency.to_string())?,
+    })
+}
+
+/// Capabilities and outstanding requirements of a connected account.
+pub fn account_status(account: &stripe::Account) -> AccountStatus {
+    let requirements = account.requirements.as_ref();
+    AccountStatus {
+        account_id: account.id.to_string(),
+        charges_enabled: account.charges_enabled.unwrap_or(false),
+        payouts_enabled: account.payouts_enabled.unwrap_or(false),
+        details_submitted: account.details_submitted.unwrap_or(false),
+        currently_due: requirements
+            .and_then(|r| r.currently_due.clone())
+            .unwrap_or_default(),
+        past_due: requirements.and_then(|r| r.past_due.clone()).unwrap_or_default(),
+        disabled_reason: requirements.and_then(|r| r.disabled_reason.clone()),
+        current_deadline: requirements
+            .and_then(|r| r.current_deadline)
+            .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0)),
+    }
+}
+
+/// Convert a balance transaction with an expanded `source` into a
+/// [`BalanceEntry`]. Movements other than charges, refunds and transfers
+/// (fees, payouts to the bank, disputes…) are skipped, and so are the
+/// transfers of destination charges (`source_transaction` set), which are
+/// part of a booking payment rather than a payout.
+fn balance_entry(txn: stripe::BalanceTransaction) -> Result<Option<BalanceEntry>, GatewayError> {
+    let Some(stripe::Expandable::Object(source)) = txn.source else {
+        return Ok(None);
+    };
+    let created = chrono::DateTime::from_timestamp(txn.created, 0).unwrap_or_default();
+
+    let entry = match *source {
+        stripe::BalanceTransactionSourceUnion::Charge(charge) => {
+            let currency = charge.currency.to_string();
+            BalanceEntry {
+                id: txn.id.to_string(),
+                kind: BalanceEntryKind::Charge,
+                source_id: charge.id.to_string(),
+                payment_intent_id: charge.payment_intent.as_ref().map(expandable_id),
+                amount: Money::from_stripe_minor(charge.amount, &currency)?,
+                application_fee: charge
+                    .application_fee_amount
+                    .map(|fee| Money::from_stripe_minor(fee, &currency))
+                    .transpose()?,
+                created,
+            }
+        }
+        stripe::BalanceTransactionSourceUnion::Refund(refund) => BalanceEntry {
+            id: txn.id.to_string(),
+            kind: BalanceEntryKind::Refund,
+            source_id: refund.id.to_string(),
+            payment_intent_id: refund.payment_intent.as_ref().map(expandable_id),
+            amount: Money::from_stripe_minor(refund.amount, &refund.currency.to_string())?,
+            application_fee: None,
+            created,
+        },
+        stripe::BalanceTransactionSourceUnion::Transfer(transfer) => {
+            if transfer.source_transaction.is_some() {
+                return Ok(None);
+            }
+            BalanceEntry {
+                id: txn.id.to_string(),
+                kind: BalanceEntryKind::Transfer,
+                source_id: transfer.id.to_string(),
+                payment_intent_id: None,
+                amount: Money::from_stripe_minor(transfer.amount, &transfer.currency.to_string())?,
+                application_fee: None,
+                created,
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(entry))
+}
+
+#[async_trait]
+impl PaymentGateway for StripeGateway {
+    async fn create_checkout_session(
+        &self,
+        request: CheckoutRequest,
+    ) -> Result<CheckoutSession, GatewayError> {
+        let amount_cents = request.amount.to_stripe_minor(RoundingMode::HalfUp)?;
+        let currency = request.amount.stripe_currency()?;
+
+        let mut payment_intent_data = stripe::CreateCheckoutSessionPaymentIntentData {
+            metadata: Some(request.metadata.clone()),
+            ..Default::default()
+        };
+
+        if let Some(ref acct_id) = request.destination_account {
+            if let Some(ref fee) = request.application_fee {
+                payment_intent_data.application_fee_amount =
+                    Some(fee.to_stripe_minor(RoundingMode::HalfUp)?);
+            }
+            payment_intent_data.transfer_data =
+                Some(stripe::CreateCheckoutSessionPaymentIntentDataTransferData {
+                    destination: acct_id.clone(),
+                    ..Default::default()
+                });
+        }
+
+        let params = stripe::CreateCheckoutSession {
+            mode: Some(stripe::CheckoutSessionMode::Payment),
+            line_items: Some(vec![stripe::CreateCheckoutSessionLineItems {
+                price_data: Some(stripe::CreateCheckoutSessionLineItemsPriceData {
+                    currency,
+                    unit_amount: Some(amount_cents),
+                    product_data: Some(
+                        stripe::CreateCheckoutSessionLineItemsPriceDataProductData {
+                            name: request.product_name,
+                            ..Default::default()
+                        },
+                    ),
+                    ..Default::default()
+                }),
+                quantity: Some(1),
+                ..Default::default()
+            }]),
+            metadata: Some(request.metadata),
+            payment_intent_data: Some(payment_intent_data),
+            success_url: Some(&request.success_url),
+            cancel_url: Some(&request.cancel_url),
+            ..Default::default()
+        };
+
+        let session = stripe::CheckoutSession::create(&self.client, params)
+            .await
+            .map_err(provider_error)?;
+
+        let url = session.url.ok_or_else(|| {
+            GatewayError::Provider("Stripe returned a session without a URL".to_owned())
+        })?;
+
+        Ok(CheckoutSession {
+            id: session.id.to_string(),
+            url,
+            expires_at: chrono::DateTime::from_timestamp(session.expires_at, 0).unwrap_or_default(),
+        })
+    }
+
+    async fn expire_checkout_session(&self, session_id: &str) -> Result<(), GatewayError> {
+        let id: stripe::CheckoutSessionId = session_id
+            .parse()
+            .map_err(|_| GatewayError::InvalidRequest("Invalid Checkout Session ID".to_owned()))?;
+        stripe::CheckoutSession::expire(&self.client, &id)
+            .await
+            .map_err(provider_error)?;
+        Ok(())
+    }
+
+    async fn create_payment_intent(
+        &self,
+        request: PaymentIntentRequest,
+    ) -> Result<PaymentIntent, GatewayError> {
+        let amount_cents = request.amount.to_stripe_minor(RoundingMode::HalfUp)?;
+        let currency = request.amount.stripe_currency()?;
+
+        let mut params = stripe::CreatePaymentIntent::new(amount_cents, currency);
+        params.automatic_payment_methods = Some(stripe::CreatePaymentIntentAutomaticPaymentMethods {
+            enabled: true,
+            ..Default::default()
+        });
+        params.description = Some(&request.description);
+        params.metadata = Some(request.metadata);
+
+        if let Some(ref acct_id) = request.destination_account {
+            if let Some(ref fee) = request.application_fee {
+                params.application_fee_amount = Some(fee.to_stripe_minor(RoundingMode::HalfUp)?);
+            }
+            params.transfer_data = Some(stripe::CreatePaymentIntentTransferData {
+                destination: acct_id.clone(),
+                ..Default::default()
+            });
+        }
+
+        let pi = stripe::PaymentIntent::create(&self.client, params)
+            .await
+            .map_err(provider_error)?;
+        payment_intent(pi)
+    }
+
+    async fn retrieve_payment_intent(&self, id: &str) -> Result<PaymentIntent, GatewayError> {
+        let id = parse_payment_intent_id(id)?;
+        let pi = stripe::PaymentIntent::retrieve(&self.client, &id, &[])
+            .await
+            .map_err(provider_error)?;
+        payment_intent(pi)
+    }
+
+    async fn cancel_payment_intent(&self, id: &str) -> Result<(), GatewayError> {
+        let id = parse_payment_intent_id(id)?;
+        stripe::PaymentIntent::cancel(
+            &self.client,
+            id.as_str(),
+            stripe::CancelPaymentIntent {
+                cancellation_reason: Some(stripe::PaymentIntentCancellationReason::Abandoned),
+            },
+        )
+        .await
+        .map_err(provider_error)?;
+        Ok(())
+    }
+
+    async fn create_refund(&self, request: RefundRequest) -> Result<Refund, GatewayError> {
+        let payment_intent = parse_payment_intent_id(&request.payment_intent_id)?;
+
+        let mut params = stripe::CreateRefund::new();
+        params.payment_intent = Some(payment_intent);
+        params.metadata = Some(request.metadata);
+        if let Some(ref amount) = request.amount {
+            params.amount = Some(amount.to_stripe_minor(RoundingMode::HalfUp)?);
+        }
+        if request.reverse_transfer {
+            params.reverse_transfer = Some(true);
+            params.refund_application_fee = Some(true);
+        }
+
+        let client = match request.idempotency_key {
+            Some(key) => self.client.clone().with_strategy(RequestStrategy::Idempotent(key)),
+            None => self.client.clone(),
+        };
+        let refund = stripe::Refund::create(&client, params)
+            .await
+            .map_err(provider_error)?;
+
+        Ok(Refund {
+            id: refund.id.to_string(),
+            status: refund.status.unwrap_or_else(|| "pending".to_owned()),
+        })
+    }
+
+    async fn create_transfer(&self, request: TransferRequest) -> Result<Transfer, GatewayError> {
+        let amount_cents = request.amount.to_stripe_minor(RoundingMode::Down)?;
+        let currency = request.amount.stripe_currency()?;
+
+        let mut params = stripe::CreateTransfer::new(currency, request.destination_account);
+        params.amount = Some(amount_cents);
+        params.description = request.description.as_deref();
+        params.metadata = Some(request.metadata);
+
+        let client = match request.idempotency_key {
+            Some(key) => self.client.clone().with_strategy(RequestStrategy::Idempotent(key)),
+            None => self.client.clone(),
+        };
+        let transfer = stripe::Transfer::create(&client, params)
+            .await
+            .map_err(provider_error)?;
+
+        Ok(Transfer {
+            id: transfer.id.to_string(),
+        })
+    }
+
+    async fn create_connect_account(&self) -> Result<String, GatewayError> {
+        let mut params = stripe::CreateAccount::new();
+        params.type_ = Some(stripe::AccountType::Express);
+        let account = stripe::Account::create(&self.client, params)
+            .await
+            .map_err(provider_error)?;
+        Ok(account.id.to_string())
+    }
+
+    async fn create_account_link(
+        &self,
+        request: AccountLinkRequest,
+    ) -> Result<String, GatewayError> {
+        let account_id = parse_account_id(&request.account_id)?;
+
+        let mut params =
+            stripe::CreateAccountLink::new(account_id, stripe::AccountLinkType::AccountOnboarding);
+        params.refresh_url = Some(&request.refresh_url);
+        params.return_url = Some(&request.return_url);
+
+        let link = stripe::AccountLink::create(&self.client, params)
+            .await
+            .map_err(provider_error)?;
+        Ok(link.url)
+    }
+
+    async fn retrieve_account(&self, account_id: &str) -> Result<AccountStatus, GatewayError> {
+        let account_id = parse_account_id(account_id)?;
+        let account = stripe::Account::retrieve(&self.client, &account_id, &[])
+            .await
+            .map_err(provider_error)?;
+        Ok(account_status(&account))
+    }
+
+    async fn create_login_link(&self, account_id: &str) -> Result<String, GatewayError> {
+        let account_id = parse_account_id(account_id)?;
+        // `LoginLink::create` always sends `redirect_url`, which current API
+        // versions reject; post the bare form instead.
+        let params = stripe::CreateLoginLink {
+            expand: &[],
+            redirect_url: None,
+        };
+        let link: stripe::LoginLink = self
+            .client
+            .post_form(&format!("/accounts/{account_id}/login_links"), &params)
+            .await
+            .map_err(provider_error)?;
+        Ok(link.url)
+    }
+
+    async fn list_balance_entries(
+        &self,
+        from: chrono::DateTime<chrono::Utc>,
+        to: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<BalanceEntry>, GatewayError> {
+        let mut entries = Vec::new();
+        let mut starting_after: Option<stripe::BalanceTransactionId> = None;
+
+        loop {
+            let mut params = stripe::ListBalanceTransactions::new();
+            params.created = Some(stripe::RangeQuery::Bounds(stripe::RangeBounds {
+                gte: Some(from.timestamp()),
+                lt: Some(to.timestamp()),
+                ..Default::default()
+            }));
+            params.expand = &["data.source"];
+            params.limit = Some(100);
+            params.starting_after = starting_after.take();
+
+            let page = stripe::BalanceTransaction::list(&self.client, &params)
+                .await
+                .map_err(provider_error)?;
+
+            starting_after = page.data.last().map(|t| t.id.clone());
+            let has_more = page.has_more;
+            for txn in page.data {
+                entries.extend(balance_entry(txn)?);
+            }
+            if !has_more || starting_after.is_none() {
+                break;
+            }
+        }
+
+        Ok(entries)
+    }
+}
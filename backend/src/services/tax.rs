@@ -0,0 +1,279 @@
+//! VAT computation for bookings and platform commissions.
+//!
+//! Two taxable supplies are involved in every paid booking:
+//!
+//! - the dive itself, sold by the center to the diver. Prices are displayed
+//!   VAT-inclusive, so the gross amount is split using the VAT rate of the
+//!   center's country;
+//! - the platform commission, sold by EviDive (a French company) to the
+//!   center. French centers are charged French VAT, centers elsewhere in the
+//!   EU are invoiced under reverse charge, and centers outside the EU are out
+//!   of scope of French VAT.
+//!
+//! Rates are read from `t_platform_config` (category `tax`) and fall back to
+//! the EU standard rates below. The commission amount withheld from the
+//! payment is treated as gross, so Stripe amounts are unaffected.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::{Money, MoneyError, RoundingMode};
+
+/// Country of the platform operator.
+pub const PLATFORM_COUNTRY: &str = "FR";
+
+/// Config key for the VAT rate applied to commissions invoiced to French centers.
+pub const PLATFORM_VAT_RATE_KEY: &str = "vat_rate_platform";
+
+/// Config key prefix for the VAT rate on dive prices, e.g. `vat_rate_service_FR`.
+pub const SERVICE_VAT_RATE_PREFIX: &str = "vat_rate_service_";
+
+/// EU standard VAT rates (percent), used when no override is configured.
+const EU_STANDARD_RATES: &[(&str, &str)] = &[
+    ("AT", "20"),
+    ("BE", "21"),
+    ("BG", "20"),
+    ("CY", "19"),
+    ("CZ", "21"),
+    ("DE", "19"),
+    ("DK", "25"),
+    ("EE", "24"),
+    ("ES", "21"),
+    ("FI", "25.5"),
+    ("FR", "20"),
+    ("GR", "24"),
+    ("HR", "25"),
+    ("HU", "27"),
+    ("IE", "23"),
+    ("IT", "22"),
+    ("LT", "21"),
+    ("LU", "17"),
+    ("LV", "21"),
+    ("MT", "18"),
+    ("NL", "21"),
+    ("PL", "23"),
+    ("PT", "23"),
+    ("RO", "21"),
+    ("SE", "25"),
+    ("SI", "22"),
+    ("SK", "23"),
+];
+
+/// Whether a country (ISO 3166-1 alpha-2) is an EU member state.
+pub fn is_eu(country: &str) -> bool {
+    EU_STANDARD_RATES.iter().any(|(code, _)| *code == country)
+}
+
+/// Built-in standard rate for a country, if it is an EU member state.
+pub fn default_service_rate(country: &str) -> Option<Decimal> {
+    EU_STANDARD_RATES
+        .iter()
+        .find(|(code, _)| *code == country)
+        .and_then(|(_, rate)| rate.parse().ok())
+}
+
+/// How VAT applies to the platform commission.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeTaxRegime {
+    /// French center: French VAT is charged.
+    FrenchVat,
+    /// EU center outside France: VAT is self-assessed by the center.
+    ReverseCharge,
+    /// Center outside the EU: not subject to French VAT.
+    OutOfScope,
+}
+
+impl FeeTaxRegime {
+    pub fn for_country(country: &str) -> Self {
+        if country == PLATFORM_COUNTRY {
+            Self::FrenchVat
+        } else if is_eu(country) {
+            Self::ReverseCharge
+        } else {
+            Self::OutOfScope
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::FrenchVat => "french_vat",
+            Self::ReverseCharge => "reverse_charge",
+            Self::OutOfScope => "out_of_scope",
+        }
+    }
+
+    /// Legal mention printed on commission invoices.
+    pub fn invoice_mention(&self) -> Option<&'static str> {
+        match self {
+            Self::FrenchVat => None,
+            Self::ReverseCharge => Some("Autoliquidation / Reverse charge (art. 196 dir. 2006/112/CE)"),
+            Self::OutOfScope => Some("TVA non applicable, art. 259-1 du CGI / VAT not applicable"),
+        }
+    }
+}
+
+/// Net / VAT / gross split of one amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaxLine {
+    pub rate: Decimal,
+    pub net: Decimal,
+    pub vat: Decimal,
+    pub gross: Decimal,
+}
+
+impl TaxLine {
+    /// Split a VAT-inclusive amount. The VAT is rounded to the currency and the
+    /// net is derived from it, so `net + vat == gross` always holds.
+    pub fn from_gross(gross: &Money, rate: Decimal) -> Self {
+        let net_exact = gross.amount() * Decimal::ONE_HUNDRED / (Decimal::ONE_HUNDRED + rate);
+        let vat = Money::new(gross.amount() - net_exact, gross.currency())
+            .map(|m| m.round(RoundingMode::HalfUp).amount())
+            .unwrap_or(Decimal::ZERO);
+        Self {
+            rate,
+            net: gross.amount() - vat,
+            vat,
+            gross: gross.amount(),
+        }
+    }
+
+    /// An amount on which no VAT is charged.
+    pub fn untaxed(gross: &Money) -> Self {
+        Self {
+            rate: Decimal::ZERO,
+            net: gross.amount(),
+            vat: Decimal::ZERO,
+            gross: gross.amount(),
+        }
+    }
+}
+
+/// Configured VAT rates for one center country.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaxRates {
+    /// Rate on the dive price in the center's country.
+    pub service_rate: Decimal,
+    /// French VAT rate applied to commissions for French centers.
+    pub platform_rate: Decimal,
+}
+
+/// Full tax breakdown of a booking.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaxBreakdown {
+    pub country: String,
+    pub price: TaxLine,
+    pub fee: TaxLine,
+    pub fee_regime: FeeTaxRegime,
+}
+
+/// Compute the breakdown for a dive price and its commission (both gross).
+pub fn compute(
+    country: &str,
+    price: &Money,
+    fee: &Money,
+    rates: &TaxRates,
+) -> Result<TaxBreakdown, MoneyError> {
+    if price.currency() != fee.currency() {
+        return Err(MoneyError::CurrencyMismatch {
+            left: price.currency().to_owned(),
+            right: fee.currency().to_owned(),
+        });
+    }
+
+    let fee_regime = FeeTaxRegime::for_country(country);
+    let fee_line = match fee_regime {
+        FeeTaxRegime::FrenchVat => TaxLine::from_gross(fee, rates.platform_rate),
+        FeeTaxRegime::ReverseCharge | FeeTaxRegime::OutOfScope => TaxLine::untaxed(fee),
+    };
+
+    Ok(TaxBreakdown {
+        country: country.to_owned(),
+        price: TaxLine::from_gross(price, rates.service_rate),
+        fee: fee_line,
+        fee_regime,
+    })
+}
+
+/// The center's country code, validated against `ref_countries`.
+pub async fn center_country<'e, E>(executor: E, center_id: Uuid) -> Result<String, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let country: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT rc.code
+        FROM centers c
+        INNER JOIN ref_countries rc ON rc.code = UPPER(TRIM(c.country))
+        WHERE c.id = $1
+        "#,
+    )
+    .bind(center_id)
+    .fetch_optional(executor)
+    .await?;
+
+    country.ok_or_else(|| {
+        AppError::BadRequest(
+            "Center country is missing or unknown; taxes cannot be computed".to_owned(),
+        )
+    })
+}
+
+/// Load the VAT rates for a country from `t_platform_config`.
+pub async fn load_rates<'e, E>(executor: E, country: &str) -> Result<TaxRates, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let service_key = format!("{SERVICE_VAT_RATE_PREFIX}{country}");
+    let rows: Vec<(String, String)> =
+        sqlx::query_as("SELECT key, value FROM t_platform_config WHERE key IN ($1, $2)")
+            .bind(PLATFORM_VAT_RATE_KEY)
+            .bind(&service_key)
+            .fetch_all(executor)
+            .await?;
+
+    let configured = |key: &str| -> Result<Option<Decimal>, AppError> {
+        match rows.iter().find(|(k, _)| k == key) {
+            Some((_, v)) => v
+                .trim()
+                .parse::<Decimal>()
+                .map(Some)
+                .map_err(|_| AppError::Internal(format!("Invalid {key} in platform config: {v}"))),
+            None => Ok(None),
+        }
+    };
+
+    let service_rate = configured(&service_key)?
+        .or_else(|| default_service_rate(country))
+        .unwrap_or(Decimal::ZERO);
+    let platform_rate = configured(PLATFORM_VAT_RATE_KEY)?
+        .or_else(|| default_service_rate(PLATFORM_COUNTRY))
+        .unwrap_or(Decimal::ZERO);
+
+    Ok(TaxRates {
+        service_rate,
+        platform_rate,
+    })
+}
+
+/// Validate the center's country, load its rates and compute the breakdown.
+/// `None` when the center's country is missing or unknown; database and
+/// rate configuration errors are returned.
+pub async fn breakdown_for_center(
+    pool: &sqlx::PgPool,
+    center_id: Uuid,
+    price: &Money,
+    fee: &Money,
+) -> Result<Option<TaxBreakdown>, AppError> {
+    let country = match center_country(pool, center_id).await {
+        Ok(country) => country,
+        Err(AppError::BadRequest(_)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let rates = load_rates(pool, &country).await?;
+    compute(&country, price, fee, &rates)
+        .map(Some)
+        .map_err(|e| AppError::Internal(e.to_string()))
+}
@@ -0,0 +1,634 @@
+//! Outgoing signed webhooks for center integrations.
+//!
+//! Centers register HTTPS endpoints and choose event types. Triggers
+//! (migration 030) insert a delivery per matching endpoint with the change
+//! that caused it; the webhooks job POSTs due deliveries and retries them
+//! with the email outbox backoff ([`outbox::retry_delay`]). Each request
+//! carries `EviDive-Signature: t=<unix>,v1=<hex>`, the HMAC-SHA256 of
+//! `"<t>.<body>"` with the endpoint secret, the same scheme Stripe uses
+//! for the events we receive.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::services::outbox;
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+type HmacSha256 = Hmac<Sha256>;
+
+/// Event types an endpoint can subscribe to.
+pub const EVENT_TYPES: &[&str] = &[
+    "booking.created",
+    "booking.confirmed",
+    "booking.cancelled",
+    "booking.completed",
+    "payment.received",
+    "payment.refunded",
+];
+
+pub const SIGNATURE_HEADER: &str = "EviDive-Signature";
+pub const EVENT_HEADER: &str = "EviDive-Event";
+pub const DELIVERY_HEADER: &str = "EviDive-Delivery";
+
+/// Receivers should reject signatures older than this.
+pub const SIGNATURE_TOLERANCE_SECONDS: i64 = 300;
+
+pub const MAX_ENDPOINTS_PER_CENTER: i64 = 10;
+
+/// Deliveries claimed per job run.
+pub const CLAIM_BATCH: i64 = 50;
+pub const CLAIM_LOCK_MINUTES: i64 = 5;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_RESPONSE_BODY: usize = 1024;
+
+// ──────────────────────── Signing ────────────────────────
+
+/// New endpoint secret: `whsec_` followed by 64 hex characters.
+pub fn generate_secret() -> String {
+    format!(
+        "whsec_{}{}",
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+fn mac(secret: &str, timestamp: i64, body: &str) -> HmacSha256 {
+    // HMAC accepts keys of any length.
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC key of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    mac
+}
+
+/// `EviDive-Signature` header value for `body` sent at `timestamp`.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let signature = hex::encode(mac(secret, timestamp, body).finalize().into_bytes());
+    format!("t={timestamp},v1={signature}")
+}
+
+/// Verify a signature header the way receivers should: the timestamp must
+/// be within [`SIGNATURE_TOLERANCE_SECONDS`] of `now` and one `v1` must
+/// match (several are sent while a secret is being rotated).
+pub fn verify(secret: &str, header: &str, body: &str, now: i64) -> bool {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", t)) => timestamp = t.parse::<i64>().ok(),
+            Some(("v1", v)) => signatures.extend(hex::decode(v).ok()),
+            _ => {}
+        }
+    }
+    let Some(timestamp) = timestamp else {
+        return false;
+    };
+    if (now - timestamp).abs() > SIGNATURE_TOLERANCE_SECONDS {
+        return false;
+    }
+    signatures
+        .iter()
+        .any(|signature| mac(secret, timestamp, body).verify_slice(signature).is_ok())
+}
+
+// ──────────────────────── Validation ────────────────────────
+
+/// Endpoints must be public HTTPS URLs: no credentials, no localhost and
+/// no private, loopback or link-local IP literal. Host names are checked
+/// when the delivery client resolves them.
+pub fn validate_url(url: &str) -> Result<reqwest::Url, AppError> {
+    let invalid = |reason: &str| AppError::BadRequest(format!("Invalid webhook URL: {reason}"));
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|_| invalid("not a URL"))?;
+
+    if parsed.scheme() != "https" {
+        return Err(invalid("HTTPS is required"));
+    }
+    if !parsed.username().is_empty() || parsed.password().is_some() {
+        return Err(invalid("credentials are not allowed"));
+    }
+    let host = parsed.host_str().ok_or_else(|| invalid("missing host"))?;
+    let host = host.trim_start_matches('[').trim_end_matches(']').to_lowercase();
+    if host == "localhost" || host.ends_with(".localhost") || host.ends_with(".local") {
+        return Err(invalid("local hosts are not allowed"));
+    }
+    if host.parse::<IpAddr>().is_ok_and(is_private) {
+        return Err(invalid("private addresses are not allowed"));
+    }
+    Ok(parsed)
+}
+
+/// Private, loopback, link-local, shared (CGNAT) and unspecified
+/// addresses, which an endpoint must not point at, whether written in the
+/// URL or resolved from its host name. IPv6 forms embedding an IPv4
+/// address (NAT64, IPv4-compatible) are refused whatever the address, as
+/// they reach IPv4 hosts through the network's own translation.
+pub fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                // 100.64.0.0/10 shared address space (carrier-grade NAT)
+                || (v4.octets()[0] == 100 && (v4.octets()[1] & 0xc0) == 64)
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_private(IpAddr::V4(v4)),
+            None => {
+                let segments = v6.segments();
+                v6.is_loopback()
+                    || v6.is_unspecified()
+                    // fc00::/7 unique local, fe80::/10 link-local
+                    || (segments[0] & 0xfe00) == 0xfc00
+                    || (segments[0] & 0xffc0) == 0xfe80
+                    // 64:ff9b::/96 NAT64
+                    || segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0]
+                    // ::/96 IPv4-compatible (deprecated)
+                    || segments[..6] == [0; 6]
+            }
+        },
+    }
+}
+
+/// Resolver of the delivery client. A public host name can resolve to a
+/// private address, or be re-pointed at one after it was registered, so
+/// the addresses are checked each time a request connects.
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            if addrs.iter().any(|addr| is_private(addr.ip())) {
+                return Err(format!("{} resolves to a private address", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// Deduplicate and check event types against [`EVENT_TYPES`].
+pub fn validate_event_types(types: &[String]) -> Result<Vec<String>, AppError> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for t in types {
+        let t = t.trim();
+        if !EVENT_TYPES.contains(&t) {
+            return Err(AppError::BadRequest(format!(
+                "Unknown event type '{t}'. Allowed: {}",
+                EVENT_TYPES.join(", ")
+            )));
+        }
+        if !cleaned.iter().any(|c| c == t) {
+            cleaned.push(t.to_owned());
+        }
+    }
+    if cleaned.is_empty() {
+        return Err(AppError::BadRequest("At least one event type is required".to_owned()));
+    }
+    Ok(cleaned)
+}
+
+// ──────────────────────── Endpoints ────────────────────────
+
+/// Endpoint as listed; the secret is only returned on creation and rotation.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Endpoint {
+    pub id: Uuid,
+    pub center_id: Uuid,
+    pub url: String,
+    pub description: Option<String>,
+    pub event_types: Vec<String>,
+    pub is_active: bool,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+const ENDPOINT_COLUMNS: &str = "id, center_id, url, description, event_types, is_active, created_at, updated_at";
+
+pub struct NewEndpoint {
+    pub center_id: Uuid,
+    pub url: String,
+    pub description: Option<String>,
+    pub event_types: Vec<String>,
+    pub created_by: Uuid,
+}
+
+/// Create an endpoint. Returns it with its secret.
+pub async fn create_endpoint(pool: &sqlx::PgPool, new: NewEndpoint) -> Result<(Endpoint, String), AppError> {
+    let url = validate_url(&new.url)?;
+    let event_types = validate_event_types(&new.event_types)?;
+
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM webhook_endpoints WHERE center_id = $1 AND deleted_at IS NULL",
+    )
+    .bind(new.center_id)
+    .fetch_one(pool)
+    .await?;
+    if count >= MAX_ENDPOINTS_PER_CENTER {
+        return Err(AppError::Conflict(format!(
+            "A center can have at most {MAX_ENDPOINTS_PER_CENTER} webhook endpoints"
+        )));
+    }
+
+    let secret = generate_secret();
+    let endpoint = sqlx::query_as::<_, Endpoint>(&format!(
+        "INSERT INTO webhook_endpoints (center_id, url, description, event_types, secret, created_by) \
+         VALUES ($1, $2, $3, $4, $5, $6) RETURNING {ENDPOINT_COLUMNS}"
+    ))
+    .bind(new.center_id)
+    .bind(url.as_str())
+    .bind(new.description.as_deref().map(str::trim).filter(|d| !d.is_empty()))
+    .bind(&event_types)
+    .bind(&secret)
+    .bind(new.created_by)
+    .fetch_one(pool)
+    .await?;
+    Ok((endpoint, secret))
+}
+
+pub async fn list_endpoints(pool: &sqlx::PgPool, center_id: Uuid) -> Result<Vec<Endpoint>, AppError> {
+    let rows = sqlx::query_as::<_, Endpoint>(&format!(
+        "SELECT {ENDPOINT_COLUMNS} FROM webhook_endpoints \
+         WHERE center_id = $1 AND deleted_at IS NULL ORDER BY created_at"
+    ))
+    .bind(center_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn get_endpoint<'e, E>(executor: E, center_id: Uuid, endpoint_id: Uuid) -> Result<Endpoint, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, Endpoint>(&format!(
+        "SELECT {ENDPOINT_COLUMNS} FROM webhook_endpoints \
+         WHERE id = $1 AND center_id = $2 AND deleted_at IS NULL"
+    ))
+    .bind(endpoint_id)
+    .bind(center_id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Webhook endpoint not found".to_owned()))
+}
+
+#[derive(Debug, Default)]
+pub struct EndpointChanges {
+    pub url: Option<String>,
+    pub description: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    pub is_active: Option<bool>,
+}
+
+pub async fn update_endpoint(
+    pool: &sqlx::PgPool,
+    center_id: Uuid,
+    endpoint_id: Uuid,
+    changes: EndpointChanges,
+) -> Result<Endpoint, AppError> {
+    let url = changes.url.as_deref().map(validate_url).transpose()?;
+    let event_types = changes
+        .event_types
+        .as_deref()
+        .map(validate_event_types)
+        .transpose()?;
+
+    sqlx::query_as::<_, Endpoint>(&format!(
+        "UPDATE webhook_endpoints \
+         SET url = COALESCE($3, url), description = COALESCE($4, description), \
+             event_types = COALESCE($5, event_types), is_active = COALESCE($6, is_active), \
+             updated_at = NOW() \
+         WHERE id = $1 AND center_id = $2 AND deleted_at IS NULL \
+         RETURNING {ENDPOINT_COLUMNS}"
+    ))
+    .bind(endpoint_id)
+    .bind(center_id)
+    .bind(url.as_ref().map(reqwest::Url::as_str))
+    .bind(changes.description.as_deref().map(str::trim))
+    .bind(event_types)
+    .bind(changes.is_active)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Webhook endpoint not found".to_owned()))
+}
+
+/// Soft-delete an endpoint and drop its pending deliveries.
+pub async fn delete_endpoint(pool: &sqlx::PgPool, center_id: Uuid, endpoint_id: Uuid) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    let deleted = sqlx::query(
+        "UPDATE webhook_endpoints SET deleted_at = NOW(), is_active = false, updated_at = NOW() \
+         WHERE id = $1 AND center_id = $2 AND deleted_at IS NULL",
+    )
+    .bind(endpoint_id)
+    .bind(center_id)
+    .execute(&mut *tx)
+    .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Webhook endpoint not found".to_owned()));
+    }
+    sqlx::query(
+        "UPDATE webhook_deliveries SET status = 'failed', last_error = 'Endpoint deleted', \
+             locked_until = NULL, updated_at = NOW() \
+         WHERE endpoint_id = $1 AND status = 'pending'",
+    )
+    .bind(endpoint_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Replace the secret; deliveries signed from now on use the new one.
+pub async fn rotate_secret(pool: &sqlx::PgPool, center_id: Uuid, endpoint_id: Uuid) -> Result<String, AppError> {
+    let secret = generate_secret();
+    let updated = sqlx::query(
+        "UPDATE webhook_endpoints SET secret = $3, updated_at = NOW() \
+         WHERE id = $1 AND center_id = $2 AND deleted_at IS NULL",
+    )
+    .bind(endpoint_id)
+    .bind(center_id)
+    .bind(&secret)
+    .execute(pool)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound("Webhook endpoint not found".to_owned()));
+    }
+    Ok(secret)
+}
+
+// ──────────────────────── Delivery log ────────────────────────
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Delivery {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub redelivery_of: Option<Uuid>,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub next_attempt_at: Timestamp,
+    pub response_status: Option<i32>,
+    pub response_body: Option<String>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<Timestamp>,
+    pub created_at: Timestamp,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct DeliveryDetail {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub delivery: Delivery,
+    pub payload: serde_json::Value,
+}
+
+const DELIVERY_COLUMNS: &str = "d.id, d.endpoint_id, d.event_id, d.event_type, d.redelivery_of, d.status, \
+     d.attempts, d.max_attempts, d.next_attempt_at, d.response_status, d.response_body, d.last_error, \
+     d.delivered_at, d.created_at";
+
+pub async fn list_deliveries(
+    pool: &sqlx::PgPool,
+    center_id: Uuid,
+    endpoint_id: Uuid,
+    status: Option<&str>,
+    limit: i64,
+) -> Result<Vec<Delivery>, AppError> {
+    get_endpoint(pool, center_id, endpoint_id).await?;
+    let rows = sqlx::query_as::<_, Delivery>(&format!(
+        "SELECT {DELIVERY_COLUMNS} FROM webhook_deliveries d \
+         WHERE d.endpoint_id = $1 AND ($2::text IS NULL OR d.status = $2) \
+         ORDER BY d.created_at DESC LIMIT $3"
+    ))
+    .bind(endpoint_id)
+    .bind(status)
+    .bind(limit.clamp(1, 200))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+pub async fn get_delivery<'e, E>(executor: E, center_id: Uuid, delivery_id: Uuid) -> Result<DeliveryDetail, AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, DeliveryDetail>(&format!(
+        "SELECT {DELIVERY_COLUMNS}, d.payload FROM webhook_deliveries d \
+         JOIN webhook_endpoints e ON e.id = d.endpoint_id \
+         WHERE d.id = $1 AND e.center_id = $2"
+    ))
+    .bind(delivery_id)
+    .bind(center_id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Webhook delivery not found".to_owned()))
+}
+
+/// Queue a new delivery of the same event (same payload and event id, so
+/// receivers can deduplicate), due immediately. The original stays in the
+/// log untouched.
+pub async fn redeliver(pool: &sqlx::PgPool, center_id: Uuid, delivery_id: Uuid) -> Result<DeliveryDetail, AppError> {
+    let original = get_delivery(pool, center_id, delivery_id).await?;
+    let endpoint = get_endpoint(pool, center_id, original.delivery.endpoint_id).await?;
+    if !endpoint.is_active {
+        return Err(AppError::Conflict("The webhook endpoint is disabled".to_owned()));
+    }
+
+    let id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO webhook_deliveries (endpoint_id, event_id, event_type, payload, redelivery_of)
+        SELECT endpoint_id, event_id, event_type, payload, id
+        FROM webhook_deliveries WHERE id = $1
+        RETURNING id
+        "#,
+    )
+    .bind(delivery_id)
+    .fetch_one(pool)
+    .await?;
+    get_delivery(pool, center_id, id).await
+}
+
+// ──────────────────────── Job ────────────────────────
+
+#[derive(Debug, sqlx::FromRow)]
+struct ClaimedDelivery {
+    id: Uuid,
+    event_type: String,
+    body: String,
+    attempts: i32,
+    max_attempts: i32,
+    url: String,
+    secret: String,
+    endpoint_active: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct DeliveryRun {
+    pub succeeded: usize,
+    pub retried: usize,
+    pub failed: usize,
+}
+
+/// What one attempt produced.
+struct Attempt {
+    response_status: Option<i32>,
+    response_body: Option<String>,
+    error: Option<String>,
+}
+
+pub fn http_client() -> Result<reqwest::Client, AppError> {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        // A redirect could point at an address `validate_url` rejects.
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(Arc::new(PublicResolver))
+        .user_agent("EviDive-Webhooks/1.0")
+        .build()
+        .map_err(|e| AppError::Internal(format!("Webhook HTTP client: {e}")))
+}
+
+/// Send up to [`CLAIM_BATCH`] due deliveries.
+pub async fn deliver_due(pool: &sqlx::PgPool, client: &reqwest::Client) -> Result<DeliveryRun, AppError> {
+    let claimed = sqlx::query_as::<_, ClaimedDelivery>(
+        r#"
+        UPDATE webhook_deliveries d
+        SET status = 'sending', attempts = d.attempts + 1,
+            locked_until = NOW() + make_interval(mins => $2), updated_at = NOW()
+        FROM webhook_endpoints e
+        WHERE e.id = d.endpoint_id AND d.id IN (
+            SELECT id FROM webhook_deliveries
+            WHERE (status = 'pending' AND next_attempt_at <= NOW())
+               OR (status = 'sending' AND locked_until < NOW())
+            ORDER BY next_attempt_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING d.id, d.event_type, d.payload::text AS body, d.attempts, d.max_attempts,
+                  e.url, e.secret, (e.is_active AND e.deleted_at IS NULL) AS endpoint_active
+        "#,
+    )
+    .bind(CLAIM_BATCH)
+    .bind(CLAIM_LOCK_MINUTES as i32)
+    .fetch_all(pool)
+    .await?;
+
+    let mut run = DeliveryRun::default();
+    for delivery in claimed {
+        let attempt = if delivery.endpoint_active {
+            send(client, &delivery).await
+        } else {
+            Attempt {
+                response_status: None,
+                response_body: None,
+                error: Some("Endpoint disabled".to_owned()),
+            }
+        };
+
+        let Some(error) = attempt.error else {
+            sqlx::query(
+                r#"
+                UPDATE webhook_deliveries
+                SET status = 'succeeded', delivered_at = NOW(), locked_until = NULL,
+                    response_status = $2, response_body = $3, last_error = NULL, updated_at = NOW()
+                WHERE id = $1
+                "#,
+            )
+            .bind(delivery.id)
+            .bind(attempt.response_status)
+            .bind(&attempt.response_body)
+            .execute(pool)
+            .await?;
+            run.succeeded += 1;
+            continue;
+        };
+
+        // A disabled endpoint is not retried.
+        let retry = outbox::retry_delay(delivery.attempts, delivery.max_attempts)
+            .filter(|_| delivery.endpoint_active);
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = CASE WHEN $2::bigint IS NULL THEN 'failed' ELSE 'pending' END,
+                next_attempt_at = NOW() + make_interval(secs => COALESCE($2::bigint, 0)),
+                locked_until = NULL, response_status = $3, response_body = $4,
+                last_error = $5, updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(delivery.id)
+        .bind(retry.map(|d| d.num_seconds()))
+        .bind(attempt.response_status)
+        .bind(&attempt.response_body)
+        .bind(&error)
+        .execute(pool)
+        .await?;
+
+        if retry.is_some() {
+            tracing::warn!(delivery_id = %delivery.id, attempts = delivery.attempts, error = %error, "Webhook delivery failed, will retry");
+            run.retried += 1;
+        } else {
+            tracing::warn!(delivery_id = %delivery.id, attempts = delivery.attempts, error = %error, "Webhook delivery failed permanently");
+            run.failed += 1;
+        }
+    }
+    Ok(run)
+}
+
+async fn send(client: &reqwest::Client, delivery: &ClaimedDelivery) -> Attempt {
+    // IP literals never reach the resolver.
+    if let Err(AppError::BadRequest(reason)) = validate_url(&delivery.url) {
+        return Attempt {
+            response_status: None,
+            response_body: None,
+            error: Some(reason),
+        };
+    }
+    let timestamp = chrono::Utc::now().timestamp();
+    let result = client
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, sign(&delivery.secret, timestamp, &delivery.body))
+        .header(EVENT_HEADER, &delivery.event_type)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .body(delivery.body.clone())
+        .send()
+        .await;
+
+    let mut response = match result {
+        Ok(response) => response,
+        Err(e) => {
+            return Attempt {
+                response_status: None,
+                response_body: None,
+                error: Some(format!("Request failed: {e}")),
+            }
+        }
+    };
+
+    let status = response.status();
+    // Only the start of the body is kept, so only that much is read.
+    let mut bytes = Vec::new();
+    while bytes.len() < MAX_RESPONSE_BODY {
+        match response.chunk().await {
+            Ok(Some(chunk)) => bytes.extend_from_slice(&chunk),
+            _ => break,
+        }
+    }
+    bytes.truncate(MAX_RESPONSE_BODY);
+    let body = String::from_utf8_lossy(&bytes).into_owned();
+    Attempt {
+        response_status: Some(i32::from(status.as_u16())),
+        response_body: Some(body).filter(|b| !b.is_empty()),
+        error: (!status.is_success()).then(|| format!("HTTP {status}")),
+    }
+}
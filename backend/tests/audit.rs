@@ -0,0 +1,92 @@
+use serde_json::json;
+use uuid::Uuid;
+
+use evidive_api::services::audit::{center_of, changes, redact, strip_personal, AuditFilter, Entity};
+
+#[test]
+fn changes_keep_only_modified_fields() {
+    let before = json!({ "id": "a", "status": "pending", "name": "Blue", "updated_at": "2026-01-01" });
+    let after = json!({ "id": "a", "status": "active", "name": "Blue", "updated_at": "2026-01-02" });
+
+    let (before, after) = changes(Some(before), Some(after));
+    assert_eq!(before, Some(json!({ "status": "pending" })));
+    assert_eq!(after, Some(json!({ "status": "active" })));
+}
+
+#[test]
+fn creations_and_deletions_keep_the_whole_row() {
+    let row = json!({ "id": "a", "name": "Blue" });
+    assert_eq!(changes(None, Some(row.clone())), (None, Some(row.clone())));
+    assert_eq!(changes(Some(row.clone()), None), (Some(row), None));
+}
+
+#[test]
+fn secret_settings_are_masked() {
+    let secret = redact(json!({ "key": "stripe_secret", "value": "sk_live_abcd1234", "is_secret": true }));
+    assert_eq!(secret["value"], "****1234");
+
+    let public = redact(json!({ "key": "commission_rate", "value": "0.1", "is_secret": false }));
+    assert_eq!(public["value"], "0.1");
+}
+
+#[test]
+fn personal_data_is_left_out_of_snapshots() {
+    let invitation = strip_personal(
+        Entity::Invitation,
+        json!({ "id": "i", "email": "diver@example.com", "role_in_center": "staff", "status": "pending" }),
+    );
+    assert_eq!(invitation, json!({ "id": "i", "role_in_center": "staff", "status": "pending" }));
+
+    let message = strip_personal(
+        Entity::ContactMessage,
+        json!({ "id": "m", "name": "Ana", "email": "ana@example.com", "subject": "Hi", "message": "Call me",
+                "ip_hash": "ab", "user_agent": "curl", "status": "new" }),
+    );
+    assert_eq!(message, json!({ "id": "m", "status": "new" }));
+
+    let email = strip_personal(
+        Entity::Email,
+        json!({ "id": "e", "to_address": "ana@example.com", "reply_to": null, "subject": "Booking", "body": "Hello Ana",
+                "status": "dead" }),
+    );
+    assert_eq!(email, json!({ "id": "e", "status": "dead" }));
+
+    let booking = strip_personal(Entity::Booking, json!({ "id": "b", "client_note": "Allergic", "status": "pending" }));
+    assert_eq!(booking, json!({ "id": "b", "status": "pending" }));
+
+    let tag = json!({ "id": "t", "name": "reef" });
+    assert_eq!(strip_personal(Entity::Tag, tag.clone()), tag);
+}
+
+#[test]
+fn center_comes_from_the_row_or_the_center_id() {
+    let center = Uuid::new_v4();
+    let id = center.to_string();
+    assert_eq!(center_of(Entity::Center, &id, None), Some(center));
+
+    let member = json!({ "id": Uuid::new_v4(), "fk_center": center });
+    assert_eq!(center_of(Entity::Member, "x", Some(&member)), Some(center));
+
+    let invitation = json!({ "id": Uuid::new_v4(), "center_id": center });
+    assert_eq!(center_of(Entity::Invitation, "x", Some(&invitation)), Some(center));
+
+    assert_eq!(center_of(Entity::Tag, "x", Some(&json!({ "name": "reef" }))), None);
+}
+
+#[test]
+fn action_filter_matches_exactly_or_by_prefix() {
+    let filter = |action: &str| AuditFilter { action: Some(action.to_owned()), ..Default::default() };
+
+    assert_eq!(filter("center.update_status").action_pattern().as_deref(), Some("center.update\\_status"));
+    assert_eq!(filter("center.").action_pattern().as_deref(), Some("center.%"));
+    assert_eq!(filter("100%").action_pattern().as_deref(), Some("100\\%"));
+    assert_eq!(filter("  ").action_pattern(), None);
+    assert_eq!(AuditFilter::default().action_pattern(), None);
+}
+
+#[test]
+fn entity_names_are_stable() {
+    assert_eq!(Entity::ContactMessage.as_str(), "contact_message");
+    assert_eq!(Entity::Member.as_str(), "member");
+    assert_eq!(Entity::Setting.as_str(), "setting");
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use http::StatusCode;
+use uuid::Uuid;
+
+use common::{call, post, test_app, test_pool};
+use evidive_api::services::bans::{validate, Ban, Scope};
+use evidive_api::AppError;
+
+fn ban(scope: &str, expires_in: Option<Duration>) -> Ban {
+    let now = Utc::now();
+    Ban {
+        id: Uuid::nil(),
+        user_id: Uuid::nil(),
+        scope: scope.to_owned(),
+        reason: "Repeated no-shows".to_owned(),
+        expires_at: expires_in.map(|d| now + d),
+        banned_by: None,
+        lifted_at: None,
+        lifted_by: None,
+        created_at: now - Duration::hours(1),
+    }
+}
+
+#[test]
+fn platform_ban_covers_every_action() {
+    for action in [Scope::Booking, Scope::Review, Scope::Platform] {
+        assert!(Scope::Platform.covers(action));
+    }
+    assert!(Scope::Booking.covers(Scope::Booking));
+    assert!(!Scope::Booking.covers(Scope::Review));
+    assert!(!Scope::Review.covers(Scope::Booking));
+}
+
+#[test]
+fn only_booking_and_platform_bans_cancel_bookings() {
+    assert!(Scope::Booking.cancels_bookings());
+    assert!(Scope::Platform.cancels_bookings());
+    assert!(!Scope::Review.cancels_bookings());
+}
+
+#[test]
+fn scope_round_trips() {
+    for scope in [Scope::Booking, Scope::Review, Scope::Platform] {
+        assert_eq!(Scope::parse(scope.as_str()), Some(scope));
+    }
+    assert_eq!(Scope::parse("everything"), None);
+}
+
+#[test]
+fn message_gives_reason_and_expiry() {
+    assert_eq!(ban("platform", None).message(), "Your account is suspended: Repeated no-shows");
+
+    let mut temporary = ban("booking", None);
+    temporary.expires_at = Some(Utc.with_ymd_and_hms(2026, 11, 2, 9, 30, 0).unwrap());
+    assert_eq!(
+        temporary.message(),
+        "Your account is banned from booking until 2026-11-02 09:30 UTC: Repeated no-shows"
+    );
+}
+
+#[test]
+fn ban_needs_reason_and_future_expiry() {
+    let now = Utc::now();
+    assert!(validate("Spam reviews", None, now).is_ok());
+    assert!(validate("Spam reviews", Some(now + Duration::days(1)), now).is_ok());
+    assert!(matches!(validate("  ", None, now), Err(AppError::BadRequest(_))));
+    assert!(matches!(validate("Spam reviews", Some(now), now), Err(AppError::BadRequest(_))));
+    assert!(matches!(validate(&"x".repeat(1001), None, now), Err(AppError::BadRequest(_))));
+}
+
+// ──────────────────────── Database ────────────────────────
+
+async fn booking_status(pool: &sqlx::PgPool, booking: Uuid) -> String {
+    sqlx::query_scalar("SELECT status::text FROM bookings WHERE id = $1")
+        .bind(booking)
+        .fetch_one(pool)
+        .await
+        .expect("booking")
+}
+
+#[tokio::test]
+async fn booking_ban_cancels_pending_bookings_only() {
+    let Some(pool) = test_pool().await else { return };
+    let app = test_app(&pool);
+    let (admin, owner, client) = (common::admin(&pool).await, common::user(&pool, "owner").await, common::user(&pool, "client").await);
+    let (center, _) = common::center(&pool, owner).await;
+    let pending = common::booking(&pool, center, client).await;
+    let confirmed = common::booking(&pool, center, client).await;
+    sqlx::query("UPDATE bookings SET status = 'confirmed'::booking_status WHERE id = $1")
+        .bind(confirmed)
+        .execute(&pool)
+        .await
+        .expect("confirm booking");
+
+    let uri = format!("/api/v1/admin/users/{client}/bans");
+    let (status, body) = call(&app, post(&uri, admin, serde_json::json!({ "scope": "booking", "reason": "No-shows" }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{body}");
+
+    assert_eq!(booking_status(&pool, pending).await, "cancelled");
+    assert_eq!(booking_status(&pool, confirmed).await, "confirmed");
+    let notified: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE user_id = $1")
+        .bind(owner)
+        .fetch_one(&pool)
+        .await
+        .expect("notifications");
+    assert_eq!(notified, 1);
+    let ban_id: Uuid = sqlx::query_scalar("SELECT id FROM user_bans WHERE user_id = $1")
+        .bind(client)
+        .fetch_one(&pool)
+        .await
+        .expect("ban");
+    assert_eq!(common::audit_actions(&pool, ban_id).await, vec!["user.ban"]);
+
+    common::cleanup_center(&pool, center).await;
+    common::cleanup_users(&pool, &[admin, owner, client]).await;
+}
+
+#[tokio::test]
+async fn review_ban_leaves_bookings_alone() {
+    let Some(pool) = test_pool().await else { return };
+    let app = test_app(&pool);
+    let (admin, owner, client) = (common::admin(&pool).await, common::user(&pool, "owner").await, common::user(&pool, "client").await);
+    let (center, _) = common::center(&pool, owner).await;
+    let pending = common::booking(&pool, center, client).await;
+
+    let uri = format!("/api/v1/admin/users/{client}/bans");
+    let (status, body) = call(&app, post(&uri, admin, serde_json::json!({ "scope": "review", "reason": "Fake reviews" }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{body}");
+    assert_eq!(booking_status(&pool, pending).await, "pending");
+
+    common::cleanup_center(&pool, center).await;
+    common::cleanup_users(&pool, &[admin, owner, client]).await;
+}
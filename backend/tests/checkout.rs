@@ -0,0 +1,61 @@
+use std::str::FromStr;
+
+use chrono::{Duration, TimeZone, Utc};
+use rust_decimal::Decimal;
+
+use evidive_api::services::checkout::{expire_quietly, StoredSession};
+use evidive_api::services::payment_gateway::{GatewayCall, InMemoryGateway};
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).expect("valid decimal")
+}
+
+fn session(expires_in: Duration) -> StoredSession {
+    StoredSession {
+        checkout_session_id: "cs_test_000001".to_owned(),
+        checkout_session_url: "https://checkout.test/cs_test_000001".to_owned(),
+        checkout_session_amount: dec("120.00"),
+        checkout_session_expires_at: Utc::now() + expires_in,
+    }
+}
+
+#[test]
+fn valid_session_is_reused_for_same_amount() {
+    let now = Utc::now();
+    let s = session(Duration::hours(2));
+    assert!(s.is_reusable(dec("120.00"), now));
+    assert!(s.is_reusable(dec("120"), now));
+    assert!(!s.is_reusable(dec("100.00"), now));
+}
+
+#[test]
+fn session_close_to_expiry_is_replaced_but_still_open() {
+    let now = Utc::now();
+    let s = session(Duration::minutes(5));
+    assert!(!s.is_reusable(dec("120.00"), now));
+    assert!(s.is_open(now));
+
+    let expired = StoredSession {
+        checkout_session_expires_at: Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap(),
+        ..s
+    };
+    assert!(!expired.is_open(now));
+}
+
+#[tokio::test]
+async fn only_open_sessions_are_expired() {
+    let gateway = InMemoryGateway::new();
+
+    expire_quietly(&gateway, &session(-Duration::minutes(1))).await;
+    assert!(gateway.calls().is_empty());
+
+    expire_quietly(&gateway, &session(Duration::hours(1))).await;
+    assert_eq!(
+        gateway.calls(),
+        vec![GatewayCall::ExpireCheckoutSession("cs_test_000001".to_owned())]
+    );
+
+    // Failures are swallowed: the session expires on its own.
+    gateway.fail_with(Some("session already completed"));
+    expire_quietly(&gateway, &session(Duration::hours(1))).await;
+}
@@ -0,0 +1,18 @@
+use axum::http::HeaderMap;
+
+use evidive_api::middleware::client_ip::client_ip;
+
+#[test]
+fn client_ip_prefers_first_forwarded_address() {
+    let mut headers = HeaderMap::new();
+    assert_eq!(client_ip(&headers), None);
+
+    headers.insert("x-real-ip", "198.51.100.7".parse().unwrap());
+    assert_eq!(client_ip(&headers), Some("198.51.100.7".parse().unwrap()));
+
+    headers.insert("x-forwarded-for", "203.0.113.9, 10.0.0.1".parse().unwrap());
+    assert_eq!(client_ip(&headers), Some("203.0.113.9".parse().unwrap()));
+
+    headers.insert("x-forwarded-for", "garbage".parse().unwrap());
+    assert_eq!(client_ip(&headers), Some("198.51.100.7".parse().unwrap()));
+}
@@ -0,0 +1,259 @@
+//! Harness of the database-backed tests: application state, signed-in
+//! requests and seed rows.
+//!
+//! The tests need a PostgreSQL database with the platform schema (via
+//! `DATABASE_URL`) and are skipped when it is not set. Each test seeds its
+//! own users and center and removes them afterwards; `audit_log` rows stay,
+//! as the table is append-only.
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::Router;
+use http::{Request, StatusCode};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use evidive_api::config::Config;
+use evidive_api::services::payment_gateway::InMemoryGateway;
+use evidive_api::AppState;
+
+pub const JWT_SECRET: &[u8] = b"test_jwt_secret";
+pub const WEBHOOK_SECRET: &str = "whsec_test_secret";
+
+pub async fn test_pool() -> Option<sqlx::PgPool> {
+    let Ok(url) = std::env::var("DATABASE_URL") else {
+        eprintln!("SKIP: DATABASE_URL is not set");
+        return None;
+    };
+    Some(sqlx::PgPool::connect(&url).await.expect("database reachable"))
+}
+
+pub fn test_state(pool: sqlx::PgPool, gateway: Arc<InMemoryGateway>) -> Arc<AppState> {
+    let config = Config {
+        port: 0,
+        database_url: String::new(),
+        supabase_url: "https://test-project.supabase.co".to_owned(),
+        supabase_publishable_key: "eyJ-test-key".to_owned(),
+        cors_origin: "http://localhost:3000".to_owned(),
+        stripe_secret_key: "sk_test_ci_000".to_owned(),
+        stripe_webhook_secret: WEBHOOK_SECRET.to_owned(),
+        smtp_host: None,
+        smtp_port: 25,
+        smtp_user: None,
+        smtp_pass: None,
+        smtp_from: None,
+        cron_secret: None,
+        ip_hash_secret: Some("ip-hash-test-secret".to_owned()),
+    };
+    let jwks = evidive_api::middleware::jwks::JwksCache::with_key(
+        None,
+        jsonwebtoken::DecodingKey::from_secret(JWT_SECRET),
+        jsonwebtoken::Algorithm::HS256,
+    );
+    Arc::new(AppState {
+        pool,
+        config,
+        mailer: None,
+        payments: gateway,
+        jwks,
+        jwt_issuer: "test".to_owned(),
+        events: evidive_api::services::events::EventHub::new(),
+    })
+}
+
+/// The API router over `pool`, with the in-memory payment gateway.
+pub fn test_app(pool: &sqlx::PgPool) -> Router {
+    app(test_state(pool.clone(), Arc::new(InMemoryGateway::new())))
+}
+
+pub fn app(state: Arc<AppState>) -> Router {
+    Router::new().nest("/api/v1", evidive_api::routes::api_routes()).with_state(state)
+}
+
+pub fn token(user_id: Uuid) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let claims = serde_json::json!({
+        "sub": user_id,
+        "role": "authenticated",
+        "aud": "authenticated",
+        "iss": "test",
+        "iat": now,
+        "exp": now + 600,
+    });
+    jsonwebtoken::encode(
+        &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::HS256),
+        &claims,
+        &jsonwebtoken::EncodingKey::from_secret(JWT_SECRET),
+    )
+    .expect("token encodes")
+}
+
+pub async fn call(app: &Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+    let res = app.clone().oneshot(request).await.expect("service ready");
+    let status = res.status();
+    let body = res.into_body().collect().await.expect("body readable").to_bytes();
+    (status, serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null))
+}
+
+/// A JSON request signed in as `user_id`.
+pub fn request(method: &str, uri: &str, user_id: Uuid, body: serde_json::Value) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Authorization", format!("Bearer {}", token(user_id)))
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("valid request")
+}
+
+pub fn post(uri: &str, user_id: Uuid, body: serde_json::Value) -> Request<Body> {
+    request("POST", uri, user_id, body)
+}
+
+// ──────────────────────── Seed rows ────────────────────────
+
+/// Email of the users seeded by [`user`].
+pub fn email_of(name: &str, id: Uuid) -> String {
+    format!("{name}-{}@tests.test", id.simple())
+}
+
+/// A signed-up user with a profile.
+pub async fn user(pool: &sqlx::PgPool, name: &str) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query("INSERT INTO auth.users (id, email) VALUES ($1, $2)")
+        .bind(id)
+        .bind(email_of(name, id))
+        .execute(pool)
+        .await
+        .expect("auth user");
+    sqlx::query("INSERT INTO profiles (id, first_name) VALUES ($1, $2)")
+        .bind(id)
+        .bind(name)
+        .execute(pool)
+        .await
+        .expect("profile");
+    id
+}
+
+pub async fn admin(pool: &sqlx::PgPool) -> Uuid {
+    let id = user(pool, "admin").await;
+    sqlx::query("UPDATE profiles SET role = 'admin_diver' WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await
+        .expect("admin role");
+    id
+}
+
+/// An active French center owned by `owner`. Returns its id and slug.
+pub async fn center(pool: &sqlx::PgPool, owner: Uuid) -> (Uuid, String) {
+    let slug = format!("test-diving-{}", owner.simple());
+    let id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO centers (owner_id, name, slug, country, email, status)
+        VALUES ($1, 'Test Diving', $2, 'FR', 'center@tests.test', 'active')
+        RETURNING id
+        "#,
+    )
+    .bind(owner)
+    .bind(&slug)
+    .fetch_one(pool)
+    .await
+    .expect("center");
+    member(pool, id, owner, "owner").await;
+    (id, slug)
+}
+
+/// Make `user_id` a member of `center_id`. Returns the `tli_pr_ce` id.
+pub async fn member(pool: &sqlx::PgPool, center_id: Uuid, user_id: Uuid, role: &str) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO tli_pr_ce (fk_profile, fk_center, role_in_center) VALUES ($1, $2, $3) RETURNING id",
+    )
+    .bind(user_id)
+    .bind(center_id)
+    .bind(role)
+    .fetch_one(pool)
+    .await
+    .expect("membership")
+}
+
+/// A pending 120 EUR booking of `client` in a week: two divers at 60 EUR,
+/// 20% commission.
+pub async fn booking(pool: &sqlx::PgPool, center_id: Uuid, client: Uuid) -> Uuid {
+    let service: Uuid = sqlx::query_scalar(
+        "INSERT INTO services (center_id, name, price) VALUES ($1, 'Discovery dive', 60) RETURNING id",
+    )
+    .bind(center_id)
+    .fetch_one(pool)
+    .await
+    .expect("service");
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO bookings (service_id, center_id, client_id, booking_date, time_slot, participants,
+                              unit_price, total_price, commission_rate, commission_amount, currency)
+        VALUES ($1, $2, $3, CURRENT_DATE + 7, '09:00', 2, 60, 120, 20, 24, 'EUR')
+        RETURNING id
+        "#,
+    )
+    .bind(service)
+    .bind(center_id)
+    .bind(client)
+    .fetch_one(pool)
+    .await
+    .expect("booking")
+}
+
+/// Actions recorded in `audit_log` for `entity_id`, oldest first.
+pub async fn audit_actions(pool: &sqlx::PgPool, entity_id: Uuid) -> Vec<String> {
+    sqlx::query_scalar("SELECT action FROM audit_log WHERE entity_id = $1 ORDER BY id")
+        .bind(entity_id.to_string())
+        .fetch_all(pool)
+        .await
+        .expect("audit log")
+}
+
+// ──────────────────────── Cleanup ────────────────────────
+
+pub async fn cleanup_center(pool: &sqlx::PgPool, center_id: Uuid) {
+    let statements = [
+        "DELETE FROM invoices WHERE center_id = $1",
+        "DELETE FROM transactions WHERE booking_id IN (SELECT id FROM bookings WHERE center_id = $1)",
+        "DELETE FROM notifications WHERE user_id IN (SELECT fk_profile FROM tli_pr_ce WHERE fk_center = $1)",
+        "DELETE FROM payouts WHERE center_id = $1",
+        "DELETE FROM refunds WHERE booking_id IN (SELECT id FROM bookings WHERE center_id = $1)",
+        "DELETE FROM bookings WHERE center_id = $1",
+        "DELETE FROM services WHERE center_id = $1",
+        "DELETE FROM center_invitations WHERE center_id = $1",
+        "DELETE FROM center_ownership_transfers WHERE center_id = $1",
+        "DELETE FROM tli_pr_ce WHERE fk_center = $1",
+        "DELETE FROM centers WHERE id = $1",
+    ];
+    for statement in statements {
+        if let Err(e) = sqlx::query(statement).bind(center_id).execute(pool).await {
+            eprintln!("cleanup: {statement}: {e}");
+        }
+    }
+}
+
+pub async fn cleanup_users(pool: &sqlx::PgPool, users: &[Uuid]) {
+    let statements = [
+        "DELETE FROM email_outbox WHERE profile_id = $1",
+        // Sent to the address only, as invitations are
+        "DELETE FROM email_outbox WHERE to_address LIKE '%-' || replace($1::text, '-', '') || '@tests.test'",
+        "DELETE FROM notifications WHERE user_id = $1",
+        "DELETE FROM user_bans WHERE user_id = $1",
+        "DELETE FROM profiles WHERE id = $1",
+        "DELETE FROM auth.users WHERE id = $1",
+    ];
+    for user in users {
+        for statement in statements {
+            if let Err(e) = sqlx::query(statement).bind(user).execute(pool).await {
+                eprintln!("cleanup: {statement}: {e}");
+            }
+        }
+    }
+}
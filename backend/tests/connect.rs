@@ -0,0 +1,83 @@
+use uuid::Uuid;
+
+use evidive_api::services::connect::CenterConnect;
+use evidive_api::services::payment_gateway::{
+    AccountStatus, GatewayCall, InMemoryGateway, PaymentGateway,
+};
+use evidive_api::AppError;
+
+fn center(account: Option<&str>, onboarded: bool) -> CenterConnect {
+    CenterConnect {
+        center_id: Uuid::nil(),
+        stripe_account_id: account.map(str::to_owned),
+        stripe_onboarding_complete: onboarded,
+        stripe_payouts_enabled: onboarded,
+        stripe_details_submitted: onboarded,
+        stripe_requirements_currently_due: Vec::new(),
+        stripe_requirements_past_due: Vec::new(),
+        stripe_disabled_reason: None,
+        stripe_requirements_deadline: None,
+        stripe_status_synced_at: None,
+        stripe_deauthorized_at: None,
+        stripe_reverification_required_at: None,
+    }
+}
+
+#[test]
+fn checkout_uses_destination_only_once_onboarded() {
+    assert_eq!(
+        center(Some("acct_1"), true).checkout_destination().unwrap(),
+        Some("acct_1".to_owned())
+    );
+    assert_eq!(center(Some("acct_1"), false).checkout_destination().unwrap(), None);
+    assert_eq!(center(None, false).checkout_destination().unwrap(), None);
+}
+
+#[test]
+fn deauthorized_center_blocks_checkout_and_payouts() {
+    let mut c = center(Some("acct_1"), true);
+    c.stripe_deauthorized_at = Some(chrono::Utc::now());
+
+    assert!(matches!(c.checkout_destination(), Err(AppError::Conflict(_))));
+    assert!(matches!(c.payout_destination(), Err(AppError::Conflict(_))));
+}
+
+#[test]
+fn payouts_require_completed_onboarding() {
+    assert_eq!(center(Some("acct_1"), true).payout_destination().unwrap(), "acct_1");
+    assert!(matches!(
+        center(Some("acct_1"), false).payout_destination(),
+        Err(AppError::BadRequest(_))
+    ));
+    assert!(matches!(center(Some(""), true).payout_destination(), Err(AppError::BadRequest(_))));
+}
+
+#[tokio::test]
+async fn fake_gateway_serves_account_status_and_login_links() {
+    let gateway = InMemoryGateway::new();
+    gateway.set_account_status(AccountStatus {
+        account_id: "acct_1".to_owned(),
+        details_submitted: true,
+        currently_due: vec!["external_account".to_owned()],
+        ..Default::default()
+    });
+
+    let status = gateway.retrieve_account("acct_1").await.unwrap();
+    assert_eq!(status.currently_due, vec!["external_account".to_owned()]);
+    assert!(!status.charges_enabled);
+    assert!(gateway.retrieve_account("acct_unknown").await.is_err());
+
+    let url = gateway.create_login_link("acct_1").await.unwrap();
+    assert!(url.starts_with("https://connect.test/express/"));
+    assert_eq!(gateway.calls().last(), Some(&GatewayCall::LoginLink("acct_1".to_owned())));
+}
+
+#[test]
+fn ownership_transfer_pauses_destination_until_new_account() {
+    let mut c = center(Some("acct_previous_owner"), true);
+    c.stripe_reverification_required_at = Some(chrono::Utc::now());
+
+    // The platform collects payments instead of the previous owner's account.
+    assert_eq!(c.checkout_destination().unwrap(), None);
+    assert!(matches!(c.payout_destination(), Err(AppError::Conflict(_))));
+}
@@ -0,0 +1,105 @@
+use chrono::TimeZone;
+use uuid::Uuid;
+
+use evidive_api::error::AppError;
+use evidive_api::services::contact::{
+    check_throttle, hash_ip, reply_body, reply_subject, validate_status, ContactForm,
+    ContactMessage, EMAIL_LIMIT, IP_LIMIT, MAX_MESSAGE_LEN,
+};
+
+fn form() -> ContactForm {
+    ContactForm {
+        name: "Jane Diver".to_owned(),
+        email: "jane@example.com".to_owned(),
+        subject: "Group booking".to_owned(),
+        message: "Hello,\nwe are six.".to_owned(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn valid_form_passes() {
+    assert!(form().validate().is_ok());
+}
+
+#[test]
+fn invalid_forms_are_rejected() {
+    let cases = [
+        ContactForm { name: " ".to_owned(), ..form() },
+        ContactForm { email: "jane".to_owned(), ..form() },
+        ContactForm { email: "jane@example".to_owned(), ..form() },
+        ContactForm { subject: "Hi\r\nBcc: x@example.com".to_owned(), ..form() },
+        ContactForm { message: "x".repeat(MAX_MESSAGE_LEN + 1), ..form() },
+    ];
+    for case in cases {
+        assert!(matches!(case.validate(), Err(AppError::BadRequest(_))), "{case:?}");
+    }
+}
+
+#[test]
+fn filled_honeypot_is_spam() {
+    assert!(!form().is_spam());
+    assert!(!ContactForm { website: Some("  ".to_owned()), ..form() }.is_spam());
+    assert!(ContactForm { website: Some("http://spam.example".to_owned()), ..form() }.is_spam());
+}
+
+#[test]
+fn ip_hash_is_stable_and_hides_the_address() {
+    let ip = "203.0.113.9".parse().unwrap();
+    let hash = hash_ip("secret", &ip);
+    assert_eq!(hash.len(), 64);
+    assert_eq!(hash, hash_ip("secret", &ip));
+    assert!(!hash.contains("203"));
+    // Keyed: another secret gives another hash
+    assert_ne!(hash, hash_ip("other secret", &ip));
+}
+
+#[test]
+fn throttle_limits_ip_and_email() {
+    assert!(check_throttle(0, 0).is_ok());
+    assert!(check_throttle(IP_LIMIT - 1, EMAIL_LIMIT - 1).is_ok());
+    assert!(matches!(check_throttle(IP_LIMIT, 0), Err(AppError::TooManyRequests(_))));
+    assert!(matches!(check_throttle(0, EMAIL_LIMIT), Err(AppError::TooManyRequests(_))));
+}
+
+#[test]
+fn statuses_are_checked() {
+    for status in ["new", "replied", "closed"] {
+        assert!(validate_status(status).is_ok());
+    }
+    assert!(validate_status("open").is_err());
+}
+
+#[test]
+fn reply_subject_carries_the_reference_once() {
+    assert_eq!(reply_subject(42, "Group booking"), "Re: Group booking [#42]");
+    assert_eq!(reply_subject(42, "RE: Group booking"), "RE: Group booking [#42]");
+}
+
+#[test]
+fn reply_body_quotes_the_original() {
+    let at = chrono::Utc.with_ymd_and_hms(2026, 7, 14, 9, 30, 0).unwrap();
+    let original = ContactMessage {
+        id: Uuid::nil(),
+        reference: 42,
+        center_id: None,
+        name: "Jane Diver".to_owned(),
+        email: "jane@example.com".to_owned(),
+        subject: "Group booking".to_owned(),
+        message: "Hello,\n\nwe are six.".to_owned(),
+        status: "new".to_owned(),
+        user_agent: None,
+        last_reply_at: None,
+        closed_at: None,
+        created_at: at,
+        updated_at: at,
+    };
+    assert_eq!(
+        reply_body(" Sure, see you there. \n", &original),
+        "Sure, see you there.\n\n\
+         Jane Diver <jane@example.com> wrote on 2026-07-14 09:30 UTC:\n\
+         > Hello,\n\
+         >\n\
+         > we are six."
+    );
+}
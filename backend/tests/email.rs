@@ -0,0 +1,73 @@
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use evidive_api::services::email::{render, Locale, Template, Value};
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).expect("valid decimal")
+}
+
+fn sample_vars(template: Template) -> Vec<(&'static str, Value)> {
+    let mut vars: Vec<(&'static str, Value)> = template
+        .variables()
+        .iter()
+        .map(|&key| {
+            let value = match key {
+                "booking_date" | "expires_on" => Value::Date(NaiveDate::from_ymd_opt(2026, 7, 14).unwrap()),
+                "participants" => Value::Number(2),
+                "total" | "amount" => Value::Amount(dec("240"), "EUR".to_owned()),
+                other => Value::Text(format!("<{other}>")),
+            };
+            (key, value)
+        })
+        .collect();
+    vars.push(("name", "Marie".into()));
+    vars
+}
+
+#[test]
+fn every_template_renders_in_every_locale() {
+    for template in Template::ALL {
+        for locale in Locale::ALL {
+            let email = render(template, locale, &sample_vars(template))
+                .unwrap_or_else(|e| panic!("{}/{}: {e:?}", locale.code(), template.name()));
+
+            assert!(!email.subject.is_empty(), "{}/{}", locale.code(), template.name());
+            assert!(!email.subject.contains('\n'));
+            assert!(email.body.contains("Marie"), "{}/{}", locale.code(), template.name());
+            assert!(!email.subject.contains("{{") && !email.body.contains("{{"));
+        }
+    }
+}
+
+#[test]
+fn templates_differ_per_locale() {
+    let vars = sample_vars(Template::BookingConfirmed);
+    let en = render(Template::BookingConfirmed, Locale::En, &vars).unwrap();
+    let fr = render(Template::BookingConfirmed, Locale::Fr, &vars).unwrap();
+    let de = render(Template::BookingConfirmed, Locale::De, &vars).unwrap();
+
+    assert_eq!(en.subject, "Your booking at <center_name> is confirmed");
+    assert_eq!(fr.subject, "Votre réservation chez <center_name> est confirmée");
+    assert!(de.body.contains("14.07.2026"));
+    assert!(fr.body.contains("14/07/2026"));
+    assert!(fr.body.contains("240,00 EUR"));
+    assert!(en.body.contains("240.00 EUR"));
+}
+
+#[test]
+fn missing_variable_is_an_error() {
+    let vars = vec![("name", Value::from("Marie"))];
+    assert!(render(Template::CenterApproved, Locale::En, &vars).is_err());
+}
+
+#[test]
+fn preferred_locale_falls_back_to_english() {
+    assert_eq!(Locale::from_preference(Some("fr")), Locale::Fr);
+    assert_eq!(Locale::from_preference(Some("pt-BR")), Locale::Pt);
+    assert_eq!(Locale::from_preference(Some("NL")), Locale::Nl);
+    assert_eq!(Locale::from_preference(Some("ja")), Locale::En);
+    assert_eq!(Locale::from_preference(None), Locale::En);
+}
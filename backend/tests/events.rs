@@ -0,0 +1,84 @@
+use std::collections::HashSet;
+
+use uuid::Uuid;
+
+use evidive_api::services::events::{AppEvent, EventType, Subscription};
+
+fn subscription(user_id: Uuid, centers: &[Uuid]) -> Subscription {
+    Subscription {
+        user_id,
+        centers: centers.iter().copied().collect::<HashSet<_>>(),
+    }
+}
+
+fn event(kind: EventType, center_id: Option<Uuid>, user_id: Option<Uuid>) -> AppEvent {
+    AppEvent {
+        kind,
+        id: Some(Uuid::new_v4()),
+        center_id,
+        user_id,
+    }
+}
+
+#[test]
+fn trigger_payloads_parse() {
+    let center = Uuid::new_v4();
+    let payload = format!(
+        r#"{{"type": "booking.confirmed", "id": "{center}", "center_id": "{center}", "user_id": null}}"#
+    );
+    let parsed = AppEvent::parse(&payload).expect("valid payload");
+    assert_eq!(parsed.kind, EventType::BookingConfirmed);
+    assert_eq!(parsed.center_id, Some(center));
+    assert_eq!(parsed.user_id, None);
+
+    assert!(AppEvent::parse(r#"{"type": "booking.exploded", "id": null}"#).is_none());
+    assert!(AppEvent::parse("not json").is_none());
+}
+
+#[test]
+fn event_names_match_serde() {
+    for kind in [
+        EventType::BookingCreated,
+        EventType::BookingConfirmed,
+        EventType::BookingCancelled,
+        EventType::PaymentReceived,
+        EventType::ReviewCreated,
+        EventType::NotificationCreated,
+        EventType::MembershipChanged,
+        EventType::Resync,
+    ] {
+        assert_eq!(serde_json::to_value(kind).unwrap(), kind.as_str());
+    }
+}
+
+#[test]
+fn members_see_their_centers_events_only() {
+    let (me, mine, other) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let sub = subscription(me, &[mine]);
+    let client = Some(Uuid::new_v4());
+
+    assert!(sub.accepts(&event(EventType::BookingCreated, Some(mine), client)));
+    assert!(sub.accepts(&event(EventType::PaymentReceived, Some(mine), client)));
+    assert!(!sub.accepts(&event(EventType::BookingCreated, Some(other), client)));
+    assert!(!sub.accepts(&event(EventType::NotificationCreated, None, client)));
+}
+
+#[test]
+fn divers_see_their_own_events() {
+    let me = Uuid::new_v4();
+    let sub = subscription(me, &[]);
+
+    assert!(sub.accepts(&event(EventType::NotificationCreated, None, Some(me))));
+    assert!(sub.accepts(&event(EventType::BookingConfirmed, Some(Uuid::new_v4()), Some(me))));
+    assert!(sub.accepts(&AppEvent::resync()));
+}
+
+#[test]
+fn own_membership_changes_reload_centers() {
+    let me = Uuid::new_v4();
+    let sub = subscription(me, &[]);
+
+    assert!(sub.needs_reload(&event(EventType::MembershipChanged, Some(Uuid::new_v4()), Some(me))));
+    assert!(!sub.needs_reload(&event(EventType::MembershipChanged, Some(Uuid::new_v4()), Some(Uuid::new_v4()))));
+    assert!(!sub.needs_reload(&event(EventType::BookingCreated, None, Some(me))));
+}
@@ -0,0 +1,185 @@
+use std::str::FromStr;
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use evidive_api::services::fec::{
+    build_entries, center_auxiliary, convert_to_eur, file_name, render, ChartOfAccounts,
+    ExchangeRate, FecPayment, FecPayout, FecRefund,
+};
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).expect("valid decimal")
+}
+
+fn center() -> Uuid {
+    Uuid::from_str("0b7e1c2d-3f4a-4b5c-8d9e-0f1a2b3c4d5e").unwrap()
+}
+
+fn payment() -> FecPayment {
+    FecPayment {
+        transaction_id: Uuid::nil(),
+        center_id: center(),
+        center_name: "Ocean Dive".to_owned(),
+        payment_intent_id: Some("pi_1".to_owned()),
+        invoice_number: Some("EVD-F2026-000001".to_owned()),
+        amount: dec("100.00"),
+        platform_fee: dec("12.00"),
+        fee_vat: Some(dec("2.00")),
+        currency: "EUR".to_owned(),
+        destination_account: None,
+        created_at: Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap(),
+    }
+}
+
+fn refund() -> FecRefund {
+    FecRefund {
+        refund_id: Uuid::nil(),
+        center_id: center(),
+        center_name: "Ocean Dive".to_owned(),
+        stripe_refund_id: Some("re_1".to_owned()),
+        amount: dec("50.00"),
+        payment_amount: dec("100.00"),
+        payment_fee: dec("12.00"),
+        payment_fee_vat: Some(dec("2.00")),
+        destination_account: None,
+        currency: "EUR".to_owned(),
+        processed_at: Utc.with_ymd_and_hms(2026, 3, 4, 9, 0, 0).unwrap(),
+    }
+}
+
+fn payout() -> FecPayout {
+    FecPayout {
+        payout_id: Uuid::nil(),
+        center_id: center(),
+        center_name: "Ocean Dive".to_owned(),
+        stripe_transfer_id: Some("tr_1".to_owned()),
+        amount: dec("44.00"),
+        currency: "EUR".to_owned(),
+        created_at: Utc.with_ymd_and_hms(2026, 3, 3, 9, 0, 0).unwrap(),
+    }
+}
+
+#[test]
+fn chart_uses_settings_and_falls_back_to_defaults() {
+    let chart = ChartOfAccounts::from_settings(&[
+        ("fec_account_commission".to_owned(), "706100".to_owned()),
+        ("fec_journal_bank".to_owned(), "  ".to_owned()),
+    ]);
+    assert_eq!(chart.commission.number, "706100");
+    assert_eq!(chart.commission.label, "Commissions sur réservations");
+    assert_eq!(chart.bank_journal.code, "BQ");
+    assert_eq!(chart.stripe.number, "512100");
+}
+
+#[test]
+fn entries_balance_and_are_ordered_by_date() {
+    let chart = ChartOfAccounts::from_settings(&[]);
+    let entries = build_entries(&chart, &[payment()], &[refund()], &[payout()]);
+
+    assert_eq!(entries.len(), 3);
+    assert!(entries.iter().all(|e| e.is_balanced()));
+    assert_eq!(entries[0].piece_ref, "EVD-F2026-000001");
+    assert_eq!(entries[1].piece_ref, "tr_1");
+    assert_eq!(entries[2].piece_ref, "re_1");
+
+    // Half refund: 6.00 commission back, of which 1.00 VAT.
+    let refund = &entries[2];
+    let amount_for = |account: &str| {
+        refund
+            .lines
+            .iter()
+            .find(|l| l.account.number == account)
+            .map(|l| l.debit - l.credit)
+    };
+    assert_eq!(amount_for("467100"), Some(dec("44.00")));
+    assert_eq!(amount_for("709000"), Some(dec("5.00")));
+    assert_eq!(amount_for("445710"), Some(dec("1.00")));
+    assert_eq!(amount_for("512100"), Some(dec("-50.00")));
+}
+
+#[test]
+fn destination_charges_settle_the_center_account() {
+    let chart = ChartOfAccounts::from_settings(&[]);
+    let mut p = payment();
+    p.destination_account = Some("acct_1".to_owned());
+    let mut r = refund();
+    r.destination_account = Some("acct_1".to_owned());
+    let entries = build_entries(&chart, &[p], &[r], &[]);
+
+    assert_eq!(entries.len(), 4);
+    assert!(entries.iter().all(|e| e.is_balanced()));
+    let center_balance: Decimal = entries
+        .iter()
+        .flat_map(|e| &e.lines)
+        .filter(|l| l.account.number == "467100")
+        .map(|l| l.credit - l.debit)
+        .sum();
+    assert_eq!(center_balance, Decimal::ZERO);
+}
+
+#[test]
+fn untaxed_commission_has_no_vat_line() {
+    let chart = ChartOfAccounts::from_settings(&[]);
+    let mut p = payment();
+    p.fee_vat = None;
+    let entries = build_entries(&chart, &[p], &[], &[]);
+    assert_eq!(entries[0].lines.len(), 3);
+    assert!(entries[0].is_balanced());
+}
+
+#[test]
+fn render_follows_fec_layout() {
+    let chart = ChartOfAccounts::from_settings(&[]);
+    let file = render(&build_entries(&chart, &[payment()], &[], &[payout()]));
+    let lines: Vec<&str> = file.split("\r\n").filter(|l| !l.is_empty()).collect();
+
+    assert!(lines[0].starts_with("JournalCode|JournalLib|EcritureNum|"));
+    assert!(lines.iter().all(|l| l.split('|').count() == 18));
+
+    let (aux, _) = center_auxiliary(center(), "Ocean Dive");
+    assert_eq!(
+        lines[2],
+        format!(
+            "VE|Ventes|VE000001|20260302|467100|Centres partenaires|{aux}|Ocean Dive|\
+             EVD-F2026-000001|20260302|Réservation Ocean Dive - commission|0,00|88,00|||20260302||"
+        )
+    );
+    assert!(lines[5].starts_with("BQ|Banque Stripe|BQ000001|20260303|467100|"));
+}
+
+#[test]
+fn foreign_entries_are_converted_at_the_rate_of_their_date() {
+    let chart = ChartOfAccounts::from_settings(&[]);
+    let mut p = payment();
+    p.currency = "USD".to_owned();
+    let rate = |day: u32, rate: &str| ExchangeRate {
+        currency: "USD".to_owned(),
+        rate_date: NaiveDate::from_ymd_opt(2026, 2, day).unwrap(),
+        rate: dec(rate),
+    };
+    // No rate on Monday 2 March: Friday's applies.
+    let rates = [rate(26, "1.2000"), rate(27, "1.0857")];
+
+    let entries = convert_to_eur(build_entries(&chart, &[p.clone()], &[], &[]), &rates).unwrap();
+    let entry = &entries[0];
+    assert!(entry.is_balanced());
+    let stripe = entry.lines.iter().find(|l| l.account.number == "512100").unwrap();
+    // 100 / 1.0857 = 92.11, less the cent the rounded credits are short of.
+    assert_eq!((stripe.debit, stripe.foreign_amount), (dec("92.10"), Some(dec("100.00"))));
+
+    let file = render(&entries);
+    let centers = file.lines().find(|l| l.contains("|467100|")).unwrap();
+    assert!(centers.ends_with("|0,00|81,05|||20260302|88,00|USD"), "{centers}");
+
+    let early = [rate(27, "1.0857")];
+    p.created_at = Utc.with_ymd_and_hms(2026, 2, 20, 9, 0, 0).unwrap();
+    assert!(convert_to_eur(build_entries(&chart, &[p], &[], &[]), &early).is_err());
+}
+
+#[test]
+fn file_name_uses_siren_and_closing_date() {
+    let closing = NaiveDate::from_ymd_opt(2026, 12, 31).unwrap();
+    assert_eq!(file_name("123456789", closing), "123456789FEC20261231.txt");
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use http::StatusCode;
+use uuid::Uuid;
+
+use common::{call, post, test_app, test_pool};
+use evidive_api::services::invitations::{
+    check_acceptable, check_resend, generate_token, hash_token, invite_url, normalize_email,
+    Invitation, RESEND_COOLDOWN_SECONDS,
+};
+use evidive_api::AppError;
+
+fn invitation(status: &str, expires_in: Duration) -> Invitation {
+    let now = Utc::now();
+    Invitation {
+        id: Uuid::nil(),
+        center_id: Uuid::nil(),
+        center_name: "Blue Lagoon".to_owned(),
+        email: "marie@example.com".to_owned(),
+        role_in_center: "employee".to_owned(),
+        status: status.to_owned(),
+        invited_by: None,
+        inviter_name: None,
+        locale: "fr".to_owned(),
+        expires_at: now + expires_in,
+        send_count: 1,
+        last_sent_at: now - Duration::hours(1),
+        accepted_by: None,
+        accepted_at: None,
+        revoked_at: None,
+        created_at: now - Duration::hours(1),
+    }
+}
+
+#[test]
+fn tokens_are_random_and_stored_hashed() {
+    let a = generate_token();
+    let b = generate_token();
+    assert_ne!(a, b);
+    assert_eq!(a.len(), 64);
+    assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+
+    let hash = hash_token(&a);
+    assert_eq!(hash.len(), 64);
+    assert_ne!(hash, a);
+    assert_eq!(hash, hash_token(&format!(" {a}\n")));
+    assert_ne!(hash, hash_token(&b));
+
+    assert_eq!(invite_url("https://evidive.com/", &a), format!("https://evidive.com/invitations/{a}"));
+}
+
+#[test]
+fn emails_are_normalized() {
+    assert_eq!(normalize_email("  Marie@Example.COM ").unwrap(), "marie@example.com");
+    for bad in ["", "marie", "@example.com", "marie@localhost", "ma rie@example.com"] {
+        assert!(matches!(normalize_email(bad), Err(AppError::BadRequest(_))), "{bad}");
+    }
+}
+
+#[test]
+fn only_the_invited_address_accepts_an_open_invitation() {
+    let now = Utc::now();
+    let open = invitation("pending", Duration::days(3));
+
+    assert!(check_acceptable(&open, "marie@example.com", now).is_ok());
+    assert!(check_acceptable(&open, " Marie@Example.com", now).is_ok());
+    assert!(matches!(check_acceptable(&open, "paul@example.com", now), Err(AppError::BadRequest(_))));
+
+    let expired = invitation("pending", Duration::seconds(-1));
+    assert!(matches!(check_acceptable(&expired, "marie@example.com", now), Err(AppError::Conflict(_))));
+
+    for status in ["accepted", "revoked", "expired"] {
+        let closed = invitation(status, Duration::days(3));
+        assert!(matches!(check_acceptable(&closed, "marie@example.com", now), Err(AppError::Conflict(_))), "{status}");
+    }
+}
+
+#[test]
+fn resend_renews_open_invitations_but_not_too_often() {
+    let now = Utc::now();
+    assert!(check_resend(&invitation("pending", Duration::days(3)), now).is_ok());
+    assert!(check_resend(&invitation("expired", Duration::days(-1)), now).is_ok());
+
+    for status in ["accepted", "revoked"] {
+        assert!(matches!(check_resend(&invitation(status, Duration::days(3)), now), Err(AppError::Conflict(_))), "{status}");
+    }
+
+    let mut just_sent = invitation("pending", Duration::days(7));
+    just_sent.last_sent_at = now - Duration::seconds(RESEND_COOLDOWN_SECONDS - 5);
+    assert!(matches!(check_resend(&just_sent, now), Err(AppError::TooManyRequests(_))));
+}
+
+// ──────────────────────── Database ────────────────────────
+
+/// The token of the link emailed to `email`.
+async fn emailed_token(pool: &sqlx::PgPool, email: &str) -> String {
+    let body: String = sqlx::query_scalar(
+        "SELECT body FROM email_outbox WHERE to_address = $1 AND template = 'member_invitation' ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(email)
+    .fetch_one(pool)
+    .await
+    .expect("invitation email");
+    let (_, rest) = body.split_once("/invitations/").expect("invitation link");
+    rest.chars().take_while(char::is_ascii_hexdigit).collect()
+}
+
+#[tokio::test]
+async fn accepted_invitation_adds_the_member_once() {
+    let Some(pool) = test_pool().await else { return };
+    let app = test_app(&pool);
+    let (owner, invitee, other) = (
+        common::user(&pool, "owner").await,
+        common::user(&pool, "invitee").await,
+        common::user(&pool, "other").await,
+    );
+    let (center, slug) = common::center(&pool, owner).await;
+    let email = common::email_of("invitee", invitee);
+
+    let uri = format!("/api/v1/centers/{slug}/invitations");
+    let (status, body) = call(&app, post(&uri, owner, serde_json::json!({ "email": email, "role_in_center": "employee" }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{body}");
+    let invitation: Uuid = body["data"]["id"].as_str().and_then(|id| id.parse().ok()).expect("invitation id");
+    let token = emailed_token(&pool, &email).await;
+    assert_eq!(hash_token(&token), sqlx::query_scalar::<_, String>("SELECT token_hash FROM center_invitations WHERE id = $1")
+        .bind(invitation)
+        .fetch_one(&pool)
+        .await
+        .expect("invitation"));
+
+    // Only the invited address accepts.
+    let accept = format!("/api/v1/invitations/{token}/accept");
+    let (status, _) = call(&app, post(&accept, other, serde_json::json!({}))).await;
+    assert!(status.is_client_error(), "{status}");
+    let (status, body) = call(&app, post(&accept, invitee, serde_json::json!({}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let member: Uuid = body["data"]["id"].as_str().and_then(|id| id.parse().ok()).expect("member id");
+    let (status, _) = call(&app, post(&accept, invitee, serde_json::json!({}))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let role: String = sqlx::query_scalar("SELECT role_in_center FROM tli_pr_ce WHERE id = $1 AND fk_profile = $2 AND fk_center = $3")
+        .bind(member)
+        .bind(invitee)
+        .bind(center)
+        .fetch_one(&pool)
+        .await
+        .expect("membership");
+    assert_eq!(role, "employee");
+    assert_eq!(common::audit_actions(&pool, invitation).await, vec!["member.invite", "invitation.accept"]);
+    assert_eq!(common::audit_actions(&pool, member).await, vec!["member.add"]);
+
+    common::cleanup_center(&pool, center).await;
+    common::cleanup_users(&pool, &[owner, invitee, other]).await;
+}
+
+#[tokio::test]
+async fn revoked_invitation_cannot_be_accepted() {
+    let Some(pool) = test_pool().await else { return };
+    let app = test_app(&pool);
+    let (owner, invitee) = (common::user(&pool, "owner").await, common::user(&pool, "invitee").await);
+    let (center, slug) = common::center(&pool, owner).await;
+    let email = common::email_of("invitee", invitee);
+
+    let uri = format!("/api/v1/centers/{slug}/invitations");
+    let (status, body) = call(&app, post(&uri, owner, serde_json::json!({ "email": email, "role_in_center": "staff" }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{body}");
+    let invitation = body["data"]["id"].as_str().expect("invitation id").to_owned();
+    let token = emailed_token(&pool, &email).await;
+
+    let revoke = common::request("DELETE", &format!("{uri}/{invitation}"), owner, serde_json::json!({}));
+    let (status, body) = call(&app, revoke).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let (status, _) = call(&app, post(&format!("/api/v1/invitations/{token}/accept"), invitee, serde_json::json!({}))).await;
+    assert!(status.is_client_error(), "{status}");
+
+    let members: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tli_pr_ce WHERE fk_center = $1")
+        .bind(center)
+        .fetch_one(&pool)
+        .await
+        .expect("memberships");
+    assert_eq!(members, 1);
+
+    common::cleanup_center(&pool, center).await;
+    common::cleanup_users(&pool, &[owner, invitee]).await;
+}
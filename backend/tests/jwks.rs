@@ -0,0 +1,57 @@
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey};
+
+use evidive_api::middleware::jwks::{parse_jwks, JwksCache};
+
+const X: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8";
+const Y: &str = "ICEiIyQlJicoKSorLC0uLzAxMjM0NTY3ODk6Ozw9Pj8";
+
+fn ec_key(kid: &str, alg: Option<&str>) -> serde_json::Value {
+    let mut key = serde_json::json!({ "kty": "EC", "crv": "P-256", "x": X, "y": Y, "kid": kid, "use": "sig" });
+    if let Some(alg) = alg {
+        key["alg"] = alg.into();
+    }
+    key
+}
+
+fn jwks(keys: Vec<serde_json::Value>) -> JwkSet {
+    serde_json::from_value(serde_json::json!({ "keys": keys })).unwrap()
+}
+
+#[test]
+fn keys_are_indexed_by_kid() {
+    let keys = parse_jwks(&jwks(vec![ec_key("old", Some("ES256")), ec_key("new", None)]));
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys["old"].algorithm, Algorithm::ES256);
+    // Algorithm inferred from the curve when `alg` is missing.
+    assert_eq!(keys["new"].algorithm, Algorithm::ES256);
+}
+
+#[test]
+fn unusable_keys_are_skipped() {
+    let mut encryption = ec_key("enc", Some("ES256"));
+    encryption["use"] = "enc".into();
+    let symmetric = serde_json::json!({ "kty": "oct", "k": "c2VjcmV0", "alg": "HS256", "kid": "hmac" });
+
+    let keys = parse_jwks(&jwks(vec![encryption, symmetric, ec_key("sig", Some("ES256"))]));
+    assert_eq!(keys.keys().collect::<Vec<_>>(), vec!["sig"]);
+}
+
+#[test]
+fn select_uses_the_token_kid() {
+    let cache = JwksCache::with_key(Some("k1"), DecodingKey::from_secret(b"s"), Algorithm::HS256);
+    assert!(cache.select(Some("k1")).is_some());
+    assert!(cache.select(Some("k2")).is_none());
+    // Without kid, the only key is used.
+    assert!(cache.select(None).is_some());
+    assert_eq!(cache.kids(), vec!["k1"]);
+}
+
+#[tokio::test]
+async fn unreachable_jwks_is_not_fatal() {
+    // Nothing listens on the discard port.
+    let cache = JwksCache::new("http://127.0.0.1:9/jwks.json");
+    assert!(cache.refresh().await.is_err());
+    assert!(cache.key_for(Some("k1")).await.is_none());
+    assert!(cache.kids().is_empty());
+}
@@ -0,0 +1,76 @@
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use evidive_api::models::{Money, MoneyError, RoundingMode};
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).expect("valid decimal literal")
+}
+
+/// Two-decimal currencies convert to cents, rounding fractional cents
+/// instead of failing like the old `to_string().parse::<i64>()`.
+#[test]
+fn eur_to_stripe_minor_rounds_fractional_cents() {
+    let m = Money::new(dec("12.345"), "eur").expect("EUR is supported");
+    assert_eq!(m.currency(), "EUR");
+    assert_eq!(m.to_stripe_minor(RoundingMode::HalfUp), Ok(1235));
+    assert_eq!(m.to_stripe_minor(RoundingMode::HalfEven), Ok(1234));
+    assert_eq!(m.to_stripe_minor(RoundingMode::Down), Ok(1234));
+    assert_eq!(m.to_stripe_minor(RoundingMode::Up), Ok(1235));
+}
+
+/// Zero- and three-decimal currencies use their ISO 4217 exponent.
+#[test]
+fn zero_and_three_decimal_currencies() {
+    let jpy = Money::new(dec("1500.6"), "JPY").expect("JPY is supported");
+    assert_eq!(jpy.to_stripe_minor(RoundingMode::HalfUp), Ok(1501));
+
+    let bhd = Money::new(dec("10.1234"), "BHD").expect("BHD is supported");
+    assert_eq!(bhd.to_stripe_minor(RoundingMode::HalfUp), Ok(10123));
+
+    assert_eq!(
+        Money::from_stripe_minor(1501, "jpy").map(|m| m.amount()),
+        Ok(dec("1501"))
+    );
+    assert_eq!(
+        Money::from_stripe_minor(10123, "BHD").map(|m| m.amount()),
+        Ok(dec("10.123"))
+    );
+}
+
+/// ISK is zero-decimal in ISO 4217 but Stripe expects a two-decimal amount
+/// that is a multiple of 100.
+#[test]
+fn isk_uses_stripe_legacy_representation() {
+    let isk = Money::new(dec("990.4"), "ISK").expect("ISK is supported");
+    assert_eq!(isk.to_stripe_minor(RoundingMode::HalfUp), Ok(99000));
+    assert_eq!(
+        Money::from_stripe_minor(99000, "ISK").map(|m| m.amount()),
+        Ok(dec("990"))
+    );
+}
+
+#[test]
+fn commission_split_keeps_totals_consistent() {
+    let total = Money::new(dec("99.99"), "EUR").expect("EUR is supported");
+    let fee = total.percentage(dec("15"), RoundingMode::HalfUp);
+    assert_eq!(fee.amount(), dec("15.00"));
+    let vendor = total.checked_sub(&fee).expect("same currency");
+    assert_eq!(vendor.amount() + fee.amount(), total.amount());
+}
+
+#[test]
+fn unknown_and_mismatched_currencies_are_rejected() {
+    assert_eq!(
+        Money::new(Decimal::ONE, "XYZ"),
+        Err(MoneyError::UnsupportedCurrency("XYZ".to_owned()))
+    );
+
+    let eur = Money::new(Decimal::ONE, "EUR").expect("EUR is supported");
+    let usd = Money::new(Decimal::ONE, "USD").expect("USD is supported");
+    assert!(matches!(
+        eur.checked_sub(&usd),
+        Err(MoneyError::CurrencyMismatch { .. })
+    ));
+}
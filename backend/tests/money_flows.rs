@@ -0,0 +1,424 @@
+//! Checkout → webhook → payout, driven through the HTTP handlers with the
+//! in-memory payment gateway.
+//!
+//! Requires a PostgreSQL database with the platform schema (via
+//! `DATABASE_URL`); skipped when it is not set. Each test creates its own
+//! users, center and booking and removes them afterwards.
+
+mod common;
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::Router;
+use hmac::{Hmac, Mac};
+use http::{Request, StatusCode};
+use rust_decimal::Decimal;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use common::{app, call, post, test_pool, test_state, WEBHOOK_SECRET};
+use evidive_api::services::payment_gateway::InMemoryGateway;
+
+const CONNECTED_ACCOUNT: &str = "acct_test_flows";
+
+/// The fake gateway numbers its objects from 1 in every test, and session
+/// IDs are unique in the database: run the flows one at a time.
+static SERIAL: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// A Stripe webhook delivery signed with the test secret.
+fn webhook(event: serde_json::Value) -> Request<Body> {
+    let payload = event.to_string();
+    let timestamp = chrono::Utc::now().timestamp();
+    let mut mac = Hmac::<Sha256>::new_from_slice(WEBHOOK_SECRET.as_bytes()).expect("any key size");
+    mac.update(format!("{timestamp}.{payload}").as_bytes());
+    let signature = hex::encode(mac.finalize().into_bytes());
+    Request::builder()
+        .method("POST")
+        .uri("/api/v1/stripe/webhook")
+        .header("Content-Type", "application/json")
+        .header("Stripe-Signature", format!("t={timestamp},v1={signature}"))
+        .body(Body::from(payload))
+        .expect("valid request")
+}
+
+/// Stripe copies the session's metadata (`booking_id`, `destination_account`)
+/// into the event.
+fn checkout_completed(
+    session_id: &str,
+    payment_intent: &str,
+    metadata: &std::collections::HashMap<String, String>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "id": format!("evt_{}", Uuid::new_v4().simple()),
+        "object": "event",
+        "type": "checkout.session.completed",
+        "created": chrono::Utc::now().timestamp(),
+        "livemode": false,
+        "pending_webhooks": 1,
+        "data": { "object": {
+            "id": session_id,
+            "object": "checkout.session",
+            "amount_total": 12000,
+            "currency": "eur",
+            "payment_intent": payment_intent,
+            "payment_status": "paid",
+            "status": "complete",
+            "mode": "payment",
+            "metadata": metadata,
+            "livemode": false,
+            "created": chrono::Utc::now().timestamp(),
+            "expires_at": chrono::Utc::now().timestamp() + 3600,
+            "payment_method_types": ["card"],
+            "automatic_tax": { "enabled": false, "status": null },
+            "custom_fields": [],
+            "custom_text": {},
+            "shipping_options": [],
+        } },
+    })
+}
+
+struct Fixture {
+    client: Uuid,
+    owner: Uuid,
+    center: Uuid,
+    booking: Uuid,
+    /// Whether checkout routes the payment to the center (destination charge).
+    onboarded: bool,
+}
+
+/// A diver with a pending 120 EUR booking. An `onboarded` center is paid by
+/// destination charge; otherwise the platform collects the payment and the
+/// center is paid out later.
+async fn seed(pool: &sqlx::PgPool, onboarded: bool) -> Fixture {
+    let (client, owner) = (common::user(pool, "client").await, common::user(pool, "owner").await);
+    let (center, _) = common::center(pool, owner).await;
+    sqlx::query(
+        r#"
+        UPDATE centers
+        SET stripe_account_id = $2, stripe_onboarding_complete = $3, stripe_payouts_enabled = $3
+        WHERE id = $1
+        "#,
+    )
+    .bind(center)
+    .bind(CONNECTED_ACCOUNT)
+    .bind(onboarded)
+    .execute(pool)
+    .await
+    .expect("stripe account");
+    let booking = common::booking(pool, center, client).await;
+    Fixture { client, owner, center, booking, onboarded }
+}
+
+async fn cleanup(pool: &sqlx::PgPool, f: &Fixture) {
+    common::cleanup_center(pool, f.center).await;
+    common::cleanup_users(pool, &[f.client, f.owner]).await;
+}
+
+/// Pay the booking through Checkout and let the webhook record it.
+async fn pay(app: &Router, gateway: &InMemoryGateway, f: &Fixture) -> String {
+    let (status, body) = call(app, post(&format!("/api/v1/bookings/{}/checkout", f.booking), f.client, serde_json::json!({}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let sessions = gateway.checkout_sessions();
+    assert_eq!(sessions.len(), 1);
+    let destination = f.onboarded.then_some(CONNECTED_ACCOUNT);
+    assert_eq!(sessions[0].destination_account.as_deref(), destination);
+    assert_eq!(sessions[0].metadata.get("booking_id"), Some(&f.booking.to_string()));
+
+    let payment_intent = format!("pi_test_{}", f.booking.simple());
+    let event = checkout_completed("cs_test_000001", &payment_intent, &sessions[0].metadata);
+    let (status, _) = call(app, webhook(event.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    // Stripe delivers at least once.
+    let (status, _) = call(app, webhook(event)).await;
+    assert_eq!(status, StatusCode::OK);
+    payment_intent
+}
+
+#[tokio::test]
+async fn checkout_webhook_records_the_payment_once() {
+    let _serial = SERIAL.lock().await;
+    let Some(pool) = test_pool().await else { return };
+    let gateway = Arc::new(InMemoryGateway::new());
+    let app = app(test_state(pool.clone(), gateway.clone()));
+    let f = seed(&pool, true).await;
+
+    let payment_intent = pay(&app, &gateway, &f).await;
+
+    let rows: Vec<(Decimal, Decimal, Decimal)> = sqlx::query_as(
+        "SELECT amount, platform_fee, vendor_amount FROM transactions WHERE stripe_payment_intent_id = $1",
+    )
+    .bind(&payment_intent)
+    .fetch_all(&pool)
+    .await
+    .expect("transactions");
+    assert_eq!(rows, vec![(Decimal::new(12000, 2), Decimal::new(2400, 2), Decimal::new(9600, 2))]);
+
+    cleanup(&pool, &f).await;
+}
+
+#[tokio::test]
+async fn payment_to_a_center_of_unknown_country_is_recorded_untaxed() {
+    let _serial = SERIAL.lock().await;
+    let Some(pool) = test_pool().await else { return };
+    let gateway = Arc::new(InMemoryGateway::new());
+    let app = app(test_state(pool.clone(), gateway.clone()));
+    let f = seed(&pool, true).await;
+    sqlx::query("UPDATE centers SET country = 'ZZ' WHERE id = $1")
+        .bind(f.center)
+        .execute(&pool)
+        .await
+        .expect("unknown country");
+
+    let payment_intent = pay(&app, &gateway, &f).await;
+
+    let (amount, tax_country, price_vat): (Decimal, Option<String>, Option<Decimal>) = sqlx::query_as(
+        "SELECT amount, tax_country, price_vat FROM transactions WHERE stripe_payment_intent_id = $1",
+    )
+    .bind(&payment_intent)
+    .fetch_one(&pool)
+    .await
+    .expect("transaction");
+    assert_eq!((amount, tax_country, price_vat), (Decimal::new(12000, 2), None, None));
+
+    cleanup(&pool, &f).await;
+}
+
+/// The dive took place, and the center finished its Stripe onboarding after
+/// the diver paid the platform.
+async fn complete_and_onboard(pool: &sqlx::PgPool, f: &Fixture) {
+    sqlx::query("UPDATE bookings SET status = 'completed'::booking_status, completed_at = NOW() WHERE id = $1")
+        .bind(f.booking)
+        .execute(pool)
+        .await
+        .expect("complete booking");
+    sqlx::query("UPDATE centers SET stripe_onboarding_complete = true, stripe_payouts_enabled = true WHERE id = $1")
+        .bind(f.center)
+        .execute(pool)
+        .await
+        .expect("onboard center");
+}
+
+#[tokio::test]
+async fn payout_is_reserved_and_sent_with_its_idempotency_key() {
+    let _serial = SERIAL.lock().await;
+    let Some(pool) = test_pool().await else { return };
+    let gateway = Arc::new(InMemoryGateway::new());
+    let app = app(test_state(pool.clone(), gateway.clone()));
+    let f = seed(&pool, false).await;
+    pay(&app, &gateway, &f).await;
+    complete_and_onboard(&pool, &f).await;
+
+    let request = serde_json::json!({ "center_id": f.center, "amount": "96.00", "currency": "EUR" });
+    let (status, body) = call(&app, post("/api/v1/payouts/request", f.owner, request.clone())).await;
+    assert_eq!(status, StatusCode::CREATED, "{body}");
+
+    let (payout_id, transfer_id): (Uuid, Option<String>) =
+        sqlx::query_as("SELECT id, stripe_transfer_id FROM payouts WHERE center_id = $1")
+            .bind(f.center)
+            .fetch_one(&pool)
+            .await
+            .expect("payout recorded");
+    let transfers = gateway.transfers();
+    assert_eq!(transfers.len(), 1);
+    assert_eq!(transfers[0].destination_account, CONNECTED_ACCOUNT);
+    assert_eq!(transfers[0].amount.amount(), Decimal::new(9600, 2));
+    assert_eq!(transfers[0].idempotency_key, Some(format!("payout-{payout_id}")));
+    assert_eq!(transfer_id.as_deref(), Some(body["data"]["transfer_id"].as_str().expect("transfer id")));
+
+    // The balance is spent.
+    let (status, _) = call(&app, post("/api/v1/payouts/request", f.owner, request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(gateway.transfers().len(), 1);
+
+    cleanup(&pool, &f).await;
+}
+
+#[tokio::test]
+async fn payout_with_unknown_outcome_is_retried_without_a_second_transfer() {
+    let _serial = SERIAL.lock().await;
+    let Some(pool) = test_pool().await else { return };
+    let gateway = Arc::new(InMemoryGateway::new());
+    let app = app(test_state(pool.clone(), gateway.clone()));
+    let f = seed(&pool, false).await;
+    pay(&app, &gateway, &f).await;
+    complete_and_onboard(&pool, &f).await;
+
+    // Stripe creates the transfer but the response never arrives.
+    gateway.lose_responses(true);
+    let request = serde_json::json!({ "center_id": f.center, "amount": "50.00" });
+    let (status, _) = call(&app, post("/api/v1/payouts/request", f.owner, request)).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+    // The transfer may exist: the amount stays reserved, not failed.
+    let (payout_id, status, transfer_id): (Uuid, String, Option<String>) =
+        sqlx::query_as("SELECT id, status, stripe_transfer_id FROM payouts WHERE center_id = $1")
+            .bind(f.center)
+            .fetch_one(&pool)
+            .await
+            .expect("payout reserved");
+    assert_eq!((status.as_str(), transfer_id), ("created", None));
+    let available = evidive_api::services::payouts::available_balance(&pool, f.center, "EUR")
+        .await
+        .expect("balance");
+    assert_eq!(available, Decimal::new(4600, 2));
+
+    // The job resumes it with the same key and gets the same transfer back.
+    gateway.lose_responses(false);
+    sqlx::query("UPDATE payouts SET created_at = NOW() - INTERVAL '1 hour' WHERE id = $1")
+        .bind(payout_id)
+        .execute(&pool)
+        .await
+        .expect("age payout");
+    let today = chrono::Utc::now().date_naive();
+    evidive_api::services::payouts::run_scheduled(&pool, gateway.as_ref(), today)
+        .await
+        .expect("job runs");
+
+    let transfers = gateway.transfers();
+    assert_eq!(transfers.len(), 2);
+    assert!(transfers.iter().all(|t| t.idempotency_key == Some(format!("payout-{payout_id}"))));
+    let created = gateway.created_transfers();
+    assert_eq!(created.len(), 1);
+    let transfer_id: Option<String> = sqlx::query_scalar("SELECT stripe_transfer_id FROM payouts WHERE id = $1")
+        .bind(payout_id)
+        .fetch_one(&pool)
+        .await
+        .expect("payout");
+    assert_eq!(transfer_id, Some(created[0].clone()));
+
+    cleanup(&pool, &f).await;
+}
+
+#[tokio::test]
+async fn refund_with_unknown_outcome_is_retried_without_a_second_refund() {
+    let _serial = SERIAL.lock().await;
+    let Some(pool) = test_pool().await else { return };
+    let gateway = Arc::new(InMemoryGateway::new());
+    let app = app(test_state(pool.clone(), gateway.clone()));
+    let f = seed(&pool, true).await;
+    let payment_intent = pay(&app, &gateway, &f).await;
+
+    // The owner doubles as the platform admin approving the refund.
+    sqlx::query("UPDATE profiles SET role = 'admin_diver' WHERE id = $1")
+        .bind(f.owner)
+        .execute(&pool)
+        .await
+        .expect("admin");
+    let refund_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO refunds (booking_id, amount, currency) VALUES ($1, 50, 'EUR') RETURNING id",
+    )
+    .bind(f.booking)
+    .fetch_one(&pool)
+    .await
+    .expect("refund requested");
+    let approve = format!("/api/v1/admin/refunds/{refund_id}/approve");
+
+    gateway.lose_responses(true);
+    let (status, _) = call(&app, post(&approve, f.owner, serde_json::json!({}))).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    let status: String = sqlx::query_scalar("SELECT status FROM refunds WHERE id = $1")
+        .bind(refund_id)
+        .fetch_one(&pool)
+        .await
+        .expect("refund");
+    assert_eq!(status, "processing");
+
+    gateway.lose_responses(false);
+    let (status, body) = call(&app, post(&approve, f.owner, serde_json::json!({}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let refunds = gateway.refunds();
+    assert_eq!(refunds.len(), 2);
+    assert!(refunds.iter().all(|r| r.payment_intent_id == payment_intent
+        && r.idempotency_key == Some(format!("refund-{refund_id}"))));
+    let created = gateway.created_refunds();
+    assert_eq!(created.len(), 1);
+    let (status, stripe_refund_id): (String, Option<String>) =
+        sqlx::query_as("SELECT status, stripe_refund_id FROM refunds WHERE id = $1")
+            .bind(refund_id)
+            .fetch_one(&pool)
+            .await
+            .expect("refund");
+    assert_eq!((status.as_str(), stripe_refund_id), ("approved", Some(created[0].clone())));
+
+    cleanup(&pool, &f).await;
+}
+
+#[tokio::test]
+async fn destination_charge_is_never_paid_out_again() {
+    let _serial = SERIAL.lock().await;
+    let Some(pool) = test_pool().await else { return };
+    let gateway = Arc::new(InMemoryGateway::new());
+    let app = app(test_state(pool.clone(), gateway.clone()));
+    let f = seed(&pool, true).await;
+    let payment_intent = pay(&app, &gateway, &f).await;
+    complete_and_onboard(&pool, &f).await;
+
+    let destination: Option<String> = sqlx::query_scalar(
+        "SELECT stripe_destination_account FROM transactions WHERE stripe_payment_intent_id = $1",
+    )
+    .bind(&payment_intent)
+    .fetch_one(&pool)
+    .await
+    .expect("transaction");
+    assert_eq!(destination.as_deref(), Some(CONNECTED_ACCOUNT));
+
+    let available = evidive_api::services::payouts::available_balance(&pool, f.center, "EUR")
+        .await
+        .expect("balance");
+    assert_eq!(available, Decimal::ZERO);
+
+    let request = serde_json::json!({ "center_id": f.center, "amount": "96.00" });
+    let (status, _) = call(&app, post("/api/v1/payouts/request", f.owner, request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Weekly on Mondays: due every day of the week.
+    sqlx::query("INSERT INTO center_payout_schedules (center_id, frequency, weekday) VALUES ($1, 'weekly', 1)")
+        .bind(f.center)
+        .execute(&pool)
+        .await
+        .expect("schedule");
+    let today = chrono::Utc::now().date_naive();
+    let run = evidive_api::services::payouts::run_scheduled(&pool, gateway.as_ref(), today)
+        .await
+        .expect("job runs");
+    assert!(run.skipped.iter().any(|s| s.center_id == f.center && s.reason == "below_minimum"));
+    assert!(gateway.transfers().is_empty());
+
+    sqlx::query("DELETE FROM center_payout_schedules WHERE center_id = $1")
+        .bind(f.center)
+        .execute(&pool)
+        .await
+        .expect("schedule removed");
+    cleanup(&pool, &f).await;
+}
+
+#[tokio::test]
+async fn refunds_reduce_the_balance_by_the_center_share() {
+    let _serial = SERIAL.lock().await;
+    let Some(pool) = test_pool().await else { return };
+    let gateway = Arc::new(InMemoryGateway::new());
+    let app = app(test_state(pool.clone(), gateway.clone()));
+    let f = seed(&pool, false).await;
+    pay(&app, &gateway, &f).await;
+    complete_and_onboard(&pool, &f).await;
+
+    for status in ["approved", "processing", "pending", "rejected"] {
+        sqlx::query("INSERT INTO refunds (booking_id, amount, currency, status) VALUES ($1, 30, 'EUR', $2)")
+            .bind(f.booking)
+            .bind(status)
+            .execute(&pool)
+            .await
+            .expect("refund");
+    }
+
+    // 96 of revenue, less 80 % of the 60 refunded or being refunded.
+    let available = evidive_api::services::payouts::available_balance(&pool, f.center, "EUR")
+        .await
+        .expect("balance");
+    assert_eq!(available, Decimal::new(4800, 2));
+
+    cleanup(&pool, &f).await;
+}
@@ -0,0 +1,68 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use evidive_api::services::email::{Locale, Value};
+use evidive_api::services::notifications::{booking_link, render, Kind};
+
+fn sample_vars(kind: Kind) -> Vec<(&'static str, Value)> {
+    kind.variables()
+        .iter()
+        .map(|&key| {
+            let value = match key {
+                "booking_date" => Value::Date(NaiveDate::from_ymd_opt(2026, 7, 14).unwrap()),
+                "rating" => Value::Number(4),
+                "amount" => Value::Amount(Decimal::new(125050, 2), "EUR".to_owned()),
+                other => Value::Text(format!("<{other}>")),
+            };
+            (key, value)
+        })
+        .collect()
+}
+
+#[test]
+fn every_kind_renders_in_every_locale() {
+    for kind in Kind::ALL {
+        for locale in Locale::ALL {
+            let n = render(kind, locale, &sample_vars(kind))
+                .unwrap_or_else(|e| panic!("{}/{}: {e:?}", locale.code(), kind.as_str()));
+            assert!(!n.title.is_empty(), "{}/{}", locale.code(), kind.as_str());
+            assert!(!n.title.contains("{{") && !n.body.contains("{{"));
+        }
+    }
+}
+
+#[test]
+fn notifications_are_localized() {
+    let vars = sample_vars(Kind::PayoutSent);
+    let en = render(Kind::PayoutSent, Locale::En, &vars).unwrap();
+    let fr = render(Kind::PayoutSent, Locale::Fr, &vars).unwrap();
+
+    assert_eq!(en.body, "A payout of 1250.50 EUR was sent to <center_name>.");
+    assert_eq!(fr.body, "Un virement de 1250,50 EUR a été envoyé à <center_name>.");
+
+    let vars = sample_vars(Kind::BookingConfirmed);
+    let de = render(Kind::BookingConfirmed, Locale::De, &vars).unwrap();
+    assert!(de.body.contains("14.07.2026"));
+}
+
+#[test]
+fn missing_variable_is_an_error() {
+    assert!(render(Kind::ReviewReceived, Locale::En, &[("center_name", "Blue".into())]).is_err());
+}
+
+#[test]
+fn kinds_are_snake_case() {
+    for kind in Kind::ALL {
+        assert_eq!(serde_json::to_value(kind).unwrap(), kind.as_str());
+    }
+}
+
+#[test]
+fn booking_links_point_at_the_dashboard() {
+    let id = Uuid::nil();
+    assert_eq!(
+        booking_link(id),
+        "/dashboard/bookings?booking_id=00000000-0000-0000-0000-000000000000"
+    );
+}
@@ -0,0 +1,24 @@
+use chrono::Duration;
+
+use evidive_api::services::outbox::retry_delay;
+
+#[test]
+fn retries_back_off_exponentially() {
+    assert_eq!(retry_delay(1, 8), Some(Duration::minutes(1)));
+    assert_eq!(retry_delay(2, 8), Some(Duration::minutes(2)));
+    assert_eq!(retry_delay(3, 8), Some(Duration::minutes(4)));
+    assert_eq!(retry_delay(7, 8), Some(Duration::minutes(64)));
+}
+
+#[test]
+fn backoff_is_capped() {
+    assert_eq!(retry_delay(15, 20), Some(Duration::hours(6)));
+    assert_eq!(retry_delay(1_000, 2_000), Some(Duration::hours(6)));
+}
+
+#[test]
+fn last_attempt_goes_to_dead_letters() {
+    assert_eq!(retry_delay(8, 8), None);
+    assert_eq!(retry_delay(9, 8), None);
+    assert!(retry_delay(0, 1).is_some());
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use http::StatusCode;
+use uuid::Uuid;
+
+use common::{call, post, test_app, test_pool};
+use evidive_api::services::ownership::{check_action, Action, OwnershipTransfer, PREVIOUS_OWNER_ROLE};
+use evidive_api::AppError;
+
+const OWNER: Uuid = Uuid::from_u128(1);
+const RECIPIENT: Uuid = Uuid::from_u128(2);
+const OTHER: Uuid = Uuid::from_u128(3);
+
+fn transfer(status: &str, expires_in: Duration) -> OwnershipTransfer {
+    let now = Utc::now();
+    OwnershipTransfer {
+        id: Uuid::nil(),
+        center_id: Uuid::nil(),
+        center_name: "Blue Lagoon".to_owned(),
+        from_profile: OWNER,
+        from_name: None,
+        to_profile: RECIPIENT,
+        to_name: None,
+        status: status.to_owned(),
+        message: None,
+        previous_stripe_account_id: None,
+        expires_at: now + expires_in,
+        responded_at: None,
+        created_at: now,
+    }
+}
+
+#[test]
+fn recipient_answers_and_initiator_cancels() {
+    let t = transfer("pending", Duration::days(1));
+    let now = Utc::now();
+
+    assert!(check_action(&t, RECIPIENT, Action::Accept, now).is_ok());
+    assert!(check_action(&t, RECIPIENT, Action::Decline, now).is_ok());
+    assert!(check_action(&t, OWNER, Action::Cancel, now).is_ok());
+
+    assert!(matches!(check_action(&t, OWNER, Action::Accept, now), Err(AppError::Forbidden)));
+    assert!(matches!(check_action(&t, RECIPIENT, Action::Cancel, now), Err(AppError::Forbidden)));
+    assert!(matches!(check_action(&t, OTHER, Action::Decline, now), Err(AppError::Forbidden)));
+}
+
+#[test]
+fn answered_transfers_are_final() {
+    let now = Utc::now();
+    for status in ["accepted", "declined", "cancelled", "expired"] {
+        let t = transfer(status, Duration::days(1));
+        assert!(matches!(check_action(&t, RECIPIENT, Action::Accept, now), Err(AppError::Conflict(_))), "{status}");
+    }
+}
+
+#[test]
+fn expired_offers_cannot_be_accepted_but_can_be_cancelled() {
+    let t = transfer("pending", Duration::seconds(-1));
+    let now = Utc::now();
+
+    assert!(matches!(check_action(&t, RECIPIENT, Action::Accept, now), Err(AppError::Conflict(_))));
+    assert!(check_action(&t, OWNER, Action::Cancel, now).is_ok());
+}
+
+#[test]
+fn actions_store_their_status() {
+    assert_eq!(Action::Accept.status(), "accepted");
+    assert_eq!(Action::Decline.status(), "declined");
+    assert_eq!(Action::Cancel.status(), "cancelled");
+}
+
+// ──────────────────────── Database ────────────────────────
+
+async fn role(pool: &sqlx::PgPool, member: Uuid) -> String {
+    sqlx::query_scalar("SELECT role_in_center FROM tli_pr_ce WHERE id = $1")
+        .bind(member)
+        .fetch_one(pool)
+        .await
+        .expect("membership")
+}
+
+async fn owner_of(pool: &sqlx::PgPool, center: Uuid) -> Uuid {
+    sqlx::query_scalar("SELECT owner_id FROM centers WHERE id = $1")
+        .bind(center)
+        .fetch_one(pool)
+        .await
+        .expect("center")
+}
+
+/// The owner offers the center to an employee. Returns the transfer id.
+async fn offer(app: &axum::Router, slug: &str, owner: Uuid, member: Uuid) -> Uuid {
+    let uri = format!("/api/v1/centers/{slug}/ownership-transfers");
+    let (status, body) = call(app, post(&uri, owner, serde_json::json!({ "member_id": member }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{body}");
+    body["data"]["id"].as_str().and_then(|id| id.parse().ok()).expect("transfer id")
+}
+
+#[tokio::test]
+async fn accepted_transfer_swaps_owner_and_roles_and_is_audited() {
+    let Some(pool) = test_pool().await else { return };
+    let app = test_app(&pool);
+    let (owner, employee) = (common::user(&pool, "owner").await, common::user(&pool, "employee").await);
+    let (center, slug) = common::center(&pool, owner).await;
+    let owner_member: Uuid = sqlx::query_scalar("SELECT id FROM tli_pr_ce WHERE fk_center = $1 AND fk_profile = $2")
+        .bind(center)
+        .bind(owner)
+        .fetch_one(&pool)
+        .await
+        .expect("owner membership");
+    let employee_member = common::member(&pool, center, employee, "employee").await;
+
+    let transfer = offer(&app, &slug, owner, employee_member).await;
+    // Only the recipient answers.
+    let (status, _) = call(&app, post(&format!("/api/v1/ownership-transfers/{transfer}/accept"), owner, serde_json::json!({}))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, body) = call(&app, post(&format!("/api/v1/ownership-transfers/{transfer}/accept"), employee, serde_json::json!({}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    assert_eq!(owner_of(&pool, center).await, employee);
+    assert_eq!(role(&pool, employee_member).await, "owner");
+    assert_eq!(role(&pool, owner_member).await, PREVIOUS_OWNER_ROLE);
+    assert_eq!(
+        common::audit_actions(&pool, transfer).await,
+        vec!["ownership_transfer.offer", "ownership_transfer.accepted"]
+    );
+    assert_eq!(common::audit_actions(&pool, center).await, vec!["center.transfer_ownership"]);
+    assert_eq!(common::audit_actions(&pool, employee_member).await, vec!["member.update_role"]);
+    assert_eq!(common::audit_actions(&pool, owner_member).await, vec!["member.update_role"]);
+
+    common::cleanup_center(&pool, center).await;
+    common::cleanup_users(&pool, &[owner, employee]).await;
+}
+
+#[tokio::test]
+async fn declined_transfer_keeps_the_owner() {
+    let Some(pool) = test_pool().await else { return };
+    let app = test_app(&pool);
+    let (owner, employee) = (common::user(&pool, "owner").await, common::user(&pool, "employee").await);
+    let (center, slug) = common::center(&pool, owner).await;
+    let employee_member = common::member(&pool, center, employee, "employee").await;
+
+    let transfer = offer(&app, &slug, owner, employee_member).await;
+    let (status, body) = call(&app, post(&format!("/api/v1/ownership-transfers/{transfer}/decline"), employee, serde_json::json!({}))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    // Answered offers are final.
+    let (status, _) = call(&app, post(&format!("/api/v1/ownership-transfers/{transfer}/accept"), employee, serde_json::json!({}))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    assert_eq!(owner_of(&pool, center).await, owner);
+    assert_eq!(role(&pool, employee_member).await, "employee");
+    assert_eq!(
+        common::audit_actions(&pool, transfer).await,
+        vec!["ownership_transfer.offer", "ownership_transfer.declined"]
+    );
+
+    common::cleanup_center(&pool, center).await;
+    common::cleanup_users(&pool, &[owner, employee]).await;
+}
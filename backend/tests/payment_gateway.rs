@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use evidive_api::models::Money;
+use evidive_api::services::payment_gateway::{
+    CheckoutRequest, GatewayCall, GatewayError, InMemoryGateway, PaymentGateway,
+    PaymentIntentRequest, RefundRequest, TransferRequest,
+};
+
+fn eur(s: &str) -> Money {
+    Money::new(Decimal::from_str(s).expect("valid decimal"), "EUR").expect("EUR is supported")
+}
+
+fn checkout_request() -> CheckoutRequest {
+    CheckoutRequest {
+        amount: eur("120.00"),
+        product_name: "Baptême".to_owned(),
+        destination_account: Some("acct_center".to_owned()),
+        application_fee: Some(eur("24.00")),
+        success_url: "https://app.test/success".to_owned(),
+        cancel_url: "https://app.test/cancel".to_owned(),
+        metadata: HashMap::from([("booking_id".to_owned(), "b-1".to_owned())]),
+    }
+}
+
+/// Checkout, payout and refund go through the fake with sequential IDs and
+/// are recorded in call order.
+#[tokio::test]
+async fn fake_gateway_records_money_flow() {
+    let gateway = InMemoryGateway::new();
+
+    let session = gateway
+        .create_checkout_session(checkout_request())
+        .await
+        .expect("checkout succeeds");
+    assert_eq!(session.id, "cs_test_000001");
+    assert_eq!(session.url, "https://checkout.test/cs_test_000001");
+
+    let transfer = gateway
+        .create_transfer(TransferRequest {
+            amount: eur("96.00"),
+            destination_account: "acct_center".to_owned(),
+            description: None,
+            metadata: HashMap::new(),
+            idempotency_key: None,
+        })
+        .await
+        .expect("transfer succeeds");
+    assert_eq!(transfer.id, "tr_test_000002");
+
+    let refund = gateway
+        .create_refund(RefundRequest {
+            payment_intent_id: "pi_123".to_owned(),
+            amount: None,
+            reverse_transfer: true,
+            metadata: HashMap::new(),
+            idempotency_key: Some("refund-1".to_owned()),
+        })
+        .await
+        .expect("refund succeeds");
+    assert_eq!(refund.id, "re_test_000003");
+
+    assert_eq!(gateway.checkout_sessions(), vec![checkout_request()]);
+    assert_eq!(gateway.transfers()[0].amount, eur("96.00"));
+    assert_eq!(gateway.refunds().len(), 1);
+    assert_eq!(gateway.calls().len(), 3);
+}
+
+/// Injected failures surface as provider errors but are still recorded.
+#[tokio::test]
+async fn fake_gateway_can_fail() {
+    let gateway = InMemoryGateway::new();
+    gateway.fail_with(Some("card_declined"));
+
+    let err = gateway
+        .create_connect_account()
+        .await
+        .expect_err("configured to fail");
+    assert_eq!(err, GatewayError::Provider("card_declined".to_owned()));
+    assert_eq!(gateway.calls(), vec![GatewayCall::ConnectAccount]);
+
+    gateway.fail_with(None);
+    assert_eq!(
+        gateway.create_connect_account().await,
+        Ok("acct_test_000001".to_owned())
+    );
+}
+
+/// Only declines tell the caller nothing happened at the provider.
+#[tokio::test]
+async fn declines_are_told_apart_from_unknown_outcomes() {
+    let gateway = InMemoryGateway::new();
+    gateway.decline_with(Some("balance_insufficient"));
+    let err = gateway.create_connect_account().await.expect_err("declined");
+    assert_eq!(err, GatewayError::Declined("balance_insufficient".to_owned()));
+    assert!(err.is_declined());
+
+    gateway.fail_with(Some("timeout"));
+    let err = gateway.create_connect_account().await.expect_err("failed");
+    assert!(!err.is_declined());
+    assert!(GatewayError::InvalidRequest("bad amount".to_owned()).is_declined());
+}
+
+#[tokio::test]
+async fn fake_gateway_tracks_payment_intents() {
+    let gateway = InMemoryGateway::new();
+    let intent = gateway
+        .create_payment_intent(PaymentIntentRequest {
+            amount: eur("120.00"),
+            description: "Baptême".to_owned(),
+            destination_account: Some("acct_center".to_owned()),
+            application_fee: Some(eur("24.00")),
+            metadata: HashMap::from([("booking_id".to_owned(), "b-1".to_owned())]),
+        })
+        .await
+        .unwrap();
+
+    assert!(intent.is_payable());
+    assert!(intent.client_secret.starts_with(&intent.id));
+    assert_eq!(gateway.payment_intents()[0].application_fee, Some(eur("24.00")));
+
+    let fetched = gateway.retrieve_payment_intent(&intent.id).await.unwrap();
+    assert_eq!(fetched.amount, eur("120"));
+
+    gateway.set_payment_intent_status(&intent.id, "succeeded");
+    assert!(!gateway.retrieve_payment_intent(&intent.id).await.unwrap().is_payable());
+    assert!(gateway.cancel_payment_intent(&intent.id).await.is_err());
+}
+
+/// A repeated idempotency key returns the first object, even when the first
+/// response was lost.
+#[tokio::test]
+async fn fake_gateway_honours_idempotency_keys() {
+    let gateway = InMemoryGateway::new();
+    let transfer = |key: &str| TransferRequest {
+        amount: eur("96.00"),
+        destination_account: "acct_center".to_owned(),
+        description: None,
+        metadata: HashMap::new(),
+        idempotency_key: Some(key.to_owned()),
+    };
+
+    gateway.lose_responses(true);
+    let err = gateway.create_transfer(transfer("payout-1")).await.expect_err("response lost");
+    assert!(!err.is_declined());
+    gateway.lose_responses(false);
+
+    let first = gateway.create_transfer(transfer("payout-1")).await.unwrap();
+    let again = gateway.create_transfer(transfer("payout-1")).await.unwrap();
+    let other = gateway.create_transfer(transfer("payout-2")).await.unwrap();
+    assert_eq!(first, again);
+    assert_ne!(first, other);
+    assert_eq!(gateway.transfers().len(), 4);
+    assert_eq!(gateway.created_transfers(), vec![first.id, other.id]);
+
+    let refund = RefundRequest {
+        payment_intent_id: "pi_123".to_owned(),
+        amount: Some(eur("50.00")),
+        reverse_transfer: true,
+        metadata: HashMap::new(),
+        idempotency_key: Some("refund-1".to_owned()),
+    };
+    let first = gateway.create_refund(refund.clone()).await.unwrap();
+    let again = gateway.create_refund(refund).await.unwrap();
+    assert_eq!(first.id, again.id);
+    assert_eq!(gateway.created_refunds(), vec![first.id]);
+}
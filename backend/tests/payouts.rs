@@ -0,0 +1,112 @@
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use evidive_api::services::payouts::{is_open_dispute, PayoutFrequency, PayoutSchedule};
+use evidive_api::AppError;
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).expect("valid decimal")
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).expect("valid date")
+}
+
+fn schedule(frequency: PayoutFrequency, weekday: Option<i16>, day: Option<i16>) -> PayoutSchedule {
+    PayoutSchedule {
+        frequency,
+        weekday,
+        day_of_month: day,
+        minimum_amount: dec("50.00"),
+        ..PayoutSchedule::manual(Uuid::nil())
+    }
+}
+
+#[test]
+fn manual_schedule_is_never_due() {
+    let s = PayoutSchedule::manual(Uuid::nil());
+    assert_eq!(s.due_period(date(2026, 10, 19)), None);
+    assert!(s.validate().is_ok());
+}
+
+#[test]
+fn weekly_payout_is_due_from_the_scheduled_weekday() {
+    // Wednesday
+    let s = schedule(PayoutFrequency::Weekly, Some(3), None);
+
+    assert_eq!(s.due_period(date(2026, 10, 13)), None); // Tuesday
+    assert_eq!(s.due_period(date(2026, 10, 14)).as_deref(), Some("2026-W42"));
+    // A missed run is caught up later in the same week, under the same key.
+    assert_eq!(s.due_period(date(2026, 10, 18)).as_deref(), Some("2026-W42"));
+    assert_eq!(s.due_period(date(2026, 10, 19)), None);
+}
+
+#[test]
+fn weekly_period_uses_the_iso_year() {
+    let s = schedule(PayoutFrequency::Weekly, Some(1), None);
+    // Monday 29 December 2025 belongs to ISO week 2026-W01.
+    assert_eq!(s.due_period(date(2025, 12, 29)).as_deref(), Some("2026-W01"));
+}
+
+#[test]
+fn monthly_payout_is_due_from_the_scheduled_day() {
+    let s = schedule(PayoutFrequency::Monthly, None, Some(5));
+
+    assert_eq!(s.due_period(date(2026, 10, 4)), None);
+    assert_eq!(s.due_period(date(2026, 10, 5)).as_deref(), Some("2026-10"));
+    assert_eq!(s.due_period(date(2026, 10, 31)).as_deref(), Some("2026-10"));
+}
+
+#[test]
+fn minimum_threshold_blocks_small_balances() {
+    let s = schedule(PayoutFrequency::Monthly, None, Some(1));
+    assert!(!s.meets_minimum(dec("49.99")));
+    assert!(s.meets_minimum(dec("50.00")));
+
+    let no_minimum = PayoutSchedule {
+        minimum_amount: Decimal::ZERO,
+        ..s
+    };
+    assert!(!no_minimum.meets_minimum(Decimal::ZERO));
+    assert!(!no_minimum.meets_minimum(dec("-10.00")));
+    assert!(no_minimum.meets_minimum(dec("0.01")));
+}
+
+#[test]
+fn schedules_need_their_day() {
+    for s in [
+        schedule(PayoutFrequency::Weekly, None, None),
+        schedule(PayoutFrequency::Weekly, Some(8), None),
+        schedule(PayoutFrequency::Monthly, None, Some(29)),
+        schedule(PayoutFrequency::Monthly, Some(1), None),
+    ] {
+        assert!(matches!(s.validate(), Err(AppError::BadRequest(_))), "{s:?}");
+    }
+
+    let negative = PayoutSchedule {
+        minimum_amount: dec("-1"),
+        ..schedule(PayoutFrequency::Weekly, Some(1), None)
+    };
+    assert!(matches!(negative.validate(), Err(AppError::BadRequest(_))));
+    assert!(schedule(PayoutFrequency::Monthly, None, Some(28)).validate().is_ok());
+}
+
+#[test]
+fn frequency_round_trips() {
+    for f in [PayoutFrequency::Manual, PayoutFrequency::Weekly, PayoutFrequency::Monthly] {
+        assert_eq!(PayoutFrequency::parse(f.as_str()), Some(f));
+    }
+    assert_eq!(PayoutFrequency::parse("daily"), None);
+}
+
+#[test]
+fn only_unresolved_disputes_hold_payouts() {
+    assert!(is_open_dispute("needs_response"));
+    assert!(is_open_dispute("warning_under_review"));
+    assert!(!is_open_dispute("won"));
+    assert!(!is_open_dispute("lost"));
+    assert!(!is_open_dispute("warning_closed"));
+}
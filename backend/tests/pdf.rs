@@ -0,0 +1,28 @@
+use evidive_api::services::pdf::PdfBuilder;
+
+/// The writer emits a complete single-page document with a valid trailer.
+#[test]
+fn pdf_builder_produces_complete_document() {
+    let mut pdf = PdfBuilder::new();
+    pdf.heading("Reçu de paiement / Payment receipt")
+        .row("Total (EUR)", "120.00 €");
+    let bytes = pdf.finish();
+
+    assert!(bytes.starts_with(b"%PDF-1.4"));
+    assert!(bytes.ends_with(b"%%EOF\n"));
+    // "€" is encoded as WinAnsi 0x80, not as UTF-8.
+    assert!(bytes.windows(3).any(|w| w == b"0 \x80"));
+    assert!(!bytes.windows(3).any(|w| w == "€".as_bytes()));
+}
+
+/// Content longer than one page spills onto additional pages.
+#[test]
+fn pdf_builder_breaks_pages() {
+    let mut pdf = PdfBuilder::new();
+    for i in 0..120 {
+        pdf.line(&format!("Line {i}"));
+    }
+    let bytes = pdf.finish();
+    let text = String::from_utf8_lossy(&bytes);
+    assert!(text.contains("/Count 3"));
+}
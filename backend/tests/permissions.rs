@@ -0,0 +1,83 @@
+use evidive_api::services::permissions::{parse_list, Capability, MemberPermissions};
+
+fn member(role: &str, granted: &[&str], revoked: &[&str]) -> MemberPermissions {
+    MemberPermissions {
+        role_in_center: role.to_owned(),
+        granted_capabilities: granted.iter().map(|c| (*c).to_owned()).collect(),
+        revoked_capabilities: revoked.iter().map(|c| (*c).to_owned()).collect(),
+    }
+}
+
+#[test]
+fn employees_cannot_edit_services_schedule_or_read_finance() {
+    let employee = member("employee", &[], &[]);
+    assert!(employee.allows(Capability::ViewBookings));
+    assert!(employee.allows(Capability::ManageBookings));
+    for capability in [
+        Capability::ManageServices,
+        Capability::ManageSchedule,
+        Capability::ViewFinance,
+        Capability::ManageMembers,
+    ] {
+        assert!(!employee.allows(capability), "{capability:?}");
+    }
+}
+
+#[test]
+fn owner_payouts_and_integrations_stay_with_owners() {
+    let manager = member("manager", &[], &[]);
+    assert!(manager.allows(Capability::ViewFinance));
+    assert!(!manager.allows(Capability::ManagePayouts));
+    assert!(!manager.allows(Capability::ManageIntegrations));
+    assert!(!manager.allows(Capability::ManageMembers));
+
+    assert_eq!(member("owner", &[], &[]).effective(), Capability::ALL.to_vec());
+}
+
+#[test]
+fn overrides_apply_on_top_of_role_defaults() {
+    let employee = member("employee", &["view_finance"], &["manage_bookings"]);
+    assert!(employee.allows(Capability::ViewFinance));
+    assert!(!employee.allows(Capability::ManageBookings));
+    assert!(employee.allows(Capability::ViewBookings));
+}
+
+#[test]
+fn owners_cannot_be_restricted() {
+    let owner = member("owner", &[], &["manage_members"]);
+    assert!(owner.allows(Capability::ManageMembers));
+}
+
+#[test]
+fn unknown_roles_have_no_capability() {
+    assert!(member("guest", &[], &[]).effective().is_empty());
+    assert_eq!(member("staff", &[], &[]).effective(), vec![Capability::ViewBookings]);
+}
+
+#[test]
+fn capability_lists_are_validated_and_deduplicated() {
+    let list = parse_list(&["view_finance".to_owned(), " view_finance".to_owned(), "manage_staff".to_owned()]).unwrap();
+    assert_eq!(list, vec![Capability::ManageStaff, Capability::ViewFinance]);
+    assert!(parse_list(&["delete_everything".to_owned()]).is_err());
+}
+
+#[test]
+fn names_match_serde_and_the_migration_check() {
+    let migration = include_str!("../migrations/032_center_permissions.sql");
+    for capability in Capability::ALL {
+        assert_eq!(serde_json::to_value(capability).unwrap(), capability.as_str());
+        assert_eq!(Capability::parse(capability.as_str()), Some(capability));
+        assert!(migration.contains(&format!("'{}'", capability.as_str())), "{capability:?}");
+    }
+}
+
+#[test]
+fn members_can_only_assign_roles_within_their_capabilities() {
+    let member_admin = member("employee", &["manage_members"], &[]);
+    assert!(member_admin.check_can_assign("employee").is_ok());
+    assert!(member_admin.check_can_assign("staff").is_ok());
+    assert!(member_admin.check_can_assign("manager").is_err());
+
+    let owner = member("owner", &[], &[]);
+    assert!(owner.check_can_assign("manager").is_ok());
+}
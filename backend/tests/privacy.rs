@@ -0,0 +1,140 @@
+mod common;
+
+use chrono::NaiveDate;
+use http::StatusCode;
+use uuid::Uuid;
+
+use common::{call, request, test_app, test_pool};
+use evidive_api::services::privacy::{check_erasable, export_filename, ErasureSummary, ERASED};
+use evidive_api::AppError;
+
+#[test]
+fn export_filename_has_date_and_user() {
+    let user = Uuid::parse_str("6f1c2d3e-4a5b-4c6d-8e7f-9a0b1c2d3e4f").unwrap();
+    let date = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+    assert_eq!(
+        export_filename(user, date),
+        "evidive-export-2026-10-18-6f1c2d3e4a5b4c6d8e7f9a0b1c2d3e4f.json"
+    );
+}
+
+#[test]
+fn account_without_centers_or_upcoming_bookings_is_erasable() {
+    assert!(check_erasable(0, 0).is_ok());
+}
+
+#[test]
+fn center_owner_must_transfer_first() {
+    match check_erasable(1, 0) {
+        Err(AppError::Conflict(msg)) => assert!(msg.contains("ownership")),
+        other => panic!("expected a conflict, got {other:?}"),
+    }
+}
+
+#[test]
+fn upcoming_bookings_must_be_cancelled_first() {
+    match check_erasable(0, 2) {
+        Err(AppError::Conflict(msg)) => assert!(msg.contains("upcoming bookings")),
+        other => panic!("expected a conflict, got {other:?}"),
+    }
+}
+
+#[test]
+fn erasure_summary_serializes_every_count() {
+    let summary = serde_json::to_value(ErasureSummary { reviews: 3, ..Default::default() }).unwrap();
+    assert_eq!(summary["reviews"], 3);
+    for key in ["bookings", "coupons", "notifications", "contact_messages", "emails", "invitations", "memberships"] {
+        assert_eq!(summary[key], 0, "{key}");
+    }
+}
+
+// ──────────────────────── Database ────────────────────────
+
+fn erase_me(user_id: Uuid) -> http::Request<axum::body::Body> {
+    request("DELETE", "/api/v1/profile/me", user_id, serde_json::json!({ "confirm": true }))
+}
+
+#[tokio::test]
+async fn erasure_anonymizes_the_account_and_keeps_the_booking() {
+    let Some(pool) = test_pool().await else { return };
+    let app = test_app(&pool);
+    let (owner, client) = (common::user(&pool, "owner").await, common::user(&pool, "client").await);
+    let (center, _) = common::center(&pool, owner).await;
+    let booking = common::booking(&pool, center, client).await;
+    sqlx::query("UPDATE bookings SET booking_date = CURRENT_DATE - 7, client_note = 'Allergic to latex' WHERE id = $1")
+        .bind(booking)
+        .execute(&pool)
+        .await
+        .expect("past booking");
+    let message: Uuid = sqlx::query_scalar(
+        "INSERT INTO contact_messages (name, email, subject, message) VALUES ('Client', $1, 'Hello', 'Call me') RETURNING id",
+    )
+    .bind(common::email_of("client", client))
+    .fetch_one(&pool)
+    .await
+    .expect("contact message");
+    sqlx::query("INSERT INTO contact_replies (message_id, body) VALUES ($1, 'Hello Client')")
+        .bind(message)
+        .execute(&pool)
+        .await
+        .expect("contact reply");
+
+    let (status, body) = call(&app, erase_me(client)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let (first_name, erased): (Option<String>, bool) =
+        sqlx::query_as("SELECT first_name, erased_at IS NOT NULL FROM profiles WHERE id = $1")
+            .bind(client)
+            .fetch_one(&pool)
+            .await
+            .expect("profile kept");
+    assert_eq!((first_name, erased), (None, true));
+    let email: Option<String> = sqlx::query_scalar("SELECT email FROM auth.users WHERE id = $1")
+        .bind(client)
+        .fetch_one(&pool)
+        .await
+        .expect("auth user");
+    assert_eq!(email, None);
+    let (note, total): (Option<String>, rust_decimal::Decimal) =
+        sqlx::query_as("SELECT client_note, total_price FROM bookings WHERE id = $1")
+            .bind(booking)
+            .fetch_one(&pool)
+            .await
+            .expect("booking kept");
+    assert_eq!((note, total), (None, rust_decimal::Decimal::from(120)));
+    let (text, reply): (String, String) = sqlx::query_as(
+        "SELECT m.message, r.body FROM contact_messages m JOIN contact_replies r ON r.message_id = m.id WHERE m.id = $1",
+    )
+    .bind(message)
+    .fetch_one(&pool)
+    .await
+    .expect("contact message");
+    assert_eq!((text.as_str(), reply.as_str()), (ERASED, ERASED));
+    assert_eq!(common::audit_actions(&pool, client).await, vec!["user.erase"]);
+
+    sqlx::query("DELETE FROM contact_messages WHERE id = $1").bind(message).execute(&pool).await.expect("cleanup");
+    common::cleanup_center(&pool, center).await;
+    common::cleanup_users(&pool, &[owner, client]).await;
+}
+
+#[tokio::test]
+async fn center_owner_cannot_erase_their_account() {
+    let Some(pool) = test_pool().await else { return };
+    let app = test_app(&pool);
+    let owner = common::user(&pool, "owner").await;
+    let (center, _) = common::center(&pool, owner).await;
+
+    let (status, _) = call(&app, erase_me(owner)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let first_name: Option<String> = sqlx::query_scalar("SELECT first_name FROM profiles WHERE id = $1")
+        .bind(owner)
+        .fetch_one(&pool)
+        .await
+        .expect("profile");
+    assert_eq!(first_name.as_deref(), Some("owner"));
+    assert!(common::audit_actions(&pool, owner).await.is_empty());
+
+    common::cleanup_center(&pool, center).await;
+    common::cleanup_users(&pool, &[owner]).await;
+}
@@ -0,0 +1,130 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, TimeZone, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use evidive_api::models::Money;
+use evidive_api::services::payment_gateway::{
+    BalanceEntry, BalanceEntryKind, InMemoryGateway, PaymentGateway,
+};
+use evidive_api::services::reconciliation::{
+    compare, DiscrepancyKind, LocalLedger, LocalPayout, LocalTransaction, UnpaidBooking,
+};
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).expect("valid decimal")
+}
+
+fn eur(s: &str) -> Money {
+    Money::new(dec(s), "EUR").expect("EUR is supported")
+}
+
+fn at(day: u32, hour: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 3, day, hour, 0, 0).single().expect("valid date")
+}
+
+fn charge(pi: &str, amount: &str, fee: &str, created: DateTime<Utc>) -> BalanceEntry {
+    BalanceEntry {
+        id: format!("txn_{pi}"),
+        kind: BalanceEntryKind::Charge,
+        source_id: format!("ch_{pi}"),
+        payment_intent_id: Some(pi.to_owned()),
+        amount: eur(amount),
+        application_fee: Some(eur(fee)),
+        created,
+    }
+}
+
+fn transaction(pi: &str, amount: &str, fee: &str, created: DateTime<Utc>) -> LocalTransaction {
+    LocalTransaction {
+        id: Uuid::new_v4(),
+        booking_id: Uuid::new_v4(),
+        payment_intent_id: Some(pi.to_owned()),
+        amount: dec(amount),
+        platform_fee: dec(fee),
+        currency: "EUR".to_owned(),
+        created_at: created,
+    }
+}
+
+/// Reconcile a day against the in-memory gateway standing in for Stripe.
+#[tokio::test]
+async fn reports_each_kind_of_discrepancy() {
+    let (from, to) = (at(10, 0), at(11, 0));
+    let gateway = InMemoryGateway::new();
+
+    // Matches exactly, even though Stripe saw it just after midnight.
+    gateway.push_balance_entry(charge("pi_ok", "100.00", "20.00", at(11, 0)));
+    // Fee recomputed locally from commission_rate differs from Stripe.
+    gateway.push_balance_entry(charge("pi_fee", "99.99", "20.01", at(10, 9)));
+    // Charged in Stripe, never recorded locally.
+    gateway.push_balance_entry(charge("pi_lost", "50.00", "10.00", at(10, 12)));
+    // Transfer without a payout row.
+    gateway.push_balance_entry(BalanceEntry {
+        id: "txn_tr".to_owned(),
+        kind: BalanceEntryKind::Transfer,
+        source_id: "tr_unknown".to_owned(),
+        payment_intent_id: None,
+        amount: eur("80.00"),
+        application_fee: None,
+        created: at(10, 15),
+    });
+
+    let unpaid = Uuid::new_v4();
+    let local = LocalLedger {
+        transactions: vec![
+            transaction("pi_ok", "100.00", "20.00", at(10, 23)),
+            transaction("pi_fee", "99.99", "20.00", at(10, 9)),
+            transaction("pi_ghost", "30.00", "6.00", at(10, 18)),
+        ],
+        refunds: Vec::new(),
+        payouts: vec![LocalPayout {
+            id: Uuid::new_v4(),
+            center_id: Uuid::new_v4(),
+            stripe_transfer_id: None,
+            amount: dec("40.00"),
+            currency: "EUR".to_owned(),
+            created_at: at(10, 16),
+        }],
+        unpaid_bookings: vec![UnpaidBooking {
+            booking_id: unpaid,
+            total_price: dec("75.00"),
+            currency: "EUR".to_owned(),
+        }],
+    };
+
+    let margin = chrono::Duration::hours(24);
+    let stripe = gateway
+        .list_balance_entries(from - margin, to + margin)
+        .await
+        .expect("fake gateway lists entries");
+
+    let mut kinds: Vec<DiscrepancyKind> = compare(from, to, &local, &stripe)
+        .into_iter()
+        .map(|d| d.kind)
+        .collect();
+    kinds.sort_by_key(|k| k.as_str());
+
+    let mut expected = vec![
+        DiscrepancyKind::BookingWithoutTransaction,
+        DiscrepancyKind::FeeMismatch,
+        DiscrepancyKind::MissingTransaction,
+        DiscrepancyKind::TransactionNotInStripe,
+        DiscrepancyKind::PayoutMissingLocally,
+        DiscrepancyKind::PayoutNotInStripe,
+    ];
+    expected.sort_by_key(|k| k.as_str());
+    assert_eq!(kinds, expected);
+}
+
+#[test]
+fn matching_ledgers_have_no_discrepancies() {
+    let (from, to) = (at(10, 0), at(11, 0));
+    let local = LocalLedger {
+        transactions: vec![transaction("pi_1", "12.5", "2.5", at(10, 8))],
+        ..LocalLedger::default()
+    };
+    let stripe = [charge("pi_1", "12.50", "2.50", at(10, 8))];
+    assert!(compare(from, to, &local, &stripe).is_empty());
+}
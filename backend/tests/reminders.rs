@@ -0,0 +1,95 @@
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use evidive_api::services::email::{render, Locale, Template, Value};
+use evidive_api::services::reminders::{
+    checklist_lines, clean_checklist, meeting_point, ReminderDetails, ReminderSettings,
+};
+
+fn details(checklist: &[&str]) -> ReminderDetails {
+    ReminderDetails {
+        client_id: Uuid::nil(),
+        center_name: "Blue Reef".to_owned(),
+        service_name: Some("Wreck dive".to_owned()),
+        booking_date: NaiveDate::from_ymd_opt(2026, 7, 14).unwrap(),
+        time_slot: "09:30".to_owned(),
+        participants: 2,
+        address: Some("12 quai du Port".to_owned()),
+        postal_code: Some("83990".to_owned()),
+        city: Some("Saint-Tropez".to_owned()),
+        country: Some("France".to_owned()),
+        min_certification: Some("PADI Advanced Open Water".to_owned()),
+        min_dives: Some(20),
+        checklist: checklist.iter().map(|s| (*s).to_owned()).collect(),
+    }
+}
+
+fn settings(hours_before: i16, checklist: Vec<String>) -> ReminderSettings {
+    ReminderSettings {
+        center_id: Uuid::nil(),
+        enabled: true,
+        hours_before,
+        timezone: "Europe/Paris".to_owned(),
+        checklist,
+        updated_at: None,
+    }
+}
+
+#[test]
+fn meeting_point_joins_the_center_address() {
+    assert_eq!(
+        meeting_point(Some("12 quai du Port"), Some("83990"), Some("Saint-Tropez"), Some("France")),
+        "12 quai du Port, 83990 Saint-Tropez, France"
+    );
+    assert_eq!(meeting_point(None, None, Some(" Nice "), Some("")), "Nice");
+    assert_eq!(meeting_point(None, None, None, None), "—");
+}
+
+#[test]
+fn reminder_email_lists_requirements_and_checklist() {
+    let mut vars = details(&["Wetsuit 5 mm", "Medical certificate"]).vars();
+    vars.push(("name", Value::from("Marie")));
+    let email = render(Template::DiveReminder, Locale::En, &vars).unwrap();
+
+    assert_eq!(email.subject, "Your dive with Blue Reef is coming up");
+    assert!(email.body.contains("Date: 14/07/2026 at 09:30"));
+    assert!(email.body.contains("Meeting point: 12 quai du Port, 83990 Saint-Tropez, France"));
+    assert!(email.body.contains("Minimum certification: PADI Advanced Open Water"));
+    assert!(email.body.contains("Minimum logged dives: 20"));
+    assert!(email
+        .body
+        .contains("  - Logbook\n  - Wetsuit 5 mm\n  - Medical certificate\n"));
+}
+
+#[test]
+fn missing_requirements_are_shown_as_a_dash() {
+    let mut reminder = details(&[]);
+    reminder.min_certification = None;
+    reminder.min_dives = Some(0);
+    let mut vars = reminder.vars();
+    vars.push(("name", Value::from("Marie")));
+    let email = render(Template::DiveReminder, Locale::Fr, &vars).unwrap();
+
+    assert!(email.body.contains("Certification minimale : —"));
+    assert!(email.body.contains("Nombre minimal de plongées : —"));
+    assert!(email.body.contains("  - Carnet de plongée\n\nÀ bientôt"));
+}
+
+#[test]
+fn checklist_items_are_cleaned_and_bounded() {
+    let items = clean_checklist(vec!["  Mask ".to_owned(), "".to_owned(), "   ".to_owned()]);
+    assert_eq!(items, vec!["Mask".to_owned()]);
+    assert_eq!(checklist_lines(&items), "\n  - Mask");
+
+    assert!(settings(24, items).validate().is_ok());
+    assert!(settings(24, vec!["x".to_owned(); 21]).validate().is_err());
+    assert!(settings(24, vec!["x".repeat(201)]).validate().is_err());
+}
+
+#[test]
+fn reminder_window_is_bounded() {
+    assert!(settings(1, Vec::new()).validate().is_ok());
+    assert!(settings(168, Vec::new()).validate().is_ok());
+    assert!(settings(0, Vec::new()).validate().is_err());
+    assert!(settings(169, Vec::new()).validate().is_err());
+}
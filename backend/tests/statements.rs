@@ -0,0 +1,109 @@
+use std::str::FromStr;
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use evidive_api::services::statements::{
+    file_name, month_bounds, statement_currency, to_csv, PaymentLine, PayoutLine, RefundLine, Statement,
+    StatementTotals,
+};
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).expect("valid decimal")
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).expect("valid date")
+}
+
+fn sample() -> Statement {
+    let payments = vec![PaymentLine {
+        booking_id: Uuid::nil(),
+        booking_date: date(2026, 3, 14),
+        service_name: Some("Discovery dive, \"Blue hole\"".to_owned()),
+        client_name: Some("Jane Doe".to_owned()),
+        payment_reference: Some("pi_123".to_owned()),
+        paid_at: Utc.with_ymd_and_hms(2026, 3, 2, 10, 0, 0).unwrap(),
+        gross: dec("100.00"),
+        commission: dec("10.00"),
+        net: dec("90.00"),
+        currency: "EUR".to_owned(),
+    }];
+    let refunds = vec![RefundLine {
+        refund_id: Uuid::nil(),
+        booking_id: Uuid::nil(),
+        refunded_at: Utc.with_ymd_and_hms(2026, 3, 5, 10, 0, 0).unwrap(),
+        amount: dec("50.00"),
+        commission_refunded: dec("5.00"),
+        net: dec("45.00"),
+        currency: "EUR".to_owned(),
+    }];
+    let payouts = vec![PayoutLine {
+        payout_id: Uuid::nil(),
+        stripe_transfer_id: Some("tr_1".to_owned()),
+        paid_at: Utc.with_ymd_and_hms(2026, 3, 20, 10, 0, 0).unwrap(),
+        amount: dec("30.00"),
+        currency: "EUR".to_owned(),
+    }];
+    let totals = StatementTotals::compute(dec("20.00"), &payments, &refunds, &payouts);
+    Statement {
+        center_id: Uuid::nil(),
+        center_name: "Ocean Dive (Nice)".to_owned(),
+        period_start: date(2026, 3, 1),
+        period_end: date(2026, 4, 1),
+        currency: "EUR".to_owned(),
+        payments,
+        refunds,
+        payouts,
+        totals,
+    }
+}
+
+#[test]
+fn totals_carry_opening_balance_to_closing() {
+    let t = sample().totals;
+    assert_eq!(t.gross, dec("100.00"));
+    assert_eq!(t.commission, dec("10.00"));
+    assert_eq!(t.net, dec("90.00"));
+    assert_eq!(t.refunds, dec("45.00"));
+    assert_eq!(t.payouts, dec("30.00"));
+    // 20 + 90 − 45 − 30
+    assert_eq!(t.closing_balance, dec("35.00"));
+}
+
+#[test]
+fn statement_currency_rejects_mixed_movements() {
+    assert_eq!(statement_currency("EUR", &[]).unwrap(), "EUR");
+    assert_eq!(statement_currency("EUR", &["USD".to_owned()]).unwrap(), "USD");
+    assert!(statement_currency("EUR", &["EUR".to_owned(), "USD".to_owned()]).is_err());
+}
+
+#[test]
+fn month_bounds_roll_over_december() {
+    assert_eq!(month_bounds(2026, 12).unwrap(), (date(2026, 12, 1), date(2027, 1, 1)));
+    assert_eq!(month_bounds(2026, 2).unwrap(), (date(2026, 2, 1), date(2026, 3, 1)));
+    assert!(month_bounds(2026, 13).is_err());
+}
+
+#[test]
+fn csv_escapes_fields_and_signs_debits() {
+    let csv = to_csv(&sample());
+    let lines: Vec<&str> = csv.split("\r\n").filter(|l| !l.is_empty()).collect();
+
+    assert_eq!(
+        lines[0],
+        "type,date,booking_id,reference,description,gross,commission,net,currency"
+    );
+    assert!(lines[1].contains("\"Discovery dive, \"\"Blue hole\"\" - Jane Doe\""));
+    assert!(lines[2].starts_with("refund,2026-03-05,"));
+    assert!(lines[2].ends_with(",-50.00,-5.00,-45.00,EUR"));
+    assert!(lines[3].ends_with(",tr_1,,,,-30.00,EUR"));
+    assert_eq!(lines[4], "opening_balance,2026-03-01,,,,,,20.00,EUR");
+    assert_eq!(lines.last().copied(), Some("closing_balance,2026-03-31,,,,,,35.00,EUR"));
+}
+
+#[test]
+fn file_name_uses_center_slug_and_period() {
+    assert_eq!(file_name(&sample(), "csv"), "statement-ocean-dive-nice-2026-03.csv");
+}
@@ -0,0 +1,53 @@
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use evidive_api::models::Money;
+use evidive_api::services::tax::{compute, FeeTaxRegime, TaxRates};
+
+fn eur(s: &str) -> Money {
+    Money::new(Decimal::from_str(s).expect("valid decimal"), "EUR").expect("EUR is supported")
+}
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).expect("valid decimal")
+}
+
+fn french_rates() -> TaxRates {
+    TaxRates {
+        service_rate: dec("20"),
+        platform_rate: dec("20"),
+    }
+}
+
+/// French centers pay French VAT on the commission; gross amounts are split
+/// so that net + VAT always equals gross.
+#[test]
+fn french_center_is_charged_french_vat() {
+    let t = compute("FR", &eur("99.99"), &eur("20.00"), &french_rates()).expect("same currency");
+    assert_eq!(t.fee_regime, FeeTaxRegime::FrenchVat);
+    assert_eq!(t.price.vat, dec("16.67"));
+    assert_eq!(t.price.net + t.price.vat, dec("99.99"));
+    assert_eq!(t.fee.net, dec("16.67"));
+    assert_eq!(t.fee.vat, dec("3.33"));
+}
+
+/// EU centers outside France self-assess VAT on the commission.
+#[test]
+fn eu_center_uses_reverse_charge() {
+    let rates = TaxRates { service_rate: dec("21"), ..french_rates() };
+    let t = compute("ES", &eur("121.00"), &eur("24.20"), &rates).expect("same currency");
+    assert_eq!(t.fee_regime, FeeTaxRegime::ReverseCharge);
+    assert_eq!(t.fee.vat, Decimal::ZERO);
+    assert_eq!(t.fee.net, dec("24.20"));
+    assert_eq!(t.price.net, dec("100.00"));
+}
+
+#[test]
+fn non_eu_center_is_out_of_scope() {
+    let rates = TaxRates { service_rate: Decimal::ZERO, ..french_rates() };
+    let t = compute("EG", &eur("50"), &eur("10"), &rates).expect("same currency");
+    assert_eq!(t.fee_regime, FeeTaxRegime::OutOfScope);
+    assert_eq!(t.fee.vat, Decimal::ZERO);
+    assert_eq!(t.price.vat, Decimal::ZERO);
+}
@@ -7,11 +7,12 @@ use http_body_util::BodyExt;
 use tower::ServiceExt;
 
 use evidive_api::config::Config;
+use evidive_api::services::payment_gateway::InMemoryGateway;
 use evidive_api::AppState;
 
 /// Build a test-only `AppState` with a known webhook secret.
 /// The DB pool is lazy (never connects), mailer targets localhost,
-/// and Stripe uses a test key. Sufficient for extractor-level tests.
+/// and payments go to the in-memory gateway. Sufficient for extractor-level tests.
 fn test_state() -> Arc<AppState> {
     let config = Config {
         port: 0,
@@ -26,6 +27,8 @@ fn test_state() -> Arc<AppState> {
         smtp_user: Some("test".to_owned()),
         smtp_pass: Some("test".to_owned()),
         smtp_from: Some("test@test.local".to_owned()),
+        cron_secret: None,
+        ip_hash_secret: Some("ip-hash-test-secret".to_owned()),
     };
 
     let pool = sqlx::postgres::PgPoolOptions::new()
@@ -41,20 +44,22 @@ fn test_state() -> Arc<AppState> {
     .port(config.smtp_port)
     .build();
 
-    let stripe = stripe::Client::new(&config.stripe_secret_key);
+    let payments = Arc::new(InMemoryGateway::new());
 
-    let jwt_decoding_key =
-        jsonwebtoken::DecodingKey::from_secret(b"test_jwt_secret");
-    let jwt_algorithm = jsonwebtoken::Algorithm::HS256;
+    let jwks = evidive_api::middleware::jwks::JwksCache::with_key(
+        None,
+        jsonwebtoken::DecodingKey::from_secret(b"test_jwt_secret"),
+        jsonwebtoken::Algorithm::HS256,
+    );
 
     Arc::new(AppState {
         pool,
         config,
         mailer: Some(mailer),
-        stripe,
-        jwt_decoding_key,
-        jwt_algorithm,
+        payments,
+        jwks,
         jwt_issuer: "test".to_owned(),
+        events: evidive_api::services::events::EventHub::new(),
     })
 }
 